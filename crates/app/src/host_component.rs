@@ -67,6 +67,7 @@ where
     }
 }
 
+#[derive(Clone)]
 struct DynamicHostComponentWithHandle {
     host_component: Arc<dyn DynSafeDynamicHostComponent + Send + Sync>,
     handle: AnyHostComponentDataHandle,
@@ -78,7 +79,7 @@ struct DynamicHostComponentWithHandle {
 /// can be referenced and updated at a later point. This is effectively
 /// what makes a `DynamicHostComponent` "dynamic" and differentiates it from
 /// a regular `HostComponent`.
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub(crate) struct DynamicHostComponents {
     host_components: Vec<DynamicHostComponentWithHandle>,
 }
@@ -11,6 +11,8 @@ pub use spin_locked_app::locked;
 pub use spin_locked_app::values;
 pub use spin_locked_app::{Error, MetadataKey, Result};
 
+use std::sync::Arc;
+
 use ouroboros::self_referencing;
 use serde::Deserialize;
 use spin_core::{wasmtime, Engine, EngineBuilder, HostComponentDataHandle, StoreBuilder};
@@ -68,8 +70,13 @@ pub trait Loader {
 
 /// An `AppLoader` holds an implementation of [`Loader`] along with
 /// [`DynamicHostComponent`]s configuration.
+///
+/// Cloning an `AppLoader` is cheap; clones share the same [`Loader`] and
+/// dynamic host components, so they may be used to (re)load apps for the
+/// same [`Engine`].
+#[derive(Clone)]
 pub struct AppLoader {
-    inner: Box<dyn Loader + Send + Sync>,
+    inner: Arc<dyn Loader + Send + Sync>,
     dynamic_host_components: DynamicHostComponents,
 }
 
@@ -77,7 +84,7 @@ impl AppLoader {
     /// Creates a new [`AppLoader`].
     pub fn new(loader: impl Loader + Send + Sync + 'static) -> Self {
         Self {
-            inner: Box::new(loader),
+            inner: Arc::new(loader),
             dynamic_host_components: Default::default(),
         }
     }
@@ -142,6 +149,14 @@ impl OwnedApp {
     pub fn borrowed(&self) -> &App {
         self.borrow_app()
     }
+
+    /// Loads a fresh [`OwnedApp`] from this app's `uri`, using the same
+    /// [`AppLoader`] (and so the same dynamic host components).
+    pub async fn reload(&self) -> Result<OwnedApp> {
+        let loader = self.borrow_loader().clone();
+        let uri = self.borrowed().uri().to_owned();
+        loader.load_owned_app(uri).await
+    }
 }
 
 /// An `App` holds loaded configuration for a Spin application.
@@ -0,0 +1,317 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use spin_common::{
+    sha256::{hex_digest_from_bytes, hex_digest_from_file},
+    ui::quoted_path,
+};
+
+use crate::{construct_workdir, manifest::ComponentBuildInfo};
+
+/// The build cache location, relative to the application directory.
+const BUILD_CACHE_FILE: &str = ".spin/build-cache.json";
+
+/// Fingerprints of the components as of their last successful build.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct BuildCache {
+    #[serde(default)]
+    components: BTreeMap<String, Fingerprint>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+/// The fingerprint of a component's build: its inputs as they were before it
+/// was built, and the output it produced.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Fingerprint {
+    inputs: String,
+    output: String,
+}
+
+impl BuildCache {
+    /// Loads the build cache for the application. A missing or unreadable
+    /// cache is treated as empty, causing all components to be built.
+    pub fn load(app_dir: &Path) -> Self {
+        let path = app_dir.join(BUILD_CACHE_FILE);
+        std::fs::read(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_slice(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Saves the build cache if it has changed since it was loaded.
+    pub fn save(&self, app_dir: &Path) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let path = app_dir.join(BUILD_CACHE_FILE);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", quoted_path(dir)))?;
+        }
+        let contents = serde_json::to_vec_pretty(self)?;
+        std::fs::write(&path, contents)
+            .with_context(|| format!("Failed to write build cache {}", quoted_path(&path)))
+    }
+
+    /// Returns true if the component has been built successfully and neither
+    /// its inputs nor its output have changed since.
+    pub fn is_up_to_date(
+        &self,
+        component_id: &str,
+        inputs: Option<&str>,
+        output: Option<&str>,
+    ) -> bool {
+        match (self.components.get(component_id), inputs, output) {
+            (Some(recorded), Some(inputs), Some(output)) => {
+                recorded.inputs == inputs && recorded.output == output
+            }
+            _ => false,
+        }
+    }
+
+    /// Records the fingerprint of a component after a build, from its inputs
+    /// as they were before the build started. If either fingerprint is `None`
+    /// (e.g. after a failed build), the component is forgotten.
+    pub fn record(&mut self, component_id: &str, inputs: Option<String>, output: Option<String>) {
+        let changed = match inputs.zip(output) {
+            Some((inputs, output)) => {
+                let fingerprint = Fingerprint { inputs, output };
+                self.components
+                    .insert(component_id.to_owned(), fingerprint.clone())
+                    != Some(fingerprint)
+            }
+            None => self.components.remove(component_id).is_some(),
+        };
+        self.dirty |= changed;
+    }
+}
+/// Directories which are never treated as build inputs of a component
+/// without `watch` globs: build outputs and dependencies.
+const UNWATCHED_DIRS: &[&str] = &["target", "node_modules"];
+
+/// Computes a fingerprint of a component's build command and inputs. This
+/// should be computed before the component is built, so that changes made
+/// to the inputs during the build cause it to be rebuilt next time.
+///
+/// The inputs are the files matched by the component's `watch` globs. If it
+/// has none, they are the files under its build working directory, other
+/// than hidden files, those in [`UNWATCHED_DIRS`], and the outputs of all of
+/// the app's components (given in `outputs`), along with any directories
+/// holding those outputs. This is conservative, as that directory may
+/// contain files the build does not use.
+///
+/// Returns `None` if the component's inputs cannot be read.
+pub(crate) fn fingerprint_inputs(
+    component: &ComponentBuildInfo,
+    app_dir: &Path,
+    outputs: &[PathBuf],
+) -> Option<String> {
+    let build = component.build.as_ref()?;
+
+    let mut manifest = format!("command {}\n", build.command);
+    if let Some(workdir) = &build.workdir {
+        manifest.push_str(&format!("workdir {workdir}\n"));
+    }
+
+    let workdir = construct_workdir(app_dir, build.workdir.as_ref()).ok()?;
+    let inputs = if build.watch.is_empty() {
+        workdir_files(&workdir, outputs).ok()?
+    } else {
+        watched_files(&workdir, &build.watch).ok()?
+    };
+    for input in inputs {
+        let digest = hex_digest_from_file(&input).ok()?;
+        let relative = input.strip_prefix(&workdir).unwrap_or(&input);
+        manifest.push_str(&format!("input {} {digest}\n", relative.display()));
+    }
+
+    Some(hex_digest_from_bytes(manifest))
+}
+
+/// Computes a fingerprint of a component's output Wasm file, or `None` if it
+/// is missing.
+pub(crate) fn fingerprint_output(component: &ComponentBuildInfo, app_dir: &Path) -> Option<String> {
+    hex_digest_from_file(output_path(component, app_dir)?).ok()
+}
+
+/// The path of the Wasm file a component's build produces, if it has a local
+/// source.
+pub(crate) fn output_path(component: &ComponentBuildInfo, app_dir: &Path) -> Option<PathBuf> {
+    Some(app_dir.join(component.local_source()?))
+}
+
+/// Returns the files matching the given globs (relative to the working
+/// directory), in a stable order.
+fn watched_files(workdir: &Path, globs: &[String]) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for pattern in globs {
+        let pattern = workdir.join(pattern);
+        let pattern = pattern
+            .to_str()
+            .with_context(|| format!("Invalid watch pattern {}", quoted_path(&pattern)))?;
+        for entry in glob::glob(pattern)? {
+            let path = entry?;
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Returns the files under the working directory which may be build inputs,
+/// in a stable order. Build outputs, and the directories under the working
+/// directory which hold them, are excluded.
+fn workdir_files(workdir: &Path, outputs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let output_dirs = outputs
+        .iter()
+        .filter_map(|output| output.parent())
+        .filter(|dir| dir.starts_with(workdir) && *dir != workdir)
+        .collect::<Vec<_>>();
+    let entries = walkdir::WalkDir::new(workdir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0
+                || !(name.starts_with('.')
+                    || (entry.file_type().is_dir()
+                        && (UNWATCHED_DIRS.contains(&name.as_ref())
+                            || output_dirs.contains(&entry.path()))))
+        });
+    let mut files = vec![];
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type().is_file() && !outputs.iter().any(|output| output == entry.path()) {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use spin_manifest::schema::v2::ComponentBuildConfig;
+
+    use super::*;
+
+    fn component(id: &str, output: &str, watch: &[&str]) -> ComponentBuildInfo {
+        ComponentBuildInfo {
+            id: id.into(),
+            source: Some(toml::Value::String(output.into())),
+            build: Some(ComponentBuildConfig {
+                command: "make".into(),
+                workdir: None,
+                watch: watch.iter().map(|w| w.to_string()).collect(),
+            }),
+        }
+    }
+
+    #[test]
+    fn fingerprint_tracks_inputs_and_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_dir = dir.path();
+        std::fs::create_dir(app_dir.join("src")).unwrap();
+        std::fs::write(app_dir.join("src/main.rs"), "fn main() {}").unwrap();
+
+        let component = component("test", "out.wasm", &["src/**/*.rs"]);
+        assert_eq!(
+            None,
+            fingerprint_output(&component, app_dir),
+            "output is missing"
+        );
+
+        let inputs = fingerprint_inputs(&component, app_dir, &[]).unwrap();
+        std::fs::write(app_dir.join("out.wasm"), "wasm").unwrap();
+        let output = fingerprint_output(&component, app_dir).unwrap();
+        assert_eq!(
+            Some(&inputs),
+            fingerprint_inputs(&component, app_dir, &[]).as_ref()
+        );
+
+        std::fs::write(app_dir.join("src/main.rs"), "fn main() { }").unwrap();
+        assert_ne!(Some(inputs), fingerprint_inputs(&component, app_dir, &[]));
+
+        std::fs::write(app_dir.join("out.wasm"), "other wasm").unwrap();
+        assert_ne!(Some(output), fingerprint_output(&component, app_dir));
+    }
+
+    #[test]
+    fn components_without_watch_fingerprint_workdir() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_dir = dir.path();
+        std::fs::write(app_dir.join("main.go"), "package main").unwrap();
+
+        let first = component("first", "first.wasm", &[]);
+        let second = component("second", "dist/second.wasm", &[]);
+        let outputs = [&first, &second]
+            .iter()
+            .filter_map(|c| output_path(c, app_dir))
+            .collect::<Vec<_>>();
+        let initial = fingerprint_inputs(&first, app_dir, &outputs).unwrap();
+
+        // Build outputs, artifacts and hidden files are not inputs
+        std::fs::write(app_dir.join("first.wasm"), "wasm").unwrap();
+        std::fs::create_dir(app_dir.join("dist")).unwrap();
+        std::fs::write(app_dir.join("dist/second.wasm"), "wasm").unwrap();
+        std::fs::write(app_dir.join("dist/second.js"), "").unwrap();
+        std::fs::create_dir_all(app_dir.join("target/debug")).unwrap();
+        std::fs::write(app_dir.join("target/debug/cache"), "").unwrap();
+        std::fs::create_dir(app_dir.join(".spin")).unwrap();
+        std::fs::write(app_dir.join(".spin/build-cache.json"), "{}").unwrap();
+        assert_eq!(
+            Some(&initial),
+            fingerprint_inputs(&first, app_dir, &outputs).as_ref()
+        );
+
+        std::fs::write(app_dir.join("main.go"), "package main\n").unwrap();
+        assert_ne!(Some(initial), fingerprint_inputs(&first, app_dir, &outputs));
+    }
+
+    #[test]
+    fn inputs_changed_during_build_are_rebuilt() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_dir = dir.path();
+        std::fs::write(app_dir.join("main.go"), "package main").unwrap();
+        let component = component("test", "out.wasm", &["*.go"]);
+        let mut cache = BuildCache::default();
+
+        let inputs = fingerprint_inputs(&component, app_dir, &[]);
+        // The source is edited while the build is running
+        std::fs::write(app_dir.join("out.wasm"), "wasm").unwrap();
+        std::fs::write(app_dir.join("main.go"), "package main\n").unwrap();
+        cache.record("test", inputs, fingerprint_output(&component, app_dir));
+
+        assert!(!cache.is_up_to_date(
+            "test",
+            fingerprint_inputs(&component, app_dir, &[]).as_deref(),
+            fingerprint_output(&component, app_dir).as_deref(),
+        ));
+    }
+
+    #[test]
+    fn cache_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = BuildCache::load(dir.path());
+        assert!(!cache.is_up_to_date("test", Some("abc"), Some("out")));
+
+        cache.record("test", Some("abc".into()), Some("out".into()));
+        cache.save(dir.path()).unwrap();
+
+        let mut cache = BuildCache::load(dir.path());
+        assert!(cache.is_up_to_date("test", Some("abc"), Some("out")));
+        assert!(!cache.is_up_to_date("test", Some("def"), Some("out")));
+        assert!(!cache.is_up_to_date("test", Some("abc"), Some("other")));
+        assert!(!cache.is_up_to_date("test", Some("abc"), None));
+
+        cache.record("test", None, Some("out".into()));
+        assert!(!cache.is_up_to_date("test", Some("abc"), Some("out")));
+    }
+}
@@ -2,22 +2,55 @@
 
 //! A library for building Spin components.
 
+mod cache;
 mod manifest;
 
 use anyhow::{anyhow, bail, Context, Result};
+use futures::StreamExt;
 use manifest::ComponentBuildInfo;
 use spin_common::{paths::parent_dir, ui::quoted_path};
 use std::{
     collections::HashSet,
     path::{Path, PathBuf},
+    process::Stdio,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    process::Command,
+};
+
+use crate::{
+    cache::{fingerprint_inputs, fingerprint_output, output_path, BuildCache},
+    manifest::component_build_configs,
 };
-use subprocess::{Exec, Redirection};
 
-use crate::manifest::component_build_configs;
+/// Options for building Spin components.
+#[derive(Debug, Default)]
+pub struct BuildOptions {
+    /// Build components even if they are up to date with their watched
+    /// inputs.
+    pub force: bool,
+    /// The manifest profile to apply, if any.
+    pub profile: Option<String>,
+}
 
 /// If present, run the build command of each component.
 pub async fn build(manifest_file: &Path, component_ids: &[String]) -> Result<()> {
-    let components = component_build_configs(manifest_file)
+    build_with_options(manifest_file, component_ids, &BuildOptions::default()).await
+}
+
+/// If present, run the build command of each component, with the given options.
+///
+/// Components are built in parallel. A component is skipped if it has been
+/// built before, and neither its inputs (the files matched by its `watch`
+/// globs, or if it has none, the files in its build directory other than
+/// build outputs) nor its output Wasm file have changed since.
+pub async fn build_with_options(
+    manifest_file: &Path,
+    component_ids: &[String],
+    options: &BuildOptions,
+) -> Result<()> {
+    let components = component_build_configs(manifest_file, options.profile.as_deref())
         .await
         .with_context(|| {
             format!(
@@ -26,6 +59,10 @@ pub async fn build(manifest_file: &Path, component_ids: &[String]) -> Result<()>
             )
         })?;
     let app_dir = parent_dir(manifest_file)?;
+    let outputs = components
+        .iter()
+        .filter_map(|c| output_path(c, &app_dir))
+        .collect::<Vec<_>>();
 
     let components_to_build = if component_ids.is_empty() {
         components
@@ -53,57 +90,174 @@ pub async fn build(manifest_file: &Path, component_ids: &[String]) -> Result<()>
         return Ok(());
     }
 
-    components_to_build
+    let mut cache = BuildCache::load(&app_dir);
+    // Inputs are fingerprinted before building, so that any changes made to
+    // them while building are picked up by the next build.
+    let components_to_build: Vec<_> = components_to_build
         .into_iter()
-        .map(|c| build_component(c, &app_dir))
-        .collect::<Result<Vec<_>, _>>()?;
+        .filter(|c| c.build.is_some())
+        .map(|c| {
+            let inputs = fingerprint_inputs(&c, &app_dir, &outputs);
+            (c, inputs)
+        })
+        .filter(|(c, inputs)| {
+            let up_to_date = !options.force
+                && cache.is_up_to_date(
+                    &c.id,
+                    inputs.as_deref(),
+                    fingerprint_output(c, &app_dir).as_deref(),
+                );
+            if up_to_date {
+                terminal::step!("Skipping", "component {} as it is up to date", c.id);
+            }
+            !up_to_date
+        })
+        .collect();
+
+    // Prefix output only if it could be interleaved
+    let prefix_output = components_to_build.len() > 1;
+    let parallelism = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let results: Vec<_> = futures::stream::iter(
+        components_to_build
+            .into_iter()
+            .map(|(c, inputs)| build_component(c, inputs, &app_dir, prefix_output)),
+    )
+    .buffer_unordered(parallelism)
+    .collect()
+    .await;
+
+    let mut errors = vec![];
+    for (component, inputs, result) in results {
+        match result {
+            Ok(()) => cache.record(
+                &component.id,
+                inputs,
+                fingerprint_output(&component, &app_dir),
+            ),
+            Err(e) => {
+                cache.record(&component.id, None, None);
+                errors.push(e);
+            }
+        }
+    }
+    if let Err(e) = cache.save(&app_dir) {
+        tracing::warn!("Failed to save build cache: {e:#}");
+    }
+
+    match errors.len() {
+        0 => {}
+        1 => return Err(errors.remove(0)),
+        _ => {
+            for e in &errors {
+                terminal::error!("{e:#}");
+            }
+            bail!("{} components failed to build", errors.len());
+        }
+    }
 
     terminal::step!("Finished", "building all Spin components");
     Ok(())
 }
 
-/// Run the build command of the component.
-fn build_component(build_info: ComponentBuildInfo, app_dir: &Path) -> Result<()> {
-    match build_info.build {
-        Some(b) => {
-            terminal::step!(
-                "Building",
-                "component {} with `{}`",
-                build_info.id,
-                b.command
-            );
-            let workdir = construct_workdir(app_dir, b.workdir.as_ref())?;
-            if b.workdir.is_some() {
-                println!("Working directory: {}", quoted_path(&workdir));
-            }
+/// Run the build command of the component, returning the component and the
+/// result of the build.
+async fn build_component(
+    build_info: ComponentBuildInfo,
+    inputs: Option<String>,
+    app_dir: &Path,
+    prefix_output: bool,
+) -> (ComponentBuildInfo, Option<String>, Result<()>) {
+    let result = run_build_command(&build_info, app_dir, prefix_output).await;
+    (build_info, inputs, result)
+}
 
-            let exit_status = Exec::shell(&b.command)
-                .cwd(workdir)
-                .stdout(Redirection::None)
-                .stderr(Redirection::None)
-                .stdin(Redirection::None)
-                .popen()
-                .map_err(|err| {
-                    anyhow!(
-                        "Cannot spawn build process '{:?}' for component {}: {}",
-                        &b.command,
-                        build_info.id,
-                        err
-                    )
-                })?
-                .wait()?;
-
-            if !exit_status.success() {
-                bail!(
-                    "Build command for component {} failed with status {:?}",
-                    build_info.id,
-                    exit_status,
-                );
-            }
+async fn run_build_command(
+    build_info: &ComponentBuildInfo,
+    app_dir: &Path,
+    prefix_output: bool,
+) -> Result<()> {
+    let Some(b) = &build_info.build else {
+        return Ok(());
+    };
+    terminal::step!(
+        "Building",
+        "component {} with `{}`",
+        build_info.id,
+        b.command
+    );
+    let workdir = construct_workdir(app_dir, b.workdir.as_ref())?;
+    if b.workdir.is_some() {
+        println!("Working directory: {}", quoted_path(&workdir));
+    }
+
+    let mut command = shell_command(&b.command);
+    command.current_dir(workdir).stdin(Stdio::null());
+    if prefix_output {
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+    let mut child = command.spawn().map_err(|err| {
+        anyhow!(
+            "Cannot spawn build process '{:?}' for component {}: {}",
+            &b.command,
+            build_info.id,
+            err
+        )
+    })?;
+
+    let exit_status = if prefix_output {
+        let prefix = format!("[{}]", build_info.id);
+        let stdout = forward_prefixed(child.stdout.take(), &prefix, Stream::Stdout);
+        let stderr = forward_prefixed(child.stderr.take(), &prefix, Stream::Stderr);
+        let (exit_status, _, _) = tokio::join!(child.wait(), stdout, stderr);
+        exit_status?
+    } else {
+        child.wait().await?
+    };
+
+    if !exit_status.success() {
+        bail!(
+            "Build command for component {} failed with status {:?}",
+            build_info.id,
+            exit_status,
+        );
+    }
 
-            Ok(())
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd.exe");
+    cmd.arg("/C").raw_arg(command);
+    cmd
+}
+
+enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Copies lines of build output to our own output, prefixed to identify the
+/// component being built.
+async fn forward_prefixed(output: Option<impl AsyncRead + Unpin>, prefix: &str, stream: Stream) {
+    let Some(output) = output else {
+        return;
+    };
+    let mut lines = BufReader::new(output).split(b'\n');
+    while let Ok(Some(line)) = lines.next_segment().await {
+        let line = String::from_utf8_lossy(&line);
+        let line = line.trim_end_matches('\r');
+        match stream {
+            Stream::Stdout => println!("{prefix} {line}"),
+            Stream::Stderr => eprintln!("{prefix} {line}"),
         }
-        _ => Ok(()),
     }
 }
 
@@ -1,22 +1,29 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use serde::Deserialize;
 use std::{collections::BTreeMap, path::Path};
 
 use spin_manifest::{schema::v2, ManifestVersion};
 
 /// Returns a map of component IDs to [`v2::ComponentBuildConfig`]s for the
-/// given (v1 or v2) manifest path.
+/// given (v1 or v2) manifest path, with the named profile (if any) applied.
 pub async fn component_build_configs(
     manifest_file: impl AsRef<Path>,
+    profile: Option<&str>,
 ) -> Result<Vec<ComponentBuildInfo>> {
     let manifest_text = tokio::fs::read_to_string(manifest_file).await?;
     Ok(match ManifestVersion::detect(&manifest_text)? {
         ManifestVersion::V1 => {
+            if let Some(profile) = profile {
+                bail!("Cannot apply profile '{profile}': profiles require spin_manifest_version 2");
+            }
             let v1: ManifestV1BuildInfo = toml::from_str(&manifest_text)?;
             v1.components
         }
         ManifestVersion::V2 => {
-            let v2: ManifestV2BuildInfo = toml::from_str(&manifest_text)?;
+            let mut v2: ManifestV2BuildInfo = toml::from_str(&manifest_text)?;
+            if let Some(profile) = profile {
+                v2.apply_profile(profile)?;
+            }
             v2.components
                 .into_iter()
                 .map(|(id, mut c)| {
@@ -32,9 +39,18 @@ pub async fn component_build_configs(
 pub struct ComponentBuildInfo {
     #[serde(default)]
     pub id: String,
+    pub source: Option<toml::Value>,
     pub build: Option<v2::ComponentBuildConfig>,
 }
 
+impl ComponentBuildInfo {
+    /// The path of the component's Wasm file, relative to the application
+    /// directory, if it is a local file.
+    pub fn local_source(&self) -> Option<&str> {
+        self.source.as_ref()?.as_str()
+    }
+}
+
 #[derive(Deserialize)]
 struct ManifestV1BuildInfo {
     #[serde(rename = "component")]
@@ -45,4 +61,59 @@ struct ManifestV1BuildInfo {
 struct ManifestV2BuildInfo {
     #[serde(rename = "component")]
     components: BTreeMap<String, ComponentBuildInfo>,
+    #[serde(rename = "profile", default)]
+    profiles: BTreeMap<String, v2::Profile>,
+}
+
+impl ManifestV2BuildInfo {
+    /// Overlays the build-related settings of the named profile.
+    fn apply_profile(&mut self, name: &str) -> Result<()> {
+        let profile = self
+            .profiles
+            .remove(name)
+            .ok_or_else(|| anyhow!("The manifest has no profile '{name}'"))?;
+        for (id, overlay) in profile.components {
+            let id = id.as_ref();
+            let component = self.components.get_mut(id).ok_or_else(|| {
+                anyhow!("Profile '{name}' refers to component '{id}', which does not exist")
+            })?;
+            // Only the build-related settings of the overlay matter here
+            if let Some(source) = overlay.source {
+                component.source = Some(toml::Value::try_from(source)?);
+            }
+            if overlay.build.is_some() {
+                component.build = overlay.build;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_overlays_build_settings() {
+        let mut manifest: ManifestV2BuildInfo = toml::from_str(
+            r#"
+            [component.api]
+            source = "target/release/api.wasm"
+            build = { command = "cargo build --release" }
+
+            [profile.debug.component.api]
+            source = "target/debug/api.wasm"
+            build = { command = "cargo build" }
+            environment = { RUST_LOG = "debug" }
+            "#,
+        )
+        .unwrap();
+        manifest.apply_profile("debug").unwrap();
+
+        let api = &manifest.components["api"];
+        assert_eq!(Some("target/debug/api.wasm"), api.local_source());
+        assert_eq!("cargo build", api.build.as_ref().unwrap().command);
+
+        manifest.apply_profile("missing").unwrap_err();
+    }
 }
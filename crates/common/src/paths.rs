@@ -36,6 +36,15 @@ pub fn resolve_manifest_file_path(provided_path: impl AsRef<Path>) -> Result<Pat
     }
 }
 
+/// Searches the given directory and its ancestors for a default manifest
+/// file, returning the path to the first one found.
+pub fn search_upwards_for_manifest(dir: impl AsRef<Path>) -> Option<PathBuf> {
+    dir.as_ref()
+        .ancestors()
+        .map(|dir| dir.join(DEFAULT_MANIFEST_FILE))
+        .find(|path| path.is_file())
+}
+
 /// Resolves the parent directory of a path, returning an error if the path
 /// has no parent. A path with a single component will return ".".
 pub fn parent_dir(path: impl AsRef<Path>) -> Result<PathBuf> {
@@ -67,4 +76,20 @@ mod tests {
     fn no_parent_returns_err() {
         parent_dir("").unwrap_err();
     }
+
+    #[test]
+    fn search_finds_manifest_in_ancestor() {
+        let root = tempfile::tempdir().unwrap();
+        let nested = root.path().join("a/b");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(search_upwards_for_manifest(&nested), None);
+
+        let manifest = root.path().join(DEFAULT_MANIFEST_FILE);
+        std::fs::write(&manifest, "").unwrap();
+        assert_eq!(search_upwards_for_manifest(&nested), Some(manifest));
+
+        let nearer = root.path().join("a").join(DEFAULT_MANIFEST_FILE);
+        std::fs::write(&nearer, "").unwrap();
+        assert_eq!(search_upwards_for_manifest(&nested), Some(nearer));
+    }
 }
@@ -17,7 +17,7 @@ pub mod wasi_2023_11_10;
 
 use std::{path::PathBuf, sync::Arc, time::Duration};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use crossbeam_channel::Sender;
 use tracing::instrument;
 use wasmtime::{InstanceAllocationStrategy, PoolingAllocationConfig};
@@ -52,6 +52,9 @@ const WASM_PAGE_SIZE: u64 = 64 * 1024;
 /// This is currently only used for advanced (undocumented) use cases.
 pub struct Config {
     inner: wasmtime::Config,
+    pooling: Option<PoolingAllocatorSettings>,
+    epoch_tick_interval: Duration,
+    fuel: Option<u64>,
 }
 
 impl Config {
@@ -79,10 +82,59 @@ impl Config {
 
     /// Disable the pooling instance allocator.
     pub fn disable_pooling(&mut self) -> &mut Self {
+        self.pooling = None;
         self.inner
             .allocation_strategy(wasmtime::InstanceAllocationStrategy::OnDemand);
         self
     }
+
+    /// Update the settings of the pooling instance allocator. This has no
+    /// effect if pooling has been disabled.
+    pub fn update_pooling(&mut self, f: impl FnOnce(&mut PoolingAllocatorSettings)) -> &mut Self {
+        if let Some(pooling) = &mut self.pooling {
+            f(pooling);
+            self.inner
+                .allocation_strategy(InstanceAllocationStrategy::Pooling(pooling.to_wasmtime()));
+        }
+        self
+    }
+
+    /// Update the settings of the pooling instance allocator from
+    /// `SPIN_WASMTIME_*` environment variables, which are supported as an
+    /// escape valve. This has no effect if pooling has been disabled.
+    pub fn update_pooling_from_env(&mut self) -> Result<()> {
+        if let Some(pooling) = &mut self.pooling {
+            pooling.update_from_env()?;
+            self.inner
+                .allocation_strategy(InstanceAllocationStrategy::Pooling(pooling.to_wasmtime()));
+        }
+        Ok(())
+    }
+
+    /// Set the Cranelift optimization level used when compiling Wasm.
+    pub fn cranelift_opt_level(&mut self, level: wasmtime::OptLevel) -> &mut Self {
+        self.inner.cranelift_opt_level(level);
+        self
+    }
+
+    /// Set the epoch tick interval of engines built with this config. The
+    /// default is [`DEFAULT_EPOCH_TICK_INTERVAL`].
+    ///
+    /// See [`EngineBuilder::epoch_tick_interval`].
+    pub fn epoch_tick_interval(&mut self, interval: Duration) -> &mut Self {
+        self.epoch_tick_interval = interval;
+        self
+    }
+
+    /// Limit the fuel each [`Store`] may consume. Execution traps when a
+    /// store runs out of fuel.
+    ///
+    /// See [`wasmtime::Config::consume_fuel`](https://docs.rs/wasmtime/latest/wasmtime/struct.Config.html#method.consume_fuel).
+    pub fn fuel_per_store(&mut self, fuel: u64) -> &mut Self {
+        self.inner.consume_fuel(true);
+        self.fuel = Some(fuel);
+        self
+    }
 }
 
 impl Default for Config {
@@ -95,49 +147,172 @@ impl Default for Config {
         // By default enable the pooling instance allocator in Wasmtime. This
         // drastically reduces syscall/kernel overhead for wasm execution,
         // especially in async contexts where async stacks must be allocated.
-        // The general goal here is that the default settings here rarely, if
-        // ever, need to be modified. As a result there aren't fine-grained
-        // knobs for each of these settings just yet and instead they're
-        // generally set to defaults. Environment-variable-based fallbacks are
-        // supported though as an escape valve for if this is a problem.
-        let mut pooling_config = PoolingAllocationConfig::default();
-        pooling_config
-            .total_component_instances(env("SPIN_WASMTIME_INSTANCE_COUNT", 1_000))
+        let pooling = PoolingAllocatorSettings::default();
+        inner.allocation_strategy(InstanceAllocationStrategy::Pooling(pooling.to_wasmtime()));
+
+        Self {
+            inner,
+            pooling: Some(pooling),
+            epoch_tick_interval: DEFAULT_EPOCH_TICK_INTERVAL,
+            fuel: None,
+        }
+    }
+}
+
+/// Settings for Wasmtime's pooling instance allocator.
+///
+/// See [`wasmtime::PoolingAllocationConfig`](https://docs.rs/wasmtime/latest/wasmtime/struct.PoolingAllocationConfig.html)
+/// for details of each setting.
+#[derive(Clone, Debug)]
+pub struct PoolingAllocatorSettings {
+    /// The maximum number of component instances which may be allocated at once.
+    pub total_component_instances: u32,
+    /// The maximum size, in bytes, of the host data for a component instance.
+    pub max_component_instance_size: usize,
+    /// The maximum number of core instances in a single component.
+    pub max_core_instances_per_component: u32,
+    /// The maximum number of tables in a single component.
+    pub max_tables_per_component: u32,
+    /// The maximum number of elements in a table.
+    pub table_elements: u32,
+    /// The maximum number of memories in a single component.
+    pub max_memories_per_component: u32,
+    /// The maximum number of memories which may be allocated at once.
+    pub total_memories: u32,
+    /// The maximum number of tables which may be allocated at once.
+    pub total_tables: u32,
+    /// The maximum number of Wasm pages in a linear memory.
+    pub memory_pages: u64,
+    /// The number of bytes of a linear memory kept resident between instantiations.
+    pub linear_memory_keep_resident: usize,
+    /// The number of bytes of a table kept resident between instantiations.
+    pub table_keep_resident: usize,
+}
+
+impl PoolingAllocatorSettings {
+    fn to_wasmtime(&self) -> PoolingAllocationConfig {
+        let mut config = PoolingAllocationConfig::default();
+        config
+            .total_component_instances(self.total_component_instances)
+            .max_component_instance_size(self.max_component_instance_size)
+            .max_core_instances_per_component(self.max_core_instances_per_component)
+            .max_tables_per_component(self.max_tables_per_component)
+            .table_elements(self.table_elements)
+            .max_memories_per_component(self.max_memories_per_component)
+            .total_memories(self.total_memories)
+            .total_tables(self.total_tables)
+            .memory_pages(self.memory_pages)
+            .linear_memory_keep_resident(self.linear_memory_keep_resident)
+            .table_keep_resident(self.table_keep_resident);
+        config
+    }
+
+    /// Overrides settings with the values of any `SPIN_WASMTIME_*`
+    /// environment variables which are set, failing if a value is invalid.
+    pub fn update_from_env(&mut self) -> Result<()> {
+        self.update_from_lookup(|name| std::env::var(name))
+    }
+
+    /// Overrides settings with the values of any `SPIN_WASMTIME_*` variables
+    /// returned by `lookup`, which stands in for [`std::env::var`].
+    pub fn update_from_lookup(
+        &mut self,
+        lookup: impl Fn(&str) -> Result<String, std::env::VarError>,
+    ) -> Result<()> {
+        env(
+            &lookup,
+            "SPIN_WASMTIME_INSTANCE_COUNT",
+            &mut self.total_component_instances,
+        )?;
+        env(
+            &lookup,
+            "SPIN_WASMTIME_INSTANCE_SIZE",
+            &mut self.max_component_instance_size,
+        )?;
+        env(
+            &lookup,
+            "SPIN_WASMTIME_CORE_INSTANCE_COUNT",
+            &mut self.max_core_instances_per_component,
+        )?;
+        env(
+            &lookup,
+            "SPIN_WASMTIME_INSTANCE_TABLES",
+            &mut self.max_tables_per_component,
+        )?;
+        env(
+            &lookup,
+            "SPIN_WASMTIME_INSTANCE_TABLE_ELEMENTS",
+            &mut self.table_elements,
+        )?;
+        env(
+            &lookup,
+            "SPIN_WASMTIME_INSTANCE_MEMORIES",
+            &mut self.max_memories_per_component,
+        )?;
+        env(
+            &lookup,
+            "SPIN_WASMTIME_TOTAL_MEMORIES",
+            &mut self.total_memories,
+        )?;
+        env(
+            &lookup,
+            "SPIN_WASMTIME_TOTAL_TABLES",
+            &mut self.total_tables,
+        )?;
+        return Ok(());
+
+        fn env<T>(
+            lookup: &impl Fn(&str) -> Result<String, std::env::VarError>,
+            name: &str,
+            setting: &mut T,
+        ) -> Result<()>
+        where
+            T: std::str::FromStr,
+            T::Err: std::error::Error + Send + Sync + 'static,
+        {
+            match lookup(name) {
+                Ok(val) => {
+                    *setting = val
+                        .parse()
+                        .with_context(|| format!("Invalid value for env var `{name}`: {val:?}"))?;
+                }
+                Err(std::env::VarError::NotPresent) => {}
+                Err(e) => return Err(e).with_context(|| format!("Invalid env var `{name}`")),
+            }
+            Ok(())
+        }
+    }
+}
+
+impl Default for PoolingAllocatorSettings {
+    // The general goal here is that the default settings rarely, if ever, need
+    // to be modified. Runtime config can override them, and environment
+    // variables are supported as an escape valve (see `update_from_env`).
+    fn default() -> Self {
+        Self {
+            total_component_instances: 1_000,
             // This number accounts for internal data structures that Wasmtime allocates for each instance.
             // Instance allocation is proportional to the number of "things" in a wasm module like functions,
             // globals, memories, etc. Instance allocations are relatively small and are largely inconsequential
             // compared to other runtime state, but a number needs to be chosen here so a relatively large threshold
             // of 10MB is arbitrarily chosen. It should be unlikely that any reasonably-sized module hits this limit.
-            .max_component_instance_size(
-                env("SPIN_WASMTIME_INSTANCE_SIZE", (10 * MB) as u32) as usize
-            )
-            .max_core_instances_per_component(env("SPIN_WASMTIME_CORE_INSTANCE_COUNT", 200))
-            .max_tables_per_component(env("SPIN_WASMTIME_INSTANCE_TABLES", 20))
-            .table_elements(env("SPIN_WASMTIME_INSTANCE_TABLE_ELEMENTS", 30_000))
+            max_component_instance_size: (10 * MB) as usize,
+            max_core_instances_per_component: 200,
+            max_tables_per_component: 20,
+            table_elements: 30_000,
             // The number of memories an instance can have effectively limits the number of inner components
             // a composed component can have (since each inner component has its own memory). We default to 32 for now, and
             // we'll see how often this limit gets reached.
-            .max_memories_per_component(env("SPIN_WASMTIME_INSTANCE_MEMORIES", 32))
-            .total_memories(env("SPIN_WASMTIME_TOTAL_MEMORIES", 1_000))
-            .total_tables(env("SPIN_WASMTIME_TOTAL_TABLES", 2_000))
+            max_memories_per_component: 32,
+            total_memories: 1_000,
+            total_tables: 2_000,
             // Nothing is lost from allowing the maximum size of memory for
             // all instance as it's still limited through other the normal
             // `StoreLimitsAsync` accounting method too.
-            .memory_pages(4 * GB / WASM_PAGE_SIZE)
+            memory_pages: 4 * GB / WASM_PAGE_SIZE,
             // These numbers are completely arbitrary at something above 0.
-            .linear_memory_keep_resident((2 * MB) as usize)
-            .table_keep_resident((MB / 2) as usize);
-        inner.allocation_strategy(InstanceAllocationStrategy::Pooling(pooling_config));
-
-        return Self { inner };
-
-        fn env(name: &str, default: u32) -> u32 {
-            match std::env::var(name) {
-                Ok(val) => val
-                    .parse()
-                    .unwrap_or_else(|e| panic!("failed to parse env var `{name}={val}`: {e}")),
-                Err(_) => default,
-            }
+            linear_memory_keep_resident: (2 * MB) as usize,
+            table_keep_resident: (MB / 2) as usize,
         }
     }
 }
@@ -262,6 +437,7 @@ pub struct EngineBuilder<T> {
     host_components_builder: HostComponentsBuilder,
     epoch_tick_interval: Duration,
     epoch_ticker_thread: bool,
+    fuel: Option<u64>,
 }
 
 impl<T: Send + Sync + OutboundWasiHttpHandler> EngineBuilder<T> {
@@ -280,8 +456,9 @@ impl<T: Send + Sync + OutboundWasiHttpHandler> EngineBuilder<T> {
             linker,
             module_linker,
             host_components_builder: HostComponents::builder(),
-            epoch_tick_interval: DEFAULT_EPOCH_TICK_INTERVAL,
+            epoch_tick_interval: config.epoch_tick_interval,
             epoch_ticker_thread: true,
+            fuel: config.fuel,
         })
     }
 }
@@ -370,6 +547,7 @@ impl<T: Send + Sync> EngineBuilder<T> {
             module_linker: self.module_linker,
             host_components,
             epoch_tick_interval: self.epoch_tick_interval,
+            fuel: self.fuel,
             _epoch_ticker_signal: epoch_ticker_signal,
         }
     }
@@ -383,6 +561,7 @@ pub struct Engine<T> {
     module_linker: ModuleLinker<T>,
     host_components: HostComponents,
     epoch_tick_interval: Duration,
+    fuel: Option<u64>,
     // Matching receiver closes on drop
     _epoch_ticker_signal: Option<Sender<()>>,
 }
@@ -398,6 +577,7 @@ impl<T: OutboundWasiHttpHandler + Send + Sync> Engine<T> {
         StoreBuilder::new(
             self.inner.clone(),
             self.epoch_tick_interval,
+            self.fuel,
             &self.host_components,
             wasi_version,
         )
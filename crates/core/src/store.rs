@@ -136,6 +136,7 @@ impl<T> wasmtime::AsContextMut for Store<T> {
 pub struct StoreBuilder {
     engine: wasmtime::Engine,
     epoch_tick_interval: Duration,
+    fuel: Option<u64>,
     wasi: std::result::Result<WasiCtxBuilder, String>,
     host_components_data: HostComponentsData,
     store_limits: StoreLimitsAsync,
@@ -147,12 +148,14 @@ impl StoreBuilder {
     pub(crate) fn new(
         engine: wasmtime::Engine,
         epoch_tick_interval: Duration,
+        fuel: Option<u64>,
         host_components: &HostComponents,
         wasi: WasiVersion,
     ) -> Self {
         Self {
             engine,
             epoch_tick_interval,
+            fuel,
             wasi: Ok(wasi.into()),
             host_components_data: host_components.new_data(),
             store_limits: StoreLimitsAsync::default(),
@@ -437,6 +440,10 @@ impl StoreBuilder {
         // forever" for any plausible tick interval.
         inner.set_epoch_deadline(u64::MAX / 2);
 
+        if let Some(fuel) = self.fuel {
+            inner.set_fuel(fuel)?;
+        }
+
         Ok(Store {
             inner,
             epoch_tick_interval: self.epoch_tick_interval,
@@ -6,7 +6,8 @@ use std::{
 
 use anyhow::Context;
 use spin_core::{
-    Component, Config, Engine, HostComponent, I32Exit, Store, StoreBuilder, Trap, WasiVersion,
+    Component, Config, Engine, HostComponent, I32Exit, PoolingAllocatorSettings, Store,
+    StoreBuilder, Trap, WasiVersion,
 };
 use tempfile::TempDir;
 use tokio::{fs, io::AsyncWrite};
@@ -131,6 +132,59 @@ async fn test_set_deadline_violated() {
     assert_eq!(trap, Trap::Interrupt);
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_fuel_obeyed() {
+    let engine = test_engine_with_config(|config| {
+        config.fuel_per_store(1_000_000_000);
+    });
+    let stdout = run_core_wasi_test_engine(&engine, ["multiply", "5"], |_| {}, |_| {})
+        .await
+        .unwrap();
+    assert_eq!(stdout, "10");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_fuel_exhausted() {
+    let engine = test_engine_with_config(|config| {
+        config.fuel_per_store(1_000);
+    });
+    let err = run_core_wasi_test_engine(&engine, ["multiply", "5"], |_| {}, |_| {})
+        .await
+        .unwrap_err();
+    let trap = err.downcast::<Trap>().expect("trap");
+    assert_eq!(trap, Trap::OutOfFuel);
+}
+
+#[test]
+fn test_pooling_settings_from_lookup() {
+    let lookup = |name: &str| match name {
+        "SPIN_WASMTIME_INSTANCE_COUNT" => Ok("7".to_owned()),
+        _ => Err(std::env::VarError::NotPresent),
+    };
+    let mut settings = PoolingAllocatorSettings::default();
+    settings.update_from_lookup(lookup).unwrap();
+    assert_eq!(7, settings.total_component_instances);
+    assert_eq!(
+        PoolingAllocatorSettings::default().total_tables,
+        settings.total_tables
+    );
+}
+
+#[test]
+fn test_invalid_pooling_setting_fails() {
+    let lookup = |name: &str| match name {
+        "SPIN_WASMTIME_TOTAL_TABLES" => Ok("lots".to_owned()),
+        _ => Err(std::env::VarError::NotPresent),
+    };
+    let err = PoolingAllocatorSettings::default()
+        .update_from_lookup(lookup)
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("SPIN_WASMTIME_TOTAL_TABLES"),
+        "unexpected error: {err:#}"
+    );
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_host_component() {
     let stdout = run_core_wasi_test(["multiply", "5"], |_| {}).await.unwrap();
@@ -176,7 +230,13 @@ fn test_config() -> Config {
 }
 
 fn test_engine() -> Engine<()> {
-    let mut builder = Engine::builder(&test_config()).unwrap();
+    test_engine_with_config(|_| {})
+}
+
+fn test_engine_with_config(update_config: impl FnOnce(&mut Config)) -> Engine<()> {
+    let mut config = test_config();
+    update_config(&mut config);
+    let mut builder = Engine::builder(&config).unwrap();
     builder.add_host_component(MultiplierHostComponent).unwrap();
     builder
         .link_import(|l, _| wasmtime_wasi::preview2::command::add_to_linker(l))
@@ -14,6 +14,8 @@ pub mod manifest;
 pub mod rustlang;
 /// Test helpers.
 pub mod test;
+/// Diagnoses for Go, JavaScript and Python toolchain problems.
+pub mod toolchain;
 /// Diagnoses for Wasm source problems.
 pub mod wasm;
 
@@ -37,8 +39,13 @@ impl Checkup {
             .add_diagnostic::<manifest::upgrade::UpgradeDiagnostic>()
             .add_diagnostic::<manifest::version::VersionDiagnostic>()
             .add_diagnostic::<manifest::trigger::TriggerDiagnostic>()
+            .add_diagnostic::<manifest::hosts::HostsDiagnostic>()
+            .add_diagnostic::<manifest::variables::VariablesDiagnostic>()
+            .add_diagnostic::<manifest::routes::RoutesDiagnostic>()
             .add_diagnostic::<rustlang::target::TargetDiagnostic>() // Do toolchain checks _before_ build check
-            .add_diagnostic::<wasm::missing::WasmMissingDiagnostic>();
+            .add_diagnostic::<toolchain::missing::ToolchainDiagnostic>()
+            .add_diagnostic::<wasm::missing::WasmMissingDiagnostic>()
+            .add_diagnostic::<wasm::capabilities::CapabilitiesDiagnostic>();
         Ok(checkup)
     }
 
@@ -162,6 +169,14 @@ pub trait Treatment: Sync {
     /// an imperative, e.g. "Upgrade the library".
     fn summary(&self) -> String;
 
+    /// Return true if this fix only edits the app's own files, and so may be
+    /// applied without confirmation (e.g. by `spin doctor --fix`). Return
+    /// false for fixes which run commands or change the user's environment,
+    /// which is the default implementation.
+    fn is_safe(&self) -> bool {
+        false
+    }
+
     /// Return a detailed description of what this fix will do, such as a file
     /// diff or list of commands to be executed.
     ///
@@ -7,13 +7,37 @@ use toml_edit::Document;
 
 use crate::Treatment;
 
+/// Diagnose deprecated component `allowed_http_hosts`.
+pub mod hosts;
+/// Diagnose HTTP routes which shadow each other.
+pub mod routes;
 /// Diagnose app manifest trigger config problems.
 pub mod trigger;
 /// Diagnose old app manifest versions.
 pub mod upgrade;
+/// Diagnose references to undefined app variables.
+pub mod variables;
 /// Diagnose upgradable app manifest versions.
 pub mod version;
 
+/// Returns true if the document is a version 2 app manifest.
+pub(crate) fn is_v2_manifest(doc: &Document) -> bool {
+    doc.get("spin_manifest_version")
+        .and_then(|item| item.as_integer())
+        == Some(2)
+}
+
+/// Returns the `[component.<id>]` table of a version 2 app manifest.
+pub(crate) fn v2_component_mut<'a>(
+    doc: &'a mut Document,
+    component_id: &str,
+) -> Result<&'a mut dyn toml_edit::TableLike> {
+    doc.get_mut("component")
+        .and_then(|components| components.get_mut(component_id))
+        .and_then(|component| component.as_table_like_mut())
+        .with_context(|| format!("missing component {component_id:?}"))
+}
+
 /// ManifestTreatment helps implement [`Treatment`]s for app manifest problems.
 #[async_trait]
 pub trait ManifestTreatment {
@@ -21,6 +45,13 @@ pub trait ManifestTreatment {
     /// an imperative, e.g. "Add default trigger config".
     fn summary(&self) -> String;
 
+    /// Return true if this fix may be applied without confirmation. See
+    /// [`Treatment::is_safe`]. Fixes which grant the app permissions it did
+    /// not have must return false; the default implementation returns true.
+    fn is_safe(&self) -> bool {
+        true
+    }
+
     /// Attempt to fix this problem. See [`Treatment::treat`].
     async fn treat_manifest(&self, doc: &mut Document) -> Result<()>;
 }
@@ -31,6 +62,10 @@ impl<T: ManifestTreatment + Sync> Treatment for T {
         ManifestTreatment::summary(self)
     }
 
+    fn is_safe(&self) -> bool {
+        ManifestTreatment::is_safe(self)
+    }
+
     async fn dry_run(&self, patient: &crate::PatientApp) -> Result<String> {
         let mut after_doc = patient.manifest_doc.clone();
         self.treat_manifest(&mut after_doc).await?;
@@ -0,0 +1,131 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use spin_manifest::compat::convert_allowed_http_to_allowed_hosts;
+use toml_edit::{de::from_document, Array, Document, Item};
+
+use crate::{Diagnosis, Diagnostic, PatientApp, Treatment};
+
+use super::{is_v2_manifest, v2_component_mut, ManifestTreatment};
+
+/// HostsDiagnostic detects components using the deprecated
+/// `allowed_http_hosts` field.
+#[derive(Default)]
+pub struct HostsDiagnostic;
+
+#[async_trait]
+impl Diagnostic for HostsDiagnostic {
+    type Diagnosis = HostsDiagnosis;
+
+    async fn diagnose(&self, patient: &PatientApp) -> Result<Vec<Self::Diagnosis>> {
+        let doc = &patient.manifest_doc;
+        if !is_v2_manifest(doc) {
+            // V1 manifests are upgraded as a whole by the upgrade diagnostic
+            return Ok(vec![]);
+        }
+        let probe: HostsProbe =
+            from_document(doc.clone()).context("failed to decode HostsProbe")?;
+
+        let mut diags = vec![];
+        for (component_id, component) in probe.component {
+            if component.allowed_http_hosts.is_empty() {
+                continue;
+            }
+            let replacement =
+                convert_allowed_http_to_allowed_hosts(&component.allowed_http_hosts, false).ok();
+            diags.push(HostsDiagnosis {
+                component_id,
+                replacement,
+            });
+        }
+        Ok(diags)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct HostsProbe {
+    #[serde(default)]
+    component: indexmap::IndexMap<String, ComponentHostsProbe>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ComponentHostsProbe {
+    #[serde(default)]
+    allowed_http_hosts: Vec<String>,
+}
+
+/// HostsDiagnosis represents a component using the deprecated
+/// `allowed_http_hosts` field.
+#[derive(Debug)]
+pub struct HostsDiagnosis {
+    component_id: String,
+    /// The equivalent `allowed_outbound_hosts`, or None if the existing
+    /// hosts couldn't be converted.
+    replacement: Option<Vec<String>>,
+}
+
+impl Diagnosis for HostsDiagnosis {
+    fn description(&self) -> String {
+        format!(
+            "Component {:?} uses the deprecated field `allowed_http_hosts`",
+            self.component_id
+        )
+    }
+
+    fn is_critical(&self) -> bool {
+        false
+    }
+
+    fn treatment(&self) -> Option<&dyn Treatment> {
+        self.replacement.is_some().then_some(self)
+    }
+}
+
+#[async_trait]
+impl ManifestTreatment for HostsDiagnosis {
+    fn summary(&self) -> String {
+        format!(
+            "Replace `allowed_http_hosts` with `allowed_outbound_hosts` for component {:?}",
+            self.component_id
+        )
+    }
+
+    async fn treat_manifest(&self, doc: &mut Document) -> Result<()> {
+        let replacement = self
+            .replacement
+            .as_ref()
+            .context("allowed_http_hosts cannot be converted")?;
+        let component = v2_component_mut(doc, &self.component_id)?;
+        component.remove("allowed_http_hosts");
+
+        let hosts = component
+            .entry("allowed_outbound_hosts")
+            .or_insert(Item::Value(Array::new().into()))
+            .as_array_mut()
+            .context("existing allowed_outbound_hosts is not an array")?;
+        for host in replacement {
+            if !hosts.iter().any(|existing| existing.as_str() == Some(host)) {
+                hosts.push(host.as_str());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test::{run_broken_test, run_correct_test};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_correct() {
+        run_correct_test::<HostsDiagnostic>("manifest_hosts").await;
+    }
+
+    #[tokio::test]
+    async fn test_allowed_http_hosts() {
+        let diag = run_broken_test::<HostsDiagnostic>("manifest_hosts", "allowed_http_hosts").await;
+        assert_eq!(diag.component_id, "api");
+    }
+}
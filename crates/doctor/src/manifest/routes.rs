@@ -0,0 +1,124 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use spin_http::routes::Router;
+use spin_manifest::{normalize::normalize_manifest, schema::v2::ComponentSpec};
+
+use crate::{Diagnosis, Diagnostic, PatientApp};
+
+/// RoutesDiagnostic detects HTTP routes which are duplicated, and so
+/// shadow each other.
+#[derive(Default)]
+pub struct RoutesDiagnostic;
+
+#[async_trait]
+impl Diagnostic for RoutesDiagnostic {
+    type Diagnosis = RoutesDiagnosis;
+
+    async fn diagnose(&self, patient: &PatientApp) -> Result<Vec<Self::Diagnosis>> {
+        let manifest_str = patient.manifest_doc.to_string();
+        let mut manifest = spin_manifest::manifest_from_str(&manifest_str)?;
+        normalize_manifest(&mut manifest);
+
+        let base = manifest
+            .application
+            .trigger_global_configs
+            .get("http")
+            .and_then(|config| config.get("base"))
+            .and_then(|base| base.as_str())
+            .unwrap_or("/");
+        let base = if base.starts_with('/') {
+            base.to_owned()
+        } else {
+            format!("/{base}")
+        };
+
+        let http_triggers = manifest.triggers.get("http").into_iter().flatten();
+        let component_routes = http_triggers.filter_map(|trigger| {
+            let Some(ComponentSpec::Reference(component_id)) = &trigger.component else {
+                return None;
+            };
+            let route = trigger.config.get("route")?.as_str()?;
+            let host = trigger.config.get("host").and_then(|host| host.as_str());
+            Some((component_id.as_ref(), host, route))
+        });
+
+        let (_, duplicates) = Router::build_with_hosts(&base, component_routes)?;
+        Ok(duplicates
+            .into_iter()
+            .map(|dup| RoutesDiagnosis {
+                host: (!dup.host.is_any()).then(|| dup.host.to_string()),
+                route: dup.route.full_pattern_non_empty().into_owned(),
+                shadowed_id: dup.replaced_id,
+                effective_id: dup.effective_id,
+            })
+            .collect())
+    }
+}
+
+/// RoutesDiagnosis represents an HTTP route which is shadowed by another
+/// component's identical route.
+#[derive(Debug)]
+pub struct RoutesDiagnosis {
+    host: Option<String>,
+    route: String,
+    shadowed_id: String,
+    effective_id: String,
+}
+
+impl Diagnosis for RoutesDiagnosis {
+    fn description(&self) -> String {
+        let host = self.host.as_deref().unwrap_or_default();
+        format!(
+            "Component {:?} route {host}{} is shadowed by component {:?} and will never be used",
+            self.shadowed_id, self.route, self.effective_id
+        )
+    }
+
+    fn is_critical(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test::{assert_single_diagnosis, TestPatient};
+
+    use super::*;
+
+    const MANIFEST: &str = r#"
+        spin_manifest_version = 2
+        [application]
+        name = "routes-test"
+        [[trigger.http]]
+        route = "/api/..."
+        component = "first"
+        [[trigger.http]]
+        route = "/api/..."
+        component = "second"
+        [component.first]
+        source = "first.wasm"
+        [component.second]
+        source = "second.wasm"
+    "#;
+
+    #[tokio::test]
+    async fn test_duplicate_route() {
+        let patient = TestPatient::from_toml_str(MANIFEST);
+        let diag = assert_single_diagnosis::<RoutesDiagnostic>(&patient).await;
+        assert_eq!(diag.shadowed_id, "first");
+        assert_eq!(diag.effective_id, "second");
+        assert!(diag.treatment().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_distinct_hosts() {
+        let manifest = MANIFEST.replacen(
+            "component = \"second\"",
+            "component = \"second\"\nhost = \"example.com\"",
+            1,
+        );
+        let patient = TestPatient::from_toml_str(manifest);
+        let diags = RoutesDiagnostic.diagnose(&patient).await.unwrap();
+        assert!(diags.is_empty(), "expected no diagnoses, got {diags:?}");
+    }
+}
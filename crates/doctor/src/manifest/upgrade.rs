@@ -48,6 +48,11 @@ impl Treatment for UpgradeDiagnosis {
         "Upgrade manifest to version 2".into()
     }
 
+    fn is_safe(&self) -> bool {
+        // The original manifest is backed up
+        true
+    }
+
     async fn treat(&self, patient: &mut PatientApp) -> Result<()> {
         let v1: AppManifestV1 = from_document(patient.manifest_doc.clone())
             .context("failed to decode AppManifestV1")?;
@@ -98,7 +103,7 @@ impl Treatment for UpgradeDiagnosis {
         let v1_backup_path = patient.manifest_path.with_extension("toml.v1_backup");
         std::fs::rename(&patient.manifest_path, &v1_backup_path)
             .context("failed to back up existing manifest")?;
-        println!(
+        eprintln!(
             "Version 1 manifest backed up to {}.",
             quoted_path(&v1_backup_path)
         );
@@ -0,0 +1,124 @@
+use std::collections::BTreeSet;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use spin_manifest::{schema::v2::SnakeId, validate::template_references};
+use toml_edit::{de::from_document, Document, InlineTable, Item, Table};
+
+use crate::{Diagnosis, Diagnostic, PatientApp, Treatment};
+
+use super::{is_v2_manifest, ManifestTreatment};
+
+/// VariablesDiagnostic detects component variables which refer to app
+/// variables that aren't defined.
+#[derive(Default)]
+pub struct VariablesDiagnostic;
+
+#[async_trait]
+impl Diagnostic for VariablesDiagnostic {
+    type Diagnosis = VariablesDiagnosis;
+
+    async fn diagnose(&self, patient: &PatientApp) -> Result<Vec<Self::Diagnosis>> {
+        let doc = &patient.manifest_doc;
+        if !is_v2_manifest(doc) {
+            // V1 manifests are upgraded as a whole by the upgrade diagnostic
+            return Ok(vec![]);
+        }
+        let probe: VariablesProbe =
+            from_document(doc.clone()).context("failed to decode VariablesProbe")?;
+
+        let mut undefined = BTreeSet::new();
+        for component in probe.component.values() {
+            for template in component.variables.values() {
+                // Malformed templates are reported by manifest validation
+                let Ok(references) = template_references(template) else {
+                    continue;
+                };
+                undefined.extend(
+                    references
+                        .into_iter()
+                        .filter(|name| !probe.variables.contains_key(*name))
+                        .map(ToOwned::to_owned),
+                );
+            }
+        }
+        Ok(undefined
+            .into_iter()
+            .map(|name| VariablesDiagnosis { name })
+            .collect())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct VariablesProbe {
+    #[serde(default)]
+    variables: toml::Table,
+    #[serde(default)]
+    component: indexmap::IndexMap<String, ComponentVariablesProbe>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ComponentVariablesProbe {
+    #[serde(default)]
+    variables: indexmap::IndexMap<String, String>,
+}
+
+/// VariablesDiagnosis represents an app variable which is referred to by a
+/// component but not defined.
+#[derive(Debug)]
+pub struct VariablesDiagnosis {
+    name: String,
+}
+
+impl Diagnosis for VariablesDiagnosis {
+    fn description(&self) -> String {
+        format!(
+            "Variable {:?} is referred to by a component but not defined in [variables]",
+            self.name
+        )
+    }
+
+    fn treatment(&self) -> Option<&dyn Treatment> {
+        SnakeId::try_from(self.name.clone()).is_ok().then_some(self)
+    }
+}
+
+#[async_trait]
+impl ManifestTreatment for VariablesDiagnosis {
+    fn summary(&self) -> String {
+        format!("Add required variable {:?} to [variables]", self.name)
+    }
+
+    async fn treat_manifest(&self, doc: &mut Document) -> Result<()> {
+        let variables = doc
+            .entry("variables")
+            .or_insert(Item::Table(Table::new()))
+            .as_table_like_mut()
+            .context("existing variables value is not a table")?;
+        let mut variable = InlineTable::new();
+        variable.insert("required", true.into());
+        variables.insert(&self.name, Item::Value(variable.into()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test::{run_broken_test, run_correct_test};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_correct() {
+        run_correct_test::<VariablesDiagnostic>("manifest_variables").await;
+    }
+
+    #[tokio::test]
+    async fn test_undefined_variable() {
+        let diag =
+            run_broken_test::<VariablesDiagnostic>("manifest_variables", "undefined_variable")
+                .await;
+        assert_eq!(diag.name, "name");
+    }
+}
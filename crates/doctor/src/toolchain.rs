@@ -0,0 +1,200 @@
+/// Diagnose missing or outdated language toolchains.
+pub mod missing;
+
+use std::fmt::Display;
+
+/// A source language whose toolchain can be checked, inferred from a
+/// component's `build.command`. Rust is checked by [`crate::rustlang`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Language {
+    /// Go, built with TinyGo
+    Go,
+    /// JavaScript or TypeScript, built with componentize-js via Node
+    JavaScript,
+    /// Python, built with componentize-py
+    Python,
+}
+
+impl Language {
+    /// Infers the language of a component from its build command, or None
+    /// if the command doesn't use a known toolchain.
+    pub fn infer(build_command: &str) -> Option<Self> {
+        let programs = build_command
+            .split(|c: char| c.is_whitespace() || "&|;()".contains(c))
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>();
+        let uses = |names: &[&str]| programs.iter().any(|word| names.contains(word));
+
+        if uses(&["tinygo"]) {
+            Some(Self::Go)
+        } else if uses(&["componentize-py"]) {
+            Some(Self::Python)
+        } else if uses(&["npm", "npx", "node", "yarn", "pnpm", "js2wasm", "jco"]) {
+            Some(Self::JavaScript)
+        } else {
+            None
+        }
+    }
+
+    /// The tools which must be on the PATH to build components in this
+    /// language, in the order they should be checked.
+    pub fn tools(&self) -> &'static [Tool] {
+        match self {
+            Self::Go => &[GO, TINYGO],
+            Self::JavaScript => &[NODE, NPM],
+            Self::Python => &[PYTHON, COMPONENTIZE_PY],
+        }
+    }
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Go => "Go",
+            Self::JavaScript => "JavaScript",
+            Self::Python => "Python",
+        })
+    }
+}
+
+/// A command-line tool required to build components.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Tool {
+    /// Human-friendly name, e.g. "TinyGo"
+    pub name: &'static str,
+    /// The program to run, e.g. "tinygo"
+    pub program: &'static str,
+    /// Arguments which make the program print its version
+    pub version_args: &'static [&'static str],
+    /// The minimum supported version, if any
+    pub min_version: Option<&'static str>,
+    /// Where to find installation instructions
+    pub install_hint: &'static str,
+    /// The pip package providing the tool, if it can be installed with pip
+    pub pip_package: Option<&'static str>,
+}
+
+const GO: Tool = Tool {
+    name: "Go",
+    program: "go",
+    version_args: &["version"],
+    min_version: Some("1.20"),
+    install_hint: "https://go.dev/doc/install",
+    pip_package: None,
+};
+
+const TINYGO: Tool = Tool {
+    name: "TinyGo",
+    program: "tinygo",
+    version_args: &["version"],
+    min_version: Some("0.28.1"),
+    install_hint: "https://tinygo.org/getting-started/install/",
+    pip_package: None,
+};
+
+const NODE: Tool = Tool {
+    name: "Node.js",
+    program: "node",
+    version_args: &["--version"],
+    min_version: Some("18"),
+    install_hint: "https://nodejs.org/en/download",
+    pip_package: None,
+};
+
+const NPM: Tool = Tool {
+    name: "npm",
+    program: "npm",
+    version_args: &["--version"],
+    min_version: None,
+    install_hint: "https://nodejs.org/en/download",
+    pip_package: None,
+};
+
+const PYTHON: Tool = Tool {
+    name: "Python",
+    program: PYTHON_PROGRAM,
+    version_args: &["--version"],
+    min_version: Some("3.10"),
+    install_hint: "https://www.python.org/downloads/",
+    pip_package: None,
+};
+
+const COMPONENTIZE_PY: Tool = Tool {
+    name: "componentize-py",
+    program: "componentize-py",
+    version_args: &["--version"],
+    min_version: Some("0.7.0"),
+    install_hint: "https://pypi.org/project/componentize-py/",
+    pip_package: Some("componentize-py"),
+};
+
+#[cfg(not(windows))]
+const PYTHON_PROGRAM: &str = "python3";
+#[cfg(windows)]
+const PYTHON_PROGRAM: &str = "python";
+
+/// Parses the first version number in a tool's `--version` output, e.g.
+/// "1.21.3" from "go version go1.21.3 linux/amd64". Missing minor and patch
+/// numbers are taken to be zero.
+pub fn parse_version(output: &str) -> Option<semver::Version> {
+    output.split_whitespace().find_map(|word| {
+        let word = word.trim_start_matches(|c: char| !c.is_ascii_digit());
+        let numbers = word
+            .split('.')
+            .map_while(|part| part.parse::<u64>().ok())
+            .collect::<Vec<_>>();
+        match numbers.as_slice() {
+            [] => None,
+            [major] => Some(semver::Version::new(*major, 0, 0)),
+            [major, minor] => Some(semver::Version::new(*major, *minor, 0)),
+            [major, minor, patch, ..] => Some(semver::Version::new(*major, *minor, *patch)),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn infers_language_from_build_command() {
+        let cases = [
+            (
+                "tinygo build -target=wasi -o main.wasm main.go",
+                Some(Language::Go),
+            ),
+            ("npm install && npm run build", Some(Language::JavaScript)),
+            (
+                "componentize-py -w spin-http componentize app -o app.wasm",
+                Some(Language::Python),
+            ),
+            ("cargo build --target wasm32-wasi --release", None),
+            ("make", None),
+        ];
+        for (command, expected) in cases {
+            assert_eq!(Language::infer(command), expected, "{command}");
+        }
+    }
+
+    #[test]
+    fn parses_tool_versions() {
+        let cases = [
+            ("go version go1.21.3 linux/amd64", "1.21.3"),
+            (
+                "tinygo version 0.30.0 linux/amd64 (using go version go1.21.3)",
+                "0.30.0",
+            ),
+            ("v18.17.1", "18.17.1"),
+            ("Python 3.11", "3.11.0"),
+            ("componentize-py 0.7.1", "0.7.1"),
+        ];
+        for (output, expected) in cases {
+            assert_eq!(
+                parse_version(output).unwrap().to_string(),
+                expected,
+                "{output}"
+            );
+        }
+        assert!(parse_version("no version here").is_none());
+    }
+}
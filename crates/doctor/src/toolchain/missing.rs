@@ -0,0 +1,211 @@
+use std::{collections::BTreeMap, path::PathBuf, process::Command};
+
+use anyhow::{bail, ensure, Result};
+use async_trait::async_trait;
+use spin_common::{paths::parent_dir, ui::quoted_path};
+
+use crate::{Diagnosis, Diagnostic, PatientApp, Treatment};
+
+use super::{parse_version, Language, Tool, PYTHON_PROGRAM};
+
+/// ToolchainDiagnostic detects missing or outdated toolchains for the
+/// languages used by an app's components.
+#[derive(Default)]
+pub struct ToolchainDiagnostic;
+
+#[async_trait]
+impl Diagnostic for ToolchainDiagnostic {
+    type Diagnosis = ToolchainDiagnosis;
+
+    async fn diagnose(&self, patient: &PatientApp) -> Result<Vec<Self::Diagnosis>> {
+        let manifest_str = patient.manifest_doc.to_string();
+        let manifest = spin_manifest::manifest_from_str(&manifest_str)?;
+        let app_dir = parent_dir(&patient.manifest_path)?;
+
+        // Check each language's toolchain once, however many components use it
+        let mut languages = BTreeMap::<Language, Vec<PathBuf>>::new();
+        for component in manifest.components.values() {
+            let Some(build) = &component.build else {
+                continue;
+            };
+            if let Some(language) = Language::infer(&build.command) {
+                let workdir = app_dir.join(build.workdir.as_deref().unwrap_or_default());
+                languages.entry(language).or_default().push(workdir);
+            }
+        }
+
+        let mut diags = vec![];
+        for (language, workdirs) in languages {
+            let tool_diags = diagnose_tools(language).await?;
+            let toolchain_ok = tool_diags.is_empty();
+            diags.extend(tool_diags);
+            if language == Language::JavaScript && toolchain_ok {
+                diags.extend(
+                    workdirs
+                        .into_iter()
+                        .filter(|dir| dir.join("package.json").exists())
+                        .filter(|dir| !dir.join("node_modules").exists())
+                        .map(ToolchainDiagnosis::NodeModulesNotInstalled),
+                );
+            }
+        }
+        Ok(diags)
+    }
+}
+
+/// Checks the language's tools in order, stopping at the first problem as
+/// later tools (e.g. TinyGo) may depend on earlier ones (e.g. Go).
+async fn diagnose_tools(language: Language) -> Result<Vec<ToolchainDiagnosis>> {
+    for tool in language.tools() {
+        let output = tokio::process::Command::new(tool.program)
+            .args(tool.version_args)
+            .output()
+            .await;
+        let output = match output {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(vec![ToolchainDiagnosis::ToolNotInstalled(language, tool)]);
+            }
+            Err(e) => bail!(
+                "Failed to run `{} {}`: {e:#}",
+                tool.program,
+                tool.version_args.join(" ")
+            ),
+            Ok(output) => output,
+        };
+        let Some(min_version) = tool.min_version.and_then(parse_version) else {
+            continue;
+        };
+        // Some tools print their version to stderr
+        let version_output = format!(
+            "{}\n{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        match parse_version(&version_output) {
+            Some(version) if version < min_version => {
+                return Ok(vec![ToolchainDiagnosis::ToolOutdated(
+                    language,
+                    tool,
+                    version.to_string(),
+                )]);
+            }
+            Some(_) => (),
+            None => terminal::warn!(
+                "Spin Doctor can't determine which version of {} is installed.",
+                tool.name
+            ),
+        }
+    }
+    Ok(vec![])
+}
+
+/// ToolchainDiagnosis represents a problem with a language toolchain.
+#[derive(Debug)]
+pub enum ToolchainDiagnosis {
+    /// A required tool is not on the PATH
+    ToolNotInstalled(Language, &'static Tool),
+    /// A required tool is older than the minimum supported version; holds
+    /// the installed version
+    ToolOutdated(Language, &'static Tool, String),
+    /// A JavaScript component's npm dependencies haven't been installed;
+    /// holds the component's build directory
+    NodeModulesNotInstalled(PathBuf),
+}
+
+impl ToolchainDiagnosis {
+    fn pip_install_cmd(&self) -> Option<Command> {
+        let (tool, upgrade) = match self {
+            Self::ToolNotInstalled(_, tool) => (tool, false),
+            Self::ToolOutdated(_, tool, _) => (tool, true),
+            Self::NodeModulesNotInstalled(_) => return None,
+        };
+        let package = tool.pip_package?;
+        let mut cmd = Command::new(PYTHON_PROGRAM);
+        cmd.args(["-m", "pip", "install"]);
+        if upgrade {
+            cmd.arg("--upgrade");
+        }
+        cmd.arg(package);
+        Some(cmd)
+    }
+
+    fn npm_install_cmd(&self) -> Option<Command> {
+        let Self::NodeModulesNotInstalled(dir) = self else {
+            return None;
+        };
+        let mut cmd = Command::new("npm");
+        cmd.arg("install").current_dir(dir);
+        Some(cmd)
+    }
+
+    fn treatment_cmd(&self) -> Option<Command> {
+        self.pip_install_cmd().or_else(|| self.npm_install_cmd())
+    }
+}
+
+impl Diagnosis for ToolchainDiagnosis {
+    fn description(&self) -> String {
+        match self {
+            Self::ToolNotInstalled(language, tool) => format!(
+                "{} ('{}') is required to build {language} components but isn't installed. See {} for installation instructions.",
+                tool.name, tool.program, tool.install_hint
+            ),
+            Self::ToolOutdated(language, tool, version) => format!(
+                "{} {version} is installed, but building {language} components requires version {} or later. See {} for installation instructions.",
+                tool.name,
+                tool.min_version.unwrap_or_default(),
+                tool.install_hint
+            ),
+            Self::NodeModulesNotInstalled(dir) => format!(
+                "The npm dependencies in {} haven't been installed",
+                quoted_path(dir)
+            ),
+        }
+    }
+
+    fn treatment(&self) -> Option<&dyn Treatment> {
+        self.treatment_cmd().is_some().then_some(self)
+    }
+}
+
+#[async_trait]
+impl Treatment for ToolchainDiagnosis {
+    fn summary(&self) -> String {
+        match self {
+            Self::ToolNotInstalled(_, tool) => format!("Install {} with pip", tool.name),
+            Self::ToolOutdated(_, tool, _) => format!("Upgrade {} with pip", tool.name),
+            Self::NodeModulesNotInstalled(_) => "Run `npm install`".into(),
+        }
+    }
+
+    async fn dry_run(&self, _patient: &PatientApp) -> Result<String> {
+        let Some(cmd) = self.treatment_cmd() else {
+            bail!("cannot be fixed");
+        };
+        let args = cmd
+            .get_args()
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut message = format!(
+            "Run the following command:\n    `{} {args}`",
+            cmd.get_program().to_string_lossy()
+        );
+        if let Some(dir) = cmd.get_current_dir() {
+            message.push_str(&format!("\nin {}", quoted_path(dir)));
+        }
+        Ok(message)
+    }
+
+    async fn treat(&self, _patient: &mut PatientApp) -> Result<()> {
+        let Some(mut cmd) = self.treatment_cmd() else {
+            bail!("cannot be fixed");
+        };
+        let status = cmd.status()?;
+        ensure!(
+            status.success(),
+            "Installation command {cmd:?} failed: {status:?}"
+        );
+        Ok(())
+    }
+}
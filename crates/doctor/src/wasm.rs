@@ -1,3 +1,5 @@
+/// Diagnose mismatches between Wasm imports and manifest grants.
+pub mod capabilities;
 /// Diagnose missing Wasm sources.
 pub mod missing;
 
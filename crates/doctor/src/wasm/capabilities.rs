@@ -0,0 +1,309 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use toml_edit::{Array, Document, Item};
+
+use crate::{
+    manifest::{is_v2_manifest, v2_component_mut, ManifestTreatment},
+    Diagnosis, PatientApp, Treatment,
+};
+
+use super::{PatientWasm, WasmDiagnostic};
+
+/// CapabilitiesDiagnostic detects mismatches between the Spin interfaces a
+/// component's Wasm imports and the resources its manifest grants it.
+#[derive(Default)]
+pub struct CapabilitiesDiagnostic;
+
+#[async_trait]
+impl WasmDiagnostic for CapabilitiesDiagnostic {
+    type Diagnosis = CapabilitiesDiagnosis;
+
+    async fn diagnose_wasm(
+        &self,
+        app: &PatientApp,
+        wasm: PatientWasm,
+    ) -> Result<Vec<Self::Diagnosis>> {
+        if !is_v2_manifest(&app.manifest_doc) {
+            // Treatments edit V2 component tables
+            return Ok(vec![]);
+        }
+        // Missing Wasm is diagnosed elsewhere; unparseable Wasm can't be checked
+        let Some(imports) = wasm
+            .abs_source_path()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|bytes| import_names(&bytes).ok())
+        else {
+            return Ok(vec![]);
+        };
+
+        let mut diags = vec![];
+        for capability in Capability::ALL {
+            let imported = imports.iter().any(|name| capability.is_imported_as(name));
+            let granted = capability.is_granted(&wasm);
+            let problem = match (imported, granted) {
+                (true, false) => CapabilityProblem::Missing,
+                (false, true) => CapabilityProblem::Unused,
+                _ => continue,
+            };
+            diags.push(CapabilitiesDiagnosis {
+                component_id: wasm.component_id().to_owned(),
+                capability,
+                problem,
+            });
+        }
+        Ok(diags)
+    }
+}
+
+/// Returns the names of the interfaces imported by a Wasm module or
+/// component, including those of any nested modules.
+fn import_names(wasm: &[u8]) -> Result<HashSet<String>> {
+    let mut names = HashSet::new();
+    for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+        match payload? {
+            wasmparser::Payload::ImportSection(imports) => {
+                for import in imports {
+                    names.insert(import?.module.to_owned());
+                }
+            }
+            wasmparser::Payload::ComponentImportSection(imports) => {
+                for import in imports {
+                    names.insert(import?.name.0.to_owned());
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(names)
+}
+
+/// A runtime resource which must be granted to a component in the manifest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Capability {
+    /// Key-value stores
+    KeyValue,
+    /// SQLite databases
+    Sqlite,
+    /// LLM inferencing
+    Llm,
+    /// Vector stores
+    VectorStore,
+}
+
+impl Capability {
+    const ALL: [Self; 4] = [Self::KeyValue, Self::Sqlite, Self::Llm, Self::VectorStore];
+
+    /// The (unversioned) names of the Spin interfaces used to access the
+    /// resource, the main one first.
+    fn interfaces(&self) -> &'static [&'static str] {
+        match self {
+            Self::KeyValue => &["key-value"],
+            Self::Sqlite => &["sqlite"],
+            Self::Llm => &["llm", "llm-streaming"],
+            Self::VectorStore => &["vector-store"],
+        }
+    }
+
+    fn interface(&self) -> &'static str {
+        self.interfaces()[0]
+    }
+
+    /// Returns true if the import name is one of the resource's interfaces,
+    /// such as `fermyon:spin/key-value` or `fermyon:spin/key-value@2.0.0`.
+    fn is_imported_as(&self, import_name: &str) -> bool {
+        let Some(interface) = import_name.strip_prefix("fermyon:spin/") else {
+            return false;
+        };
+        let interface = interface
+            .split_once('@')
+            .map_or(interface, |(name, _version)| name);
+        self.interfaces().contains(&interface)
+    }
+
+    /// The component manifest field which grants access to the resource.
+    fn field(&self) -> &'static str {
+        match self {
+            Self::KeyValue => "key_value_stores",
+            Self::Sqlite => "sqlite_databases",
+            Self::Llm => "ai_models",
+            Self::VectorStore => "vector_stores",
+        }
+    }
+
+    fn is_granted(&self, wasm: &PatientWasm) -> bool {
+        let component = &wasm.component;
+        match self {
+            Self::KeyValue => !component.key_value_stores.is_empty(),
+            Self::Sqlite => !component.sqlite_databases.is_empty(),
+            Self::Llm => !component.ai_models.is_empty(),
+            Self::VectorStore => !component.vector_stores.is_empty(),
+        }
+    }
+}
+
+/// How a component's grant of a [`Capability`] differs from its use.
+#[derive(Debug, PartialEq, Eq)]
+pub enum CapabilityProblem {
+    /// The Wasm imports the interface but the manifest grants no resources
+    Missing,
+    /// The manifest grants resources but the Wasm doesn't import the interface
+    Unused,
+}
+
+/// CapabilitiesDiagnosis represents a mismatch between a component's imports
+/// and its manifest grants.
+#[derive(Debug)]
+pub struct CapabilitiesDiagnosis {
+    component_id: String,
+    capability: Capability,
+    problem: CapabilityProblem,
+}
+
+impl Diagnosis for CapabilitiesDiagnosis {
+    fn description(&self) -> String {
+        let id = &self.component_id;
+        let interface = self.capability.interface();
+        let field = self.capability.field();
+        match self.problem {
+            CapabilityProblem::Missing => format!(
+                "Component {id:?} imports the {interface} interface but its `{field}` is empty, so any accesses will be denied"
+            ),
+            CapabilityProblem::Unused => format!(
+                "Component {id:?} is granted `{field}` but does not use the {interface} interface"
+            ),
+        }
+    }
+
+    fn is_critical(&self) -> bool {
+        // Importing an interface doesn't mean the component uses it: for
+        // example, componentize-js and componentize-py import the whole
+        // platform world
+        false
+    }
+
+    fn treatment(&self) -> Option<&dyn Treatment> {
+        match (&self.problem, self.capability) {
+            // There is no default model to grant
+            (CapabilityProblem::Missing, Capability::Llm) => None,
+            _ => Some(self),
+        }
+    }
+}
+
+#[async_trait]
+impl ManifestTreatment for CapabilitiesDiagnosis {
+    fn summary(&self) -> String {
+        let id = &self.component_id;
+        let field = self.capability.field();
+        match self.problem {
+            CapabilityProblem::Missing => {
+                format!("Set `{field} = [\"default\"]` for component {id:?}")
+            }
+            CapabilityProblem::Unused => format!("Remove `{field}` from component {id:?}"),
+        }
+    }
+
+    fn is_safe(&self) -> bool {
+        // Granting access to a resource must be confirmed by the user
+        self.problem == CapabilityProblem::Unused
+    }
+
+    async fn treat_manifest(&self, doc: &mut Document) -> Result<()> {
+        let component = v2_component_mut(doc, &self.component_id)?;
+        let field = self.capability.field();
+        match (&self.problem, self.capability) {
+            (CapabilityProblem::Missing, Capability::Llm) => bail!("cannot be fixed"),
+            (CapabilityProblem::Missing, _) => {
+                let mut default = Array::new();
+                default.push("default");
+                component.insert(field, Item::Value(default.into()));
+            }
+            (CapabilityProblem::Unused, _) => {
+                component.remove(field);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test::{assert_single_diagnosis, TestPatient},
+        Diagnostic,
+    };
+
+    use super::*;
+
+    const KV_COMPONENT: &str = r#"(component (import "fermyon:spin/key-value" (instance)))"#;
+
+    fn patient_with_wasm(wasm: &[u8], grants: &str) -> (TestPatient, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let wasm_path = dir.path().join("test.wasm");
+        std::fs::write(&wasm_path, wasm).unwrap();
+        let manifest = format!(
+            r#"
+            spin_manifest_version = 2
+            [application]
+            name = "capabilities-test"
+            [[trigger.http]]
+            route = "/..."
+            component = "test"
+            [component.test]
+            source = {wasm_path:?}
+            {grants}
+            "#
+        );
+        (TestPatient::from_toml_str(manifest), dir)
+    }
+
+    #[tokio::test]
+    async fn test_missing_grant() {
+        let wasm = wat::parse_str(KV_COMPONENT).unwrap();
+        let (mut patient, _dir) = patient_with_wasm(&wasm, "");
+        let diag = assert_single_diagnosis::<CapabilitiesDiagnostic>(&patient).await;
+        assert_eq!(diag.capability, Capability::KeyValue);
+        assert_eq!(diag.problem, CapabilityProblem::Missing);
+        assert!(!diag.treatment().unwrap().is_safe());
+
+        diag.treatment().unwrap().treat(&mut patient).await.unwrap();
+        let diags = CapabilitiesDiagnostic.diagnose(&patient).await.unwrap();
+        assert!(diags.is_empty(), "expected no diagnoses, got {diags:?}");
+    }
+
+    #[test]
+    fn test_import_names_match_exactly() {
+        assert!(Capability::KeyValue.is_imported_as("fermyon:spin/key-value"));
+        assert!(Capability::KeyValue.is_imported_as("fermyon:spin/key-value@2.0.0"));
+        assert!(Capability::Llm.is_imported_as("fermyon:spin/llm-streaming@2.1.0"));
+        assert!(!Capability::Llm.is_imported_as("example:llm/llm@1.0.0"));
+        assert!(!Capability::Llm.is_imported_as("fermyon:spin/llmx"));
+        assert!(!Capability::Sqlite.is_imported_as("fermyon:spin/sqlite-extra@2.0.0"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_grant_is_not_critical() {
+        let wasm = wat::parse_str(KV_COMPONENT).unwrap();
+        let (patient, _dir) = patient_with_wasm(&wasm, "");
+        let diag = assert_single_diagnosis::<CapabilitiesDiagnostic>(&patient).await;
+        assert!(!diag.is_critical());
+    }
+
+    #[tokio::test]
+    async fn test_unused_grant() {
+        let wasm = wat::parse_str(KV_COMPONENT).unwrap();
+        let (patient, _dir) = patient_with_wasm(&wasm, r#"sqlite_databases = ["default"]"#);
+        let diags = CapabilitiesDiagnostic.diagnose(&patient).await.unwrap();
+        assert!(diags
+            .iter()
+            .any(|diag| diag.capability == Capability::Sqlite
+                && diag.problem == CapabilityProblem::Unused));
+        assert!(diags.iter().all(|diag| diag.treatment().is_some()));
+        assert!(diags
+            .iter()
+            .filter(|diag| diag.problem == CapabilityProblem::Unused)
+            .all(|diag| diag.treatment().unwrap().is_safe()));
+    }
+}
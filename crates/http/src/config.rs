@@ -8,6 +8,11 @@ pub struct HttpTriggerConfig {
     pub component: String,
     /// HTTP route the component will be invoked for
     pub route: String,
+    /// Host the component will be invoked for. This may be an exact host
+    /// name, or a wildcard such as `*.example.com`. If not specified, the
+    /// component is invoked for any host.
+    #[serde(default)]
+    pub host: Option<String>,
     /// The HTTP executor the component requires
     #[serde(default)]
     pub executor: Option<HttpExecutorType>,
@@ -76,4 +81,24 @@ mod tests {
         assert_eq!(config.entrypoint, "_start");
         assert_eq!(config.argv, "${SCRIPT_NAME} ${ARGS}");
     }
+
+    #[test]
+    fn host_config_smoke_test() {
+        let config: HttpTriggerConfig = toml::toml! {
+            component = "test"
+            route = "/..."
+            host = "*.example.com"
+        }
+        .try_into()
+        .unwrap();
+        assert_eq!(config.host.as_deref(), Some("*.example.com"));
+
+        let config: HttpTriggerConfig = toml::toml! {
+            component = "test"
+            route = "/..."
+        }
+        .try_into()
+        .unwrap();
+        assert!(config.host.is_none());
+    }
 }
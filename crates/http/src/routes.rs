@@ -2,7 +2,7 @@
 
 #![deny(missing_docs)]
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use http::Uri;
 use indexmap::IndexMap;
 use std::{borrow::Cow, fmt};
@@ -10,12 +10,16 @@ use std::{borrow::Cow, fmt};
 /// Router for the HTTP trigger.
 #[derive(Clone, Debug)]
 pub struct Router {
-    /// Ordered map between a path and the component ID that should handle it.
-    pub(crate) routes: IndexMap<RoutePattern, String>,
+    /// Ordered map between a host pattern and the routes served for that host.
+    /// Each route set is an ordered map between a path and the component ID
+    /// that should handle it.
+    pub(crate) routes: IndexMap<HostPattern, IndexMap<RoutePattern, String>>,
 }
 
 /// A detected duplicate route.
 pub struct DuplicateRoute {
+    /// The host pattern of the duplicated route.
+    pub host: HostPattern,
     /// The duplicated route pattern.
     pub route: RoutePattern,
     /// The raw route that was duplicated.
@@ -25,25 +29,45 @@ pub struct DuplicateRoute {
 }
 
 impl Router {
-    /// Builds a router based on application configuration.
+    /// Builds a router based on application configuration, where no
+    /// component is restricted to a host.
     pub fn build<'a>(
         base: &str,
         component_routes: impl IntoIterator<Item = (&'a str, &'a str)>,
     ) -> Result<(Self, Vec<DuplicateRoute>)> {
-        let mut routes = IndexMap::new();
+        Self::build_with_hosts(
+            base,
+            component_routes
+                .into_iter()
+                .map(|(component_id, route)| (component_id, None, route)),
+        )
+    }
+
+    /// Builds a router based on application configuration, where each
+    /// component route may optionally be restricted to a host pattern.
+    pub fn build_with_hosts<'a>(
+        base: &str,
+        component_routes: impl IntoIterator<Item = (&'a str, Option<&'a str>, &'a str)>,
+    ) -> Result<(Self, Vec<DuplicateRoute>)> {
+        let mut routes: IndexMap<HostPattern, IndexMap<RoutePattern, String>> = IndexMap::new();
         let mut duplicates = vec![];
 
-        let routes_iter = component_routes.into_iter().map(|(component_id, route)| {
-            (RoutePattern::from(base, route), component_id.to_string())
-        });
+        for (component_id, host, route) in component_routes {
+            let host = HostPattern::parse(host)
+                .with_context(|| format!("Invalid host for component {component_id}"))?;
+            let route = RoutePattern::from(base, route);
+            let component_id = component_id.to_string();
 
-        for (route, component_id) in routes_iter {
-            let replaced = routes.insert(route.clone(), component_id.clone());
+            let replaced = routes
+                .entry(host.clone())
+                .or_default()
+                .insert(route.clone(), component_id.clone());
             if let Some(replaced) = replaced {
                 duplicates.push(DuplicateRoute {
-                    route: route.clone(),
+                    host,
+                    route,
                     replaced_id: replaced,
-                    effective_id: component_id.clone(),
+                    effective_id: component_id,
                 });
             }
         }
@@ -51,14 +75,53 @@ impl Router {
         Ok((Self { routes }, duplicates))
     }
 
-    /// Returns the constructed routes.
-    pub fn routes(&self) -> impl Iterator<Item = (&RoutePattern, &String)> {
-        self.routes.iter()
+    /// Returns the constructed routes, together with the host pattern each
+    /// route is served for.
+    pub fn routes(&self) -> impl Iterator<Item = (&HostPattern, &RoutePattern, &String)> {
+        self.routes
+            .iter()
+            .flat_map(|(host, routes)| routes.iter().map(move |(rp, id)| (host, rp, id)))
     }
 
-    /// This returns the component id and route pattern for a matched route.
+    /// This returns the component id and route pattern for a matched route,
+    /// as for a request with no `Host` header.
+    ///
+    /// Only routes that are not restricted to a host are considered; use
+    /// [`Router::route_host_full`] to route requests which name a host.
     pub fn route_full(&self, p: &str) -> Result<(&str, &RoutePattern)> {
-        let matches = self.routes.iter().filter(|(rp, _)| rp.matches(p));
+        self.route_host_full(None, p)
+    }
+
+    /// This returns the component id and route pattern for a matched host
+    /// and route.
+    ///
+    /// Host patterns are tried from most to least specific (exact hosts, then
+    /// wildcard domains from longest to shortest, then routes without a host),
+    /// and the first host pattern with a route matching the path wins.
+    pub fn route_host_full(&self, host: Option<&str>, p: &str) -> Result<(&str, &RoutePattern)> {
+        let host = host.map(HostPattern::normalize);
+
+        let mut candidates = self
+            .routes
+            .iter()
+            .filter(|(hp, _)| hp.matches(host.as_deref()))
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(hp, _)| std::cmp::Reverse(hp.specificity()));
+
+        candidates
+            .into_iter()
+            .find_map(|(_, routes)| Self::best_match(routes, p))
+            .ok_or_else(|| match host {
+                Some(host) => anyhow!("Cannot match route for host {host} and path {p}"),
+                None => anyhow!("Cannot match route for path {p}"),
+            })
+    }
+
+    fn best_match<'a>(
+        routes: &'a IndexMap<RoutePattern, String>,
+        p: &str,
+    ) -> Option<(&'a str, &'a RoutePattern)> {
+        let matches = routes.iter().filter(|(rp, _)| rp.matches(p));
 
         let mut best_match: (Option<&str>, Option<&RoutePattern>, usize) = (None, None, 0); // matched id, pattern and length
 
@@ -66,7 +129,7 @@ impl Router {
             match rp {
                 RoutePattern::Exact(_m) => {
                     // Exact matching routes take precedence over wildcard matches.
-                    return Ok((id, rp));
+                    return Some((id, rp));
                 }
                 RoutePattern::Wildcard(m) => {
                     // Check and find longest matching prefix of wildcard pattern.
@@ -80,11 +143,11 @@ impl Router {
 
         let (id, rp, _) = best_match;
         id.zip(rp)
-            .ok_or_else(|| anyhow!("Cannot match route for path {p}"))
     }
 
     /// This returns the component ID that should handle the given path, or an error
-    /// if no component matches.
+    /// if no component matches. As with [`Router::route_full`], only
+    /// routes that are not restricted to a host are considered.
     ///
     /// If multiple components could potentially handle the same request based on their
     /// defined routes, components with matching exact routes take precedence followed
@@ -92,6 +155,111 @@ impl Router {
     pub fn route(&self, p: &str) -> Result<&str> {
         self.route_full(p).map(|(r, _)| r)
     }
+
+    /// This returns the component ID that should handle the given host and path,
+    /// or an error if no component matches.
+    ///
+    /// See [`Router::route_host_full`] for how host patterns are prioritised.
+    pub fn route_host(&self, host: Option<&str>, p: &str) -> Result<&str> {
+        self.route_host_full(host, p).map(|(r, _)| r)
+    }
+}
+
+/// Host patterns for HTTP components.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum HostPattern {
+    /// A host pattern that matches any host. This is used for routes that
+    /// do not specify a host.
+    #[default]
+    Any,
+    /// A host pattern that only matches the exact host name given.
+    Exact(String),
+    /// A host pattern that matches any subdomain of the given domain.
+    Wildcard(String),
+}
+
+impl HostPattern {
+    /// Returns a HostPattern given an optional host from trigger configuration.
+    ///
+    /// A host of the form `*.example.com` matches any subdomain of `example.com`
+    /// (but not `example.com` itself).
+    pub fn parse(host: Option<&str>) -> Result<Self> {
+        let Some(host) = host else {
+            return Ok(Self::Any);
+        };
+        let host = Self::normalize(host);
+        if host.is_empty() {
+            bail!("Host must not be empty");
+        }
+        if host.contains('/') {
+            bail!("Host '{host}' must not contain a path");
+        }
+        match host.strip_prefix("*.") {
+            Some(domain) if domain.is_empty() || domain.contains('*') => {
+                bail!("Host '{host}' must be a host name or of the form '*.example.com'")
+            }
+            Some(domain) => Ok(Self::Wildcard(domain.to_owned())),
+            None if host.contains('*') => {
+                bail!("Host '{host}' must be a host name or of the form '*.example.com'")
+            }
+            None => Ok(Self::Exact(host)),
+        }
+    }
+
+    /// Returns true if the given request host can be handled by the host
+    /// pattern. A request with no host is only handled by [`HostPattern::Any`].
+    pub fn matches(&self, host: Option<&str>) -> bool {
+        match (self, host) {
+            (Self::Any, _) => true,
+            (_, None) => false,
+            (Self::Exact(pattern), Some(host)) => pattern == host,
+            (Self::Wildcard(domain), Some(host)) => host
+                .strip_suffix(domain.as_str())
+                .map(|prefix| prefix.len() > 1 && prefix.ends_with('.'))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns true if the pattern matches any host.
+    pub fn is_any(&self) -> bool {
+        matches!(self, Self::Any)
+    }
+
+    /// Normalizes a host for comparison: the port and any trailing dot are
+    /// stripped, and the host is lowercased.
+    pub fn normalize(host: &str) -> String {
+        let host = match host.strip_prefix('[') {
+            // IPv6 literal, possibly followed by a port
+            Some(rest) => rest
+                .split_once(']')
+                .map(|(addr, _)| format!("[{addr}]"))
+                .unwrap_or_else(|| host.to_owned()),
+            None => match host.rsplit_once(':') {
+                Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name.to_owned(),
+                _ => host.to_owned(),
+            },
+        };
+        host.trim_end_matches('.').to_ascii_lowercase()
+    }
+
+    /// Orders host patterns so that more specific patterns sort higher.
+    fn specificity(&self) -> (u8, usize) {
+        match self {
+            Self::Any => (0, 0),
+            Self::Wildcard(domain) => (1, domain.len()),
+            Self::Exact(host) => (2, host.len()),
+        }
+    }
+}
+
+impl fmt::Display for HostPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self {
+            HostPattern::Any => write!(f, "*"),
+            HostPattern::Exact(host) => write!(f, "{}", host),
+            HostPattern::Wildcard(domain) => write!(f, "*.{}", domain),
+        }
+    }
 }
 
 /// Route patterns for HTTP components.
@@ -207,6 +375,12 @@ mod route_tests {
 
     use super::*;
 
+    fn path_router(routes: IndexMap<RoutePattern, String>) -> Router {
+        Router {
+            routes: [(HostPattern::Any, routes)].into_iter().collect(),
+        }
+    }
+
     #[test]
     fn test_exact_route() {
         init_tracing();
@@ -322,7 +496,7 @@ mod route_tests {
         routes.insert(RoutePattern::from("/", "/foo"), "foo".to_string());
         routes.insert(RoutePattern::from("/", "/foo/bar"), "foobar".to_string());
 
-        let r = Router { routes };
+        let r = path_router(routes);
 
         assert_eq!(r.route("/foo")?, "foo".to_string());
         assert_eq!(r.route("/foo/bar")?, "foobar".to_string());
@@ -335,7 +509,7 @@ mod route_tests {
             "foobar".to_string(),
         );
 
-        let r = Router { routes };
+        let r = path_router(routes);
 
         assert_eq!(r.route("/base/foo")?, "foo".to_string());
         assert_eq!(r.route("/base/foo/bar")?, "foobar".to_string());
@@ -344,7 +518,7 @@ mod route_tests {
 
         routes.insert(RoutePattern::from("/", "/..."), "all".to_string());
 
-        let r = Router { routes };
+        let r = path_router(routes);
 
         assert_eq!(r.route("/foo/bar")?, "all".to_string());
         assert_eq!(r.route("/abc/")?, "all".to_string());
@@ -369,7 +543,7 @@ mod route_tests {
             "onetwothree_wildcard".to_string(),
         );
 
-        let r = Router { routes };
+        let r = path_router(routes);
 
         assert_eq!(
             r.route("/one/two/three/four")?,
@@ -391,7 +565,7 @@ mod route_tests {
             "one_wildcard".to_string(),
         );
 
-        let r = Router { routes };
+        let r = path_router(routes);
 
         assert_eq!(
             r.route("/one/two/three/four")?,
@@ -405,7 +579,7 @@ mod route_tests {
 
         routes.insert(RoutePattern::from("/", "/..."), "wildcard".to_string());
 
-        let r = Router { routes };
+        let r = path_router(routes);
 
         assert_eq!(r.route("/one")?, "one_exact".to_string(),);
 
@@ -425,7 +599,7 @@ mod route_tests {
         )
         .unwrap();
 
-        assert_eq!(4, routes.routes.len());
+        assert_eq!(4, routes.routes[&HostPattern::Any].len());
         assert_eq!(0, duplicates.len());
     }
 
@@ -442,10 +616,10 @@ mod route_tests {
         )
         .unwrap();
 
-        assert_eq!("/", routes.routes[0]);
-        assert_eq!("/foo", routes.routes[1]);
-        assert_eq!("/bar", routes.routes[2]);
-        assert_eq!("/whee/...", routes.routes[3]);
+        assert_eq!("/", routes.routes[&HostPattern::Any][0]);
+        assert_eq!("/foo", routes.routes[&HostPattern::Any][1]);
+        assert_eq!("/bar", routes.routes[&HostPattern::Any][2]);
+        assert_eq!("/whee/...", routes.routes[&HostPattern::Any][3]);
     }
 
     #[test]
@@ -461,7 +635,7 @@ mod route_tests {
         )
         .unwrap();
 
-        assert_eq!(3, routes.routes.len());
+        assert_eq!(3, routes.routes[&HostPattern::Any].len());
         assert_eq!(1, duplicates.len());
     }
 
@@ -478,8 +652,111 @@ mod route_tests {
         )
         .unwrap();
 
-        assert_eq!("second /foo", routes.routes[1]);
+        assert_eq!("second /foo", routes.routes[&HostPattern::Any][1]);
         assert_eq!("first /foo", duplicates[0].replaced_id);
         assert_eq!("second /foo", duplicates[0].effective_id);
     }
+
+    #[test]
+    fn test_host_pattern() -> Result<()> {
+        assert_eq!(HostPattern::parse(None)?, HostPattern::Any);
+        assert_eq!(
+            HostPattern::parse(Some("Example.COM"))?,
+            HostPattern::Exact("example.com".to_owned())
+        );
+        assert_eq!(
+            HostPattern::parse(Some("*.example.com"))?,
+            HostPattern::Wildcard("example.com".to_owned())
+        );
+        assert!(HostPattern::parse(Some("")).is_err());
+        assert!(HostPattern::parse(Some("*")).is_err());
+        assert!(HostPattern::parse(Some("foo.*.com")).is_err());
+        assert!(HostPattern::parse(Some("example.com/foo")).is_err());
+
+        let hp = HostPattern::parse(Some("*.example.com"))?;
+        assert!(hp.matches(Some("a.example.com")));
+        assert!(hp.matches(Some("a.b.example.com")));
+        assert!(!hp.matches(Some("example.com")));
+        assert!(!hp.matches(Some("aexample.com")));
+        assert!(!hp.matches(None));
+
+        assert!(HostPattern::Any.matches(None));
+        assert!(HostPattern::Any.matches(Some("example.com")));
+
+        assert_eq!(HostPattern::normalize("Example.com:3000"), "example.com");
+        assert_eq!(HostPattern::normalize("example.com."), "example.com");
+        assert_eq!(HostPattern::normalize("[::1]:3000"), "[::1]");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_host_router() -> Result<()> {
+        let (r, duplicates) = Router::build_with_hosts(
+            "/",
+            vec![
+                ("default", None, "/..."),
+                ("api", Some("api.example.com"), "/..."),
+                ("api-users", Some("api.example.com"), "/users"),
+                ("tenant", Some("*.example.com"), "/..."),
+                ("tenant-deep", Some("*.eu.example.com"), "/..."),
+                ("only-foo", Some("foo.example.com"), "/foo"),
+            ],
+        )?;
+        assert_eq!(0, duplicates.len());
+
+        assert_eq!(r.route_host(Some("api.example.com"), "/bar")?, "api");
+        assert_eq!(
+            r.route_host(Some("API.example.com:3000"), "/users")?,
+            "api-users"
+        );
+        assert_eq!(r.route_host(Some("acme.example.com"), "/bar")?, "tenant");
+        assert_eq!(
+            r.route_host(Some("acme.eu.example.com"), "/")?,
+            "tenant-deep"
+        );
+        assert_eq!(r.route_host(Some("example.com"), "/bar")?, "default");
+        assert_eq!(r.route_host(None, "/bar")?, "default");
+        assert_eq!(r.route("/bar")?, "default");
+
+        // A host-specific route set without a matching path falls back to less
+        // specific host patterns.
+        assert_eq!(r.route_host(Some("foo.example.com"), "/foo")?, "only-foo");
+        assert_eq!(r.route_host(Some("foo.example.com"), "/bar")?, "tenant");
+
+        let (r, _) = Router::build_with_hosts("/", vec![("api", Some("api.example.com"), "/...")])?;
+        assert!(r.route_host(Some("www.example.com"), "/").is_err());
+        assert!(r.route("/").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn duplicate_routes_are_per_host() {
+        let (routes, duplicates) = Router::build_with_hosts(
+            "/",
+            vec![
+                ("first /foo", None, "/foo"),
+                ("a /foo", Some("a.example.com"), "/foo"),
+                ("b /foo", Some("b.example.com"), "/foo"),
+                ("second a /foo", Some("A.example.com"), "/foo"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(3, routes.routes().count());
+        assert_eq!(1, duplicates.len());
+        assert_eq!(
+            HostPattern::Exact("a.example.com".to_owned()),
+            duplicates[0].host
+        );
+        assert_eq!("a /foo", duplicates[0].replaced_id);
+        assert_eq!("second a /foo", duplicates[0].effective_id);
+    }
+
+    #[test]
+    fn invalid_host_is_an_error() {
+        let result = Router::build_with_hosts("/", vec![("bad", Some("*.*.com"), "/foo")]);
+        assert!(result.is_err());
+    }
 }
@@ -11,7 +11,7 @@ use llm::{
 use rand::SeedableRng;
 use spin_common::ui::quoted_path;
 use spin_core::async_trait;
-use spin_llm::{LlmEngine, MODEL_ALL_MINILM_L6_V2};
+use spin_llm::{LlmEngine, StreamingInference, StreamingInferenceEvent, MODEL_ALL_MINILM_L6_V2};
 use spin_world::v2::llm::{self as wasi_llm};
 use std::{
     collections::hash_map::Entry,
@@ -22,6 +22,10 @@ use std::{
 };
 use tokenizers::PaddingParams;
 
+/// The number of generated tokens which may be waiting for a streaming
+/// request's guest to read them before generation pauses.
+const STREAM_BUFFER_TOKENS: usize = 64;
+
 #[derive(Clone)]
 pub struct LocalLlmEngine {
     registry: PathBuf,
@@ -39,58 +43,40 @@ impl LlmEngine for LocalLlmEngine {
         params: wasi_llm::InferencingParams,
     ) -> Result<wasi_llm::InferencingResult, wasi_llm::Error> {
         let model = self.inferencing_model(model).await?;
-        let cfg = InferenceSessionConfig {
-            memory_k_type: ModelKVMemoryType::Float16,
-            memory_v_type: ModelKVMemoryType::Float16,
-            n_batch: 8,
-            n_threads: num_cpus::get(),
-        };
-
-        let mut session = Model::start_session(model.as_ref(), cfg);
-        let inference_params = InferenceParameters {
-            sampler: generate_sampler(params),
-        };
-        let mut rng = rand::rngs::StdRng::from_entropy();
         let mut text = String::new();
-
-        #[cfg(debug_assertions)]
-        {
-            terminal::warn!(
-                "\
-                This is a debug build - running inference might be prohibitively slow\n\
-                You may want to consider switching to the release build"
-            )
-        }
-        let res = session.infer::<Infallible>(
-            model.as_ref(),
-            &mut rng,
-            &llm::InferenceRequest {
-                prompt: prompt.as_str().into(),
-                parameters: &inference_params,
-                play_back_previous_tokens: false,
-                maximum_token_count: Some(params.max_tokens as usize),
-            },
-            &mut Default::default(),
-            |r| {
-                match r {
-                    InferenceResponse::InferredToken(t) => text.push_str(&t),
-                    InferenceResponse::EotToken => return Ok(InferenceFeedback::Halt),
-                    _ => {}
-                };
-                Ok(InferenceFeedback::Continue)
-            },
-        );
-        let stats = res.map_err(|e| {
-            wasi_llm::Error::RuntimeError(format!("Error occurred during inferencing: {e}"))
+        let usage = run_inference(model.as_ref(), &prompt, params, |t| {
+            text.push_str(t);
+            InferenceFeedback::Continue
         })?;
-        let usage = wasi_llm::InferencingUsage {
-            prompt_token_count: stats.prompt_tokens as u32,
-            generated_token_count: (stats.predict_tokens - stats.prompt_tokens) as u32,
-        };
         let response = wasi_llm::InferencingResult { text, usage };
         Ok(response)
     }
 
+    async fn infer_stream(
+        &mut self,
+        model: wasi_llm::InferencingModel,
+        prompt: String,
+        params: wasi_llm::InferencingParams,
+    ) -> Result<StreamingInference, wasi_llm::Error> {
+        let model = self.inferencing_model(model).await?;
+        let (sender, stream) = StreamingInference::channel(STREAM_BUFFER_TOKENS);
+        tokio::task::spawn_blocking(move || {
+            let result = run_inference(model.as_ref(), &prompt, params, |t| {
+                match sender.blocking_send(StreamingInferenceEvent::Chunk(t.to_owned())) {
+                    Ok(()) => InferenceFeedback::Continue,
+                    // The guest has dropped the stream, so stop generating.
+                    Err(_) => InferenceFeedback::Halt,
+                }
+            });
+            let last_event = match result {
+                Ok(usage) => StreamingInferenceEvent::Done(usage),
+                Err(e) => StreamingInferenceEvent::Failed(e),
+            };
+            _ = sender.blocking_send(last_event);
+        });
+        Ok(stream)
+    }
+
     async fn generate_embeddings(
         &mut self,
         model: wasi_llm::EmbeddingModel,
@@ -232,6 +218,60 @@ impl LocalLlmEngine {
     }
 }
 
+/// Runs an inferencing session, passing each generated token to `on_token`
+/// and returning usage information once generation finishes.
+fn run_inference(
+    model: &dyn Model,
+    prompt: &str,
+    params: wasi_llm::InferencingParams,
+    mut on_token: impl FnMut(&str) -> InferenceFeedback,
+) -> Result<wasi_llm::InferencingUsage, wasi_llm::Error> {
+    let cfg = InferenceSessionConfig {
+        memory_k_type: ModelKVMemoryType::Float16,
+        memory_v_type: ModelKVMemoryType::Float16,
+        n_batch: 8,
+        n_threads: num_cpus::get(),
+    };
+
+    let mut session = Model::start_session(model, cfg);
+    let inference_params = InferenceParameters {
+        sampler: generate_sampler(params),
+    };
+    let mut rng = rand::rngs::StdRng::from_entropy();
+
+    #[cfg(debug_assertions)]
+    {
+        terminal::warn!(
+            "\
+            This is a debug build - running inference might be prohibitively slow\n\
+            You may want to consider switching to the release build"
+        )
+    }
+    let res = session.infer::<Infallible>(
+        model,
+        &mut rng,
+        &llm::InferenceRequest {
+            prompt: prompt.into(),
+            parameters: &inference_params,
+            play_back_previous_tokens: false,
+            maximum_token_count: Some(params.max_tokens as usize),
+        },
+        &mut Default::default(),
+        |r| match r {
+            InferenceResponse::InferredToken(t) => Ok(on_token(&t)),
+            InferenceResponse::EotToken => Ok(InferenceFeedback::Halt),
+            _ => Ok(InferenceFeedback::Continue),
+        },
+    );
+    let stats = res.map_err(|e| {
+        wasi_llm::Error::RuntimeError(format!("Error occurred during inferencing: {e}"))
+    })?;
+    Ok(wasi_llm::InferencingUsage {
+        prompt_token_count: stats.prompt_tokens as u32,
+        generated_token_count: (stats.predict_tokens - stats.prompt_tokens) as u32,
+    })
+}
+
 /// Get the model binary and arch from walking the registry file structure
 async fn walk_registry_for_model(
     registry_path: &Path,
@@ -1,13 +1,26 @@
 use anyhow::Result;
 use reqwest::{
-    header::{HeaderMap, HeaderValue},
+    header::{HeaderMap, HeaderValue, CONTENT_TYPE},
     Client, Url,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use spin_core::async_trait;
-use spin_llm::LlmEngine;
+use spin_llm::{LlmEngine, StreamingInference, StreamingInferenceEvent};
 use spin_world::v2::llm::{self as wasi_llm};
+use tokio::sync::mpsc;
+
+mod openai;
+
+pub use openai::{OpenAiApi, OpenAiLlmEngine};
+
+/// The number of streamed events which may be waiting for the guest to read
+/// them before the response stops being read.
+const STREAM_BUFFER_EVENTS: usize = 64;
+
+/// The content type of streamed inferencing responses. Servers which don't
+/// support streaming respond with the whole result instead.
+const STREAM_CONTENT_TYPE: &str = "application/x-ndjson";
 
 #[derive(Clone)]
 pub struct RemoteHttpLlmEngine {
@@ -40,6 +53,15 @@ struct InferResponseBody {
     usage: InferUsage,
 }
 
+/// An event in a streamed inferencing response: either a chunk of generated
+/// text, or the usage once generation has finished.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum InferStreamEvent {
+    Text { text: String },
+    Usage { usage: InferUsage },
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all(deserialize = "camelCase"))]
 struct EmbeddingUsage {
@@ -60,58 +82,56 @@ impl LlmEngine for RemoteHttpLlmEngine {
         prompt: String,
         params: wasi_llm::InferencingParams,
     ) -> Result<wasi_llm::InferencingResult, wasi_llm::Error> {
-        let client = self.client.get_or_insert_with(Default::default);
-
-        let mut headers = HeaderMap::new();
-        headers.insert(
-            "authorization",
-            HeaderValue::from_str(&format!("bearer {}", self.auth_token)).map_err(|_| {
-                wasi_llm::Error::RuntimeError("Failed to create authorization header".to_string())
-            })?,
-        );
-        let inference_options = InferRequestBodyParams {
-            max_tokens: params.max_tokens,
-            repeat_penalty: params.repeat_penalty,
-            repeat_penalty_last_n_token_count: params.repeat_penalty_last_n_token_count,
-            temperature: params.temperature,
-            top_k: params.top_k,
-            top_p: params.top_p,
-        };
-        let body = serde_json::to_string(&json!({
-            "model": model,
-            "prompt": prompt,
-            "options": inference_options
-        }))
-        .map_err(|_| wasi_llm::Error::RuntimeError("Failed to serialize JSON".to_string()))?;
-
-        let infer_url = self
-            .url
-            .join("/infer")
-            .map_err(|_| wasi_llm::Error::RuntimeError("Failed to create URL".to_string()))?;
-        tracing::info!("Sending remote inference request to {infer_url}");
-
-        let resp = client
-            .request(http::Method::POST, infer_url)
-            .headers(headers)
-            .body(body)
-            .send()
-            .await
-            .map_err(|err| {
-                wasi_llm::Error::RuntimeError(format!("POST /infer request error: {err}"))
-            })?;
+        let resp = self
+            .send_infer_request(model, prompt, params, false)
+            .await?;
+        infer_result(resp).await
+    }
 
-        match resp.json::<InferResponseBody>().await {
-            Ok(val) => Ok(wasi_llm::InferencingResult {
-                text: val.text,
-                usage: wasi_llm::InferencingUsage {
-                    prompt_token_count: val.usage.prompt_token_count,
-                    generated_token_count: val.usage.generated_token_count,
-                },
-            }),
-            Err(err) => Err(wasi_llm::Error::RuntimeError(format!(
-                "Failed to deserialize response for \"POST  /index\": {err}"
-            ))),
+    async fn infer_stream(
+        &mut self,
+        model: wasi_llm::InferencingModel,
+        prompt: String,
+        params: wasi_llm::InferencingParams,
+    ) -> Result<StreamingInference, wasi_llm::Error> {
+        let mut resp = self.send_infer_request(model, prompt, params, true).await?;
+        if !is_streamed(&resp) {
+            return Ok(StreamingInference::complete(infer_result(resp).await?));
         }
+
+        let (sender, stream) = StreamingInference::channel(STREAM_BUFFER_EVENTS);
+        tokio::spawn(async move {
+            // The response body is a sequence of newline-delimited JSON
+            // events, which may be split arbitrarily across chunks.
+            let mut pending = Vec::new();
+            let drained = loop {
+                match resp.chunk().await {
+                    Ok(Some(chunk)) => {
+                        pending.extend_from_slice(&chunk);
+                        match drain_stream_events(&mut pending, &sender).await {
+                            Ok(true) => continue,
+                            result => break result,
+                        }
+                    }
+                    Ok(None) => break finish_stream_events(&mut pending, &sender).await,
+                    Err(err) => {
+                        break Err(wasi_llm::Error::RuntimeError(format!(
+                            "Failed to read response for \"POST /infer\": {err}"
+                        )))
+                    }
+                }
+            };
+            let error = match drained {
+                // Usage was reported, or the guest has dropped the stream.
+                Ok(false) => return,
+                Ok(true) => wasi_llm::Error::RuntimeError(
+                    "Response for \"POST /infer\" ended without reporting usage".to_string(),
+                ),
+                Err(e) => e,
+            };
+            _ = sender.send(StreamingInferenceEvent::Failed(error)).await;
+        });
+        Ok(stream)
     }
 
     async fn generate_embeddings(
@@ -172,4 +192,337 @@ impl RemoteHttpLlmEngine {
             client: None,
         }
     }
+
+    async fn send_infer_request(
+        &mut self,
+        model: wasi_llm::InferencingModel,
+        prompt: String,
+        params: wasi_llm::InferencingParams,
+        stream: bool,
+    ) -> Result<reqwest::Response, wasi_llm::Error> {
+        let client = self.client.get_or_insert_with(Default::default);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "authorization",
+            HeaderValue::from_str(&format!("bearer {}", self.auth_token)).map_err(|_| {
+                wasi_llm::Error::RuntimeError("Failed to create authorization header".to_string())
+            })?,
+        );
+        let inference_options = InferRequestBodyParams {
+            max_tokens: params.max_tokens,
+            repeat_penalty: params.repeat_penalty,
+            repeat_penalty_last_n_token_count: params.repeat_penalty_last_n_token_count,
+            temperature: params.temperature,
+            top_k: params.top_k,
+            top_p: params.top_p,
+        };
+        let mut body = json!({
+            "model": model,
+            "prompt": prompt,
+            "options": inference_options
+        });
+        if stream {
+            body["stream"] = json!(true);
+        }
+        let body = serde_json::to_string(&body)
+            .map_err(|_| wasi_llm::Error::RuntimeError("Failed to serialize JSON".to_string()))?;
+
+        let infer_url = self
+            .url
+            .join("/infer")
+            .map_err(|_| wasi_llm::Error::RuntimeError("Failed to create URL".to_string()))?;
+        tracing::info!("Sending remote inference request to {infer_url}");
+
+        let resp = client
+            .request(http::Method::POST, infer_url)
+            .headers(headers)
+            .body(body)
+            .send()
+            .await
+            .map_err(|err| {
+                wasi_llm::Error::RuntimeError(format!("POST /infer request error: {err}"))
+            })?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(wasi_llm::Error::RuntimeError(format!(
+                "POST /infer returned {status}: {body}"
+            )));
+        }
+        Ok(resp)
+    }
+}
+
+/// Reads a whole inferencing result from the response.
+async fn infer_result(
+    resp: reqwest::Response,
+) -> Result<wasi_llm::InferencingResult, wasi_llm::Error> {
+    match resp.json::<InferResponseBody>().await {
+        Ok(val) => Ok(wasi_llm::InferencingResult {
+            text: val.text,
+            usage: wasi_llm::InferencingUsage {
+                prompt_token_count: val.usage.prompt_token_count,
+                generated_token_count: val.usage.generated_token_count,
+            },
+        }),
+        Err(err) => Err(wasi_llm::Error::RuntimeError(format!(
+            "Failed to deserialize response for \"POST  /index\": {err}"
+        ))),
+    }
+}
+
+/// Returns true if the server is streaming the inferencing result, rather
+/// than responding with the whole result.
+fn is_streamed(resp: &reqwest::Response) -> bool {
+    resp.headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with(STREAM_CONTENT_TYPE))
+}
+
+/// Sends every complete event line in `pending` to the stream, leaving any
+/// trailing partial line in place. Returns `Ok(false)` if the stream should
+/// stop, either because usage has been reported or the guest dropped it.
+async fn drain_stream_events(
+    pending: &mut Vec<u8>,
+    sender: &mpsc::Sender<StreamingInferenceEvent>,
+) -> Result<bool, wasi_llm::Error> {
+    while let Some(end) = pending.iter().position(|b| *b == b'\n') {
+        let line: Vec<u8> = pending.drain(..=end).collect();
+        let Some(event) = parse_stream_event(&line)? else {
+            continue;
+        };
+        let done = matches!(event, StreamingInferenceEvent::Done(_));
+        if sender.send(event).await.is_err() || done {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Sends any events left in `pending` once the response has ended, including
+/// a last event which is not followed by a newline. Returns `Ok(true)` if the
+/// response ended without reporting usage.
+async fn finish_stream_events(
+    pending: &mut Vec<u8>,
+    sender: &mpsc::Sender<StreamingInferenceEvent>,
+) -> Result<bool, wasi_llm::Error> {
+    pending.push(b'\n');
+    drain_stream_events(pending, sender).await
+}
+
+fn parse_stream_event(line: &[u8]) -> Result<Option<StreamingInferenceEvent>, wasi_llm::Error> {
+    if line.iter().all(u8::is_ascii_whitespace) {
+        return Ok(None);
+    }
+    let event: InferStreamEvent = serde_json::from_slice(line).map_err(|err| {
+        wasi_llm::Error::RuntimeError(format!(
+            "Failed to deserialize streamed response for \"POST /infer\": {err}"
+        ))
+    })?;
+    let event = match event {
+        InferStreamEvent::Text { text } => StreamingInferenceEvent::Chunk(text),
+        InferStreamEvent::Usage { usage } => {
+            StreamingInferenceEvent::Done(wasi_llm::InferencingUsage {
+                prompt_token_count: usage.prompt_token_count,
+                generated_token_count: usage.generated_token_count,
+            })
+        }
+    };
+    Ok(Some(event))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spin_test_http_server::{ok, StatusCode, TestServer};
+
+    fn chunk_text(event: Option<StreamingInferenceEvent>) -> String {
+        match event {
+            Some(StreamingInferenceEvent::Chunk(text)) => text,
+            _ => panic!("expected a chunk of text"),
+        }
+    }
+
+    #[test]
+    fn parses_stream_events() {
+        let text = parse_stream_event(br#"{"text":"hello"}"#).unwrap();
+        assert_eq!("hello", chunk_text(text));
+
+        let usage =
+            parse_stream_event(br#"{"usage":{"promptTokenCount":4,"generatedTokenCount":7}}"#)
+                .unwrap();
+        let Some(StreamingInferenceEvent::Done(usage)) = usage else {
+            panic!("expected usage");
+        };
+        assert_eq!(4, usage.prompt_token_count);
+        assert_eq!(7, usage.generated_token_count);
+
+        assert!(parse_stream_event(b" \r\n").unwrap().is_none());
+        assert!(matches!(
+            parse_stream_event(b"{\"nope\":1}\n"),
+            Err(wasi_llm::Error::RuntimeError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn drain_leaves_partial_lines_pending() {
+        let (sender, mut stream) = StreamingInference::channel(STREAM_BUFFER_EVENTS);
+        let mut pending = b"{\"text\":\"a\"}\n\n{\"text\":\"b\"}\n{\"te".to_vec();
+
+        assert!(drain_stream_events(&mut pending, &sender).await.unwrap());
+        assert_eq!(b"{\"te", pending.as_slice());
+        assert_eq!(Some("a".into()), stream.next_chunk().await.unwrap());
+        assert_eq!(Some("b".into()), stream.next_chunk().await.unwrap());
+
+        pending.extend_from_slice(b"xt\":\"c\"}\n");
+        assert!(drain_stream_events(&mut pending, &sender).await.unwrap());
+        assert!(pending.is_empty());
+        assert_eq!(Some("c".into()), stream.next_chunk().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn drain_stops_once_usage_is_reported() {
+        let (sender, mut stream) = StreamingInference::channel(STREAM_BUFFER_EVENTS);
+        let mut pending = concat!(
+            "{\"text\":\"a\"}\n",
+            "{\"usage\":{\"promptTokenCount\":1,\"generatedTokenCount\":1}}\n",
+            "{\"text\":\"ignored\"}\n",
+        )
+        .as_bytes()
+        .to_vec();
+
+        assert!(!drain_stream_events(&mut pending, &sender).await.unwrap());
+        assert_eq!(Some("a".into()), stream.next_chunk().await.unwrap());
+        assert_eq!(None, stream.next_chunk().await.unwrap());
+        assert_eq!(1, stream.usage().generated_token_count);
+    }
+
+    #[tokio::test]
+    async fn finish_sends_last_unterminated_event() {
+        let (sender, mut stream) = StreamingInference::channel(STREAM_BUFFER_EVENTS);
+        let mut pending = concat!(
+            "{\"text\":\"a\"}\n",
+            "{\"usage\":{\"promptTokenCount\":1,\"generatedTokenCount\":1}}",
+        )
+        .as_bytes()
+        .to_vec();
+
+        assert!(drain_stream_events(&mut pending, &sender).await.unwrap());
+        assert!(!finish_stream_events(&mut pending, &sender).await.unwrap());
+        assert_eq!(Some("a".into()), stream.next_chunk().await.unwrap());
+        assert_eq!(None, stream.next_chunk().await.unwrap());
+
+        let (sender, _stream) = StreamingInference::channel(STREAM_BUFFER_EVENTS);
+        let mut pending = b"{\"text\":\"a\"}".to_vec();
+        assert!(finish_stream_events(&mut pending, &sender).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn drain_stops_when_guest_drops_stream() {
+        let (sender, stream) = StreamingInference::channel(STREAM_BUFFER_EVENTS);
+        drop(stream);
+        let mut pending = b"{\"text\":\"a\"}\n".to_vec();
+        assert!(!drain_stream_events(&mut pending, &sender).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn drain_reports_malformed_events() {
+        let (sender, _stream) = StreamingInference::channel(STREAM_BUFFER_EVENTS);
+        let mut pending = b"not json\n".to_vec();
+        drain_stream_events(&mut pending, &sender)
+            .await
+            .unwrap_err();
+    }
+
+    fn params() -> wasi_llm::InferencingParams {
+        wasi_llm::InferencingParams {
+            max_tokens: 10,
+            repeat_penalty: 1.1,
+            repeat_penalty_last_n_token_count: 64,
+            temperature: 0.5,
+            top_k: 40,
+            top_p: 0.9,
+        }
+    }
+
+    fn engine(server: &TestServer) -> RemoteHttpLlmEngine {
+        RemoteHttpLlmEngine::new(Url::parse(&server.url("/")).unwrap(), "sekrit".to_owned())
+    }
+
+    #[tokio::test]
+    async fn infer_stream_reads_streamed_events() {
+        let server = TestServer::serve(
+            STREAM_CONTENT_TYPE,
+            concat!(
+                "{\"text\":\"hel\"}\n",
+                "{\"text\":\"lo\"}\n",
+                "{\"usage\":{\"promptTokenCount\":2,\"generatedTokenCount\":2}}",
+            ),
+        )
+        .await;
+
+        let mut stream = engine(&server)
+            .infer_stream("llama2-chat".to_owned(), "say hello".to_owned(), params())
+            .await
+            .unwrap();
+        assert_eq!(Some("hel".into()), stream.next_chunk().await.unwrap());
+        assert_eq!(Some("lo".into()), stream.next_chunk().await.unwrap());
+        assert_eq!(None, stream.next_chunk().await.unwrap());
+        assert_eq!(2, stream.usage().generated_token_count);
+
+        let [request]: [_; 1] = server.requests().try_into().unwrap();
+        assert_eq!("/infer", request.path);
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        assert_eq!(json!(true), body["stream"]);
+    }
+
+    #[tokio::test]
+    async fn infer_stream_falls_back_to_whole_result() {
+        let server = TestServer::serve(
+            "application/json",
+            r#"{"text":"hello","usage":{"promptTokenCount":2,"generatedTokenCount":1}}"#,
+        )
+        .await;
+
+        let mut stream = engine(&server)
+            .infer_stream("llama2-chat".to_owned(), "say hello".to_owned(), params())
+            .await
+            .unwrap();
+        assert_eq!(Some("hello".into()), stream.next_chunk().await.unwrap());
+        assert_eq!(None, stream.next_chunk().await.unwrap());
+        assert_eq!(1, stream.usage().generated_token_count);
+    }
+
+    #[tokio::test]
+    async fn infer_reports_error_statuses() {
+        for code in [StatusCode::UNAUTHORIZED, StatusCode::INTERNAL_SERVER_ERROR] {
+            let server = TestServer::start(move |_| {
+                let mut response = ok("text/plain", "nope");
+                *response.status_mut() = code;
+                response
+            })
+            .await;
+            let mut engine = engine(&server);
+
+            let Err(wasi_llm::Error::RuntimeError(message)) = engine
+                .infer("llama2-chat".to_owned(), "say hello".to_owned(), params())
+                .await
+            else {
+                panic!("expected {code} to fail inferencing");
+            };
+            assert!(message.contains(code.as_str()), "{message}");
+            assert!(message.contains("nope"), "{message}");
+
+            let Err(wasi_llm::Error::RuntimeError(message)) = engine
+                .infer_stream("llama2-chat".to_owned(), "say hello".to_owned(), params())
+                .await
+            else {
+                panic!("expected {code} to fail streaming");
+            };
+            assert!(message.contains(code.as_str()), "{message}");
+        }
+    }
 }
@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+
+use reqwest::{Client, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+use spin_core::async_trait;
+use spin_llm::LlmEngine;
+use spin_world::v2::llm::{self as wasi_llm};
+
+/// Which OpenAI API is used for inferencing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenAiApi {
+    /// `/v1/chat/completions`, with the prompt sent as a single user message.
+    #[default]
+    Chat,
+    /// `/v1/completions`, with the prompt sent as is.
+    Completions,
+}
+
+/// An engine which uses a server implementing the OpenAI API, such as
+/// llama.cpp or vLLM.
+///
+/// The OpenAI API has no equivalent of the repeat penalty and top-k
+/// inferencing parameters, so these are not sent to the server.
+#[derive(Clone)]
+pub struct OpenAiLlmEngine {
+    url: Url,
+    api_key: Option<String>,
+    api: OpenAiApi,
+    models: HashMap<String, String>,
+    client: Option<Client>,
+}
+
+#[derive(Serialize)]
+struct ChatMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct CompletionsResponseBody {
+    choices: Vec<CompletionChoice>,
+    #[serde(default)]
+    usage: CompletionUsage,
+}
+
+#[derive(Deserialize)]
+struct CompletionChoice {
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionsResponseBody {
+    choices: Vec<ChatCompletionChoice>,
+    #[serde(default)]
+    usage: CompletionUsage,
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionChoice {
+    message: ChatCompletionMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatCompletionMessage {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+struct CompletionUsage {
+    prompt_tokens: u32,
+    completion_tokens: u32,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingsResponseBody {
+    data: Vec<EmbeddingData>,
+    #[serde(default)]
+    usage: EmbeddingsUsage,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    index: usize,
+}
+
+#[derive(Default, Deserialize)]
+struct EmbeddingsUsage {
+    prompt_tokens: u32,
+}
+
+#[async_trait]
+impl LlmEngine for OpenAiLlmEngine {
+    async fn infer(
+        &mut self,
+        model: wasi_llm::InferencingModel,
+        prompt: String,
+        params: wasi_llm::InferencingParams,
+    ) -> Result<wasi_llm::InferencingResult, wasi_llm::Error> {
+        let model = self.model_name(&model).to_owned();
+        let (text, usage) = match self.api {
+            OpenAiApi::Chat => {
+                let body = json!({
+                    "model": model,
+                    "messages": [ChatMessage { role: "user", content: &prompt }],
+                    "max_tokens": params.max_tokens,
+                    "temperature": params.temperature,
+                    "top_p": params.top_p,
+                });
+                let resp: ChatCompletionsResponseBody =
+                    self.post("v1/chat/completions", body).await?;
+                let text = resp
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|choice| choice.message.content);
+                (text, resp.usage)
+            }
+            OpenAiApi::Completions => {
+                let body = json!({
+                    "model": model,
+                    "prompt": prompt,
+                    "max_tokens": params.max_tokens,
+                    "temperature": params.temperature,
+                    "top_p": params.top_p,
+                });
+                let resp: CompletionsResponseBody = self.post("v1/completions", body).await?;
+                let text = resp.choices.into_iter().next().map(|choice| choice.text);
+                (text, resp.usage)
+            }
+        };
+        let text = text.ok_or_else(|| {
+            wasi_llm::Error::RuntimeError("OpenAI server returned no completions".to_string())
+        })?;
+        Ok(wasi_llm::InferencingResult {
+            text,
+            usage: wasi_llm::InferencingUsage {
+                prompt_token_count: usage.prompt_tokens,
+                generated_token_count: usage.completion_tokens,
+            },
+        })
+    }
+
+    async fn generate_embeddings(
+        &mut self,
+        model: wasi_llm::EmbeddingModel,
+        data: Vec<String>,
+    ) -> Result<wasi_llm::EmbeddingsResult, wasi_llm::Error> {
+        let body = json!({
+            "model": self.model_name(&model),
+            "input": data,
+        });
+        let mut resp: EmbeddingsResponseBody = self.post("v1/embeddings", body).await?;
+        // The API does not guarantee that embeddings come back in input order.
+        resp.data.sort_by_key(|d| d.index);
+        Ok(wasi_llm::EmbeddingsResult {
+            embeddings: resp.data.into_iter().map(|d| d.embedding).collect(),
+            usage: wasi_llm::EmbeddingsUsage {
+                prompt_token_count: resp.usage.prompt_tokens,
+            },
+        })
+    }
+}
+
+impl OpenAiLlmEngine {
+    /// Creates an engine for the server at `url`, to which the `/v1/...`
+    /// endpoint paths are appended. Spin model ids which appear in `models`
+    /// are sent as the corresponding server model name; others are sent as is.
+    pub fn new(
+        url: Url,
+        api_key: Option<String>,
+        api: OpenAiApi,
+        models: HashMap<String, String>,
+    ) -> Self {
+        Self {
+            url,
+            api_key,
+            api,
+            models,
+            client: None,
+        }
+    }
+
+    fn model_name<'a>(&'a self, model: &'a str) -> &'a str {
+        self.models.get(model).map(String::as_str).unwrap_or(model)
+    }
+
+    fn endpoint_url(&self, path: &str) -> Result<Url, wasi_llm::Error> {
+        let mut base = self.url.clone();
+        if !base.path().ends_with('/') {
+            base.set_path(&format!("{}/", base.path()));
+        }
+        base.join(path)
+            .map_err(|_| wasi_llm::Error::RuntimeError("Failed to create URL".to_string()))
+    }
+
+    async fn post<T: DeserializeOwned>(
+        &mut self,
+        path: &str,
+        body: serde_json::Value,
+    ) -> Result<T, wasi_llm::Error> {
+        let url = self.endpoint_url(path)?;
+        tracing::info!("Sending OpenAI request to {url}");
+
+        let client = self.client.get_or_insert_with(Default::default);
+        let mut request = client.post(url).json(&body);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let resp = request.send().await.map_err(|err| {
+            wasi_llm::Error::RuntimeError(format!("POST /{path} request error: {err}"))
+        })?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let message = resp.text().await.unwrap_or_default();
+            return Err(wasi_llm::Error::RuntimeError(format!(
+                "POST /{path} failed with status {status}: {message}"
+            )));
+        }
+        resp.json::<T>().await.map_err(|err| {
+            wasi_llm::Error::RuntimeError(format!(
+                "Failed to deserialize response for \"POST /{path}\": {err}"
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spin_test_http_server::{ReceivedRequest, TestServer};
+
+    /// Serves every request with the given JSON body.
+    async fn mock_server(response: serde_json::Value) -> (TestServer, Url) {
+        let server = TestServer::serve("application/json", response.to_string()).await;
+        let url = Url::parse(&server.url("/")).unwrap();
+        (server, url)
+    }
+
+    /// The only request the server received, with its JSON body.
+    fn single_request(server: &TestServer) -> (ReceivedRequest, serde_json::Value) {
+        let [request]: [ReceivedRequest; 1] = server.requests().try_into().unwrap();
+        let body = serde_json::from_slice(&request.body).unwrap();
+        (request, body)
+    }
+
+    fn params() -> wasi_llm::InferencingParams {
+        wasi_llm::InferencingParams {
+            max_tokens: 10,
+            repeat_penalty: 1.1,
+            repeat_penalty_last_n_token_count: 64,
+            temperature: 0.5,
+            top_k: 40,
+            top_p: 0.9,
+        }
+    }
+
+    #[tokio::test]
+    async fn infer_uses_chat_completions() {
+        let (server, url) = mock_server(json!({
+            "choices": [{ "index": 0, "message": { "role": "assistant", "content": "hello" } }],
+            "usage": { "prompt_tokens": 3, "completion_tokens": 1, "total_tokens": 4 }
+        }))
+        .await;
+        let models = [("llama2-chat".to_owned(), "llama-2-7b-chat".to_owned())].into();
+        let mut engine =
+            OpenAiLlmEngine::new(url, Some("sekrit".to_owned()), OpenAiApi::Chat, models);
+
+        let result = engine
+            .infer("llama2-chat".to_owned(), "say hello".to_owned(), params())
+            .await
+            .unwrap();
+        assert_eq!("hello", result.text);
+        assert_eq!(3, result.usage.prompt_token_count);
+        assert_eq!(1, result.usage.generated_token_count);
+
+        let (request, body) = single_request(&server);
+        assert_eq!("/v1/chat/completions", request.path);
+        assert_eq!(Some("Bearer sekrit"), request.header("authorization"));
+        assert_eq!("llama-2-7b-chat", body["model"]);
+        assert_eq!("say hello", body["messages"][0]["content"]);
+        assert_eq!(10, body["max_tokens"]);
+    }
+
+    #[tokio::test]
+    async fn infer_uses_completions() {
+        let (server, url) = mock_server(json!({
+            "choices": [{ "index": 0, "text": "hello" }],
+            "usage": { "prompt_tokens": 3, "completion_tokens": 1, "total_tokens": 4 }
+        }))
+        .await;
+        let mut engine = OpenAiLlmEngine::new(
+            url.join("/api/").unwrap(),
+            None,
+            OpenAiApi::Completions,
+            Default::default(),
+        );
+
+        let result = engine
+            .infer("my-model".to_owned(), "say hello".to_owned(), params())
+            .await
+            .unwrap();
+        assert_eq!("hello", result.text);
+
+        let (request, body) = single_request(&server);
+        assert_eq!("/api/v1/completions", request.path);
+        assert_eq!(None, request.header("authorization"));
+        assert_eq!("my-model", body["model"]);
+        assert_eq!("say hello", body["prompt"]);
+    }
+
+    #[tokio::test]
+    async fn generate_embeddings_orders_by_index() {
+        let (server, url) = mock_server(json!({
+            "data": [
+                { "object": "embedding", "index": 1, "embedding": [2.0] },
+                { "object": "embedding", "index": 0, "embedding": [1.0] }
+            ],
+            "usage": { "prompt_tokens": 2, "total_tokens": 2 }
+        }))
+        .await;
+        let models = [("all-minilm-l6-v2".to_owned(), "minilm".to_owned())].into();
+        let mut engine = OpenAiLlmEngine::new(url, None, OpenAiApi::Chat, models);
+
+        let result = engine
+            .generate_embeddings(
+                "all-minilm-l6-v2".to_owned(),
+                vec!["a".to_owned(), "b".to_owned()],
+            )
+            .await
+            .unwrap();
+        assert_eq!(vec![vec![1.0], vec![2.0]], result.embeddings);
+        assert_eq!(2, result.usage.prompt_token_count);
+
+        let (request, body) = single_request(&server);
+        assert_eq!("/v1/embeddings", request.path);
+        assert_eq!("minilm", body["model"]);
+        assert_eq!(json!(["a", "b"]), body["input"]);
+    }
+}
@@ -1,10 +1,11 @@
 use spin_app::DynamicHostComponent;
 use spin_core::HostComponent;
 
-use crate::{LlmDispatch, LlmEngine, AI_MODELS_KEY};
+use crate::{quota::UsageAccounting, LlmDispatch, LlmEngine, LlmQuotas, AI_MODELS_KEY};
 
 pub struct LlmComponent {
     create_engine: Box<dyn Fn() -> Box<dyn LlmEngine> + Send + Sync>,
+    accounting: UsageAccounting,
 }
 
 impl LlmComponent {
@@ -14,8 +15,15 @@ impl LlmComponent {
     {
         Self {
             create_engine: Box::new(create_engine),
+            accounting: Default::default(),
         }
     }
+
+    /// Limits components' use of LLMs. Without quotas, use is unlimited.
+    pub fn with_quotas(mut self, quotas: LlmQuotas) -> Self {
+        self.accounting = UsageAccounting::new(quotas);
+        self
+    }
 }
 
 impl HostComponent for LlmComponent {
@@ -26,14 +34,12 @@ impl HostComponent for LlmComponent {
         get: impl Fn(&mut spin_core::Data<T>) -> &mut Self::Data + Send + Sync + Copy + 'static,
     ) -> anyhow::Result<()> {
         spin_world::v1::llm::add_to_linker(linker, get)?;
-        spin_world::v2::llm::add_to_linker(linker, get)
+        spin_world::v2::llm::add_to_linker(linker, get)?;
+        spin_world::v2_1::llm_streaming::add_to_linker(linker, get)
     }
 
     fn build_data(&self) -> Self::Data {
-        LlmDispatch {
-            engine: (self.create_engine)(),
-            allowed_models: Default::default(),
-        }
+        LlmDispatch::new((self.create_engine)(), self.accounting.clone())
     }
 }
 
@@ -44,6 +50,7 @@ impl DynamicHostComponent for LlmComponent {
         component: &spin_app::AppComponent,
     ) -> anyhow::Result<()> {
         data.allowed_models = component.get_metadata(AI_MODELS_KEY)?.unwrap_or_default();
+        data.component_id = component.id().to_owned();
         Ok(())
     }
 }
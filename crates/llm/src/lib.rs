@@ -1,16 +1,25 @@
 pub mod host_component;
+mod quota;
 
+use anyhow::Context;
 use spin_app::MetadataKey;
-use spin_core::async_trait;
+use spin_core::{async_trait, wasmtime::component::Resource};
 use spin_world::v1::llm::{self as v1};
 use spin_world::v2::llm::{self as v2};
+use spin_world::v2_1::llm_streaming::{self as v2_1};
 use std::collections::HashSet;
+use table::Table;
+use tokio::sync::mpsc;
 
 pub use crate::host_component::LlmComponent;
+use crate::quota::UsageAccounting;
+pub use crate::quota::{LlmQuota, LlmQuotas};
 
 pub const MODEL_ALL_MINILM_L6_V2: &str = "all-minilm-l6-v2";
 pub const AI_MODELS_KEY: MetadataKey<HashSet<String>> = MetadataKey::new("ai_models");
 
+const DEFAULT_STREAM_TABLE_CAPACITY: u32 = 256;
+
 #[async_trait]
 pub trait LlmEngine: Send + Sync {
     async fn infer(
@@ -20,6 +29,19 @@ pub trait LlmEngine: Send + Sync {
         params: v2::InferencingParams,
     ) -> Result<v2::InferencingResult, v2::Error>;
 
+    /// Performs inferencing, making the generated text available as it is
+    /// generated. The default implementation waits for the whole result and
+    /// returns it as a single chunk.
+    async fn infer_stream(
+        &mut self,
+        model: v1::InferencingModel,
+        prompt: String,
+        params: v2::InferencingParams,
+    ) -> Result<StreamingInference, v2::Error> {
+        let result = self.infer(model, prompt, params).await?;
+        Ok(StreamingInference::complete(result))
+    }
+
     async fn generate_embeddings(
         &mut self,
         model: v2::EmbeddingModel,
@@ -27,9 +49,157 @@ pub trait LlmEngine: Send + Sync {
     ) -> Result<v2::EmbeddingsResult, v2::Error>;
 }
 
+/// Something that happened during a streaming inferencing request.
+pub enum StreamingInferenceEvent {
+    /// The model generated some text.
+    Chunk(String),
+    /// Generation completed.
+    Done(v2::InferencingUsage),
+    /// Generation failed.
+    Failed(v2::Error),
+}
+
+/// The receiving end of a streaming inferencing request. Engines send
+/// `StreamingInferenceEvent`s to the paired sender as generation progresses.
+pub struct StreamingInference {
+    events: mpsc::Receiver<StreamingInferenceEvent>,
+    usage: v2::InferencingUsage,
+    finished: bool,
+}
+
+impl StreamingInference {
+    /// Creates a stream, and the sender through which an engine reports
+    /// generation events. The engine should stop generating if sending fails,
+    /// as that means the guest is no longer interested in the result.
+    pub fn channel(buffer: usize) -> (mpsc::Sender<StreamingInferenceEvent>, Self) {
+        let (sender, events) = mpsc::channel(buffer);
+        let stream = Self {
+            events,
+            usage: v2::InferencingUsage {
+                prompt_token_count: 0,
+                generated_token_count: 0,
+            },
+            finished: false,
+        };
+        (sender, stream)
+    }
+
+    /// Creates a stream which yields an already complete result.
+    pub fn complete(result: v2::InferencingResult) -> Self {
+        let (sender, stream) = Self::channel(2);
+        // The channel has room for both events, so these cannot fail.
+        _ = sender.try_send(StreamingInferenceEvent::Chunk(result.text));
+        _ = sender.try_send(StreamingInferenceEvent::Done(result.usage));
+        stream
+    }
+
+    /// Waits for the next chunk of generated text. Returns `None` once
+    /// generation is complete.
+    pub async fn next_chunk(&mut self) -> Result<Option<String>, v2::Error> {
+        if self.finished {
+            return Ok(None);
+        }
+        match self.events.recv().await {
+            Some(StreamingInferenceEvent::Chunk(text)) => {
+                self.usage.generated_token_count += 1;
+                Ok(Some(text))
+            }
+            Some(StreamingInferenceEvent::Done(usage)) => {
+                self.usage = usage;
+                self.finished = true;
+                Ok(None)
+            }
+            Some(StreamingInferenceEvent::Failed(e)) => {
+                self.finished = true;
+                Err(e)
+            }
+            None => {
+                self.finished = true;
+                Err(v2::Error::RuntimeError(
+                    "Inferencing ended without completing".into(),
+                ))
+            }
+        }
+    }
+
+    /// Usage information, which is complete once the stream is finished.
+    /// Until then, each chunk read counts as one generated token.
+    pub fn usage(&self) -> v2::InferencingUsage {
+        self.usage
+    }
+
+    /// Stops reading the stream, returning its usage. Events the engine has
+    /// already sent are taken into account, so a final usage report the guest
+    /// did not read is not lost.
+    pub fn close(&mut self) -> v2::InferencingUsage {
+        while !self.finished {
+            match self.events.try_recv() {
+                Ok(StreamingInferenceEvent::Chunk(_)) => self.usage.generated_token_count += 1,
+                Ok(StreamingInferenceEvent::Done(usage)) => {
+                    self.usage = usage;
+                    self.finished = true;
+                }
+                Ok(StreamingInferenceEvent::Failed(_)) | Err(_) => self.finished = true,
+            }
+        }
+        self.events.close();
+        self.usage
+    }
+}
+
 pub struct LlmDispatch {
     engine: Box<dyn LlmEngine>,
     allowed_models: HashSet<String>,
+    streams: Table<StreamingInference>,
+    component_id: String,
+    accounting: UsageAccounting,
+}
+
+impl LlmDispatch {
+    fn new(engine: Box<dyn LlmEngine>, accounting: UsageAccounting) -> Self {
+        Self {
+            engine,
+            allowed_models: Default::default(),
+            streams: Table::new(DEFAULT_STREAM_TABLE_CAPACITY),
+            component_id: Default::default(),
+            accounting,
+        }
+    }
+
+    /// Checks that the component may use the model and is within its quota,
+    /// and applies the quota to the inferencing parameters.
+    fn check_inferencing_request(
+        &self,
+        model: &str,
+        params: Option<v2::InferencingParams>,
+    ) -> Result<v2::InferencingParams, v2::Error> {
+        if !self.allowed_models.contains(model) {
+            return Err(access_denied_error(model));
+        }
+        self.accounting.check(&self.component_id)?;
+        let mut params = params.unwrap_or_else(default_params);
+        params.max_tokens = self
+            .accounting
+            .limit_max_tokens(&self.component_id, params.max_tokens);
+        Ok(params)
+    }
+
+    fn record_inferencing_usage(&self, usage: &v2::InferencingUsage) {
+        self.accounting.record(
+            &self.component_id,
+            usage.prompt_token_count,
+            usage.generated_token_count,
+        );
+    }
+
+    fn get_stream(
+        &mut self,
+        stream: Resource<v2_1::InferencingStream>,
+    ) -> anyhow::Result<&mut StreamingInference> {
+        self.streams
+            .get_mut(stream.rep())
+            .context("invalid inferencing stream")
+    }
 }
 
 #[async_trait]
@@ -40,24 +210,15 @@ impl v2::Host for LlmDispatch {
         prompt: String,
         params: Option<v2::InferencingParams>,
     ) -> anyhow::Result<Result<v2::InferencingResult, v2::Error>> {
-        if !self.allowed_models.contains(&model) {
-            return Ok(Err(access_denied_error(&model)));
-        }
-        Ok(self
-            .engine
-            .infer(
-                model,
-                prompt,
-                params.unwrap_or(v2::InferencingParams {
-                    max_tokens: 100,
-                    repeat_penalty: 1.1,
-                    repeat_penalty_last_n_token_count: 64,
-                    temperature: 0.8,
-                    top_k: 40,
-                    top_p: 0.9,
-                }),
-            )
-            .await)
+        let params = match self.check_inferencing_request(&model, params) {
+            Ok(params) => params,
+            Err(e) => return Ok(Err(e)),
+        };
+        let result = self.engine.infer(model, prompt, params).await;
+        if let Ok(result) = &result {
+            self.record_inferencing_usage(&result.usage);
+        }
+        Ok(result)
     }
 
     async fn generate_embeddings(
@@ -68,7 +229,80 @@ impl v2::Host for LlmDispatch {
         if !self.allowed_models.contains(&m) {
             return Ok(Err(access_denied_error(&m)));
         }
-        Ok(self.engine.generate_embeddings(m, data).await)
+        if let Err(e) = self.accounting.check(&self.component_id) {
+            return Ok(Err(e));
+        }
+        let result = self.engine.generate_embeddings(m, data).await;
+        if let Ok(result) = &result {
+            self.accounting
+                .record(&self.component_id, result.usage.prompt_token_count, 0);
+        }
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl v2_1::Host for LlmDispatch {
+    async fn infer_stream(
+        &mut self,
+        model: v2::InferencingModel,
+        prompt: String,
+        params: Option<v2::InferencingParams>,
+    ) -> anyhow::Result<Result<Resource<v2_1::InferencingStream>, v2::Error>> {
+        let params = match self.check_inferencing_request(&model, params) {
+            Ok(params) => params,
+            Err(e) => return Ok(Err(e)),
+        };
+        let stream = match self.engine.infer_stream(model, prompt, params).await {
+            Ok(stream) => stream,
+            Err(e) => return Ok(Err(e)),
+        };
+        match self.streams.push(stream) {
+            Ok(rep) => Ok(Ok(Resource::new_own(rep))),
+            Err(()) => Ok(Err(v2::Error::RuntimeError(
+                "Too many inferencing streams are open".into(),
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl v2_1::HostInferencingStream for LlmDispatch {
+    async fn next_chunk(
+        &mut self,
+        stream: Resource<v2_1::InferencingStream>,
+    ) -> anyhow::Result<Result<Option<String>, v2::Error>> {
+        let stream = self.get_stream(stream)?;
+        let was_finished = stream.finished;
+        let chunk = stream.next_chunk().await;
+        // Record usage once, when the stream completes or fails
+        if !was_finished && stream.finished {
+            let usage = stream.usage();
+            self.record_inferencing_usage(&usage);
+        }
+        Ok(chunk)
+    }
+
+    async fn usage(
+        &mut self,
+        stream: Resource<v2_1::InferencingStream>,
+    ) -> anyhow::Result<v2::InferencingUsage> {
+        let stream = self.get_stream(stream)?;
+        Ok(stream.usage())
+    }
+
+    fn drop(&mut self, stream: Resource<v2_1::InferencingStream>) -> anyhow::Result<()> {
+        // Dropping the stream closes the channel, which tells the engine to
+        // stop generating.
+        if let Some(mut stream) = self.streams.remove(stream.rep()) {
+            // Tokens generated before the guest gave up still count towards
+            // its quota.
+            if !stream.finished {
+                let usage = stream.close();
+                self.record_inferencing_usage(&usage);
+            }
+        }
+        Ok(())
     }
 }
 
@@ -100,8 +334,237 @@ impl v1::Host for LlmDispatch {
     }
 }
 
+fn default_params() -> v2::InferencingParams {
+    v2::InferencingParams {
+        max_tokens: 100,
+        repeat_penalty: 1.1,
+        repeat_penalty_last_n_token_count: 64,
+        temperature: 0.8,
+        top_k: 40,
+        top_p: 0.9,
+    }
+}
+
 fn access_denied_error(model: &str) -> v2::Error {
     v2::Error::InvalidInput(format!(
         "The component does not have access to use '{model}'. To give the component access, add '{model}' to the 'ai_models' key for the component in your spin.toml manifest"
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(prompt_token_count: u32, generated_token_count: u32) -> v2::InferencingUsage {
+        v2::InferencingUsage {
+            prompt_token_count,
+            generated_token_count,
+        }
+    }
+
+    #[tokio::test]
+    async fn complete_stream_yields_whole_result() {
+        let mut stream = StreamingInference::complete(v2::InferencingResult {
+            text: "hello world".into(),
+            usage: usage(3, 2),
+        });
+        assert_eq!(
+            Some("hello world".into()),
+            stream.next_chunk().await.unwrap()
+        );
+        assert_eq!(None, stream.next_chunk().await.unwrap());
+        assert_eq!(2, stream.usage().generated_token_count);
+        // Reading past the end keeps returning `None`
+        assert_eq!(None, stream.next_chunk().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn stream_reports_usage_once_done() {
+        let (sender, mut stream) = StreamingInference::channel(4);
+        sender
+            .send(StreamingInferenceEvent::Chunk("a".into()))
+            .await
+            .unwrap();
+        sender
+            .send(StreamingInferenceEvent::Chunk("b".into()))
+            .await
+            .unwrap();
+        sender
+            .send(StreamingInferenceEvent::Done(usage(5, 2)))
+            .await
+            .unwrap();
+
+        assert_eq!(Some("a".into()), stream.next_chunk().await.unwrap());
+        assert_eq!(0, stream.usage().prompt_token_count);
+        assert_eq!(Some("b".into()), stream.next_chunk().await.unwrap());
+        assert_eq!(None, stream.next_chunk().await.unwrap());
+        assert_eq!(5, stream.usage().prompt_token_count);
+        assert_eq!(2, stream.usage().generated_token_count);
+    }
+
+    #[tokio::test]
+    async fn stream_ends_on_failure() {
+        let (sender, mut stream) = StreamingInference::channel(4);
+        sender
+            .send(StreamingInferenceEvent::Chunk("a".into()))
+            .await
+            .unwrap();
+        sender
+            .send(StreamingInferenceEvent::Failed(v2::Error::RuntimeError(
+                "boom".into(),
+            )))
+            .await
+            .unwrap();
+
+        assert_eq!(Some("a".into()), stream.next_chunk().await.unwrap());
+        assert!(
+            matches!(stream.next_chunk().await, Err(v2::Error::RuntimeError(msg)) if msg == "boom")
+        );
+        assert_eq!(None, stream.next_chunk().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn stream_fails_if_engine_stops_without_usage() {
+        let (sender, mut stream) = StreamingInference::channel(4);
+        sender
+            .send(StreamingInferenceEvent::Chunk("a".into()))
+            .await
+            .unwrap();
+        drop(sender);
+
+        assert_eq!(Some("a".into()), stream.next_chunk().await.unwrap());
+        assert!(matches!(
+            stream.next_chunk().await,
+            Err(v2::Error::RuntimeError(_))
+        ));
+        assert_eq!(None, stream.next_chunk().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn dropping_stream_stops_engine() {
+        let (sender, stream) = StreamingInference::channel(4);
+        drop(stream);
+        assert!(sender
+            .send(StreamingInferenceEvent::Chunk("a".into()))
+            .await
+            .is_err());
+    }
+
+    /// An engine which streams whatever events the test gives it.
+    struct ScriptedEngine {
+        events: Vec<StreamingInferenceEvent>,
+    }
+
+    #[async_trait]
+    impl LlmEngine for ScriptedEngine {
+        async fn infer(
+            &mut self,
+            _model: v1::InferencingModel,
+            _prompt: String,
+            _params: v2::InferencingParams,
+        ) -> Result<v2::InferencingResult, v2::Error> {
+            unimplemented!()
+        }
+
+        async fn infer_stream(
+            &mut self,
+            _model: v1::InferencingModel,
+            _prompt: String,
+            _params: v2::InferencingParams,
+        ) -> Result<StreamingInference, v2::Error> {
+            let (sender, stream) = StreamingInference::channel(16);
+            for event in self.events.drain(..) {
+                sender.send(event).await.unwrap();
+            }
+            Ok(stream)
+        }
+
+        async fn generate_embeddings(
+            &mut self,
+            _model: v2::EmbeddingModel,
+            _data: Vec<String>,
+        ) -> Result<v2::EmbeddingsResult, v2::Error> {
+            unimplemented!()
+        }
+    }
+
+    /// A dispatcher for a component with a budget of `total_tokens`.
+    fn dispatch(events: Vec<StreamingInferenceEvent>, total_tokens: u64) -> LlmDispatch {
+        let accounting = UsageAccounting::new(LlmQuotas {
+            default: LlmQuota {
+                total_tokens: Some(total_tokens),
+                ..Default::default()
+            },
+            components: Default::default(),
+        });
+        let mut dispatch = LlmDispatch::new(Box::new(ScriptedEngine { events }), accounting);
+        dispatch.allowed_models.insert("model".into());
+        dispatch.component_id = "comp".into();
+        dispatch
+    }
+
+    async fn start_stream(dispatch: &mut LlmDispatch) -> Resource<v2_1::InferencingStream> {
+        v2_1::Host::infer_stream(dispatch, "model".into(), "prompt".into(), None)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn dropping_unread_stream_records_reported_usage() {
+        let mut dispatch = dispatch(
+            vec![
+                StreamingInferenceEvent::Chunk("a".into()),
+                StreamingInferenceEvent::Done(usage(6, 4)),
+            ],
+            10,
+        );
+        let stream = start_stream(&mut dispatch).await;
+        assert!(dispatch.accounting.check("comp").is_ok());
+
+        v2_1::HostInferencingStream::drop(&mut dispatch, stream).unwrap();
+        assert!(dispatch.accounting.check("comp").is_err());
+    }
+
+    #[tokio::test]
+    async fn dropping_partly_read_stream_records_chunks_generated() {
+        let (sender, stream) = StreamingInference::channel(4);
+        let mut dispatch = dispatch(vec![], 2);
+        let rep = dispatch.streams.push(stream).unwrap();
+        sender
+            .send(StreamingInferenceEvent::Chunk("a".into()))
+            .await
+            .unwrap();
+        sender
+            .send(StreamingInferenceEvent::Chunk("b".into()))
+            .await
+            .unwrap();
+
+        let chunk = v2_1::HostInferencingStream::next_chunk(&mut dispatch, Resource::new_own(rep))
+            .await
+            .unwrap();
+        assert_eq!(Some("a".into()), chunk.unwrap());
+        v2_1::HostInferencingStream::drop(&mut dispatch, Resource::new_own(rep)).unwrap();
+        assert!(dispatch.accounting.check("comp").is_err());
+        // The engine is told to stop generating
+        assert!(sender
+            .send(StreamingInferenceEvent::Chunk("c".into()))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn finished_stream_is_recorded_once() {
+        let mut dispatch = dispatch(vec![StreamingInferenceEvent::Done(usage(3, 0))], 5);
+        let stream = start_stream(&mut dispatch).await;
+        let rep = stream.rep();
+        let chunk = v2_1::HostInferencingStream::next_chunk(&mut dispatch, stream)
+            .await
+            .unwrap();
+        assert_eq!(None, chunk.unwrap());
+        assert!(dispatch.accounting.check("comp").is_ok());
+
+        v2_1::HostInferencingStream::drop(&mut dispatch, Resource::new_own(rep)).unwrap();
+        assert!(dispatch.accounting.check("comp").is_ok());
+    }
+}
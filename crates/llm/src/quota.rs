@@ -0,0 +1,318 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use spin_world::v2::llm::{self as v2};
+
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Limits on a component's use of LLMs. Token counts include both prompt
+/// and generated tokens. A limit of `None` means the use is unlimited.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LlmQuota {
+    /// The maximum number of tokens an inferencing request may generate.
+    /// Requests asking for more are limited to this.
+    pub max_tokens_per_request: Option<u32>,
+    /// The number of tokens a component may use in any one minute. This is
+    /// checked before each request, so the request which crosses the limit
+    /// is allowed to complete.
+    pub tokens_per_minute: Option<u64>,
+    /// The number of tokens a component may use over the life of the
+    /// application.
+    pub total_tokens: Option<u64>,
+}
+
+impl LlmQuota {
+    /// Fills in any limits not set on this quota from `fallback`.
+    pub fn or(&self, fallback: &LlmQuota) -> LlmQuota {
+        LlmQuota {
+            max_tokens_per_request: self
+                .max_tokens_per_request
+                .or(fallback.max_tokens_per_request),
+            tokens_per_minute: self.tokens_per_minute.or(fallback.tokens_per_minute),
+            total_tokens: self.total_tokens.or(fallback.total_tokens),
+        }
+    }
+}
+
+/// The quotas applied to each component of an application.
+#[derive(Clone, Debug, Default)]
+pub struct LlmQuotas {
+    /// The quota for components with no quota of their own.
+    pub default: LlmQuota,
+    /// Quotas for specific components, by component id. Limits not set here
+    /// are taken from the default quota.
+    pub components: HashMap<String, LlmQuota>,
+}
+
+impl LlmQuotas {
+    fn quota(&self, component_id: &str) -> LlmQuota {
+        match self.components.get(component_id) {
+            Some(quota) => quota.or(&self.default),
+            None => self.default.clone(),
+        }
+    }
+}
+
+/// Token usage by a component.
+#[derive(Default)]
+struct ComponentUsage {
+    requests: u64,
+    prompt_tokens: u64,
+    generated_tokens: u64,
+    /// Tokens used within the rate window, with the time they were used.
+    /// This is only kept for components with a per-minute limit.
+    recent: VecDeque<(Instant, u64)>,
+}
+
+impl ComponentUsage {
+    fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.generated_tokens
+    }
+
+    /// Forgets usage from before the rate window.
+    fn prune_recent(&mut self, now: Instant) {
+        while let Some((at, _)) = self.recent.front() {
+            if now.duration_since(*at) < RATE_WINDOW {
+                break;
+            }
+            self.recent.pop_front();
+        }
+    }
+
+    fn tokens_in_last_minute(&mut self, now: Instant) -> u64 {
+        self.prune_recent(now);
+        self.recent.iter().map(|(_, tokens)| tokens).sum()
+    }
+}
+
+/// Accounts for, and enforces quotas on, the LLM usage of all components in
+/// an application. Clones share the same usage records.
+#[derive(Clone, Default)]
+pub(crate) struct UsageAccounting {
+    quotas: Arc<LlmQuotas>,
+    usage: Arc<Mutex<HashMap<String, ComponentUsage>>>,
+}
+
+impl UsageAccounting {
+    pub fn new(quotas: LlmQuotas) -> Self {
+        Self {
+            quotas: Arc::new(quotas),
+            usage: Default::default(),
+        }
+    }
+
+    /// Limits the number of tokens an inferencing request may generate.
+    pub fn limit_max_tokens(&self, component_id: &str, max_tokens: u32) -> u32 {
+        match self.quotas.quota(component_id).max_tokens_per_request {
+            Some(limit) => max_tokens.min(limit),
+            None => max_tokens,
+        }
+    }
+
+    /// Checks whether the component may make another request.
+    pub fn check(&self, component_id: &str) -> Result<(), v2::Error> {
+        self.check_at(component_id, Instant::now())
+    }
+
+    fn check_at(&self, component_id: &str, now: Instant) -> Result<(), v2::Error> {
+        let quota = self.quotas.quota(component_id);
+        let mut all_usage = self.usage.lock().unwrap();
+        let Some(usage) = all_usage.get_mut(component_id) else {
+            return Ok(());
+        };
+        if let Some(limit) = quota.total_tokens {
+            if usage.total_tokens() >= limit {
+                tracing::warn!("Component {component_id} has used its LLM token budget of {limit}");
+                return Err(v2::Error::RuntimeError(format!(
+                    "The component has used its LLM token budget of {limit} tokens"
+                )));
+            }
+        }
+        if let Some(limit) = quota.tokens_per_minute {
+            if usage.tokens_in_last_minute(now) >= limit {
+                tracing::warn!(
+                    "Component {component_id} has reached its LLM limit of {limit} tokens per minute"
+                );
+                return Err(v2::Error::RuntimeError(format!(
+                    "The component has reached its LLM limit of {limit} tokens per minute; try again later"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Records the tokens used by a completed request.
+    pub fn record(&self, component_id: &str, prompt_tokens: u32, generated_tokens: u32) {
+        self.record_at(
+            component_id,
+            prompt_tokens,
+            generated_tokens,
+            Instant::now(),
+        )
+    }
+
+    fn record_at(
+        &self,
+        component_id: &str,
+        prompt_tokens: u32,
+        generated_tokens: u32,
+        now: Instant,
+    ) {
+        let rate_limited = self.quotas.quota(component_id).tokens_per_minute.is_some();
+        let mut all_usage = self.usage.lock().unwrap();
+        let usage = all_usage.entry(component_id.to_owned()).or_default();
+        usage.requests += 1;
+        usage.prompt_tokens += u64::from(prompt_tokens);
+        usage.generated_tokens += u64::from(generated_tokens);
+        if rate_limited {
+            usage.prune_recent(now);
+            usage
+                .recent
+                .push_back((now, u64::from(prompt_tokens) + u64::from(generated_tokens)));
+        }
+        tracing::info!(
+            component_id,
+            prompt_tokens,
+            generated_tokens,
+            total_requests = usage.requests,
+            total_prompt_tokens = usage.prompt_tokens,
+            total_generated_tokens = usage.generated_tokens,
+            "LLM usage"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accounting(default: LlmQuota, components: &[(&str, LlmQuota)]) -> UsageAccounting {
+        UsageAccounting::new(LlmQuotas {
+            default,
+            components: components
+                .iter()
+                .map(|(id, quota)| (id.to_string(), quota.clone()))
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn unlimited_by_default() {
+        let accounting = UsageAccounting::default();
+        accounting.record("comp", 1000, 1000);
+        assert!(accounting.check("comp").is_ok());
+        assert_eq!(500, accounting.limit_max_tokens("comp", 500));
+    }
+
+    #[test]
+    fn max_tokens_is_limited() {
+        let accounting = accounting(
+            LlmQuota {
+                max_tokens_per_request: Some(100),
+                ..Default::default()
+            },
+            &[],
+        );
+        assert_eq!(100, accounting.limit_max_tokens("comp", 500));
+        assert_eq!(50, accounting.limit_max_tokens("comp", 50));
+    }
+
+    #[test]
+    fn total_budget_is_enforced_per_component() {
+        let accounting = accounting(
+            LlmQuota {
+                total_tokens: Some(100),
+                ..Default::default()
+            },
+            &[],
+        );
+        accounting.record("comp", 60, 30);
+        assert!(accounting.check("comp").is_ok());
+        accounting.record("comp", 5, 5);
+        assert!(accounting.check("comp").is_err());
+        assert!(accounting.check("other").is_ok());
+    }
+
+    #[test]
+    fn rate_is_enforced_over_the_last_minute() {
+        let accounting = accounting(
+            LlmQuota {
+                tokens_per_minute: Some(100),
+                ..Default::default()
+            },
+            &[],
+        );
+        accounting.record("comp", 50, 50);
+        assert!(accounting.check("comp").is_err());
+
+        let later = Instant::now() + RATE_WINDOW;
+        assert!(accounting.check_at("comp", later).is_ok());
+    }
+
+    #[test]
+    fn component_quota_falls_back_to_default() {
+        let accounting = accounting(
+            LlmQuota {
+                max_tokens_per_request: Some(100),
+                total_tokens: Some(10),
+                ..Default::default()
+            },
+            &[(
+                "comp",
+                LlmQuota {
+                    total_tokens: Some(1000),
+                    ..Default::default()
+                },
+            )],
+        );
+        accounting.record("comp", 50, 50);
+        assert!(accounting.check("comp").is_ok());
+        assert_eq!(100, accounting.limit_max_tokens("comp", 500));
+    }
+
+    fn recent_entries(accounting: &UsageAccounting, component_id: &str) -> usize {
+        let all_usage = accounting.usage.lock().unwrap();
+        all_usage[component_id].recent.len()
+    }
+
+    #[test]
+    fn recent_usage_is_only_kept_with_a_rate_limit() {
+        let accounting = accounting(
+            LlmQuota::default(),
+            &[(
+                "limited",
+                LlmQuota {
+                    tokens_per_minute: Some(1000),
+                    ..Default::default()
+                },
+            )],
+        );
+        accounting.record("unlimited", 1, 1);
+        accounting.record("unlimited", 1, 1);
+        assert_eq!(0, recent_entries(&accounting, "unlimited"));
+        accounting.record("limited", 1, 1);
+        assert_eq!(1, recent_entries(&accounting, "limited"));
+    }
+
+    #[test]
+    fn recording_forgets_usage_outside_the_window() {
+        let accounting = accounting(
+            LlmQuota {
+                tokens_per_minute: Some(1000),
+                ..Default::default()
+            },
+            &[],
+        );
+        let start = Instant::now();
+        accounting.record_at("comp", 1, 1, start);
+        accounting.record_at("comp", 1, 1, start + RATE_WINDOW / 2);
+        assert_eq!(2, recent_entries(&accounting, "comp"));
+        accounting.record_at("comp", 1, 1, start + RATE_WINDOW);
+        assert_eq!(2, recent_entries(&accounting, "comp"));
+        accounting.record_at("comp", 1, 1, start + RATE_WINDOW * 3);
+        assert_eq!(1, recent_entries(&accounting, "comp"));
+    }
+}
@@ -39,12 +39,12 @@ impl Cache {
     }
 
     /// The Wasm bytes directory for the current cache.
-    fn wasm_dir(&self) -> PathBuf {
+    pub fn wasm_dir(&self) -> PathBuf {
         self.root.join(WASM_DIR)
     }
 
     /// The data directory for the current cache.
-    fn data_dir(&self) -> PathBuf {
+    pub fn data_dir(&self) -> PathBuf {
         self.root.join(DATA_DIR)
     }
 
@@ -99,6 +99,18 @@ impl Cache {
         self.data_dir().join(safe_name(digest).as_ref())
     }
 
+    /// Remove all manifests, Wasm and data from the cache.
+    pub async fn clear(&self) -> Result<()> {
+        for dir in [self.manifests_dir(), self.wasm_dir(), self.data_dir()] {
+            if dir.is_dir() {
+                fs::remove_dir_all(&dir)
+                    .await
+                    .with_context(|| format!("failed to remove `{}`", dir.display()))?;
+            }
+        }
+        Self::ensure_dirs(&self.root).await
+    }
+
     /// Ensure the expected configuration directories are found in the root.
     /// └── <configuration-root>
     ///     └── registry
@@ -0,0 +1,241 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, ensure, Context, Result};
+use spin_common::ui::quoted_path;
+use wasm_compose::{
+    composer::ComponentComposer,
+    config::{Config, Dependency as ComposeDependency, Instantiation, InstantiationArg},
+};
+use wit_component::DecodedWasm;
+
+/// The name wasm-compose gives the instantiation of the component being
+/// composed.
+const ROOT_INSTANTIATION: &str = "$input";
+
+/// A component which satisfies an import of the component being composed.
+pub(crate) struct Dependency {
+    /// The name of the import, e.g. `myorg:auth/authenticator`
+    pub import: String,
+    /// The path of the dependency's Wasm
+    pub path: PathBuf,
+    /// The name of the dependency's export which satisfies the import
+    pub export: String,
+}
+
+/// Composes the component at the given path with its dependencies, returning
+/// the composed component.
+pub(crate) fn compose(component_path: &Path, dependencies: &[Dependency]) -> Result<Vec<u8>> {
+    let component = read_component(component_path)?;
+    let imports = interface_names(&component, false)?;
+
+    let dir = tempfile::tempdir().context("failed to create composition directory")?;
+    let root_path = dir.path().join("component.wasm");
+    std::fs::write(&root_path, &component)?;
+
+    let mut config = Config {
+        dir: dir.path().to_owned(),
+        ..Default::default()
+    };
+    let mut root = Instantiation::default();
+    for (index, dependency) in dependencies.iter().enumerate() {
+        ensure!(
+            imports.contains(&dependency.import),
+            "the component does not import `{}`; its imports are: {}",
+            dependency.import,
+            imports.join(", ")
+        );
+        let bytes = read_component(&dependency.path)?;
+        let exports = interface_names(&bytes, true)?;
+        ensure!(
+            exports.contains(&dependency.export),
+            "{} does not export `{}`, so cannot satisfy import `{}`; its exports are: {}",
+            quoted_path(&dependency.path),
+            dependency.export,
+            dependency.import,
+            exports.join(", ")
+        );
+
+        let name = format!("dependency-{index}");
+        let path = dir.path().join(format!("{name}.wasm"));
+        std::fs::write(&path, bytes)?;
+        config
+            .dependencies
+            .insert(name.clone(), ComposeDependency { path });
+        config.instantiations.insert(
+            name.clone(),
+            Instantiation {
+                dependency: Some(name.clone()),
+                ..Default::default()
+            },
+        );
+        root.arguments.insert(
+            dependency.import.clone(),
+            InstantiationArg {
+                instance: name,
+                export: Some(dependency.export.clone()),
+            },
+        );
+    }
+    config
+        .instantiations
+        .insert(ROOT_INSTANTIATION.into(), root);
+
+    let composed = ComponentComposer::new(&root_path, &config)
+        .compose()
+        .context("failed to compose the component with its dependencies")?;
+
+    let remaining = interface_names(&composed, false)?;
+    for dependency in dependencies {
+        ensure!(
+            !remaining.contains(&dependency.import),
+            "import `{}` was not satisfied by {}",
+            dependency.import,
+            quoted_path(&dependency.path)
+        );
+    }
+    Ok(composed)
+}
+
+/// Reads a component, converting it from a module if necessary.
+fn read_component(path: &Path) -> Result<Vec<u8>> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("failed to read {}", quoted_path(path)))?;
+    let component = spin_componentize::componentize_if_necessary(&bytes)
+        .with_context(|| format!("failed to componentize {}", quoted_path(path)))?;
+    Ok(component.into_owned())
+}
+
+/// Returns the names of the component's imported (or exported) interfaces
+/// and functions.
+fn interface_names(component: &[u8], exports: bool) -> Result<Vec<String>> {
+    let DecodedWasm::Component(resolve, world) = wit_component::decode(component)? else {
+        bail!("expected a component, found a WIT package");
+    };
+    let world = &resolve.worlds[world];
+    let items = if exports {
+        &world.exports
+    } else {
+        &world.imports
+    };
+    Ok(items
+        .keys()
+        .map(|key| resolve.name_world_key(key))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use wit_component::{ComponentEncoder, StringEncoding};
+    use wit_parser::{Resolve, UnresolvedPackage};
+
+    use super::*;
+
+    const WIT: &str = r#"
+        package test:compose;
+
+        interface greeter {
+            greet: func(name: string) -> string;
+        }
+
+        interface farewell {
+            goodbye: func() -> string;
+        }
+
+        world app {
+            import greeter;
+            export run: func() -> string;
+        }
+
+        world greeter-impl {
+            export greeter;
+        }
+    "#;
+
+    // Builds a component implementing the given world, with functions that trap.
+    fn build_component(dir: &Path, world: &str) -> PathBuf {
+        let mut resolve = Resolve::default();
+        let package = UnresolvedPackage::parse(Path::new("test.wit"), WIT).unwrap();
+        let package = resolve.push(package).unwrap();
+        let world_id = resolve.select_world(package, Some(world)).unwrap();
+
+        let mut module = wit_component::dummy_module(&resolve, world_id);
+        wit_component::embed_component_metadata(
+            &mut module,
+            &resolve,
+            world_id,
+            StringEncoding::UTF8,
+        )
+        .unwrap();
+        let component = ComponentEncoder::default()
+            .module(&module)
+            .unwrap()
+            .validate(true)
+            .encode()
+            .unwrap();
+
+        let path = dir.join(format!("{world}.wasm"));
+        std::fs::write(&path, component).unwrap();
+        path
+    }
+
+    #[test]
+    fn composes_component_with_dependency() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = build_component(dir.path(), "app");
+        let greeter = build_component(dir.path(), "greeter-impl");
+        assert_eq!(
+            vec!["test:compose/greeter"],
+            interface_names(&std::fs::read(&app).unwrap(), false).unwrap()
+        );
+
+        let composed = compose(
+            &app,
+            &[Dependency {
+                import: "test:compose/greeter".into(),
+                path: greeter,
+                export: "test:compose/greeter".into(),
+            }],
+        )
+        .unwrap();
+
+        assert!(interface_names(&composed, false).unwrap().is_empty());
+        assert_eq!(vec!["run"], interface_names(&composed, true).unwrap());
+    }
+
+    #[test]
+    fn dependency_must_export_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = build_component(dir.path(), "app");
+        let greeter = build_component(dir.path(), "greeter-impl");
+
+        let err = compose(
+            &app,
+            &[Dependency {
+                import: "test:compose/greeter".into(),
+                path: greeter,
+                export: "test:compose/farewell".into(),
+            }],
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("does not export `test:compose/farewell`"),
+            "{err:#}"
+        );
+
+        let err = compose(
+            &app,
+            &[Dependency {
+                import: "test:compose/farewell".into(),
+                path: app.clone(),
+                export: "test:compose/farewell".into(),
+            }],
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("does not import `test:compose/farewell`"),
+            "{err:#}"
+        );
+    }
+}
@@ -10,14 +10,19 @@
 
 #![deny(missing_docs)]
 
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use anyhow::Result;
+use async_trait::async_trait;
 use local::LocalLoader;
 use spin_common::paths::parent_dir;
 use spin_locked_app::locked::LockedApp;
 
 pub mod cache;
+mod compose;
 mod http;
 mod local;
 
@@ -26,18 +31,39 @@ pub(crate) const MAX_FILE_LOADING_CONCURRENCY: usize = 16;
 
 /// Load a Spin locked app from a spin.toml manifest file. If `files_mount_root`
 /// is given, `files` mounts will be copied to that directory. If not, `files`
-/// mounts will validated as "direct mounts".
+/// mounts will validated as "direct mounts". If `profile` is given, the
+/// manifest profile of that name is applied to the app. Component dependencies
+/// published to registries are pulled using `registry`; if it is not given,
+/// apps with such dependencies cannot be loaded.
 pub async fn from_file(
     manifest_path: impl AsRef<Path>,
     files_mount_strategy: FilesMountStrategy,
     cache_root: Option<PathBuf>,
+    profile: Option<&str>,
+    registry: Option<Arc<dyn ComponentRegistry>>,
 ) -> Result<LockedApp> {
     let path = manifest_path.as_ref();
     let app_root = parent_dir(path)?;
-    let loader = LocalLoader::new(&app_root, files_mount_strategy, cache_root).await?;
+    let loader = LocalLoader::new(
+        &app_root,
+        files_mount_strategy,
+        cache_root,
+        profile,
+        registry,
+    )
+    .await?;
     loader.load_file(path).await
 }
 
+/// A source of Wasm components published to OCI registries, used to resolve
+/// component dependencies which refer to a registry.
+#[async_trait]
+pub trait ComponentRegistry: Send + Sync {
+    /// Pull the component at the given registry reference, returning the
+    /// path of a local copy of its Wasm.
+    async fn pull_component(&self, reference: &str) -> Result<PathBuf>;
+}
+
 /// The strategy to use for mounting WASI files into a guest.
 #[derive(Debug)]
 pub enum FilesMountStrategy {
@@ -1,9 +1,12 @@
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use anyhow::{bail, ensure, Context, Result};
 use futures::future::try_join_all;
 use reqwest::Url;
-use spin_common::{paths::parent_dir, sloth, ui::quoted_path};
+use spin_common::{paths::parent_dir, sha256, sloth, ui::quoted_path, url::parse_file_url};
 use spin_locked_app::{
     locked::{
         self, ContentPath, ContentRef, LockedApp, LockedComponent, LockedComponentSource,
@@ -14,14 +17,17 @@ use spin_locked_app::{
 use spin_manifest::schema::v2::{self, AppManifest, KebabId, WasiFilesMount};
 use tokio::{fs, sync::Semaphore};
 
-use crate::{cache::Cache, http::verified_download, FilesMountStrategy};
+use crate::{
+    cache::Cache, compose, http::verified_download, ComponentRegistry, FilesMountStrategy,
+};
 
-#[derive(Debug)]
 pub struct LocalLoader {
     app_root: PathBuf,
     files_mount_strategy: FilesMountStrategy,
     cache: Cache,
     file_loading_permits: Semaphore,
+    profile: Option<String>,
+    registry: Option<Arc<dyn ComponentRegistry>>,
 }
 
 impl LocalLoader {
@@ -29,6 +35,8 @@ impl LocalLoader {
         app_root: &Path,
         files_mount_strategy: FilesMountStrategy,
         cache_root: Option<PathBuf>,
+        profile: Option<&str>,
+        registry: Option<Arc<dyn ComponentRegistry>>,
     ) -> Result<Self> {
         let app_root = safe_canonicalize(app_root)
             .with_context(|| format!("Invalid manifest dir `{}`", app_root.display()))?;
@@ -38,6 +46,8 @@ impl LocalLoader {
             cache: Cache::new(cache_root).await?,
             // Limit concurrency to avoid hitting system resource limits
             file_loading_permits: Semaphore::new(crate::MAX_FILE_LOADING_CONCURRENCY),
+            profile: profile.map(ToOwned::to_owned),
+            registry,
         })
     }
 
@@ -68,6 +78,7 @@ impl LocalLoader {
     // Load the given manifest into a LockedApp, ready for execution.
     async fn load_manifest(&self, mut manifest: AppManifest) -> Result<LockedApp> {
         spin_manifest::normalize::normalize_manifest(&mut manifest);
+        spin_manifest::normalize::apply_profile(&mut manifest, self.profile.as_deref())?;
 
         let AppManifest {
             spin_manifest_version: _,
@@ -75,6 +86,7 @@ impl LocalLoader {
             variables,
             triggers,
             components,
+            profiles: _,
         } = manifest;
 
         let metadata = locked_metadata(application, triggers.keys().cloned())?;
@@ -127,12 +139,24 @@ impl LocalLoader {
         let _ = spin_outbound_networking::AllowedHostsConfig::parse(&allowed_outbound_hosts)
             .context("`allowed_outbound_hosts` is malformed")?;
 
+        for (name, guest_path) in &component.volumes {
+            ensure!(
+                guest_path.starts_with('/'),
+                "Volume '{name}' must be mounted at an absolute path, not {guest_path:?}"
+            );
+        }
+
+        // Only record volumes if there are any, to keep lockfiles tidy
+        let volumes = (!component.volumes.is_empty()).then_some(component.volumes);
+
         let metadata = ValuesMapBuilder::new()
             .string("description", component.description)
             .string_array("allowed_outbound_hosts", allowed_outbound_hosts)
             .string_array("key_value_stores", component.key_value_stores)
             .string_array("databases", component.sqlite_databases)
+            .string_array("vector_stores", component.vector_stores)
             .string_array("ai_models", component.ai_models)
+            .serializable("volumes", volumes)?
             .serializable("build", component.build)?
             .take();
 
@@ -141,6 +165,14 @@ impl LocalLoader {
             .await
             .with_context(|| format!("Failed to load Wasm source {}", component.source))?;
 
+        let source = if component.dependencies.is_empty() {
+            source
+        } else {
+            self.compose_dependencies(source, component.dependencies)
+                .await
+                .context("Failed to compose component dependencies")?
+        };
+
         let env = component.environment.into_iter().collect();
 
         let files = if component.files.is_empty() {
@@ -210,9 +242,73 @@ impl LocalLoader {
         })
     }
 
+    // Compose the component from the given source with the components which
+    // satisfy its dependencies, returning a source for the composed component.
+    async fn compose_dependencies(
+        &self,
+        source: LockedComponentSource,
+        dependencies: impl IntoIterator<Item = (String, v2::ComponentDependency)>,
+    ) -> Result<LockedComponentSource> {
+        let component_path = parse_file_url(
+            source
+                .content
+                .source
+                .as_deref()
+                .context("component source is not a file")?,
+        )?;
+        let dependencies = try_join_all(dependencies.into_iter().map(
+            |(import, dependency)| async move {
+                let path = self.load_dependency(&dependency).await.with_context(|| {
+                    format!("Failed to load dependency {dependency} for `{import}`")
+                })?;
+                let export = dependency.export().unwrap_or(&import).to_owned();
+                anyhow::Ok(compose::Dependency {
+                    import,
+                    path,
+                    export,
+                })
+            },
+        ))
+        .await?;
+
+        let composed =
+            tokio::task::spawn_blocking(move || compose::compose(&component_path, &dependencies))
+                .await??;
+        let digest = format!("sha256:{}", sha256::hex_digest_from_bytes(&composed));
+        self.cache.write_wasm(&composed, &digest).await?;
+        Ok(LockedComponentSource {
+            content_type: source.content_type,
+            content: file_content_ref(self.cache.wasm_path(&digest))?,
+        })
+    }
+
+    // Load the Wasm for a component dependency, returning the path of the
+    // local copy.
+    async fn load_dependency(&self, dependency: &v2::ComponentDependency) -> Result<PathBuf> {
+        match dependency {
+            v2::ComponentDependency::Local { path, .. } => Ok(self.app_root.join(path)),
+            v2::ComponentDependency::Http { url, digest, .. } => {
+                self.load_http_file(url, digest).await
+            }
+            v2::ComponentDependency::Registry { registry, .. } => {
+                let client = self.registry.as_ref().with_context(|| {
+                    format!("cannot pull {registry}: no registry client is available")
+                })?;
+                let _loading_permit = self.file_loading_permits.acquire().await?;
+                client.pull_component(registry).await
+            }
+        }
+    }
+
     // Load a Wasm source from the given HTTP ContentRef source URL and
     // return a ContentRef an absolute path to the local copy.
     async fn load_http_source(&self, url: &str, digest: &str) -> Result<ContentRef> {
+        file_content_ref(self.load_http_file(url, digest).await?)
+    }
+
+    // Download the Wasm file at the given URL, verifying its digest, and
+    // return the path of the local copy.
+    async fn load_http_file(&self, url: &str, digest: &str) -> Result<PathBuf> {
         ensure!(
             digest.starts_with("sha256:"),
             "invalid `digest` {digest:?}; must start with 'sha256:'"
@@ -228,7 +324,7 @@ impl LocalLoader {
                 .with_context(|| format!("Error fetching source URL {url:?}"))?;
             dest
         };
-        file_content_ref(path)
+        Ok(path)
     }
 
     // Copy content(s) from the given `mount`
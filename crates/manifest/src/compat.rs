@@ -79,10 +79,13 @@ pub fn v1_to_v2_app(manifest: v1::AppManifestV1) -> Result<v2::AppManifest, Erro
                 environment: component.environment,
                 files: component.files,
                 exclude_files: component.exclude_files,
+                volumes: Default::default(),
                 key_value_stores,
                 sqlite_databases,
+                vector_stores: Vec::new(),
                 ai_models,
                 build: component.build,
+                dependencies: Default::default(),
                 tool: Default::default(),
                 allowed_outbound_hosts,
                 allowed_http_hosts: Vec::new(),
@@ -104,10 +107,13 @@ pub fn v1_to_v2_app(manifest: v1::AppManifestV1) -> Result<v2::AppManifest, Erro
         variables: app_variables,
         triggers,
         components,
+        profiles: Default::default(),
     })
 }
 
-pub(crate) fn convert_allowed_http_to_allowed_hosts(
+/// Converts deprecated `allowed_http_hosts` into the equivalent
+/// `allowed_outbound_hosts`.
+pub fn convert_allowed_http_to_allowed_hosts(
     allowed_http_hosts: &[impl AsRef<str>],
     allow_database_access: bool,
 ) -> anyhow::Result<Vec<String>> {
@@ -144,7 +150,7 @@ pub(crate) fn convert_allowed_http_to_allowed_hosts(
     Ok(outbound_hosts)
 }
 
-fn component_id_from_string(id: String) -> Result<v2::KebabId, Error> {
+pub(crate) fn component_id_from_string(id: String) -> Result<v2::KebabId, Error> {
     // If it's already valid, do nothing
     if let Ok(id) = id.clone().try_into() {
         return Ok(id);
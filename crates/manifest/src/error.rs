@@ -16,6 +16,15 @@ pub enum Error {
         reason: String,
     },
 
+    /// Invalid profile
+    #[error("invalid profile `{profile}`: {reason}")]
+    InvalidProfile {
+        /// The profile name
+        profile: String,
+        /// The reason why the profile is invalid
+        reason: String,
+    },
+
     /// Invalid trigger config
     #[error("invalid `{trigger_type}` trigger config: {reason}")]
     InvalidTriggerConfig {
@@ -6,6 +6,7 @@ pub mod compat;
 pub mod error;
 pub mod normalize;
 pub mod schema;
+pub mod validate;
 
 use std::path::Path;
 
@@ -54,4 +55,12 @@ impl ManifestVersion {
             Err(Error::InvalidVersion(spin_manifest_version.to_string()))
         }
     }
+
+    /// Returns the JSON Schema for this version of the manifest.
+    pub fn json_schema(&self) -> schemars::schema::RootSchema {
+        match self {
+            Self::V1 => schemars::schema_for!(schema::v1::AppManifestV1),
+            Self::V2 => schemars::schema_for!(AppManifest),
+        }
+    }
 }
@@ -2,7 +2,13 @@
 
 use std::collections::HashSet;
 
-use crate::schema::v2::{AppManifest, ComponentSpec, KebabId};
+use crate::{
+    schema::{
+        common::Variable,
+        v2::{AppManifest, ComponentSpec, KebabId},
+    },
+    Error,
+};
 
 /// Normalizes some optional [`AppManifest`] features into a canonical form:
 /// - Inline components in trigger configs are moved into top-level
@@ -13,6 +19,60 @@ pub fn normalize_manifest(manifest: &mut AppManifest) {
     normalize_inline_components(manifest);
 }
 
+/// Applies the named profile (if any) to the [`AppManifest`], and removes all
+/// profiles from it:
+/// - The profile's variables add to the app's variables, or are merged into
+///   them; a profile cannot make a secret variable non-secret.
+/// - The profile's component settings are overlaid on the app's components
+///   (see [`ComponentOverlay`](crate::schema::v2::ComponentOverlay)).
+///
+/// This should be applied after [`normalize_manifest`], so that overlays can
+/// refer to inline components by their normalized IDs.
+pub fn apply_profile(manifest: &mut AppManifest, profile: Option<&str>) -> Result<(), Error> {
+    let mut profiles = std::mem::take(&mut manifest.profiles);
+    let Some(name) = profile else {
+        return Ok(());
+    };
+    let invalid = |reason: String| Error::InvalidProfile {
+        profile: name.to_owned(),
+        reason,
+    };
+    let profile = profiles.swap_remove(name).ok_or_else(|| {
+        let known = profiles.keys().cloned().collect::<Vec<_>>();
+        invalid(if known.is_empty() {
+            "the manifest defines no profiles".to_owned()
+        } else {
+            format!("no such profile; profiles are: {}", known.join(", "))
+        })
+    })?;
+
+    for (name, overlay) in profile.variables {
+        match manifest.variables.get_mut(&name) {
+            Some(variable) => merge_variable(variable, overlay),
+            None => {
+                manifest.variables.insert(name, overlay);
+            }
+        }
+    }
+    for (id, overlay) in profile.components {
+        let component = manifest
+            .components
+            .get_mut(&id)
+            .ok_or_else(|| invalid(format!("no such component `{id}`")))?;
+        overlay.apply_to(component);
+    }
+    Ok(())
+}
+
+/// Merges a profile's definition of a variable into the app's. The profile
+/// decides whether the variable is required or has a default, but cannot
+/// make a secret variable non-secret.
+fn merge_variable(variable: &mut Variable, overlay: Variable) {
+    variable.required = overlay.required;
+    variable.default = overlay.default;
+    variable.secret |= overlay.secret;
+}
+
 fn normalize_inline_components(manifest: &mut AppManifest) {
     // Normalize inline components
     let components = &mut manifest.components;
@@ -103,3 +163,90 @@ fn normalize_trigger_ids(manifest: &mut AppManifest) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use toml::toml;
+
+    use super::*;
+    use crate::schema::v2::SnakeId;
+
+    fn manifest() -> AppManifest {
+        AppManifest::deserialize(toml! {
+            spin_manifest_version = 2
+            [application]
+            name = "profiles"
+            [variables]
+            api_url = { default = "http://localhost:3000" }
+            [[trigger.http]]
+            route = "/..."
+            component = "api"
+            [component.api]
+            source = "api.wasm"
+            allowed_outbound_hosts = ["http://localhost:3000"]
+            environment = { LOG = "debug", REGION = "local" }
+            [profile.prod.variables]
+            api_url = { default = "https://api.example.com" }
+            [profile.prod.component.api]
+            allowed_outbound_hosts = ["https://api.example.com"]
+            environment = { LOG = "warn" }
+            build = { command = "cargo build --release" }
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn applies_profile_overlays() {
+        let mut manifest = manifest();
+        apply_profile(&mut manifest, Some("prod")).unwrap();
+        assert!(manifest.profiles.is_empty());
+
+        let api_url = &manifest.variables[&SnakeId::try_from("api_url".to_owned()).unwrap()];
+        assert_eq!(Some("https://api.example.com"), api_url.default.as_deref());
+
+        let api = &manifest.components[&KebabId::try_from("api".to_owned()).unwrap()];
+        assert_eq!(
+            vec!["https://api.example.com"],
+            api.normalized_allowed_outbound_hosts().unwrap()
+        );
+        assert_eq!("warn", api.environment["LOG"]);
+        assert_eq!("local", api.environment["REGION"]);
+        assert_eq!("cargo build --release", api.build.as_ref().unwrap().command);
+    }
+
+    #[test]
+    fn profile_variables_keep_secrecy() {
+        let mut manifest = AppManifest::deserialize(toml! {
+            spin_manifest_version = 2
+            [application]
+            name = "profiles"
+            [variables]
+            token = { required = true, secret = true }
+            [profile.dev.variables]
+            token = { default = "dev-token" }
+        })
+        .unwrap();
+        apply_profile(&mut manifest, Some("dev")).unwrap();
+
+        let token = &manifest.variables[&SnakeId::try_from("token".to_owned()).unwrap()];
+        assert!(token.secret);
+        assert!(!token.required);
+        assert_eq!(Some("dev-token"), token.default.as_deref());
+    }
+
+    #[test]
+    fn no_profile_leaves_manifest_unchanged() {
+        let mut manifest = manifest();
+        apply_profile(&mut manifest, None).unwrap();
+        assert!(manifest.profiles.is_empty());
+        let api = &manifest.components[&KebabId::try_from("api".to_owned()).unwrap()];
+        assert_eq!("debug", api.environment["LOG"]);
+    }
+
+    #[test]
+    fn unknown_profile_is_an_error() {
+        let err = apply_profile(&mut manifest(), Some("staging")).unwrap_err();
+        assert!(err.to_string().contains("profiles are: prod"), "{err}");
+    }
+}
@@ -1,9 +1,13 @@
 use std::fmt::Display;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+/// The JSON Schema of opaque TOML tables, such as trigger and tool settings.
+pub(crate) type TableSchema = serde_json::Map<String, serde_json::Value>;
+
 /// Variable definition
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Variable {
     /// `required = true`
@@ -18,7 +22,7 @@ pub struct Variable {
 }
 
 /// Component source
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields, untagged)]
 pub enum ComponentSource {
     /// `"local.wasm"`
@@ -42,7 +46,7 @@ impl Display for ComponentSource {
 }
 
 /// WASI files mount
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields, untagged)]
 pub enum WasiFilesMount {
     /// `"images/*.png"`
@@ -57,7 +61,7 @@ pub enum WasiFilesMount {
 }
 
 /// Component build configuration
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct ComponentBuildConfig {
     /// `command = "cargo build"`
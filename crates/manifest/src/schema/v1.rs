@@ -1,7 +1,9 @@
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 use spin_serde::FixedStringVersion;
 
+use super::common::TableSchema;
 pub use super::common::{
     ComponentBuildConfig as ComponentBuildConfigV1, ComponentSource as ComponentSourceV1,
     Variable as VariableV1, WasiFilesMount as WasiFilesMountV1,
@@ -10,7 +12,7 @@ pub use super::common::{
 type Map<K, V> = indexmap::IndexMap<K, V>;
 
 /// App manifest
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct AppManifestV1 {
     /// `spin_manifest_version = "1"`
@@ -40,18 +42,19 @@ pub struct AppManifestV1 {
 }
 
 /// App trigger config
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct AppTriggerV1 {
     /// `type = "trigger-type"`
     #[serde(rename = "type")]
     pub trigger_type: String,
     /// Trigger config
     #[serde(flatten)]
+    #[schemars(with = "TableSchema")]
     pub config: toml::Table,
 }
 
 /// Component definition
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct ComponentV1 {
     /// `id = "component-id"
@@ -59,6 +62,7 @@ pub struct ComponentV1 {
     /// `source = ...`
     pub source: ComponentSourceV1,
     /// `[component.trigger]`
+    #[schemars(with = "TableSchema")]
     pub trigger: toml::Table,
     /// `description = "Component description"`
     #[serde(default)]
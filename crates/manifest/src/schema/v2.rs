@@ -1,13 +1,21 @@
+use std::fmt::Display;
+
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{ArrayValidation, Schema, SchemaObject, SubschemaValidation},
+    JsonSchema,
+};
 use serde::{Deserialize, Serialize};
 use spin_serde::FixedVersion;
 pub use spin_serde::{KebabId, SnakeId};
 
+use super::common::TableSchema;
 pub use super::common::{ComponentBuildConfig, ComponentSource, Variable, WasiFilesMount};
 
 pub(crate) type Map<K, V> = indexmap::IndexMap<K, V>;
 
 /// App manifest
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct AppManifest {
     /// `spin_manifest_version = 2`
@@ -24,10 +32,14 @@ pub struct AppManifest {
     #[serde(rename = "component")]
     #[serde(default, skip_serializing_if = "Map::is_empty")]
     pub components: Map<KebabId, Component>,
+    /// `[profile.<name>]`
+    #[serde(rename = "profile")]
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub profiles: Map<String, Profile>,
 }
 
 /// App details
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct AppDetails {
     /// `name = "my-app"`
@@ -43,14 +55,16 @@ pub struct AppDetails {
     pub authors: Vec<String>,
     /// `[application.triggers.<type>]`
     #[serde(rename = "trigger", default, skip_serializing_if = "Map::is_empty")]
+    #[schemars(with = "Map<String, TableSchema>")]
     pub trigger_global_configs: Map<String, toml::Table>,
     /// Settings for custom tools or plugins. Spin ignores this field.
     #[serde(default, skip_serializing_if = "Map::is_empty")]
+    #[schemars(with = "Map<String, TableSchema>")]
     pub tool: Map<String, toml::Table>,
 }
 
 /// Trigger configuration
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub struct Trigger {
     /// `id = "trigger-id"`
     #[serde(default, skip_serializing_if = "String::is_empty")]
@@ -63,6 +77,7 @@ pub struct Trigger {
     pub components: Map<String, OneOrManyComponentSpecs>,
     /// Opaque trigger-type-specific config
     #[serde(flatten)]
+    #[schemars(with = "TableSchema")]
     pub config: toml::Table,
 }
 
@@ -71,6 +86,24 @@ pub struct Trigger {
 #[serde(transparent)]
 pub struct OneOrManyComponentSpecs(#[serde(with = "one_or_many")] pub Vec<ComponentSpec>);
 
+impl JsonSchema for OneOrManyComponentSpecs {
+    fn schema_name() -> String {
+        "OneOrManyComponentSpecs".into()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let many = SchemaObject {
+            instance_type: Some(schemars::schema::InstanceType::Array.into()),
+            array: Some(Box::new(ArrayValidation {
+                items: Some(gen.subschema_for::<ComponentSpec>().into()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        any_of(vec![gen.subschema_for::<ComponentSpec>(), many.into()])
+    }
+}
+
 /// Component reference or inline definition
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(deny_unknown_fields, untagged, try_from = "toml::Value")]
@@ -95,8 +128,32 @@ impl TryFrom<toml::Value> for ComponentSpec {
     }
 }
 
+impl JsonSchema for ComponentSpec {
+    fn schema_name() -> String {
+        "ComponentSpec".into()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        any_of(vec![
+            gen.subschema_for::<KebabId>(),
+            gen.subschema_for::<Component>(),
+        ])
+    }
+}
+
+fn any_of(schemas: Vec<Schema>) -> Schema {
+    SchemaObject {
+        subschemas: Some(Box::new(SubschemaValidation {
+            any_of: Some(schemas),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
 /// Component definition
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Component {
     /// `source = ...`
@@ -116,6 +173,9 @@ pub struct Component {
     /// `exclude_files = ["secrets/*"]`
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub exclude_files: Vec<String>,
+    /// `volumes = { cache = "/cache" }`
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub volumes: Map<SnakeId, String>,
     /// `allowed_http_hosts = ["example.com"]`
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub(crate) allowed_http_hosts: Vec<String>,
@@ -128,14 +188,21 @@ pub struct Component {
     /// `sqlite_databases = ["default"]`
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub sqlite_databases: Vec<SnakeId>,
+    /// `vector_stores = ["default"]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vector_stores: Vec<SnakeId>,
     /// `ai_models = ["llama2-chat"]`
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub ai_models: Vec<KebabId>,
     /// Build configuration
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub build: Option<ComponentBuildConfig>,
+    /// `[component.<id>.dependencies]`
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub dependencies: Map<String, ComponentDependency>,
     /// Settings for custom tools or plugins. Spin ignores this field.
     #[serde(default, skip_serializing_if = "Map::is_empty")]
+    #[schemars(with = "Map<String, TableSchema>")]
     pub tool: Map<String, toml::Table>,
 }
 
@@ -161,6 +228,160 @@ impl Component {
     }
 }
 
+/// A component which satisfies an import of another component, keyed by the
+/// name of the import (e.g. `"myorg:auth/authenticator"`)
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, untagged)]
+pub enum ComponentDependency {
+    /// `{ path = "libs/auth.wasm" }`
+    Local {
+        /// `path = "libs/auth.wasm"`
+        path: String,
+        /// `export = "myorg:auth/authenticator"`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        export: Option<String>,
+    },
+    /// `{ url = "https://example.test/auth.wasm", digest = "sha256:abc123..." }`
+    Http {
+        /// `url = "https://example.test/auth.wasm"`
+        url: String,
+        /// `digest = "sha256:abc123..."`
+        digest: String,
+        /// `export = "myorg:auth/authenticator"`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        export: Option<String>,
+    },
+    /// `{ registry = "ghcr.io/myorg/auth:1.0.0" }`
+    Registry {
+        /// `registry = "ghcr.io/myorg/auth:1.0.0"`
+        registry: String,
+        /// `export = "myorg:auth/authenticator"`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        export: Option<String>,
+    },
+}
+
+impl ComponentDependency {
+    /// The name of the dependency's export which satisfies the import, if it
+    /// differs from the name of the import.
+    pub fn export(&self) -> Option<&str> {
+        match self {
+            Self::Local { export, .. }
+            | Self::Http { export, .. }
+            | Self::Registry { export, .. } => export.as_deref(),
+        }
+    }
+}
+
+impl Display for ComponentDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Local { path, .. } => write!(f, "{path:?}"),
+            Self::Http { url, digest, .. } => write!(f, "{url:?} with digest {digest:?}"),
+            Self::Registry { registry, .. } => write!(f, "registry reference {registry:?}"),
+        }
+    }
+}
+
+/// Profile definition: settings overlaid on the app when the profile is
+/// selected (e.g. with `spin up --profile <name>`)
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// `[profile.<name>.variables]`
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub variables: Map<SnakeId, Variable>,
+    /// `[profile.<name>.component.<id>]`
+    #[serde(rename = "component")]
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub components: Map<KebabId, ComponentOverlay>,
+}
+
+/// Component settings overlaid by a profile. Settings which are present
+/// replace the component's settings, except for `variables` and
+/// `environment`, which are merged into the component's.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ComponentOverlay {
+    /// `source = ...`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<ComponentSource>,
+    /// `variables = { name = "{{ app_var }}"}`
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub variables: Map<SnakeId, String>,
+    /// `environment = { VAR = "value" }`
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub environment: Map<String, String>,
+    /// `files = [...]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub files: Option<Vec<WasiFilesMount>>,
+    /// `exclude_files = ["secrets/*"]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclude_files: Option<Vec<String>>,
+    /// `volumes = { cache = "/cache" }`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volumes: Option<Map<SnakeId, String>>,
+    /// `allowed_outbound_hosts = ["redis://myredishost.com:6379"]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_outbound_hosts: Option<Vec<String>>,
+    /// `key_value_stores = ["default"]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_value_stores: Option<Vec<SnakeId>>,
+    /// `sqlite_databases = ["default"]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sqlite_databases: Option<Vec<SnakeId>>,
+    /// `vector_stores = ["default"]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vector_stores: Option<Vec<SnakeId>>,
+    /// `ai_models = ["llama2-chat"]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_models: Option<Vec<KebabId>>,
+    /// Build configuration
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build: Option<ComponentBuildConfig>,
+}
+
+impl ComponentOverlay {
+    /// Overlays these settings onto the component.
+    pub fn apply_to(self, component: &mut Component) {
+        if let Some(source) = self.source {
+            component.source = source;
+        }
+        component.variables.extend(self.variables);
+        component.environment.extend(self.environment);
+        if let Some(files) = self.files {
+            component.files = files;
+        }
+        if let Some(exclude_files) = self.exclude_files {
+            component.exclude_files = exclude_files;
+        }
+        if let Some(volumes) = self.volumes {
+            component.volumes = volumes;
+        }
+        if let Some(allowed_outbound_hosts) = self.allowed_outbound_hosts {
+            // The profile's hosts replace all the component's hosts, including
+            // any given by the deprecated `allowed_http_hosts`.
+            component.allowed_http_hosts.clear();
+            component.allowed_outbound_hosts = allowed_outbound_hosts;
+        }
+        if let Some(key_value_stores) = self.key_value_stores {
+            component.key_value_stores = key_value_stores;
+        }
+        if let Some(sqlite_databases) = self.sqlite_databases {
+            component.sqlite_databases = sqlite_databases;
+        }
+        if let Some(vector_stores) = self.vector_stores {
+            component.vector_stores = vector_stores;
+        }
+        if let Some(ai_models) = self.ai_models {
+            component.ai_models = ai_models;
+        }
+        if let Some(build) = self.build {
+            component.build = Some(build);
+        }
+    }
+}
+
 mod one_or_many {
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
@@ -265,6 +486,37 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    fn deserializing_component_dependencies() {
+        let manifest = AppManifest::deserialize(toml! {
+            spin_manifest_version = 2
+            [application]
+            name = "dependencies"
+            [component.api]
+            source = "api.wasm"
+            [component.api.dependencies]
+            "myorg:auth/authenticator" = { path = "libs/auth.wasm" }
+            "myorg:log/logger" = { url = "https://example.test/log.wasm", digest = "sha256:abc123" }
+            "myorg:cache/store" = { registry = "ghcr.io/myorg/cache:1.0.0", export = "myorg:cache/kv" }
+        })
+        .unwrap();
+
+        let api_id: KebabId = "api".to_owned().try_into().unwrap();
+        let dependencies = &manifest.components[&api_id].dependencies;
+        assert!(matches!(
+            dependencies["myorg:auth/authenticator"],
+            ComponentDependency::Local { export: None, .. }
+        ));
+        assert!(matches!(
+            dependencies["myorg:log/logger"],
+            ComponentDependency::Http { .. }
+        ));
+        assert_eq!(
+            Some("myorg:cache/kv"),
+            dependencies["myorg:cache/store"].export()
+        );
+    }
+
     #[test]
     fn test_valid_snake_ids() {
         for valid in ["default", "mixed_CASE_words", "letters1_then2_numbers345"] {
@@ -0,0 +1,667 @@
+//! Manifest validation which reports every problem found, with its location,
+//! rather than stopping at the first.
+
+use std::{collections::HashSet, ops::Range};
+
+use serde::{de::IgnoredAny, Deserialize};
+use toml::Spanned;
+
+use crate::{
+    compat::{self, component_id_from_string, convert_allowed_http_to_allowed_hosts},
+    schema::{
+        v1::{AppManifestV1, AppTriggerV1, ComponentV1},
+        v2::{
+            AppDetails, Component, ComponentSpec, KebabId, Map, Profile, SnakeId, Trigger, Variable,
+        },
+    },
+    Error, ManifestVersion,
+};
+
+const V1_TOP_LEVEL_KEYS: &[&str] = &[
+    "spin_manifest_version",
+    "spin_version",
+    "name",
+    "version",
+    "description",
+    "authors",
+    "trigger",
+    "variables",
+    "component",
+];
+
+const V2_TOP_LEVEL_KEYS: &[&str] = &[
+    "spin_manifest_version",
+    "application",
+    "variables",
+    "trigger",
+    "component",
+    "profile",
+];
+
+/// A problem found in a manifest.
+#[derive(Debug)]
+pub struct Diagnostic {
+    /// A description of the problem.
+    pub message: String,
+    /// The byte range of the manifest text in which the problem was found, if
+    /// known.
+    pub span: Option<Range<usize>>,
+}
+
+impl Diagnostic {
+    fn new(message: impl Into<String>, span: Option<Range<usize>>) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+
+    fn from_toml_error(
+        context: impl std::fmt::Display,
+        err: toml::de::Error,
+        span: Range<usize>,
+    ) -> Self {
+        // Errors deserializing from a parsed value don't have their own span
+        let span = err.span().unwrap_or(span);
+        Self::new(format!("{context}: {}", err.message()), Some(span))
+    }
+
+    /// Returns the 1-based line and column at which the problem's span
+    /// starts in the given manifest text.
+    pub fn line_col(&self, manifest: &str) -> Option<(usize, usize)> {
+        let before = manifest.get(..self.span.as_ref()?.start)?;
+        let line = before.matches('\n').count() + 1;
+        let column = before
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .count()
+            + 1;
+        Some((line, column))
+    }
+}
+
+impl From<Error> for Diagnostic {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::TomlParse(err) => Self::new(err.message(), err.span()),
+            err => Self::new(err.to_string(), None),
+        }
+    }
+}
+
+/// Validates a V1 or V2 app manifest, returning all the problems found.
+///
+/// As well as checking the manifest against its schema, this checks that
+/// triggers and profiles refer to components which exist, and that component
+/// variable templates refer to app variables which exist.
+pub fn validate_manifest(manifest: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    match ManifestVersion::detect(manifest) {
+        Ok(ManifestVersion::V1) => validate_v1(manifest, &mut diagnostics),
+        Ok(ManifestVersion::V2) => validate_v2(manifest, &mut diagnostics),
+        Err(err) => diagnostics.push(err.into()),
+    }
+    diagnostics
+}
+
+/// The sections of a V1 manifest, with their locations.
+#[derive(Deserialize)]
+struct SpannedManifestV1 {
+    name: Option<Spanned<toml::Value>>,
+    version: Option<Spanned<toml::Value>>,
+    description: Option<Spanned<toml::Value>>,
+    authors: Option<Spanned<toml::Value>>,
+    trigger: Option<Spanned<toml::Value>>,
+    #[serde(default)]
+    variables: Map<Spanned<String>, Spanned<toml::Value>>,
+    #[serde(default, rename = "component")]
+    components: Vec<Spanned<toml::Value>>,
+}
+
+fn validate_v1(manifest: &str, diagnostics: &mut Vec<Diagnostic>) {
+    if !check_top_level_keys(manifest, V1_TOP_LEVEL_KEYS, diagnostics) {
+        return;
+    }
+
+    let sections: SpannedManifestV1 = match toml::from_str(manifest) {
+        Ok(sections) => sections,
+        Err(err) => return diagnostics.push(Error::TomlParse(err).into()),
+    };
+
+    match sections.name {
+        Some(name) => check_field::<String>("`name`", name, diagnostics),
+        None => diagnostics.push(Diagnostic::new("missing field `name`", None)),
+    }
+    if let Some(version) = sections.version {
+        check_field::<String>("`version`", version, diagnostics);
+    }
+    if let Some(description) = sections.description {
+        check_field::<String>("`description`", description, diagnostics);
+    }
+    if let Some(authors) = sections.authors {
+        check_field::<Vec<String>>("`authors`", authors, diagnostics);
+    }
+    match sections.trigger {
+        Some(trigger) => check_field::<AppTriggerV1>("`trigger`", trigger, diagnostics),
+        None => diagnostics.push(Diagnostic::new("missing field `trigger`", None)),
+    }
+
+    let variables = check_variables(sections.variables, "[variables]", diagnostics);
+
+    for (index, component) in sections.components.into_iter().enumerate() {
+        let span = component.span();
+        let component = match ComponentV1::deserialize(component.into_inner()) {
+            Ok(component) => component,
+            Err(err) => {
+                let context = format!("[[component]] #{}", index + 1);
+                diagnostics.push(Diagnostic::from_toml_error(context, err, span));
+                continue;
+            }
+        };
+        check_component_v1(&component, &variables, span, diagnostics);
+    }
+
+    // The checks above cover each section separately; converting the manifest
+    // as a whole catches anything they miss.
+    if diagnostics.is_empty() {
+        let result = toml::from_str::<AppManifestV1>(manifest)
+            .map_err(Error::TomlParse)
+            .and_then(compat::v1_to_v2_app);
+        if let Err(err) = result {
+            diagnostics.push(err.into());
+        }
+    }
+}
+
+fn check_component_v1(
+    component: &ComponentV1,
+    variables: &HashSet<String>,
+    span: Range<usize>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let context = format!("[[component]] `{}`", component.id);
+    let mut invalid = |message: String| {
+        diagnostics.push(Diagnostic::new(
+            format!("{context}: {message}"),
+            Some(span.clone()),
+        ))
+    };
+
+    if let Err(err) = component_id_from_string(component.id.clone()) {
+        invalid(err.to_string());
+    }
+    let snake_ids = component
+        .config
+        .keys()
+        .chain(&component.key_value_stores)
+        .chain(&component.sqlite_databases)
+        .chain(&component.ai_models);
+    for id in snake_ids {
+        if let Err(reason) = SnakeId::try_from(id.clone()) {
+            invalid(format!("invalid ID `{id}`: {reason}"));
+        }
+    }
+    if let Err(err) = convert_allowed_http_to_allowed_hosts(&component.allowed_http_hosts, false) {
+        invalid(format!("{err:#}"));
+    }
+
+    for (key, value) in &component.config {
+        check_template(
+            &format!("{context} config `{key}`"),
+            value,
+            variables,
+            Some(span.clone()),
+            diagnostics,
+        );
+    }
+}
+
+/// Checks that a top-level field deserializes as the given type.
+fn check_field<T: serde::de::DeserializeOwned>(
+    context: &str,
+    value: Spanned<toml::Value>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let span = value.span();
+    if let Err(err) = T::deserialize(value.into_inner()) {
+        diagnostics.push(Diagnostic::from_toml_error(context, err, span));
+    }
+}
+
+/// Reports any top-level keys which aren't in the given list. Returns false if
+/// the manifest could not be parsed at all.
+fn check_top_level_keys(
+    manifest: &str,
+    expected: &[&str],
+    diagnostics: &mut Vec<Diagnostic>,
+) -> bool {
+    let keys: Map<Spanned<String>, IgnoredAny> = match toml::from_str(manifest) {
+        Ok(keys) => keys,
+        Err(err) => {
+            diagnostics.push(Error::TomlParse(err).into());
+            return false;
+        }
+    };
+    let expected_list = expected
+        .iter()
+        .filter(|key| **key != "spin_version")
+        .map(|key| format!("`{key}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    for key in keys.keys() {
+        if !expected.contains(&key.get_ref().as_str()) {
+            diagnostics.push(Diagnostic::new(
+                format!(
+                    "unknown field `{}`, expected one of {expected_list}",
+                    key.get_ref()
+                ),
+                Some(key.span()),
+            ));
+        }
+    }
+    true
+}
+
+/// The sections of a V2 manifest, with their locations.
+#[derive(Deserialize)]
+struct SpannedManifestV2 {
+    application: Option<Spanned<toml::Value>>,
+    #[serde(default)]
+    variables: Map<Spanned<String>, Spanned<toml::Value>>,
+    #[serde(default, rename = "trigger")]
+    triggers: Map<String, Vec<Spanned<toml::Value>>>,
+    #[serde(default, rename = "component")]
+    components: Map<Spanned<String>, Spanned<toml::Value>>,
+    #[serde(default, rename = "profile")]
+    profiles: Map<Spanned<String>, Spanned<toml::Value>>,
+}
+
+fn validate_v2(manifest: &str, diagnostics: &mut Vec<Diagnostic>) {
+    if !check_top_level_keys(manifest, V2_TOP_LEVEL_KEYS, diagnostics) {
+        return;
+    }
+
+    let sections: SpannedManifestV2 = match toml::from_str(manifest) {
+        Ok(sections) => sections,
+        Err(err) => return diagnostics.push(Error::TomlParse(err).into()),
+    };
+
+    match sections.application {
+        Some(application) => {
+            let span = application.span();
+            if let Err(err) = AppDetails::deserialize(application.into_inner()) {
+                diagnostics.push(Diagnostic::from_toml_error("[application]", err, span));
+            }
+        }
+        None => diagnostics.push(Diagnostic::new("missing field `application`", None)),
+    }
+
+    let variables = check_variables(sections.variables, "[variables]", diagnostics);
+
+    let mut component_ids = HashSet::new();
+    let mut components = vec![];
+    for (id, component) in sections.components {
+        let span = component.span();
+        if let Err(reason) = KebabId::try_from(id.get_ref().clone()) {
+            diagnostics.push(Diagnostic::new(
+                format!("invalid component ID `{}`: {reason}", id.get_ref()),
+                Some(id.span()),
+            ));
+        }
+        component_ids.insert(id.get_ref().clone());
+        match Component::deserialize(component.into_inner()) {
+            Ok(component) => components.push((id.into_inner(), component, span)),
+            Err(err) => diagnostics.push(Diagnostic::from_toml_error(
+                format!("[component.{}]", id.get_ref()),
+                err,
+                span,
+            )),
+        }
+    }
+    for (id, component, span) in &components {
+        let context = format!("[component.{id}]");
+        check_templates(
+            &context,
+            component,
+            &variables,
+            Some(span.clone()),
+            diagnostics,
+        );
+    }
+
+    for (trigger_type, triggers) in sections.triggers {
+        for trigger in triggers {
+            let span = trigger.span();
+            let context = format!("[[trigger.{trigger_type}]]");
+            let trigger = match Trigger::deserialize(trigger.into_inner()) {
+                Ok(trigger) => trigger,
+                Err(err) => {
+                    diagnostics.push(Diagnostic::from_toml_error(context, err, span));
+                    continue;
+                }
+            };
+            let specs = trigger
+                .component
+                .iter()
+                .chain(trigger.components.values().flat_map(|specs| &specs.0));
+            for spec in specs {
+                match spec {
+                    ComponentSpec::Reference(id) if !component_ids.contains(id.as_ref()) => {
+                        diagnostics.push(Diagnostic::new(
+                            format!("{context}: no such component `{id}`"),
+                            Some(span.clone()),
+                        ));
+                    }
+                    ComponentSpec::Reference(_) => (),
+                    ComponentSpec::Inline(component) => {
+                        check_templates(
+                            &context,
+                            component,
+                            &variables,
+                            Some(span.clone()),
+                            diagnostics,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    for (name, profile) in sections.profiles {
+        let span = profile.span();
+        let context = format!("[profile.{}]", name.get_ref());
+        let profile = match Profile::deserialize(profile.into_inner()) {
+            Ok(profile) => profile,
+            Err(err) => {
+                diagnostics.push(Diagnostic::from_toml_error(context, err, span));
+                continue;
+            }
+        };
+        let mut variables = variables.clone();
+        for (name, variable) in &profile.variables {
+            check_variable(name.as_ref(), variable, &context, span.clone(), diagnostics);
+            variables.insert(name.to_string());
+        }
+        for (id, overlay) in &profile.components {
+            if !component_ids.contains(id.as_ref()) {
+                diagnostics.push(Diagnostic::new(
+                    format!("{context}: no such component `{id}`"),
+                    Some(span.clone()),
+                ));
+            }
+            for (key, value) in &overlay.variables {
+                check_template(
+                    &format!("{context} component `{id}` variable `{key}`"),
+                    value,
+                    &variables,
+                    Some(span.clone()),
+                    diagnostics,
+                );
+            }
+        }
+    }
+}
+
+fn check_variables(
+    variables: Map<Spanned<String>, Spanned<toml::Value>>,
+    context: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for (name, variable) in variables {
+        if let Err(reason) = SnakeId::try_from(name.get_ref().clone()) {
+            diagnostics.push(Diagnostic::new(
+                format!("invalid variable name `{}`: {reason}", name.get_ref()),
+                Some(name.span()),
+            ));
+        }
+        let span = variable.span();
+        match Variable::deserialize(variable.into_inner()) {
+            Ok(variable) => check_variable(name.get_ref(), &variable, context, span, diagnostics),
+            Err(err) => diagnostics.push(Diagnostic::from_toml_error(
+                format!("{context} `{}`", name.get_ref()),
+                err,
+                span,
+            )),
+        }
+        names.insert(name.into_inner());
+    }
+    names
+}
+
+fn check_variable(
+    name: &str,
+    variable: &Variable,
+    context: &str,
+    span: Range<usize>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if variable.required == variable.default.is_some() {
+        diagnostics.push(Diagnostic::new(
+            format!("{context} `{name}`: must be `required` OR have a `default`"),
+            Some(span),
+        ));
+    }
+}
+
+fn check_templates(
+    context: &str,
+    component: &Component,
+    variables: &HashSet<String>,
+    span: Option<Range<usize>>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for (key, value) in &component.variables {
+        check_template(
+            &format!("{context} variable `{key}`"),
+            value,
+            variables,
+            span.clone(),
+            diagnostics,
+        );
+    }
+}
+
+/// Checks that a `{{ var }}` template is well-formed and that the variables it
+/// refers to are defined.
+fn check_template(
+    context: &str,
+    template: &str,
+    variables: &HashSet<String>,
+    span: Option<Range<usize>>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let references = match template_references(template) {
+        Ok(references) => references,
+        Err(reason) => {
+            diagnostics.push(Diagnostic::new(format!("{context}: {reason}"), span));
+            return;
+        }
+    };
+    for name in references {
+        if !variables.contains(name) {
+            diagnostics.push(Diagnostic::new(
+                format!("{context}: template refers to undefined variable `{name}`"),
+                span.clone(),
+            ));
+        }
+    }
+}
+
+/// Returns the names of the variables referred to by a `{{ var }}` template,
+/// or a reason if the template is malformed.
+pub fn template_references(template: &str) -> Result<Vec<&str>, String> {
+    let mut references = vec![];
+    let mut remainder = template;
+    while let Some((_, expr_rest)) = remainder.split_once("{{") {
+        let Some((expr, rest)) = expr_rest.split_once("}}") else {
+            return Err(format!("unmatched '{{{{' in template {template:?}"));
+        };
+        references.push(expr.trim());
+        remainder = rest;
+    }
+    Ok(references)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(manifest: &str) -> Vec<String> {
+        validate_manifest(manifest)
+            .into_iter()
+            .map(|d| d.message)
+            .collect()
+    }
+
+    #[test]
+    fn valid_manifest_has_no_diagnostics() {
+        let manifest = r#"
+            spin_manifest_version = 2
+            [application]
+            name = "valid"
+            [variables]
+            greeting = { default = "hello" }
+            [[trigger.http]]
+            route = "/..."
+            component = "hello"
+            [component.hello]
+            source = "hello.wasm"
+            variables = { message = "{{ greeting }}, world" }
+        "#;
+        assert!(messages(manifest).is_empty(), "{:?}", messages(manifest));
+    }
+
+    #[test]
+    fn reports_all_problems() {
+        let manifest = r#"spin_manifest_version = 2
+[application]
+name = "invalid"
+[variables]
+token = { required = true, default = "oops" }
+[[trigger.http]]
+route = "/..."
+component = "missing"
+[component.first]
+source = "first.wasm"
+unknown_field = true
+[component.second]
+source = "second.wasm"
+variables = { message = "{{ undefined }}" }
+"#;
+        let diagnostics = validate_manifest(manifest);
+        assert_eq!(4, diagnostics.len(), "{diagnostics:?}");
+
+        let find = |needle: &str| {
+            diagnostics
+                .iter()
+                .find(|d| d.message.contains(needle))
+                .unwrap_or_else(|| panic!("no diagnostic containing {needle:?}: {diagnostics:?}"))
+        };
+        assert_eq!(Some((5, 9)), find("must be `required`").line_col(manifest));
+        assert!(find("no such component `missing`").span.is_some());
+        assert!(find("unknown field `unknown_field`").span.is_some());
+        assert!(find("undefined variable `undefined`").span.is_some());
+    }
+
+    #[test]
+    fn reports_all_v1_problems() {
+        let manifest = r#"spin_manifest_version = "1"
+name = "invalid"
+trigger = { type = "http" }
+unknown_top_level = true
+[variables]
+token = { required = true, default = "oops" }
+[[component]]
+id = "first"
+source = "first.wasm"
+unknown_field = true
+[component.trigger]
+route = "/first"
+[[component]]
+id = "second"
+source = "second.wasm"
+config = { message = "{{ undefined }}" }
+[component.trigger]
+route = "/second"
+"#;
+        let diagnostics = validate_manifest(manifest);
+        assert_eq!(4, diagnostics.len(), "{diagnostics:?}");
+
+        let find = |needle: &str| {
+            diagnostics
+                .iter()
+                .find(|d| d.message.contains(needle))
+                .unwrap_or_else(|| panic!("no diagnostic containing {needle:?}: {diagnostics:?}"))
+        };
+        assert_eq!(
+            Some((4, 1)),
+            find("unknown field `unknown_top_level`").line_col(manifest)
+        );
+        assert!(find("must be `required`").span.is_some());
+        assert!(find("unknown field `unknown_field`").span.is_some());
+        assert!(find("undefined variable `undefined`").span.is_some());
+    }
+
+    #[test]
+    fn top_level_keys_depend_on_version() {
+        let v2 = r#"
+            spin_version = 2
+            [application]
+            name = "v2"
+        "#;
+        let v2_messages = messages(v2);
+        assert_eq!(1, v2_messages.len(), "{v2_messages:?}");
+        assert!(
+            v2_messages[0].contains("unknown field `spin_version`"),
+            "{v2_messages:?}"
+        );
+
+        let v1 = r#"
+            spin_version = "1"
+            name = "v1"
+            trigger = { type = "http" }
+            [application]
+            name = "v1"
+        "#;
+        let v1_messages = messages(v1);
+        assert_eq!(1, v1_messages.len(), "{v1_messages:?}");
+        assert!(
+            v1_messages[0].contains("unknown field `application`"),
+            "{v1_messages:?}"
+        );
+    }
+
+    #[test]
+    fn reports_unmatched_template_braces() {
+        let manifest = r#"
+            spin_manifest_version = 2
+            [application]
+            name = "invalid"
+            [component.hello]
+            source = "hello.wasm"
+            variables = { message = "{{ unmatched" }
+        "#;
+        let messages = messages(manifest);
+        assert_eq!(1, messages.len(), "{messages:?}");
+        assert!(messages[0].contains("unmatched"), "{messages:?}");
+    }
+
+    #[test]
+    fn reports_syntax_errors_with_location() {
+        let manifest = "spin_manifest_version = 2\n[application\n";
+        let diagnostics = validate_manifest(manifest);
+        assert_eq!(1, diagnostics.len(), "{diagnostics:?}");
+        assert_eq!(Some(2), diagnostics[0].line_col(manifest).map(|(l, _)| l));
+    }
+
+    #[test]
+    fn json_schemas_are_generated() {
+        for version in [ManifestVersion::V1, ManifestVersion::V2] {
+            let schema = serde_json::to_value(version.json_schema()).unwrap();
+            assert!(schema["properties"].is_object(), "{schema}");
+        }
+    }
+}
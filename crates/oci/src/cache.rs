@@ -0,0 +1,359 @@
+//! Inspection and pruning of the registry cache.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, Result};
+use oci_distribution::manifest::OciImageManifest;
+use spin_common::ui::quoted_path;
+use spin_loader::cache::Cache;
+use spin_locked_app::locked::LockedApp;
+use walkdir::WalkDir;
+
+use crate::client::{CONFIG_FILE, MANIFEST_FILE};
+
+/// An app in the registry cache.
+#[derive(Debug)]
+pub struct CachedApp {
+    /// The reference the app was pulled from.
+    pub reference: String,
+    /// When the app was last pulled.
+    pub last_pulled: SystemTime,
+    /// The directory holding the app's manifest and config.
+    dir: PathBuf,
+    /// The size of the app's manifest and config.
+    metadata_size: u64,
+    /// The cached Wasm and data files the app refers to, and their sizes.
+    blobs: HashMap<PathBuf, u64>,
+}
+
+impl CachedApp {
+    /// The total size of the app's manifest, config and the cached content it
+    /// refers to. Content shared between apps is counted for each app.
+    pub fn size(&self) -> u64 {
+        self.metadata_size + self.blobs.values().sum::<u64>()
+    }
+}
+
+/// Limits on what is kept in the registry cache when pruning. Content not
+/// referred to by any cached app is always removed.
+#[derive(Debug, Default)]
+pub struct PrunePolicy {
+    /// Remove apps last pulled longer ago than this.
+    pub older_than: Option<Duration>,
+    /// Remove the least recently pulled apps until the cache is no larger
+    /// than this many bytes.
+    pub max_size: Option<u64>,
+}
+
+/// The outcome of pruning the registry cache.
+#[derive(Debug, Default)]
+pub struct PruneSummary {
+    /// The references of the apps removed from the cache.
+    pub removed_apps: Vec<String>,
+    /// The number of Wasm and data files removed from the cache.
+    pub removed_blobs: usize,
+    /// The number of bytes freed.
+    pub freed: u64,
+}
+
+/// Lists the apps in the registry cache, least recently pulled first.
+pub fn cached_apps(cache: &Cache) -> Result<Vec<CachedApp>> {
+    let manifests_dir = cache.manifests_dir();
+    let mut apps = vec![];
+    for entry in WalkDir::new(&manifests_dir) {
+        let entry = entry?;
+        if !entry.file_type().is_file() || entry.file_name() != MANIFEST_FILE {
+            continue;
+        }
+        let dir = entry
+            .path()
+            .parent()
+            .context("manifest file should be in a directory")?;
+        apps.push(cached_app(cache, &manifests_dir, dir)?);
+    }
+    apps.sort_by_key(|app| app.last_pulled);
+    Ok(apps)
+}
+
+/// The total size of the registry cache on disk.
+pub fn total_size(cache: &Cache) -> Result<u64> {
+    let mut size = 0;
+    for dir in [cache.manifests_dir(), cache.wasm_dir(), cache.data_dir()] {
+        for entry in WalkDir::new(dir) {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                size += entry.metadata()?.len();
+            }
+        }
+    }
+    Ok(size)
+}
+
+/// Removes apps from the registry cache according to the policy, then removes
+/// any content no longer referred to by a cached app.
+pub fn prune(cache: &Cache, policy: &PrunePolicy) -> Result<PruneSummary> {
+    let now = SystemTime::now();
+    let mut summary = PruneSummary::default();
+
+    let mut kept = vec![];
+    for app in cached_apps(cache)? {
+        let age = now.duration_since(app.last_pulled).unwrap_or_default();
+        if policy.older_than.is_some_and(|max_age| age > max_age) {
+            remove_app(cache, app, &mut summary)?;
+        } else {
+            kept.push(app);
+        }
+    }
+
+    if let Some(max_size) = policy.max_size {
+        // Apps are ordered least recently pulled first.
+        while !kept.is_empty() && used_size(&kept) > max_size {
+            let app = kept.remove(0);
+            remove_app(cache, app, &mut summary)?;
+        }
+    }
+
+    let referenced: HashSet<&PathBuf> = kept.iter().flat_map(|app| app.blobs.keys()).collect();
+    for dir in [cache.wasm_dir(), cache.data_dir()] {
+        for entry in std::fs::read_dir(&dir)
+            .with_context(|| format!("cannot read cache directory {}", quoted_path(&dir)))?
+        {
+            let entry = entry?;
+            let path = entry.path();
+            if !entry.file_type()?.is_file() || referenced.contains(&path) {
+                continue;
+            }
+            let size = entry.metadata()?.len();
+            std::fs::remove_file(&path)
+                .with_context(|| format!("cannot remove {}", quoted_path(&path)))?;
+            summary.removed_blobs += 1;
+            summary.freed += size;
+        }
+    }
+
+    Ok(summary)
+}
+
+fn cached_app(cache: &Cache, manifests_dir: &Path, dir: &Path) -> Result<CachedApp> {
+    let manifest_path = dir.join(MANIFEST_FILE);
+    let config_path = dir.join(CONFIG_FILE);
+    let last_pulled = std::fs::metadata(&manifest_path)?.modified()?;
+    let metadata_size = [&manifest_path, &config_path]
+        .iter()
+        .filter_map(|p| std::fs::metadata(p).ok())
+        .map(|m| m.len())
+        .sum();
+
+    let mut blobs = HashMap::new();
+    for digest in referenced_digests(&manifest_path, &config_path) {
+        for path in [cache.wasm_path(&digest), cache.data_path(&digest)] {
+            if let Ok(metadata) = std::fs::metadata(&path) {
+                blobs.insert(path, metadata.len());
+            }
+        }
+    }
+
+    Ok(CachedApp {
+        reference: reference_for_dir(manifests_dir, dir),
+        last_pulled,
+        dir: dir.to_owned(),
+        metadata_size,
+        blobs,
+    })
+}
+
+/// The digests of the layers in the manifest and the content in the locked
+/// app config. (Files unpacked from archive layers are only referred to by
+/// the latter.) Unreadable files refer to nothing, so that their content can
+/// be pruned.
+fn referenced_digests(manifest_path: &Path, config_path: &Path) -> Vec<String> {
+    let mut digests = vec![];
+    match std::fs::read(manifest_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| Ok(serde_json::from_slice::<OciImageManifest>(&json)?))
+    {
+        Ok(manifest) => digests.extend(manifest.layers.into_iter().map(|layer| layer.digest)),
+        Err(e) => tracing::warn!("Ignoring unreadable manifest {manifest_path:?}: {e:#}"),
+    }
+    match std::fs::read(config_path)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| Ok(LockedApp::from_json(&json)?))
+    {
+        Ok(locked) => {
+            for component in locked.components {
+                digests.extend(component.source.content.digest);
+                digests.extend(component.files.into_iter().filter_map(|f| f.content.digest));
+            }
+        }
+        Err(e) => tracing::warn!("Ignoring unreadable app config {config_path:?}: {e:#}"),
+    }
+    digests
+}
+
+/// Reconstructs the reference from the cache layout
+/// `<manifests_dir>/<registry>/<repository>/<tag>`.
+fn reference_for_dir(manifests_dir: &Path, dir: &Path) -> String {
+    let relative = dir.strip_prefix(manifests_dir).unwrap_or(dir);
+    let parts: Vec<_> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect();
+    match parts.split_last() {
+        Some((tag, repository)) if !repository.is_empty() => {
+            format!("{}:{tag}", repository.join("/"))
+        }
+        _ => relative.display().to_string(),
+    }
+}
+
+/// The size of the cache used by the given apps, counting shared content once.
+fn used_size(apps: &[CachedApp]) -> u64 {
+    let blobs: HashMap<&PathBuf, u64> = apps
+        .iter()
+        .flat_map(|app| app.blobs.iter().map(|(path, size)| (path, *size)))
+        .collect();
+    apps.iter().map(|app| app.metadata_size).sum::<u64>() + blobs.values().sum::<u64>()
+}
+
+fn remove_app(cache: &Cache, app: CachedApp, summary: &mut PruneSummary) -> Result<()> {
+    tracing::debug!("Removing {} from the registry cache", app.reference);
+    std::fs::remove_dir_all(&app.dir)
+        .with_context(|| format!("cannot remove {}", quoted_path(&app.dir)))?;
+
+    // Tidy up the now-empty registry and repository directories.
+    let manifests_dir = cache.manifests_dir();
+    let mut dir = app.dir.parent();
+    while let Some(d) = dir {
+        if d == manifests_dir || std::fs::remove_dir(d).is_err() {
+            break;
+        }
+        dir = d.parent();
+    }
+
+    summary.freed += app.metadata_size;
+    summary.removed_apps.push(app.reference);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use spin_common::sha256::hex_digest_from_bytes;
+
+    use super::*;
+
+    /// Caches an app whose manifest refers to the given content, and returns
+    /// the digests of the content.
+    async fn cache_app(cache: &Cache, reference: &str, contents: &[&str]) -> Vec<String> {
+        let (repository, tag) = reference.rsplit_once(':').unwrap();
+        let dir = cache.manifests_dir().join(repository).join(tag);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut digests = vec![];
+        let mut layers = vec![];
+        for content in contents {
+            let digest = format!("sha256:{}", hex_digest_from_bytes(content));
+            cache.write_data(content, &digest).await.unwrap();
+            layers.push(serde_json::json!({
+                "mediaType": "application/vnd.wasm.content.layer.v1+data",
+                "digest": digest,
+                "size": content.len(),
+            }));
+            digests.push(digest);
+        }
+        let manifest = serde_json::json!({
+            "schemaVersion": 2,
+            "config": {
+                "mediaType": "application/vnd.oci.image.config.v1+json",
+                "digest": "sha256:config",
+                "size": 2,
+            },
+            "layers": layers,
+        });
+        std::fs::write(dir.join(MANIFEST_FILE), manifest.to_string()).unwrap();
+        digests
+    }
+
+    #[tokio::test]
+    async fn lists_cached_apps() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let cache = Cache::new(Some(temp_dir.path().to_owned())).await?;
+        cache_app(&cache, "ghcr.io/fermyon/app:v1", &["wasm", "data"]).await;
+
+        let apps = cached_apps(&cache)?;
+        assert_eq!(1, apps.len());
+        assert_eq!("ghcr.io/fermyon/app:v1", apps[0].reference);
+        assert_eq!(2, apps[0].blobs.len());
+        assert!(apps[0].size() > 8);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn prune_removes_unreferenced_content() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let cache = Cache::new(Some(temp_dir.path().to_owned())).await?;
+        let referenced = cache_app(&cache, "ghcr.io/fermyon/app:v1", &["wasm"]).await;
+        cache.write_data("orphan", "sha256:orphan").await?;
+
+        let summary = prune(&cache, &PrunePolicy::default())?;
+        assert!(summary.removed_apps.is_empty());
+        assert_eq!(1, summary.removed_blobs);
+        assert!(cache.data_file(&referenced[0]).is_ok());
+        assert!(cache.data_file("sha256:orphan").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn prune_removes_least_recently_pulled_apps_over_max_size() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let cache = Cache::new(Some(temp_dir.path().to_owned())).await?;
+        let old = cache_app(&cache, "ghcr.io/fermyon/old:v1", &["old content"]).await;
+        std::thread::sleep(Duration::from_millis(20));
+        let new = cache_app(&cache, "ghcr.io/fermyon/new:v1", &["new content"]).await;
+
+        let new_size = cached_apps(&cache)?[1].size();
+        let summary = prune(
+            &cache,
+            &PrunePolicy {
+                max_size: Some(new_size),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(vec!["ghcr.io/fermyon/old:v1"], summary.removed_apps);
+        assert!(cache.data_file(&old[0]).is_err());
+        assert!(cache.data_file(&new[0]).is_ok());
+        assert!(!cache.manifests_dir().join("ghcr.io/fermyon/old").exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn prune_removes_apps_older_than_max_age() -> Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let cache = Cache::new(Some(temp_dir.path().to_owned())).await?;
+        cache_app(&cache, "ghcr.io/fermyon/app:v1", &["wasm"]).await;
+
+        let summary = prune(
+            &cache,
+            &PrunePolicy {
+                older_than: Some(Duration::from_secs(3600)),
+                ..Default::default()
+            },
+        )?;
+        assert!(summary.removed_apps.is_empty());
+
+        std::thread::sleep(Duration::from_millis(20));
+        let summary = prune(
+            &cache,
+            &PrunePolicy {
+                older_than: Some(Duration::ZERO),
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(vec!["ghcr.io/fermyon/app:v1"], summary.removed_apps);
+        assert_eq!(1, summary.removed_blobs);
+        Ok(())
+    }
+}
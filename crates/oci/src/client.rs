@@ -1,14 +1,21 @@
 //! Spin's client for distributing applications via OCI registries
 
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use anyhow::{bail, Context, Result};
+use anyhow::{bail, ensure, Context, Result};
+use async_trait::async_trait;
 use docker_credential::DockerCredential;
 use futures_util::future;
 use futures_util::stream::{self, StreamExt, TryStreamExt};
 use oci_distribution::{
-    client::ImageLayer, config::ConfigFile, manifest::OciImageManifest, secrets::RegistryAuth,
-    token_cache::RegistryTokenType, Reference, RegistryOperation,
+    client::ImageLayer,
+    config::ConfigFile,
+    manifest::{OciImageManifest, IMAGE_MANIFEST_MEDIA_TYPE, OCI_IMAGE_MEDIA_TYPE},
+    secrets::RegistryAuth,
+    token_cache::RegistryTokenType,
+    Reference, RegistryOperation,
 };
 use reqwest::Url;
 use spin_common::sha256;
@@ -17,10 +24,17 @@ use spin_common::url::parse_file_url;
 use spin_loader::cache::Cache;
 use spin_loader::FilesMountStrategy;
 use spin_locked_app::locked::{ContentPath, ContentRef, LockedApp};
-use tokio::fs;
+use tokio::{
+    fs,
+    sync::{Mutex, OnceCell},
+};
 use walkdir::WalkDir;
 
 use crate::auth::AuthConfig;
+use crate::signing::{
+    signature_tag, simple_signing_payload, SigningKey, VerificationPolicy, SIGNATURE_ANNOTATION,
+    SIMPLE_SIGNING_MEDIA_TYPE,
+};
 
 // TODO: the media types for application, data and archive layer are not final
 /// Media type for a layer representing a locked Spin application configuration
@@ -31,10 +45,12 @@ pub const DATA_MEDIATYPE: &str = "application/vnd.wasm.content.layer.v1+data";
 pub const ARCHIVE_MEDIATYPE: &str = "application/vnd.wasm.content.bundle.v1.tar+gzip";
 // Note: this will be updated with a canonical value once defined upstream
 const WASM_LAYER_MEDIA_TYPE: &str = "application/vnd.wasm.content.layer.v1+wasm";
+/// Media types of layers which may hold a published Wasm component.
+const COMPONENT_LAYER_MEDIA_TYPES: &[&str] = &[WASM_LAYER_MEDIA_TYPE, "application/wasm"];
 
-const CONFIG_FILE: &str = "config.json";
+pub(crate) const CONFIG_FILE: &str = "config.json";
 const LATEST_TAG: &str = "latest";
-const MANIFEST_FILE: &str = "manifest.json";
+pub(crate) const MANIFEST_FILE: &str = "manifest.json";
 
 const MAX_PARALLEL_PULL: usize = 16;
 /// Maximum layer count allowed per app, set in accordance to the lowest
@@ -51,6 +67,10 @@ pub struct Client {
     pub cache: Cache,
     /// Underlying OCI client.
     oci: oci_distribution::Client,
+    /// Policy for verifying the signatures of pulled apps.
+    verification: VerificationPolicy,
+    /// Whether the registry is accessed over plain HTTP.
+    insecure: bool,
 }
 
 impl Client {
@@ -59,15 +79,28 @@ impl Client {
         let client = oci_distribution::Client::new(Self::build_config(insecure));
         let cache = Cache::new(cache_root).await?;
 
-        Ok(Self { oci: client, cache })
+        Ok(Self {
+            oci: client,
+            cache,
+            verification: VerificationPolicy::default(),
+            insecure,
+        })
+    }
+
+    /// Set the policy used to verify the signatures of apps when pulling them.
+    /// By default, signatures are not checked.
+    pub fn set_verification_policy(&mut self, policy: VerificationPolicy) {
+        self.verification = policy;
     }
 
     /// Push a Spin application to an OCI registry and return the digest (or None
-    /// if the digest cannot be determined).
+    /// if the digest cannot be determined). If `profile` is given, the manifest
+    /// profile of that name is applied to the application.
     pub async fn push(
         &mut self,
         manifest_path: &Path,
         reference: impl AsRef<str>,
+        profile: Option<&str>,
     ) -> Result<Option<String>> {
         let reference: Reference = reference
             .as_ref()
@@ -76,6 +109,11 @@ impl Client {
         let auth = Self::auth(&reference).await?;
         let working_dir = tempfile::tempdir()?;
 
+        // Component dependencies are pulled with a separate client, as this
+        // one is borrowed for the push.
+        let policy = self.verification.clone();
+        let registry = ComponentRegistry::new(self.insecure, move || Ok(policy.clone()));
+
         // Create a locked application from the application manifest.
         // TODO: We don't need an extra copy here for each asset to prepare the application.
         // We should be able to use assets::collect instead when constructing the locked app.
@@ -83,6 +121,8 @@ impl Client {
             manifest_path,
             FilesMountStrategy::Copy(working_dir.path().into()),
             None,
+            profile,
+            Some(Arc::new(registry)),
         )
         .await?;
 
@@ -202,6 +242,116 @@ impl Client {
         Ok(digest)
     }
 
+    /// Sign a pushed Spin application, identified by its manifest digest, and
+    /// push the signature alongside it in the registry.
+    pub async fn sign(
+        &mut self,
+        reference: impl AsRef<str>,
+        digest: &str,
+        key: &SigningKey,
+    ) -> Result<()> {
+        let reference: Reference = reference
+            .as_ref()
+            .parse()
+            .with_context(|| format!("cannot parse reference {}", reference.as_ref()))?;
+        let auth = Self::auth(&reference).await?;
+
+        let payload = simple_signing_payload(&signed_repository(&reference), digest)?;
+        let annotations = HashMap::from([(SIGNATURE_ANNOTATION.to_owned(), key.sign(&payload))]);
+        let layers = vec![ImageLayer::new(
+            payload,
+            SIMPLE_SIGNING_MEDIA_TYPE.to_owned(),
+            Some(annotations),
+        )];
+        let config = oci_distribution::client::Config::oci_v1(b"{}".to_vec(), None);
+        let manifest = OciImageManifest::build(&layers, &config, None);
+
+        let signature_reference = signature_reference(&reference, digest);
+        self.oci
+            .push(&signature_reference, &layers, config, &auth, Some(manifest))
+            .await
+            .context("cannot push signature")?;
+        tracing::info!("Pushed signature {signature_reference}");
+        Ok(())
+    }
+
+    /// Pull an image manifest, returning it along with its digest. The digest
+    /// is computed from the manifest bytes as received, rather than taken from
+    /// the registry's response, so that a verified signature always covers the
+    /// manifest which is actually used.
+    async fn pull_manifest(
+        &mut self,
+        reference: &Reference,
+        auth: &RegistryAuth,
+    ) -> Result<(OciImageManifest, String)> {
+        let (bytes, _) = self
+            .oci
+            .pull_manifest_raw(
+                reference,
+                auth,
+                &[OCI_IMAGE_MEDIA_TYPE, IMAGE_MANIFEST_MEDIA_TYPE],
+            )
+            .await?;
+        let digest = manifest_digest(&bytes);
+        if let Some(expected) = reference.digest() {
+            ensure!(
+                expected == digest,
+                "manifest for {reference} has digest {digest}, but {expected} was requested"
+            );
+        }
+        let manifest = serde_json::from_slice(&bytes)
+            .with_context(|| format!("invalid OCI image manifest for {reference}"))?;
+        Ok((manifest, digest))
+    }
+
+    /// Check the signatures of a pulled app manifest against the verification policy.
+    async fn verify(
+        &mut self,
+        reference: &Reference,
+        digest: &str,
+        auth: &RegistryAuth,
+    ) -> Result<()> {
+        let signature_reference = signature_reference(reference, digest);
+        let mut signatures = vec![];
+        match self
+            .oci
+            .pull_image_manifest(&signature_reference, auth)
+            .await
+        {
+            Ok((manifest, _)) => {
+                for layer in manifest.layers {
+                    if layer.media_type != SIMPLE_SIGNING_MEDIA_TYPE {
+                        continue;
+                    }
+                    let Some(signature) = layer
+                        .annotations
+                        .as_ref()
+                        .and_then(|a| a.get(SIGNATURE_ANNOTATION))
+                    else {
+                        continue;
+                    };
+                    let mut payload = Vec::new();
+                    self.oci
+                        .pull_blob(&signature_reference, &layer.digest, &mut payload)
+                        .await?;
+                    let payload_digest =
+                        format!("sha256:{}", sha256::hex_digest_from_bytes(&payload));
+                    if payload_digest != layer.digest {
+                        tracing::warn!(
+                            "Ignoring signature payload with mismatched digest {}",
+                            layer.digest
+                        );
+                        continue;
+                    }
+                    signatures.push((payload, signature.clone()));
+                }
+            }
+            Err(e) => tracing::debug!("No signature found at {signature_reference}: {e}"),
+        }
+        self.verification
+            .check(&signed_repository(reference), digest, &signatures)
+    }
+
     /// Archive all of the files recursively under the source directory
     /// and push as a compressed archive layer
     async fn push_archive_layer(
@@ -285,7 +435,12 @@ impl Client {
         let auth = Self::auth(&reference).await?;
 
         // Pull the manifest from the registry.
-        let (manifest, digest) = self.oci.pull_image_manifest(&reference, &auth).await?;
+        let (manifest, digest) = self.pull_manifest(&reference, &auth).await?;
+
+        // Verify the app before trusting any of its content.
+        if self.verification.is_active() {
+            self.verify(&reference, &digest, &auth).await?;
+        }
 
         let manifest_json = serde_json::to_string(&manifest)?;
         tracing::debug!("Pulled manifest: {}", manifest_json);
@@ -352,6 +507,104 @@ impl Client {
         Ok(())
     }
 
+    /// Pull an artifact consisting of a single archive layer (such as a set of
+    /// templates) from an OCI registry, and unpack it into `dest`. The layer
+    /// content is verified against the digest in the OCI manifest. Returns the
+    /// digest of the OCI manifest.
+    pub async fn pull_archive(&mut self, reference: &str, dest: &Path) -> Result<String> {
+        let pulled = self
+            .pull_single_layer(reference, &[ARCHIVE_MEDIATYPE], "an archive", |_, _| false)
+            .await?;
+        let bytes = pulled.content.expect("archive layers are never cached");
+
+        let staging_dir = tempfile::tempdir()?;
+        let archive_path = staging_dir.path().join("archive.tar.gz");
+        fs::write(&archive_path, &bytes).await?;
+        crate::utils::unarchive(&archive_path, dest).await?;
+        tracing::info!("Pulled {}@{}", pulled.reference, pulled.digest);
+
+        Ok(pulled.digest)
+    }
+
+    /// Pull a Wasm component (such as a component dependency) from an OCI
+    /// registry into the cache, returning the path of the cached Wasm. The
+    /// layer content is verified against the digest in the OCI manifest.
+    pub async fn pull_component(&mut self, reference: &str) -> Result<PathBuf> {
+        let pulled = self
+            .pull_single_layer(
+                reference,
+                COMPONENT_LAYER_MEDIA_TYPES,
+                "a Wasm",
+                |cache, digest| cache.wasm_file(digest).is_ok(),
+            )
+            .await?;
+        match pulled.content {
+            Some(bytes) => {
+                self.cache.write_wasm(&bytes, &pulled.layer_digest).await?;
+                tracing::info!("Pulled {}@{}", pulled.reference, pulled.digest);
+            }
+            None => tracing::debug!("Layer {} already exists in cache", pulled.layer_digest),
+        }
+
+        Ok(self.cache.wasm_path(&pulled.layer_digest))
+    }
+
+    /// Pull an artifact which has exactly one layer of the given media types,
+    /// verifying its manifest against the verification policy and the layer
+    /// content against the digest in the manifest. `kind` describes the layer
+    /// in errors. The content is not pulled if `is_cached` returns true for
+    /// the layer digest.
+    async fn pull_single_layer(
+        &mut self,
+        reference: &str,
+        media_types: &[&str],
+        kind: &str,
+        is_cached: impl FnOnce(&Cache, &str) -> bool,
+    ) -> Result<SingleLayer> {
+        let reference: Reference = reference.parse().context("cannot parse reference")?;
+        let auth = Self::auth(&reference).await?;
+
+        let (manifest, digest) = self.pull_manifest(&reference, &auth).await?;
+        if self.verification.is_active() {
+            self.verify(&reference, &digest, &auth).await?;
+        }
+
+        let layer = match manifest
+            .layers
+            .iter()
+            .filter(|layer| media_types.contains(&layer.media_type.as_str()))
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            [layer] => (*layer).clone(),
+            [] => bail!("{reference} does not contain {kind} layer"),
+            _ => bail!("{reference} contains more than one {kind} layer"),
+        };
+
+        let content = if is_cached(&self.cache, &layer.digest) {
+            None
+        } else {
+            let mut bytes = Vec::with_capacity(layer.size.try_into()?);
+            self.oci
+                .pull_blob(&reference, &layer.digest, &mut bytes)
+                .await?;
+            let actual_digest = format!("sha256:{}", sha256::hex_digest_from_bytes(&bytes));
+            ensure!(
+                actual_digest == layer.digest,
+                "invalid content digest for {reference}; expected {}, downloaded {actual_digest}",
+                layer.digest
+            );
+            Some(bytes)
+        };
+
+        Ok(SingleLayer {
+            reference,
+            digest,
+            layer_digest: layer.digest,
+            content,
+        })
+    }
+
     /// Get the file path to an OCI manifest given a reference.
     /// If the directory for the manifest does not exist, this will create it.
     async fn manifest_path(&self, reference: impl AsRef<str>) -> Result<PathBuf> {
@@ -580,6 +833,85 @@ impl Client {
     }
 }
 
+/// The layer of a single-layer artifact pulled from a registry.
+struct SingleLayer {
+    reference: Reference,
+    /// The digest of the artifact's manifest.
+    digest: String,
+    layer_digest: String,
+    /// The layer content, or None if it was already cached.
+    content: Option<Vec<u8>>,
+}
+
+/// Pulls the component dependencies of apps being loaded from a manifest.
+///
+/// The registry client, and the verification policy it applies, are created
+/// when the first component is pulled and reused after that, so apps without
+/// registry dependencies never need them.
+pub struct ComponentRegistry {
+    insecure: bool,
+    policy: Box<dyn Fn() -> Result<VerificationPolicy> + Send + Sync>,
+    client: OnceCell<Mutex<Client>>,
+}
+
+impl ComponentRegistry {
+    /// Create a component registry which pulls components with a client
+    /// verifying signatures against the policy returned by `policy`.
+    pub fn new(
+        insecure: bool,
+        policy: impl Fn() -> Result<VerificationPolicy> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            insecure,
+            policy: Box::new(policy),
+            client: OnceCell::new(),
+        }
+    }
+
+    async fn client(&self) -> Result<&Mutex<Client>> {
+        self.client
+            .get_or_try_init(|| async {
+                let mut client = Client::new(self.insecure, None)
+                    .await
+                    .context("cannot create registry client")?;
+                client.set_verification_policy((self.policy)()?);
+                Ok(Mutex::new(client))
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl spin_loader::ComponentRegistry for ComponentRegistry {
+    async fn pull_component(&self, reference: &str) -> Result<PathBuf> {
+        self.client()
+            .await?
+            .lock()
+            .await
+            .pull_component(reference)
+            .await
+    }
+}
+
+/// The digest of the given manifest bytes, in the form used by OCI registries.
+fn manifest_digest(bytes: &[u8]) -> String {
+    format!("sha256:{}", sha256::hex_digest_from_bytes(bytes))
+}
+
+/// The repository named as the identity of signatures for the given reference.
+fn signed_repository(reference: &Reference) -> String {
+    format!("{}/{}", reference.registry(), reference.repository())
+}
+
+/// The reference at which the signature for the given manifest digest is stored.
+fn signature_reference(reference: &Reference, digest: &str) -> Reference {
+    Reference::with_tag(
+        reference.registry().to_owned(),
+        reference.repository().to_owned(),
+        signature_tag(digest),
+    )
+}
+
 fn digest_from_url(manifest_url: &str) -> Option<String> {
     // The URL is in the form "https://host/v2/refname/manifests/sha256:..."
     let manifest_url = Url::parse(manifest_url).ok()?;
@@ -615,6 +947,8 @@ async fn layer_count(locked: LockedApp) -> Result<usize> {
 
 #[cfg(test)]
 mod test {
+    use spin_test_http_server::{StatusCode, TestServer};
+
     use super::*;
 
     #[test]
@@ -627,6 +961,115 @@ mod test {
         );
     }
 
+    #[test]
+    fn manifest_digest_hashes_received_bytes() {
+        assert_eq!(
+            "sha256:44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a",
+            manifest_digest(b"{}")
+        );
+    }
+
+    // Serves the given content at the given paths, as an OCI registry would.
+    async fn serve_registry(content: HashMap<String, Vec<u8>>) -> TestServer {
+        TestServer::start(move |request| {
+            let body = match content.get(&request.path) {
+                // The registry API root is requested to discover authentication
+                None if request.path == "/v2/" => vec![],
+                Some(body) => body.clone(),
+                None => return spin_test_http_server::status(StatusCode::NOT_FOUND),
+            };
+            let digest = manifest_digest(&body);
+            let mut response = spin_test_http_server::ok(OCI_IMAGE_MEDIA_TYPE, body);
+            response
+                .headers_mut()
+                .insert("docker-content-digest", digest.parse().unwrap());
+            response
+        })
+        .await
+    }
+
+    // Returns registry content for an artifact with a single archive layer
+    // holding a templates directory, and the digest of its manifest.
+    async fn archive_artifact(layer: Option<Vec<u8>>) -> (HashMap<String, Vec<u8>>, String) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source = temp_dir.path().join("source");
+        std::fs::create_dir_all(source.join("templates").join("my-template")).unwrap();
+        let archive = crate::utils::archive(&source, temp_dir.path())
+            .await
+            .unwrap();
+        let archive = std::fs::read(archive).unwrap();
+        let layer_digest = format!("sha256:{}", sha256::hex_digest_from_bytes(&archive));
+
+        let manifest = serde_json::json!({
+            "schemaVersion": 2,
+            "mediaType": OCI_IMAGE_MEDIA_TYPE,
+            "config": {
+                "mediaType": "application/vnd.oci.image.config.v1+json",
+                "digest": manifest_digest(b"{}"),
+                "size": 2,
+            },
+            "layers": [{
+                "mediaType": ARCHIVE_MEDIATYPE,
+                "digest": layer_digest,
+                "size": archive.len(),
+            }],
+        });
+        let manifest = serde_json::to_vec(&manifest).unwrap();
+        let digest = manifest_digest(&manifest);
+
+        let content = [
+            ("/v2/templates/manifests/v1".to_owned(), manifest),
+            (
+                format!("/v2/templates/blobs/{layer_digest}"),
+                layer.unwrap_or(archive),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        (content, digest)
+    }
+
+    #[tokio::test]
+    async fn pull_archive_unpacks_verified_layer() {
+        let (content, expected_digest) = archive_artifact(None).await;
+        let registry = serve_registry(content).await;
+        let cache_dir = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+
+        let mut client = Client::new(true, Some(cache_dir.path().to_owned()))
+            .await
+            .unwrap();
+        let digest = client
+            .pull_archive(&format!("{}/templates:v1", registry.addr()), dest.path())
+            .await
+            .unwrap();
+
+        assert_eq!(expected_digest, digest);
+        assert!(dest.path().join("templates").join("my-template").is_dir());
+    }
+
+    #[tokio::test]
+    async fn pull_archive_rejects_tampered_layer() {
+        let (content, _) = archive_artifact(Some(b"not the archive".to_vec())).await;
+        let registry = serve_registry(content).await;
+        let cache_dir = tempfile::tempdir().unwrap();
+        let dest = tempfile::tempdir().unwrap();
+
+        let mut client = Client::new(true, Some(cache_dir.path().to_owned()))
+            .await
+            .unwrap();
+        let err = client
+            .pull_archive(&format!("{}/templates:v1", registry.addr()), dest.path())
+            .await
+            .unwrap_err();
+
+        assert!(
+            err.to_string().contains("invalid content digest"),
+            "{err:#}"
+        );
+        assert!(!dest.path().join("templates").exists());
+    }
+
     #[tokio::test]
     async fn can_get_layer_count() {
         use spin_locked_app::locked::LockedComponent;
@@ -2,12 +2,15 @@
 #![deny(missing_docs)]
 
 mod auth;
+pub mod cache;
 pub mod client;
 mod loader;
+pub mod signing;
 pub mod utils;
 
-pub use client::Client;
+pub use client::{Client, ComponentRegistry};
 pub use loader::OciLoader;
+pub use signing::{SigningKey, VerificationPolicy};
 
 /// URL scheme used for the locked app "origin" metadata field for OCI-sourced apps.
 pub const ORIGIN_URL_SCHEME: &str = "vnd.fermyon.origin-oci";
@@ -0,0 +1,366 @@
+//! Signing and verification of Spin applications distributed through OCI
+//! registries.
+//!
+//! Signatures follow the cosign "simple signing" convention: the signature is
+//! pushed as a separate artifact tagged `sha256-<digest>.sig` in the same
+//! repository as the app, whose single layer is a JSON payload naming the
+//! app's manifest digest, and whose layer annotation holds the base64-encoded
+//! ECDSA P-256 signature over that payload. Apps signed by Spin can therefore
+//! be verified by `cosign verify --key`, and vice versa.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, ensure, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use p256::{
+    ecdsa::{
+        signature::{Signer, Verifier},
+        Signature,
+    },
+    pkcs8::{DecodePrivateKey, DecodePublicKey},
+};
+use serde::{Deserialize, Serialize};
+use spin_common::ui::quoted_path;
+
+/// Media type of the layer holding a simple signing payload.
+pub const SIMPLE_SIGNING_MEDIA_TYPE: &str = "application/vnd.dev.cosign.simplesigning.v1+json";
+/// Layer annotation holding the base64-encoded signature over the payload.
+pub const SIGNATURE_ANNOTATION: &str = "dev.cosignproject.cosign/signature";
+
+const SIGNATURE_TYPE: &str = "cosign container image signature";
+const TRUST_POLICY_FILE: &str = "registry-trust.toml";
+
+/// A private key used to sign apps when pushing them.
+pub struct SigningKey(p256::ecdsa::SigningKey);
+
+impl SigningKey {
+    /// Loads an unencrypted PKCS#8 PEM-encoded ECDSA P-256 private key.
+    pub fn from_pem_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let pem = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read signing key {}", quoted_path(path)))?;
+        let key = p256::ecdsa::SigningKey::from_pkcs8_pem(&pem).with_context(|| {
+            format!(
+                "{} is not an unencrypted PKCS#8 PEM ECDSA P-256 private key",
+                quoted_path(path)
+            )
+        })?;
+        Ok(Self(key))
+    }
+
+    /// Signs the given payload, returning the base64-encoded DER signature.
+    pub(crate) fn sign(&self, payload: &[u8]) -> String {
+        let signature: Signature = self.0.sign(payload);
+        BASE64.encode(signature.to_der().as_bytes())
+    }
+}
+
+/// A public key used to verify app signatures.
+#[derive(Clone)]
+pub struct VerifyingKey(p256::ecdsa::VerifyingKey);
+
+impl VerifyingKey {
+    /// Loads a PEM-encoded ECDSA P-256 public key, such as the `cosign.pub`
+    /// file created by `cosign generate-key-pair`.
+    pub fn from_pem_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let pem = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read public key {}", quoted_path(path)))?;
+        let key = p256::ecdsa::VerifyingKey::from_public_key_pem(&pem).with_context(|| {
+            format!("{} is not a PEM ECDSA P-256 public key", quoted_path(path))
+        })?;
+        Ok(Self(key))
+    }
+
+    fn verify(&self, payload: &[u8], signature: &str) -> bool {
+        let Ok(der) = BASE64.decode(signature) else {
+            return false;
+        };
+        let Ok(signature) = Signature::from_der(&der) else {
+            return false;
+        };
+        self.0.verify(payload, &signature).is_ok()
+    }
+}
+
+/// The keys an app must be signed with to be pulled, and whether unsigned
+/// apps are refused.
+#[derive(Clone, Default)]
+pub struct VerificationPolicy {
+    keys: Vec<VerifyingKey>,
+    require_signature: bool,
+}
+
+/// The trust policy file format.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TrustPolicyFile {
+    #[serde(default)]
+    require_signature: bool,
+    #[serde(default)]
+    public_keys: Vec<PathBuf>,
+}
+
+impl VerificationPolicy {
+    /// Loads the trust policy from the default location
+    /// ($XDG_CONFIG_HOME/fermyon/registry-trust.toml), then adds the given
+    /// keys. A missing policy file trusts any app.
+    ///
+    /// The policy file contains a list of `public_keys` (paths, relative to
+    /// the policy file) and a `require_signature` flag.
+    pub fn load_default(
+        extra_keys: &[PathBuf],
+        require_signature: bool,
+    ) -> Result<VerificationPolicy> {
+        let policy_path = Self::default_path().filter(|path| path.exists());
+        let mut policy = match &policy_path {
+            Some(path) => Self::load(path)?,
+            None => Self::default(),
+        };
+        for key in extra_keys {
+            policy.keys.push(VerifyingKey::from_pem_file(key)?);
+        }
+        policy.require_signature |= require_signature;
+        policy.check_keys(policy_path.as_deref())?;
+        Ok(policy)
+    }
+
+    // A policy without keys cannot verify anything: that is an error if
+    // signatures are required, and worth a warning if the user has set up a
+    // trust policy expecting apps to be checked.
+    fn check_keys(&self, policy_path: Option<&Path>) -> Result<()> {
+        if !self.keys.is_empty() {
+            return Ok(());
+        }
+        ensure!(
+            !self.require_signature,
+            "signatures are required but no public keys are configured to verify them"
+        );
+        if let Some(path) = policy_path {
+            terminal::warn!(
+                "The trust policy {} lists no public keys, so app signatures will not be verified",
+                quoted_path(path)
+            );
+        }
+        Ok(())
+    }
+
+    fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read trust policy {}", quoted_path(path)))?;
+        let file: TrustPolicyFile = toml::from_str(&contents)
+            .with_context(|| format!("invalid trust policy {}", quoted_path(path)))?;
+        let base = path.parent().unwrap_or(Path::new("."));
+        let keys = file
+            .public_keys
+            .iter()
+            .map(|key| VerifyingKey::from_pem_file(base.join(key)))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            keys,
+            require_signature: file.require_signature,
+        })
+    }
+
+    fn default_path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("fermyon").join(TRUST_POLICY_FILE))
+    }
+
+    /// Returns true if the policy has any keys to verify against.
+    pub fn is_active(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// Checks the signatures found for an app, pulled from the given
+    /// repository, against the policy. Each signature is a payload and its
+    /// base64-encoded signature. A signature is only valid if its payload
+    /// names both the repository and the digest of the app.
+    ///
+    /// Fails if signatures are required and none is valid. Otherwise, an
+    /// unsigned or unverifiable app is allowed with a warning.
+    pub(crate) fn check(
+        &self,
+        repository: &str,
+        digest: &str,
+        signatures: &[(Vec<u8>, String)],
+    ) -> Result<()> {
+        let verified = signatures.iter().any(|(payload, signature)| {
+            payload_subject(payload).is_some_and(|(r, d)| r == repository && d == digest)
+                && self.keys.iter().any(|key| key.verify(payload, signature))
+        });
+        if verified {
+            tracing::info!("Verified signature for {digest}");
+            return Ok(());
+        }
+
+        let problem = if signatures.is_empty() {
+            "is not signed"
+        } else {
+            "has no signature matching the trusted public keys"
+        };
+        if self.require_signature {
+            bail!("app {digest} {problem}, and the trust policy requires a valid signature");
+        }
+        terminal::warn!("app {digest} {problem}");
+        Ok(())
+    }
+}
+
+/// Returns the tag under which the signature for the given manifest digest is
+/// stored.
+pub(crate) fn signature_tag(digest: &str) -> String {
+    format!("{}.sig", digest.replace(':', "-"))
+}
+
+#[derive(Serialize, Deserialize)]
+struct SimpleSigning {
+    critical: Critical,
+    optional: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Critical {
+    identity: Identity,
+    image: Image,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Identity {
+    #[serde(rename = "docker-reference")]
+    docker_reference: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Image {
+    #[serde(rename = "docker-manifest-digest")]
+    docker_manifest_digest: String,
+}
+
+/// Builds the simple signing payload for an app in the given repository.
+pub(crate) fn simple_signing_payload(repository: &str, digest: &str) -> Result<Vec<u8>> {
+    let payload = SimpleSigning {
+        critical: Critical {
+            identity: Identity {
+                docker_reference: repository.to_owned(),
+            },
+            image: Image {
+                docker_manifest_digest: digest.to_owned(),
+            },
+            kind: SIGNATURE_TYPE.to_owned(),
+        },
+        optional: None,
+    };
+    Ok(serde_json::to_vec(&payload)?)
+}
+
+/// Returns the repository and manifest digest named by a simple signing
+/// payload.
+fn payload_subject(payload: &[u8]) -> Option<(String, String)> {
+    let payload: SimpleSigning = serde_json::from_slice(payload).ok()?;
+    let critical = payload.critical;
+    (critical.kind == SIGNATURE_TYPE).then_some((
+        critical.identity.docker_reference,
+        critical.image.docker_manifest_digest,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPOSITORY: &str = "ghcr.io/fermyon/app";
+    const DIGEST: &str = "sha256:0123456789abcdef";
+
+    fn key_pair(seed: u8) -> (SigningKey, VerifyingKey) {
+        let key = p256::ecdsa::SigningKey::from_slice(&[seed; 32]).unwrap();
+        let public = VerifyingKey(*key.verifying_key());
+        (SigningKey(key), public)
+    }
+
+    fn policy(keys: Vec<VerifyingKey>, require_signature: bool) -> VerificationPolicy {
+        VerificationPolicy {
+            keys,
+            require_signature,
+        }
+    }
+
+    fn signed(key: &SigningKey, repository: &str, digest: &str) -> (Vec<u8>, String) {
+        let payload = simple_signing_payload(repository, digest).unwrap();
+        let signature = key.sign(&payload);
+        (payload, signature)
+    }
+
+    #[test]
+    fn signature_tag_follows_cosign_convention() {
+        assert_eq!("sha256-0123456789abcdef.sig", signature_tag(DIGEST));
+    }
+
+    #[test]
+    fn payload_names_the_repository_and_digest() {
+        let payload = simple_signing_payload(REPOSITORY, DIGEST).unwrap();
+        assert_eq!(
+            Some((REPOSITORY.to_owned(), DIGEST.to_owned())),
+            payload_subject(&payload)
+        );
+    }
+
+    #[test]
+    fn requiring_signatures_needs_keys() {
+        policy(vec![], true).check_keys(None).unwrap_err();
+        policy(vec![], false).check_keys(None).unwrap();
+        let (_, verifying) = key_pair(1);
+        policy(vec![verifying], true).check_keys(None).unwrap();
+    }
+
+    #[test]
+    fn accepts_valid_signature() {
+        let (signing, verifying) = key_pair(1);
+        let policy = policy(vec![verifying], true);
+        policy
+            .check(REPOSITORY, DIGEST, &[signed(&signing, REPOSITORY, DIGEST)])
+            .unwrap();
+    }
+
+    #[test]
+    fn refuses_unsigned_or_mismatched_apps_when_required() {
+        let (_, trusted) = key_pair(1);
+        let (untrusted, _) = key_pair(2);
+        let (signing, _) = key_pair(1);
+        let policy = policy(vec![trusted], true);
+
+        policy.check(REPOSITORY, DIGEST, &[]).unwrap_err();
+        policy
+            .check(
+                REPOSITORY,
+                DIGEST,
+                &[signed(&untrusted, REPOSITORY, DIGEST)],
+            )
+            .unwrap_err();
+        policy
+            .check(
+                REPOSITORY,
+                DIGEST,
+                &[signed(&signing, REPOSITORY, "sha256:other")],
+            )
+            .unwrap_err();
+    }
+
+    #[test]
+    fn refuses_signatures_for_other_repositories() {
+        let (signing, trusted) = key_pair(1);
+        let policy = policy(vec![trusted], true);
+        let other_image = signed(&signing, "ghcr.io/fermyon/other", DIGEST);
+        policy
+            .check(REPOSITORY, DIGEST, &[other_image])
+            .unwrap_err();
+    }
+
+    #[test]
+    fn allows_unsigned_apps_when_not_required() {
+        let (_, trusted) = key_pair(1);
+        let policy = policy(vec![trusted], false);
+        policy.check(REPOSITORY, DIGEST, &[]).unwrap();
+    }
+}
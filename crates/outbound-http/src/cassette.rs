@@ -0,0 +1,498 @@
+//! Recording and replaying of outbound HTTP requests.
+//!
+//! A cassette is a JSON file of outbound requests and the responses they
+//! received. [`OutboundRecorder`] sends requests upstream and records them to
+//! a cassette; [`OutboundReplayer`] answers requests from a cassette without
+//! touching the network.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use spin_common::{sha256::hex_digest_from_bytes, ui::quoted_path};
+use spin_outbound_networking::intercept::{
+    InterceptedRequest, InterceptedResponse, OutboundHttpInterceptor,
+};
+
+/// Request headers whose values are recorded only as a digest.
+const SENSITIVE_HEADERS: &[&str] = &["authorization", "cookie", "proxy-authorization"];
+
+/// Response headers which describe the framing of the original response, and
+/// so are not replayed: the replayed body is always sent in full.
+const FRAMING_HEADERS: &[&str] = &["content-length", "transfer-encoding"];
+
+/// A recording of outbound HTTP requests and their responses.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read(path)
+            .with_context(|| format!("Failed to read cassette {}", quoted_path(path)))?;
+        serde_json::from_slice(&contents)
+            .with_context(|| format!("Failed to parse cassette {}", quoted_path(path)))
+    }
+
+    pub async fn write_file(&self, path: &Path) -> Result<()> {
+        let contents = serde_json::to_vec_pretty(self)?;
+        tokio::fs::write(path, contents)
+            .await
+            .with_context(|| format!("Failed to write cassette {}", quoted_path(path)))
+    }
+}
+
+/// A single outbound request and the response it received.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    /// Request headers, keyed by lowercase name. Sensitive values are
+    /// recorded as a digest.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// The SHA-256 digest of the request body, if it was not empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_sha256: Option<String>,
+}
+
+impl RecordedRequest {
+    fn new(request: &InterceptedRequest) -> Self {
+        let mut headers = BTreeMap::<String, String>::new();
+        for (name, value) in &request.headers {
+            let name = name.to_ascii_lowercase();
+            let value = recorded_header_value(&name, value);
+            headers
+                .entry(name)
+                .and_modify(|v| {
+                    v.push_str(", ");
+                    v.push_str(&value);
+                })
+                .or_insert(value);
+        }
+        Self {
+            method: request.method.to_ascii_uppercase(),
+            url: request.url.clone(),
+            headers,
+            body_sha256: body_sha256(&request.body),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+    /// The response body, if it is valid UTF-8.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// The response body, base64-encoded, if it is not valid UTF-8.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "spin_serde::base64"
+    )]
+    pub body_base64: Option<Vec<u8>>,
+}
+
+impl RecordedResponse {
+    fn new(response: &InterceptedResponse) -> Self {
+        let (body, body_base64) = match String::from_utf8(response.body.clone()) {
+            Ok(body) => (Some(body), None),
+            Err(e) => (None, Some(e.into_bytes())),
+        };
+        Self {
+            status: response.status,
+            headers: response.headers.clone(),
+            body,
+            body_base64,
+        }
+    }
+
+    fn to_response(&self) -> InterceptedResponse {
+        let body = match (&self.body, &self.body_base64) {
+            (Some(body), _) => body.clone().into_bytes(),
+            (None, Some(body)) => body.clone(),
+            (None, None) => vec![],
+        };
+        let headers = self
+            .headers
+            .iter()
+            .filter(|(name, _)| {
+                !FRAMING_HEADERS
+                    .iter()
+                    .any(|framing| name.eq_ignore_ascii_case(framing))
+            })
+            .cloned()
+            .collect();
+        InterceptedResponse {
+            status: self.status,
+            headers,
+            body,
+        }
+    }
+}
+
+/// How replayed requests are matched against recorded requests. The method,
+/// URL and body digest must always match.
+#[derive(Clone, Debug, Default)]
+pub struct MatchRules {
+    headers: Vec<String>,
+}
+
+impl MatchRules {
+    /// Also require the given request headers to match.
+    pub fn with_headers(headers: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        Self {
+            headers: headers
+                .into_iter()
+                .map(|h| h.as_ref().to_ascii_lowercase())
+                .collect(),
+        }
+    }
+
+    fn matches(&self, recorded: &RecordedRequest, request: &RecordedRequest) -> bool {
+        recorded.method == request.method
+            && recorded.url == request.url
+            && recorded.body_sha256 == request.body_sha256
+            && self
+                .headers
+                .iter()
+                .all(|name| recorded.headers.get(name) == request.headers.get(name))
+    }
+}
+
+/// Sends outbound requests upstream, recording each request and its response
+/// to a cassette file.
+pub struct OutboundRecorder {
+    path: PathBuf,
+    client: reqwest::Client,
+    // An async lock, as it is held while the cassette is saved
+    cassette: tokio::sync::Mutex<Cassette>,
+}
+
+impl OutboundRecorder {
+    /// Creates a recorder which writes to the given file, replacing any
+    /// existing recording.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            client: Default::default(),
+            cassette: Default::default(),
+        }
+    }
+
+    async fn send(&self, request: &InterceptedRequest) -> Result<InterceptedResponse> {
+        let method = reqwest::Method::from_bytes(request.method.as_bytes())?;
+        let mut headers = HeaderMap::new();
+        for (name, value) in &request.headers {
+            let name = HeaderName::try_from(name.as_str())?;
+            // The client sets the host header from the URL
+            if name != reqwest::header::HOST {
+                headers.append(name, HeaderValue::try_from(value.as_str())?);
+            }
+        }
+        let resp = self
+            .client
+            .request(method, request.url.as_str())
+            .headers(headers)
+            .body(request.body.clone())
+            .send()
+            .await?;
+
+        let status = resp.status().as_u16();
+        let headers = resp
+            .headers()
+            .iter()
+            .filter_map(|(k, v)| Some((k.to_string(), v.to_str().ok()?.to_owned())))
+            .collect();
+        let body = resp.bytes().await?.to_vec();
+        Ok(InterceptedResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+#[async_trait]
+impl OutboundHttpInterceptor for OutboundRecorder {
+    fn handles(&self, _method: &str, _url: &str) -> bool {
+        true
+    }
+
+    async fn respond(&self, request: InterceptedRequest) -> Result<InterceptedResponse> {
+        let response = self.send(&request).await?;
+        let mut cassette = self.cassette.lock().await;
+        cassette.interactions.push(Interaction {
+            request: RecordedRequest::new(&request),
+            response: RecordedResponse::new(&response),
+        });
+        // Save after every request so that the recording survives the process
+        // being stopped.
+        if let Err(e) = cassette.write_file(&self.path).await {
+            terminal::warn!("{e:#}");
+        }
+        Ok(response)
+    }
+}
+
+/// Answers outbound requests from a cassette file recorded by an
+/// [`OutboundRecorder`].
+///
+/// Each recorded response is replayed once, in order, so that repeated
+/// requests receive the responses they received when recorded. Once all
+/// matching responses have been replayed, the last one is repeated.
+pub struct OutboundReplayer {
+    rules: MatchRules,
+    // Each interaction, and whether it has been replayed
+    interactions: Mutex<Vec<(Interaction, bool)>>,
+}
+
+impl OutboundReplayer {
+    pub fn from_file(path: &Path, rules: MatchRules) -> Result<Self> {
+        let cassette = Cassette::from_file(path)?;
+        Ok(Self::new(cassette, rules))
+    }
+
+    pub fn new(cassette: Cassette, rules: MatchRules) -> Self {
+        let interactions = cassette
+            .interactions
+            .into_iter()
+            .map(|i| (i, false))
+            .collect();
+        Self {
+            rules,
+            interactions: Mutex::new(interactions),
+        }
+    }
+}
+
+#[async_trait]
+impl OutboundHttpInterceptor for OutboundReplayer {
+    fn handles(&self, _method: &str, _url: &str) -> bool {
+        true
+    }
+
+    async fn respond(&self, request: InterceptedRequest) -> Result<InterceptedResponse> {
+        let request = RecordedRequest::new(&request);
+        let mut interactions = self.interactions.lock().unwrap();
+        let mut matching = interactions
+            .iter_mut()
+            .filter(|(i, _)| self.rules.matches(&i.request, &request))
+            .peekable();
+        let mut last = None;
+        while let Some((interaction, replayed)) = matching.next() {
+            if !*replayed || matching.peek().is_none() {
+                *replayed = true;
+                last = Some(interaction.response.to_response());
+                break;
+            }
+        }
+        last.ok_or_else(|| {
+            anyhow!(
+                "No recorded response for outbound request {} {}",
+                request.method,
+                request.url
+            )
+        })
+    }
+}
+
+fn body_sha256(body: &[u8]) -> Option<String> {
+    (!body.is_empty()).then(|| hex_digest_from_bytes(body))
+}
+
+fn recorded_header_value(name: &str, value: &str) -> String {
+    if SENSITIVE_HEADERS.contains(&name) {
+        format!("sha256:{}", hex_digest_from_bytes(value))
+    } else {
+        value.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(
+        method: &str,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) -> InterceptedRequest {
+        InterceptedRequest {
+            method: method.into(),
+            url: url.into(),
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            body: body.into(),
+        }
+    }
+
+    fn interaction(request: &InterceptedRequest, body: &str) -> Interaction {
+        Interaction {
+            request: RecordedRequest::new(request),
+            response: RecordedResponse::new(&InterceptedResponse {
+                status: 200,
+                headers: vec![],
+                body: body.into(),
+            }),
+        }
+    }
+
+    async fn replay_body(
+        replayer: &OutboundReplayer,
+        request: InterceptedRequest,
+    ) -> Option<String> {
+        let resp = replayer.respond(request).await.ok()?;
+        Some(String::from_utf8(resp.body).unwrap())
+    }
+
+    #[test]
+    fn sensitive_headers_are_digested() {
+        let recorded = RecordedRequest::new(&request(
+            "get",
+            "https://example.com/",
+            &[("Authorization", "Bearer secret"), ("Accept", "text/plain")],
+            "",
+        ));
+        assert_eq!("GET", recorded.method);
+        assert_eq!("text/plain", recorded.headers["accept"]);
+        let auth = &recorded.headers["authorization"];
+        assert!(auth.starts_with("sha256:"));
+        assert!(!auth.contains("secret"));
+        assert_eq!(None, recorded.body_sha256);
+    }
+
+    #[tokio::test]
+    async fn replays_matching_requests_in_order() {
+        let get = request("GET", "https://example.com/a", &[], "");
+        let post = request("POST", "https://example.com/a", &[], "one");
+        let cassette = Cassette {
+            interactions: vec![
+                interaction(&get, "first"),
+                interaction(&get, "second"),
+                interaction(&post, "posted"),
+            ],
+        };
+        let replayer = OutboundReplayer::new(cassette, MatchRules::default());
+
+        assert_eq!(
+            Some("first"),
+            replay_body(&replayer, get.clone()).await.as_deref()
+        );
+        assert_eq!(
+            Some("second"),
+            replay_body(&replayer, get.clone()).await.as_deref()
+        );
+        assert_eq!(Some("second"), replay_body(&replayer, get).await.as_deref());
+        assert_eq!(
+            Some("posted"),
+            replay_body(&replayer, post).await.as_deref()
+        );
+
+        let other_body = request("POST", "https://example.com/a", &[], "two");
+        assert_eq!(None, replay_body(&replayer, other_body).await);
+        let other_url = request("GET", "https://example.com/b", &[], "");
+        assert_eq!(None, replay_body(&replayer, other_url).await);
+    }
+
+    #[tokio::test]
+    async fn matches_selected_headers() {
+        let en = request(
+            "GET",
+            "https://example.com/",
+            &[("Accept-Language", "en")],
+            "",
+        );
+        let fr = request(
+            "GET",
+            "https://example.com/",
+            &[("accept-language", "fr")],
+            "",
+        );
+        let cassette = || Cassette {
+            interactions: vec![interaction(&en, "hello"), interaction(&fr, "bonjour")],
+        };
+
+        let replayer = OutboundReplayer::new(cassette(), MatchRules::default());
+        assert_eq!(
+            Some("hello"),
+            replay_body(&replayer, fr.clone()).await.as_deref()
+        );
+
+        let replayer =
+            OutboundReplayer::new(cassette(), MatchRules::with_headers(["Accept-Language"]));
+        assert_eq!(Some("bonjour"), replay_body(&replayer, fr).await.as_deref());
+    }
+
+    #[test]
+    fn framing_headers_are_not_replayed() {
+        let recorded = RecordedResponse::new(&InterceptedResponse {
+            status: 200,
+            headers: vec![
+                ("Content-Type".into(), "text/plain".into()),
+                ("Content-Length".into(), "999".into()),
+                ("transfer-encoding".into(), "chunked".into()),
+            ],
+            body: "hello".into(),
+        });
+        let replayed = recorded.to_response();
+        assert_eq!(
+            vec![("Content-Type".to_owned(), "text/plain".to_owned())],
+            replayed.headers
+        );
+        assert_eq!(b"hello".to_vec(), replayed.body);
+    }
+
+    #[tokio::test]
+    async fn cassette_file_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cassette.json");
+        let cassette = Cassette {
+            interactions: vec![interaction(
+                &request("GET", "https://example.com/", &[], ""),
+                "recorded",
+            )],
+        };
+        cassette.write_file(&path).await.unwrap();
+
+        let replayer = OutboundReplayer::from_file(&path, MatchRules::default()).unwrap();
+        let get = request("GET", "https://example.com/", &[], "");
+        assert_eq!(
+            Some("recorded"),
+            replay_body(&replayer, get).await.as_deref()
+        );
+    }
+
+    #[test]
+    fn binary_bodies_round_trip() {
+        let response = InterceptedResponse {
+            status: 200,
+            headers: vec![],
+            body: vec![0xff, 0x00, 0xfe],
+        };
+        let recorded = RecordedResponse::new(&response);
+        assert_eq!(None, recorded.body);
+        let json = serde_json::to_string(&recorded).unwrap();
+        let parsed: RecordedResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(response.body, parsed.to_response().body);
+    }
+}
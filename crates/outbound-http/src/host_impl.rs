@@ -2,7 +2,10 @@ use anyhow::Result;
 use http::HeaderMap;
 use reqwest::Client;
 use spin_core::async_trait;
-use spin_outbound_networking::{AllowedHostsConfig, OutboundUrl};
+use spin_outbound_networking::{
+    intercept::{InterceptedRequest, SharedInterceptor},
+    AllowedHostsConfig, OutboundUrl,
+};
 use spin_world::v1::{
     http as outbound_http,
     http_types::{Headers, HttpError, Method, Request, Response},
@@ -16,6 +19,8 @@ pub struct OutboundHttp {
     /// During an incoming HTTP request, origin is set to the host of that incoming HTTP request.
     /// This is used to direct outbound requests to the same host when allowed.
     pub origin: String,
+    /// If set, answers permitted outbound requests in place of the upstream server.
+    pub interceptor: Option<SharedInterceptor>,
     client: Option<Client>,
 }
 
@@ -61,6 +66,26 @@ impl outbound_http::Host for OutboundHttp {
                 req.uri.clone()
             };
 
+            if let Some(interceptor) = &self.interceptor {
+                if interceptor.handles(method.as_str(), &abs_url) {
+                    let request = InterceptedRequest {
+                        method: method.to_string(),
+                        url: abs_url,
+                        headers: req.headers,
+                        body: req.body.unwrap_or_default(),
+                    };
+                    let resp = interceptor.respond(request).await.map_err(|err| {
+                        tracing::warn!("Outbound HTTP interceptor error: {err:?}");
+                        HttpError::RuntimeError
+                    })?;
+                    return Ok(Response {
+                        status: resp.status,
+                        headers: Some(resp.headers),
+                        body: Some(resp.body),
+                    });
+                }
+            }
+
             let req_url = reqwest::Url::parse(&abs_url).map_err(|_| HttpError::InvalidUrl)?;
 
             let headers = request_headers(req.headers).map_err(|_| HttpError::RuntimeError)?;
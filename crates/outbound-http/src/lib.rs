@@ -1,4 +1,6 @@
 #[cfg(feature = "runtime")]
+pub mod cassette;
+#[cfg(feature = "runtime")]
 mod host_component;
 #[cfg(feature = "runtime")]
 mod host_impl;
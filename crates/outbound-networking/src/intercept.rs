@@ -0,0 +1,63 @@
+//! Interception of outbound HTTP requests.
+//!
+//! An [`OutboundHttpInterceptor`] can answer outbound HTTP requests made by
+//! components in place of the upstream server, for example to stub services
+//! in tests.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+/// An outbound HTTP request, with its body fully buffered.
+#[derive(Clone, Debug, Default)]
+pub struct InterceptedRequest {
+    /// The request method, e.g. "GET".
+    pub method: String,
+    /// The absolute request URL.
+    pub url: String,
+    /// The request headers.
+    pub headers: Vec<(String, String)>,
+    /// The request body.
+    pub body: Vec<u8>,
+}
+
+impl InterceptedRequest {
+    /// Returns the value of the first header with the given (case-insensitive) name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// A response to an outbound HTTP request, with its body fully buffered.
+#[derive(Clone, Debug, Default)]
+pub struct InterceptedResponse {
+    /// The response status code.
+    pub status: u16,
+    /// The response headers.
+    pub headers: Vec<(String, String)>,
+    /// The response body.
+    pub body: Vec<u8>,
+}
+
+/// Answers outbound HTTP requests in place of the upstream server.
+///
+/// Interceptors are consulted only for requests that are permitted by the
+/// component's `allowed_outbound_hosts`.
+#[async_trait]
+pub trait OutboundHttpInterceptor: Send + Sync {
+    /// Returns true if the interceptor should answer the request with the
+    /// given method and absolute URL. Requests that are not handled are sent
+    /// upstream as normal.
+    fn handles(&self, method: &str, url: &str) -> bool;
+
+    /// Returns the response to a request for which [`handles`](Self::handles)
+    /// returned true. An error is reported to the component as a failure to
+    /// send the request.
+    async fn respond(&self, request: InterceptedRequest) -> anyhow::Result<InterceptedResponse>;
+}
+
+/// A shareable [`OutboundHttpInterceptor`].
+pub type SharedInterceptor = Arc<dyn OutboundHttpInterceptor>;
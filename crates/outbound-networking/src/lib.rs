@@ -1,3 +1,5 @@
+pub mod intercept;
+
 use std::ops::Range;
 
 use anyhow::{bail, ensure, Context};
@@ -5,6 +7,16 @@ use spin_locked_app::MetadataKey;
 
 pub const ALLOWED_HOSTS_KEY: MetadataKey<Vec<String>> = MetadataKey::new("allowed_outbound_hosts");
 
+/// The domain under which the components of an app address each other, as
+/// `http://<component-id>.spin.internal`.
+pub const SERVICE_CHAINING_DOMAIN_SUFFIX: &str = ".spin.internal";
+
+/// Returns true if the host addresses a component of the app rather than a
+/// network host.
+pub fn is_service_chaining_host(host: &str) -> bool {
+    host.ends_with(SERVICE_CHAINING_DOMAIN_SUFFIX)
+}
+
 /// Checks address against allowed hosts
 ///
 /// Emits several warnings
@@ -142,7 +154,10 @@ impl SchemeConfig {
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum HostConfig {
+    /// Any network host. This does not include other components of the app.
     Any,
+    /// Any component of the app, addressed as `*.spin.internal`.
+    AnyComponent,
     ToSelf,
     List(Vec<String>),
     Cidr(ipnet::IpNet),
@@ -159,6 +174,13 @@ impl HostConfig {
             return Ok(Self::ToSelf);
         }
 
+        if host.strip_prefix('*') == Some(SERVICE_CHAINING_DOMAIN_SUFFIX) {
+            return Ok(Self::AnyComponent);
+        }
+        if host.starts_with("*.") {
+            bail!("wildcard subdomains are only supported for `*{SERVICE_CHAINING_DOMAIN_SUFFIX}`");
+        }
+
         if host.starts_with('{') {
             ensure!(host.ends_with('}'));
             bail!("host lists are not yet supported")
@@ -177,7 +199,9 @@ impl HostConfig {
 
     fn allows(&self, host: &str) -> bool {
         match self {
-            HostConfig::Any => true,
+            // Requests to other components must be allowed explicitly
+            HostConfig::Any => !is_service_chaining_host(host),
+            HostConfig::AnyComponent => is_service_chaining_host(host),
             HostConfig::List(l) => l.iter().any(|h| h.as_str() == host),
             HostConfig::ToSelf => false,
             HostConfig::Cidr(c) => {
@@ -610,4 +634,38 @@ mod test {
             .allows(&OutboundUrl::parse("mysql://user%3Apass%23word@xyz.com", "mysql").unwrap()));
         assert!(allowed.allows(&OutboundUrl::parse("user%3Apass%23word@xyz.com", "mysql").unwrap()));
     }
+
+    #[test]
+    fn test_allowed_hosts_accepts_only_component_wildcard() {
+        assert_eq!(
+            AllowedHostConfig::new(
+                SchemeConfig::new("http"),
+                HostConfig::AnyComponent,
+                PortConfig::new(80)
+            ),
+            AllowedHostConfig::parse("http://*.spin.internal").unwrap()
+        );
+        assert!(AllowedHostConfig::parse("http://*.").is_err());
+        assert!(AllowedHostConfig::parse("https://*.example.com").is_err());
+        assert!(AllowedHostConfig::parse("tcp://*.example.com:5432").is_err());
+        assert!(AllowedHostConfig::parse("http://*.*.spin.internal").is_err());
+    }
+
+    #[test]
+    fn test_service_chaining_must_be_allowed_explicitly() {
+        let backend = OutboundUrl::parse("http://backend.spin.internal/api", "http").unwrap();
+        let other = OutboundUrl::parse("http://other.spin.internal/", "http").unwrap();
+
+        let allow_all = AllowedHostsConfig::parse(&["*://*:*"]).unwrap();
+        assert!(!allow_all.allows(&backend));
+        assert!(allow_all.allows(&OutboundUrl::parse("http://example.com", "http").unwrap()));
+
+        let specific = AllowedHostsConfig::parse(&["http://backend.spin.internal"]).unwrap();
+        assert!(specific.allows(&backend));
+        assert!(!specific.allows(&other));
+
+        let wildcard = AllowedHostsConfig::parse(&["http://*.spin.internal"]).unwrap();
+        assert!(wildcard.allows(&backend));
+        assert!(wildcard.allows(&other));
+    }
 }
@@ -167,20 +167,26 @@ impl BadgerEvaluator {
     async fn available_upgrades(&self) -> anyhow::Result<AvailableUpgrades> {
         let store = self.plugin_manager.store();
 
+        let lookup = self.plugin_manager.upgrade_lookup(&self.plugin_name, None);
+
         let latest_version = {
-            let latest_lookup = crate::lookup::PluginLookup::new(&self.plugin_name, None);
-            let latest_manifest = latest_lookup
+            let latest_manifest = lookup
                 .resolve_manifest_exact(store.get_plugins_directory())
                 .await
                 .ok();
             latest_manifest.and_then(|m| semver::Version::parse(m.version()).ok())
         };
 
-        let manifests = store.catalogue_manifests()?;
-        let relevant_manifests = manifests
-            .into_iter()
-            .filter(|m| m.name() == self.plugin_name);
-        let compatible_manifests = relevant_manifests.filter(|m| {
+        let manifests = match lookup
+            .catalogue_manifests(store.get_plugins_directory())
+            .await
+        {
+            Ok(manifests) => manifests,
+            // A plugin which no catalogue lists has no upgrades to offer
+            Err(crate::error::Error::NotFound(_)) => vec![],
+            Err(e) => return Err(e.into()),
+        };
+        let compatible_manifests = manifests.into_iter().filter(|m| {
             m.has_compatible_package() && m.is_compatible_spin_version(self.spin_version)
         });
         let compatible_plugin_versions =
@@ -0,0 +1,483 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use tracing::log;
+use url::Url;
+
+use crate::{
+    git::GitSource,
+    lookup::{plugins_repo_url, PLUGINS_REPO_LOCAL_DIRECTORY, PLUGINS_REPO_MANIFESTS_DIRECTORY},
+    manifest::{is_valid_plugin_name, PluginManifest},
+    store::manifest_file_name,
+};
+
+/// The name of the public Spin plugins catalogue.
+pub const DEFAULT_CATALOGUE: &str = "spin";
+
+// Name of the file in the plugins directory which configures catalogues
+const CATALOGUES_FILE_NAME: &str = "catalogues.toml";
+
+// Name of directory that contains local copies of configured catalogues
+const CATALOGUES_LOCAL_DIRECTORY: &str = ".spin-plugins-catalogues";
+
+// Name of the file an HTTP catalogue is cached into
+const HTTP_INDEX_FILE_NAME: &str = "index.json";
+
+/// A named source of plugin manifests.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Catalogue {
+    name: String,
+    source: CatalogueSource,
+    builtin: bool,
+}
+
+/// Where a catalogue gets its plugin manifests from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CatalogueSource {
+    /// A Git repository laid out like the spin-plugins repository.
+    Git { url: Url, branch: Option<String> },
+    /// A JSON document containing an array of plugin manifests.
+    Http(Url),
+    /// A local directory laid out like the spin-plugins repository.
+    Local(PathBuf),
+}
+
+impl Catalogue {
+    /// The public Spin plugins catalogue.
+    pub fn default_catalogue() -> Self {
+        Self {
+            name: DEFAULT_CATALOGUE.to_owned(),
+            source: CatalogueSource::Git {
+                url: plugins_repo_url().expect("default plugins repo URL should be valid"),
+                branch: None,
+            },
+            builtin: true,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn source(&self) -> &CatalogueSource {
+        &self.source
+    }
+
+    /// The directory holding the local copy of the catalogue.
+    fn root_dir(&self, plugins_dir: &Path) -> PathBuf {
+        match &self.source {
+            CatalogueSource::Local(path) => path.clone(),
+            // The public catalogue keeps the location it had before catalogues
+            // were configurable, so existing snapshots remain valid.
+            _ if self.builtin => plugins_dir.join(PLUGINS_REPO_LOCAL_DIRECTORY),
+            _ => plugins_dir
+                .join(CATALOGUES_LOCAL_DIRECTORY)
+                .join(&self.name),
+        }
+    }
+
+    /// The directory containing the catalogue's manifests. Each plugin has a
+    /// subdirectory containing `<name>.json` for its latest version and
+    /// `<name>@<version>.json` for other versions.
+    pub fn manifests_dir(&self, plugins_dir: &Path) -> PathBuf {
+        self.root_dir(plugins_dir)
+            .join(PLUGINS_REPO_MANIFESTS_DIRECTORY)
+    }
+
+    /// Whether the local copy of the catalogue lists the named plugin.
+    pub fn contains(&self, plugin_name: &str, plugins_dir: &Path) -> bool {
+        self.manifests_dir(plugins_dir).join(plugin_name).is_dir()
+    }
+
+    /// Ensures there is a local copy of the catalogue, refreshing it from its
+    /// source if `update` is set.
+    pub async fn fetch(&self, plugins_dir: &Path, update: bool) -> Result<()> {
+        let root = self.root_dir(plugins_dir);
+        match &self.source {
+            CatalogueSource::Git { url, branch } => {
+                let git_source = GitSource::new(url, branch.clone(), &root);
+                if accept_as_repo(&root) {
+                    if update {
+                        git_source.pull().await?;
+                    }
+                } else {
+                    git_source.clone_repo().await?;
+                }
+            }
+            CatalogueSource::Http(url) => {
+                if update || !root.join(HTTP_INDEX_FILE_NAME).exists() {
+                    fetch_http_index(url, &root).await?;
+                }
+            }
+            CatalogueSource::Local(path) => {
+                if !path.is_dir() {
+                    bail!("catalogue directory {} does not exist", path.display());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn describe_source(&self) -> String {
+        match &self.source {
+            CatalogueSource::Git { url, .. } => url.to_string(),
+            CatalogueSource::Http(url) => url.to_string(),
+            CatalogueSource::Local(path) => path.display().to_string(),
+        }
+    }
+}
+
+/// The configured catalogues, in order of precedence.
+pub struct Catalogues {
+    catalogues: Vec<Catalogue>,
+}
+
+impl Catalogues {
+    /// Loads the catalogues configured in the plugins directory. The public
+    /// catalogue is added with the lowest precedence unless the configuration
+    /// defines a catalogue of the same name.
+    pub fn load(plugins_dir: &Path) -> Result<Self> {
+        let config_path = plugins_dir.join(CATALOGUES_FILE_NAME);
+        let text = match std::fs::read_to_string(&config_path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", config_path.display()))
+            }
+        };
+        Self::parse(&text).with_context(|| {
+            format!(
+                "Invalid catalogue configuration in {}",
+                config_path.display()
+            )
+        })
+    }
+
+    fn parse(text: &str) -> Result<Self> {
+        let raw: RawCatalogues = toml::from_str(text)?;
+        let mut catalogues = raw
+            .catalogue
+            .into_iter()
+            .map(Catalogue::try_from)
+            .collect::<Result<Vec<_>>>()?;
+
+        for (index, catalogue) in catalogues.iter().enumerate() {
+            if catalogues[..index].iter().any(|c| c.name == catalogue.name) {
+                bail!("catalogue '{}' is defined more than once", catalogue.name);
+            }
+        }
+        if !catalogues.iter().any(|c| c.name == DEFAULT_CATALOGUE) {
+            catalogues.push(Catalogue::default_catalogue());
+        }
+
+        Ok(Self { catalogues })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Catalogue> {
+        self.catalogues.iter()
+    }
+
+    /// Gets the catalogue with the given name.
+    pub fn get(&self, name: &str) -> Result<&Catalogue> {
+        self.catalogues
+            .iter()
+            .find(|c| c.name == name)
+            .ok_or_else(|| {
+                let names = self.catalogues.iter().map(|c| c.name.as_str());
+                anyhow!(
+                    "No plugin catalogue named '{name}'. Configured catalogues are: {}",
+                    names.collect::<Vec<_>>().join(", ")
+                )
+            })
+    }
+
+    /// Refreshes the local copies of all catalogues. All catalogues are
+    /// attempted even if some fail.
+    pub async fn fetch_all(&self, plugins_dir: &Path, update: bool) -> Result<()> {
+        let mut failures = vec![];
+        for catalogue in &self.catalogues {
+            if let Err(e) = catalogue.fetch(plugins_dir, update).await {
+                log::info!("Failed to fetch catalogue '{}': {e:#}", catalogue.name);
+                failures.push(format!(
+                    "{} ({}): {e:#}",
+                    catalogue.name,
+                    catalogue.describe_source()
+                ));
+            }
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            bail!(
+                "Failed to fetch plugin catalogues:\n{}",
+                failures.join("\n")
+            )
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawCatalogues {
+    #[serde(default)]
+    catalogue: Vec<RawCatalogue>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawCatalogue {
+    name: String,
+    git: Option<Url>,
+    branch: Option<String>,
+    http: Option<Url>,
+    path: Option<PathBuf>,
+}
+
+impl TryFrom<RawCatalogue> for Catalogue {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: RawCatalogue) -> Result<Self> {
+        let name = raw.name;
+        if name.is_empty() || name.contains(['@', '/', '\\']) {
+            bail!("catalogue name '{name}' is invalid: names must be non-empty and may not contain '@' or path separators");
+        }
+        if raw.branch.is_some() && raw.git.is_none() {
+            bail!("catalogue '{name}' specifies a branch, but is not a Git catalogue");
+        }
+        let source = match (raw.git, raw.http, raw.path) {
+            (Some(url), None, None) => CatalogueSource::Git {
+                url,
+                branch: raw.branch,
+            },
+            (None, Some(url), None) => CatalogueSource::Http(url),
+            (None, None, Some(path)) => CatalogueSource::Local(path),
+            _ => bail!("catalogue '{name}' must specify exactly one of 'git', 'http' or 'path'"),
+        };
+        Ok(Self {
+            name,
+            source,
+            builtin: false,
+        })
+    }
+}
+
+#[cfg(not(test))]
+fn accept_as_repo(git_root: &Path) -> bool {
+    git_root.join(".git").exists()
+}
+
+#[cfg(test)]
+fn accept_as_repo(git_root: &Path) -> bool {
+    git_root.join(".git").exists() || git_root.join("_spin_test_dot_git").exists()
+}
+
+/// Downloads an HTTP catalogue index and lays out its manifests in the same
+/// structure as a Git catalogue.
+async fn fetch_http_index(url: &Url, root: &Path) -> Result<()> {
+    log::info!("Fetching plugin catalogue index from {url}");
+    let index_text = reqwest::get(url.as_ref())
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let manifests: Vec<PluginManifest> = serde_json::from_str(&index_text)
+        .with_context(|| format!("{url} is not a valid catalogue index"))?;
+    // Names and versions become file names, so must not be able to escape
+    // the catalogue directory.
+    for manifest in &manifests {
+        let name = manifest.name();
+        if !is_valid_plugin_name(&name) {
+            bail!("{url} lists a plugin with invalid name {name:?}: names may only contain letters, digits, '-' and '_'");
+        }
+        if let Err(e) = manifest.try_version() {
+            bail!(
+                "{url} lists plugin '{name}' with invalid version {:?}: {e}",
+                manifest.version()
+            );
+        }
+    }
+
+    let manifests_dir = root.join(PLUGINS_REPO_MANIFESTS_DIRECTORY);
+    if manifests_dir.exists() {
+        tokio::fs::remove_dir_all(&manifests_dir).await?;
+    }
+    for manifest in &manifests {
+        let name = manifest.name();
+        let is_latest = manifests
+            .iter()
+            .filter(|m| m.name() == name)
+            .all(|m| version_key(m) <= version_key(manifest));
+        let file_name = if is_latest {
+            manifest_file_name(&name)
+        } else {
+            format!("{}@{}.json", name, manifest.version())
+        };
+        let plugin_dir = manifests_dir.join(&name);
+        tokio::fs::create_dir_all(&plugin_dir).await?;
+        tokio::fs::write(plugin_dir.join(file_name), serde_json::to_vec(manifest)?).await?;
+    }
+    tokio::fs::write(root.join(HTTP_INDEX_FILE_NAME), index_text).await?;
+    Ok(())
+}
+
+fn version_key(manifest: &PluginManifest) -> semver::Version {
+    manifest
+        .try_version()
+        .unwrap_or_else(|_| semver::Version::new(0, 0, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spin_test_http_server::TestServer;
+
+    // Serves the body in response to every request, returning the URL of the index on the
+    // server. The server must be kept alive for as long as the URL is used.
+    async fn serve_index(body: String) -> (TestServer, Url) {
+        let server = TestServer::serve("application/json", body).await;
+        let url = Url::parse(&server.url("/index.json")).unwrap();
+        (server, url)
+    }
+
+    fn index_entry(name: &str, version: &str) -> serde_json::Value {
+        serde_json::json!({
+            "name": name,
+            "version": version,
+            "spinCompatibility": ">=2.0",
+            "license": "Apache-2.0",
+            "packages": [],
+        })
+    }
+
+    #[tokio::test]
+    async fn http_index_is_laid_out_like_git_catalogue() {
+        let index = serde_json::json!([
+            index_entry("example", "1.0.0"),
+            index_entry("example", "1.1.0"),
+            index_entry("other_plugin", "0.1.0"),
+        ]);
+        let (_server, url) = serve_index(index.to_string()).await;
+        let root = tempfile::tempdir().unwrap();
+
+        fetch_http_index(&url, root.path()).await.unwrap();
+
+        let manifests_dir = root.path().join(PLUGINS_REPO_MANIFESTS_DIRECTORY);
+        assert!(manifests_dir.join("example/example.json").is_file());
+        assert!(manifests_dir.join("example/example@1.0.0.json").is_file());
+        assert!(manifests_dir
+            .join("other_plugin/other_plugin.json")
+            .is_file());
+        assert!(root.path().join(HTTP_INDEX_FILE_NAME).is_file());
+    }
+
+    #[tokio::test]
+    async fn http_index_entries_cannot_escape_catalogue() {
+        let bad_entries = [
+            index_entry("../escaped", "1.0.0"),
+            index_entry("nested/name", "1.0.0"),
+            index_entry("..", "1.0.0"),
+            index_entry("example", "1.0.0/../../escaped"),
+        ];
+        for bad_entry in bad_entries {
+            let index = serde_json::json!([index_entry("example", "2.0.0"), bad_entry]);
+            let (_server, url) = serve_index(index.to_string()).await;
+            let dir = tempfile::tempdir().unwrap();
+            let root = dir.path().join("catalogue");
+
+            let err = fetch_http_index(&url, &root).await.unwrap_err();
+            assert!(err.to_string().contains("invalid"), "{err:#}");
+            let written: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+            assert!(written.is_empty(), "{bad_entry}: wrote {written:?}");
+        }
+    }
+
+    #[test]
+    fn public_catalogue_is_used_if_nothing_configured() {
+        let catalogues = Catalogues::parse("").unwrap();
+        let names: Vec<_> = catalogues.iter().map(|c| c.name()).collect();
+        assert_eq!(vec![DEFAULT_CATALOGUE], names);
+    }
+
+    #[test]
+    fn configured_catalogues_take_precedence_over_public() {
+        let catalogues = Catalogues::parse(
+            r#"
+            [[catalogue]]
+            name = "acme"
+            git = "https://example.com/acme/plugins"
+            branch = "release"
+
+            [[catalogue]]
+            name = "acme-http"
+            http = "https://plugins.example.com/index.json"
+
+            [[catalogue]]
+            name = "shared"
+            path = "/mnt/shared/plugins"
+            "#,
+        )
+        .unwrap();
+        let names: Vec<_> = catalogues.iter().map(|c| c.name()).collect();
+        assert_eq!(
+            vec!["acme", "acme-http", "shared", DEFAULT_CATALOGUE],
+            names
+        );
+        assert_eq!(
+            &CatalogueSource::Git {
+                url: Url::parse("https://example.com/acme/plugins").unwrap(),
+                branch: Some("release".to_owned())
+            },
+            catalogues.get("acme").unwrap().source()
+        );
+    }
+
+    #[test]
+    fn public_catalogue_can_be_reordered() {
+        let catalogues = Catalogues::parse(
+            r#"
+            [[catalogue]]
+            name = "spin"
+            git = "https://github.com/fermyon/spin-plugins/"
+
+            [[catalogue]]
+            name = "acme"
+            path = "/mnt/acme"
+            "#,
+        )
+        .unwrap();
+        let names: Vec<_> = catalogues.iter().map(|c| c.name()).collect();
+        assert_eq!(vec![DEFAULT_CATALOGUE, "acme"], names);
+    }
+
+    #[test]
+    fn catalogue_must_have_exactly_one_source() {
+        let err = Catalogues::parse(
+            r#"
+            [[catalogue]]
+            name = "acme"
+            git = "https://example.com/acme/plugins"
+            path = "/mnt/acme"
+            "#,
+        )
+        .err()
+        .expect("should have rejected catalogue with two sources");
+        assert!(err.to_string().contains("exactly one of"));
+    }
+
+    #[test]
+    fn catalogue_names_must_be_unique() {
+        Catalogues::parse(
+            r#"
+            [[catalogue]]
+            name = "acme"
+            path = "/mnt/acme"
+
+            [[catalogue]]
+            name = "acme"
+            path = "/mnt/acme2"
+            "#,
+        )
+        .err()
+        .expect("should have rejected duplicate catalogue names");
+    }
+}
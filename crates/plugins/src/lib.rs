@@ -1,9 +1,11 @@
 pub mod badger;
+pub mod catalogue;
 pub mod error;
 mod git;
 pub mod lookup;
 pub mod manager;
 pub mod manifest;
+pub mod requirements;
 mod store;
 pub use store::PluginStore;
 
@@ -23,6 +25,7 @@ pub(crate) const SPIN_INTERNAL_COMMANDS: &[&str] = &[
     "external",
     "doctor",
     "registry",
+    "manifest",
     "watch",
     "oci",
 ];
@@ -1,4 +1,9 @@
-use crate::{error::*, git::GitSource, manifest::PluginManifest, store::manifest_file_name};
+use crate::{
+    catalogue::{Catalogue, Catalogues},
+    error::*,
+    manifest::PluginManifest,
+    store::manifest_file_name,
+};
 use semver::Version;
 use std::{
     fs::File,
@@ -9,25 +14,102 @@ use url::Url;
 
 // Name of directory that contains the cloned centralized Spin plugins
 // repository
-const PLUGINS_REPO_LOCAL_DIRECTORY: &str = ".spin-plugins";
+pub(crate) const PLUGINS_REPO_LOCAL_DIRECTORY: &str = ".spin-plugins";
 
 // Name of directory containing the installed manifests
 pub(crate) const PLUGINS_REPO_MANIFESTS_DIRECTORY: &str = "manifests";
 
 pub(crate) const SPIN_PLUGINS_REPO: &str = "https://github.com/fermyon/spin-plugins/";
 
-/// Looks up plugin manifests in centralized spin plugin repository.
+/// Looks up plugin manifests in the configured plugin catalogues.
 pub struct PluginLookup {
     pub name: String,
     pub version: Option<Version>,
+    /// The catalogue to look in. If `None`, catalogues are searched in order
+    /// of precedence.
+    pub catalogue: Option<String>,
 }
 
 impl PluginLookup {
+    /// Creates a lookup for the named plugin. The name may be of the form
+    /// `name@catalogue` to look only in the given catalogue.
     pub fn new(name: &str, version: Option<Version>) -> Self {
+        let (name, catalogue) = match name.split_once('@') {
+            Some((name, catalogue)) => (name, Some(catalogue.to_owned())),
+            None => (name, None),
+        };
         Self {
             name: name.to_lowercase(),
             version,
+            catalogue,
+        }
+    }
+
+    /// Restricts the lookup to the given catalogue, if one is specified and
+    /// the name did not already specify one.
+    pub fn with_catalogue(mut self, catalogue: Option<String>) -> Self {
+        if self.catalogue.is_none() {
+            self.catalogue = catalogue;
+        }
+        self
+    }
+
+    /// Finds the catalogue which provides the plugin: the one requested, or
+    /// otherwise the highest-precedence catalogue listing the plugin. Local
+    /// copies of catalogues are fetched as needed.
+    pub async fn resolve_catalogue(&self, plugins_dir: &Path) -> PluginLookupResult<Catalogue> {
+        let catalogues = Catalogues::load(plugins_dir)?;
+        if let Some(name) = &self.catalogue {
+            let catalogue = catalogues.get(name)?;
+            fetch_catalogue(catalogue, plugins_dir).await?;
+            return Ok(catalogue.clone());
+        }
+
+        let mut fetch_error = None;
+        for catalogue in catalogues.iter() {
+            if let Err(e) = fetch_catalogue(catalogue, plugins_dir).await {
+                fetch_error.get_or_insert(e);
+                continue;
+            }
+            if catalogue.contains(&self.name, plugins_dir) {
+                return Ok(catalogue.clone());
+            }
+        }
+        match fetch_error {
+            Some(e) => Err(e),
+            None => Err(Error::NotFound(NotFoundError::new(
+                Some(self.name.clone()),
+                catalogues
+                    .iter()
+                    .map(|c| c.name())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                "no configured catalogue lists this plugin".to_owned(),
+            ))),
+        }
+    }
+
+    /// The name of the catalogue which provides the plugin, without fetching
+    /// any catalogues. This is `None` if no local catalogue copy lists it.
+    pub fn resolved_catalogue_name(&self, plugins_dir: &Path) -> Option<String> {
+        if self.catalogue.is_some() {
+            return self.catalogue.clone();
         }
+        let catalogues = Catalogues::load(plugins_dir).ok()?;
+        let found = catalogues
+            .iter()
+            .find(|c| c.contains(&self.name, plugins_dir))?;
+        Some(found.name().to_owned())
+    }
+
+    /// All versions of the plugin listed in the catalogue which provides it.
+    pub async fn catalogue_manifests(
+        &self,
+        plugins_dir: &Path,
+    ) -> PluginLookupResult<Vec<PluginManifest>> {
+        let catalogue = self.resolve_catalogue(plugins_dir).await?;
+        let store = crate::store::PluginStore::new(plugins_dir.to_owned());
+        Ok(store.catalogue_manifests_for(&catalogue, &self.name))
     }
 
     pub async fn resolve_manifest(
@@ -44,12 +126,10 @@ impl PluginLookup {
             return Ok(exact);
         }
 
-        let store = crate::store::PluginStore::new(plugins_dir.to_owned());
-
         // TODO: This is very similar to some logic in the badger module - look for consolidation opportunities.
-        let manifests = store.catalogue_manifests()?;
-        let relevant_manifests = manifests.into_iter().filter(|m| m.name() == self.name);
-        let compatible_manifests = relevant_manifests
+        let manifests = self.catalogue_manifests(plugins_dir).await?;
+        let compatible_manifests = manifests
+            .into_iter()
             .filter(|m| m.has_compatible_package() && m.is_compatible_spin_version(spin_version));
         let highest_compatible_manifest =
             compatible_manifests.max_by_key(|m| m.try_version().unwrap_or_else(|_| null_version()));
@@ -61,15 +141,13 @@ impl PluginLookup {
         &self,
         plugins_dir: &Path,
     ) -> PluginLookupResult<PluginManifest> {
-        let url = plugins_repo_url()?;
-        log::info!("Pulling manifest for plugin {} from {url}", self.name);
-        fetch_plugins_repo(&url, plugins_dir, false)
-            .await
-            .map_err(|e| {
-                Error::ConnectionFailed(ConnectionFailedError::new(url.to_string(), e.to_string()))
-            })?;
-
-        self.resolve_manifest_exact_from_good_repo(plugins_dir)
+        let catalogue = self.resolve_catalogue(plugins_dir).await?;
+        log::info!(
+            "Pulling manifest for plugin {} from catalogue {}",
+            self.name,
+            catalogue.name()
+        );
+        self.resolve_manifest_exact_from_good_repo(&catalogue.manifests_dir(plugins_dir))
     }
 
     // This is split from resolve_manifest_exact because it may recurse (once) and that makes
@@ -77,9 +155,9 @@ impl PluginLookup {
     #[allow(clippy::let_and_return)]
     pub fn resolve_manifest_exact_from_good_repo(
         &self,
-        plugins_dir: &Path,
+        manifests_dir: &Path,
     ) -> PluginLookupResult<PluginManifest> {
-        let expected_path = spin_plugins_repo_manifest_path(&self.name, &self.version, plugins_dir);
+        let expected_path = catalogue_manifest_path(&self.name, &self.version, manifests_dir);
 
         let not_found = |e: std::io::Error| {
             Err(Error::NotFound(NotFoundError::new(
@@ -101,7 +179,7 @@ impl PluginLookup {
                 // If a user has asked for a version by number, and the path doesn't exist,
                 // it _might_ be because it's the latest version. This checks for that case.
                 let latest = Self::new(&self.name, None);
-                match latest.resolve_manifest_exact_from_good_repo(plugins_dir) {
+                match latest.resolve_manifest_exact_from_good_repo(manifests_dir) {
                     Ok(manifest) if manifest.try_version().ok() == self.version => Ok(manifest),
                     _ => not_found(e),
                 }
@@ -117,35 +195,21 @@ pub fn plugins_repo_url() -> Result<Url, url::ParseError> {
     Url::parse(SPIN_PLUGINS_REPO)
 }
 
-#[cfg(not(test))]
-fn accept_as_repo(git_root: &Path) -> bool {
-    git_root.join(".git").exists()
-}
-
-#[cfg(test)]
-fn accept_as_repo(git_root: &Path) -> bool {
-    git_root.join(".git").exists() || git_root.join("_spin_test_dot_git").exists()
-}
-
-pub async fn fetch_plugins_repo(
-    repo_url: &Url,
-    plugins_dir: &Path,
-    update: bool,
-) -> anyhow::Result<()> {
-    let git_root = plugin_manifests_repo_path(plugins_dir);
-    let git_source = GitSource::new(repo_url, None, &git_root);
-    if accept_as_repo(&git_root) {
-        if update {
-            git_source.pull().await?;
-        }
-    } else {
-        git_source.clone_repo().await?;
-    }
-    Ok(())
+/// Ensures there is a local copy of all configured plugin catalogues,
+/// refreshing them from their sources if `update` is set.
+pub async fn fetch_plugins_repo(plugins_dir: &Path, update: bool) -> anyhow::Result<()> {
+    Catalogues::load(plugins_dir)?
+        .fetch_all(plugins_dir, update)
+        .await
 }
 
-fn plugin_manifests_repo_path(plugins_dir: &Path) -> PathBuf {
-    plugins_dir.join(PLUGINS_REPO_LOCAL_DIRECTORY)
+async fn fetch_catalogue(catalogue: &Catalogue, plugins_dir: &Path) -> PluginLookupResult<()> {
+    catalogue.fetch(plugins_dir, false).await.map_err(|e| {
+        Error::ConnectionFailed(ConnectionFailedError::new(
+            format!("plugin catalogue '{}'", catalogue.name()),
+            e.to_string(),
+        ))
+    })
 }
 
 // Given a name and option version, outputs expected file name for the plugin.
@@ -157,23 +221,17 @@ fn manifest_file_name_version(plugin_name: &str, version: &Option<semver::Versio
 }
 
 /// Get expected path to the manifest of a plugin with a given name
-/// and version within the spin-plugins repository
-fn spin_plugins_repo_manifest_path(
+/// and version within a catalogue's manifests directory
+fn catalogue_manifest_path(
     plugin_name: &str,
     plugin_version: &Option<Version>,
-    plugins_dir: &Path,
+    manifests_dir: &Path,
 ) -> PathBuf {
-    spin_plugins_repo_manifest_dir(plugins_dir)
+    manifests_dir
         .join(plugin_name)
         .join(manifest_file_name_version(plugin_name, plugin_version))
 }
 
-pub fn spin_plugins_repo_manifest_dir(plugins_dir: &Path) -> PathBuf {
-    plugins_dir
-        .join(PLUGINS_REPO_LOCAL_DIRECTORY)
-        .join(PLUGINS_REPO_MANIFESTS_DIRECTORY)
-}
-
 fn null_version() -> semver::Version {
     semver::Version::new(0, 0, 0)
 }
@@ -243,6 +301,64 @@ mod tests {
         Ok(())
     }
 
+    fn plugins_dir_with_local_catalogue() -> tempfile::TempDir {
+        let plugins_dir = tempfile::tempdir().unwrap();
+        let catalogue_path = tests_store_dir().join(".spin-plugins");
+        let config = format!(
+            "[[catalogue]]\nname = \"local\"\npath = {:?}\n",
+            catalogue_path.display().to_string()
+        );
+        std::fs::write(plugins_dir.path().join("catalogues.toml"), config).unwrap();
+        plugins_dir
+    }
+
+    #[test]
+    fn catalogue_can_be_given_in_name() {
+        let lookup = PluginLookup::new("Some-Plugin@acme", None);
+        assert_eq!("some-plugin", lookup.name);
+        assert_eq!(Some("acme".to_owned()), lookup.catalogue);
+
+        let lookup = lookup.with_catalogue(Some("other".to_owned()));
+        assert_eq!(Some("acme".to_owned()), lookup.catalogue);
+    }
+
+    #[tokio::test]
+    async fn configured_catalogue_is_searched_first() -> PluginLookupResult<()> {
+        let plugins_dir = plugins_dir_with_local_catalogue();
+        let lookup = PluginLookup::new(TEST_NAME, None);
+        let resolved = lookup
+            .resolve_manifest(plugins_dir.path(), false, "99.0.0")
+            .await?;
+        assert_eq!("99.0.1", resolved.version);
+        assert_eq!(
+            Some("local".to_owned()),
+            lookup.resolved_catalogue_name(plugins_dir.path())
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn if_catalogue_given_it_gets_used() -> PluginLookupResult<()> {
+        let plugins_dir = plugins_dir_with_local_catalogue();
+        let lookup = PluginLookup::new(&format!("{TEST_NAME}@local"), None);
+        let resolved = lookup
+            .resolve_manifest(plugins_dir.path(), false, "98.0.0")
+            .await?;
+        assert_eq!("98.0.0", resolved.version);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn if_unknown_catalogue_given_then_error() {
+        let plugins_dir = plugins_dir_with_local_catalogue();
+        let lookup = PluginLookup::new(&format!("{TEST_NAME}@nope"), None);
+        let err = lookup
+            .resolve_manifest(plugins_dir.path(), false, "99.0.0")
+            .await
+            .expect_err("Should have errored because catalogue 'nope' does not exist");
+        assert!(err.to_string().contains("No plugin catalogue named 'nope'"));
+    }
+
     #[tokio::test]
     async fn if_non_existent_version_given_then_error() -> PluginLookupResult<()> {
         let lookup = PluginLookup::new(TEST_NAME, Some(semver::Version::parse("177.7.7").unwrap()));
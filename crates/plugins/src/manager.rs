@@ -8,7 +8,7 @@ use crate::{
 
 use anyhow::{anyhow, bail, Context, Result};
 use path_absolutize::Absolutize;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use spin_common::sha256;
 use std::{
     fs::{self, File},
@@ -28,12 +28,12 @@ pub enum ManifestLocation {
     Local(PathBuf),
     /// Plugin manifest should be pulled from a specific address.
     Remote(Url),
-    /// Plugin manifest lives in the centralized plugins repository
+    /// Plugin manifest lives in one of the configured plugin catalogues
     PluginsRepository(PluginLookup),
 }
 
 impl ManifestLocation {
-    pub(crate) fn to_install_record(&self) -> RawInstallRecord {
+    pub(crate) fn to_install_record(&self, plugins_dir: &Path) -> RawInstallRecord {
         match self {
             Self::Local(path) => {
                 // Plugin commands don't absolutise on the way in, so do it now.
@@ -47,17 +47,26 @@ impl ManifestLocation {
             Self::Remote(url) => RawInstallRecord::Remote {
                 url: url.to_owned(),
             },
-            Self::PluginsRepository(_) => RawInstallRecord::PluginsRepository,
+            Self::PluginsRepository(lookup) => RawInstallRecord::PluginsRepository {
+                catalogue: lookup.resolved_catalogue_name(plugins_dir),
+            },
         }
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 #[serde(rename = "snake_case", tag = "source")]
 pub(crate) enum RawInstallRecord {
-    PluginsRepository,
-    Remote { url: Url },
-    Local { file: PathBuf },
+    PluginsRepository {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        catalogue: Option<String>,
+    },
+    Remote {
+        url: Url,
+    },
+    Local {
+        file: PathBuf,
+    },
 }
 
 /// Provides accesses to functionality to inspect and manage the installation of plugins.
@@ -258,11 +267,24 @@ impl PluginManager {
         Ok(locker)
     }
 
+    /// A lookup for upgrading an installed plugin, which stays on the
+    /// catalogue the plugin was installed from unless the name specifies
+    /// a different one.
+    pub fn upgrade_lookup(
+        &self,
+        plugin_name: &str,
+        version: Option<semver::Version>,
+    ) -> PluginLookup {
+        let lookup = PluginLookup::new(plugin_name, version);
+        let catalogue = self.store.installed_catalogue(&lookup.name);
+        lookup.with_catalogue(catalogue)
+    }
+
     fn write_install_record(&self, plugin_name: &str, source: &ManifestLocation) {
         let install_record_path = self.store.installation_record_file(plugin_name);
 
         // A failure here shouldn't fail the install
-        let install_record = source.to_install_record();
+        let install_record = source.to_install_record(self.store.get_plugins_directory());
         if let Ok(record_text) = serde_json::to_string_pretty(&install_record) {
             _ = std::fs::write(install_record_path, record_text);
         }
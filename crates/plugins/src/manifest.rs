@@ -70,6 +70,16 @@ impl PluginManifest {
     }
 }
 
+/// Whether the given string is a valid plugin name. Plugin names are used as
+/// file and directory names, so may contain only ASCII letters, digits, `-`
+/// and `_`.
+pub(crate) fn is_valid_plugin_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
 /// Describes compatibility and location of a plugin source.
 #[derive(Serialize, Debug, Deserialize, PartialEq)]
 pub struct PluginPackage {
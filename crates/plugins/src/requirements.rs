@@ -0,0 +1,297 @@
+use anyhow::{anyhow, Context, Result};
+use semver::VersionReq;
+use serde::Deserialize;
+use std::{collections::BTreeMap, path::Path};
+
+use crate::{lookup::PluginLookup, manifest::PluginManifest, PluginStore};
+
+/// Name of the file, alongside the application manifest, which lists the
+/// plugins a project requires.
+pub const PROJECT_REQUIREMENTS_FILE_NAME: &str = "spin-plugins.toml";
+
+/// A plugin a project requires, and the versions it accepts.
+#[derive(Debug, PartialEq)]
+pub struct PluginRequirement {
+    pub name: String,
+    pub version: VersionReq,
+    /// The catalogue the plugin must come from, if the project specifies one.
+    pub catalogue: Option<String>,
+}
+
+/// Why an installation does not satisfy a plugin requirement.
+#[derive(Debug, PartialEq)]
+pub enum RequirementProblem {
+    NotInstalled,
+    WrongVersion {
+        installed: String,
+    },
+    /// The plugin was not installed from the required catalogue. `installed`
+    /// is the catalogue it was installed from, if any.
+    WrongCatalogue {
+        installed: Option<String>,
+    },
+}
+
+/// The plugins required by a project.
+#[derive(Debug, Default)]
+pub struct ProjectRequirements {
+    requirements: Vec<PluginRequirement>,
+}
+
+impl ProjectRequirements {
+    /// Loads the requirements file from the project directory. Returns `None`
+    /// if the project has no requirements file.
+    pub fn load(project_dir: &Path) -> Result<Option<Self>> {
+        let path = project_dir.join(PROJECT_REQUIREMENTS_FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let requirements = Self::parse(&text)
+            .with_context(|| format!("Invalid plugin requirements in {}", path.display()))?;
+        Ok(Some(requirements))
+    }
+
+    fn parse(text: &str) -> Result<Self> {
+        let raw: RawProjectRequirements = toml::from_str(text)?;
+        let requirements = raw
+            .plugins
+            .into_iter()
+            .map(|(name, raw)| {
+                let (version, catalogue) = match raw {
+                    RawPluginRequirement::Version(version) => (version, None),
+                    RawPluginRequirement::Detailed { version, catalogue } => (version, catalogue),
+                };
+                let version = VersionReq::parse(&version).map_err(|e| {
+                    anyhow!("plugin '{name}' has invalid version requirement '{version}': {e}")
+                })?;
+                Ok(PluginRequirement {
+                    name: name.to_lowercase(),
+                    version,
+                    catalogue,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { requirements })
+    }
+
+    pub fn requirements(&self) -> &[PluginRequirement] {
+        &self.requirements
+    }
+
+    /// The requirements which the installed plugins do not satisfy.
+    pub fn unsatisfied<'a>(
+        &'a self,
+        store: &PluginStore,
+    ) -> Vec<(&'a PluginRequirement, RequirementProblem)> {
+        self.requirements
+            .iter()
+            .filter_map(|req| req.check(store).err().map(|problem| (req, problem)))
+            .collect()
+    }
+}
+
+impl PluginRequirement {
+    /// Checks whether the installed plugins satisfy the requirement.
+    pub fn check(&self, store: &PluginStore) -> Result<(), RequirementProblem> {
+        let installed = store
+            .read_plugin_manifest(&self.name)
+            .map_err(|_| RequirementProblem::NotInstalled)?;
+        if !installed
+            .try_version()
+            .is_ok_and(|version| self.version.matches(&version))
+        {
+            return Err(RequirementProblem::WrongVersion {
+                installed: installed.version().to_owned(),
+            });
+        }
+        if let Some(catalogue) = &self.catalogue {
+            let installed = store.installed_catalogue(&self.name);
+            if installed.as_ref() != Some(catalogue) {
+                return Err(RequirementProblem::WrongCatalogue { installed });
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds the highest version of the plugin which satisfies the requirement
+    /// and is compatible with this Spin and platform, returning its manifest
+    /// and the lookup by which it can be installed.
+    pub async fn resolve(
+        &self,
+        plugins_dir: &Path,
+        spin_version: &str,
+    ) -> Result<(PluginManifest, PluginLookup)> {
+        let lookup = PluginLookup::new(&self.name, None).with_catalogue(self.catalogue.clone());
+        let manifests = lookup.catalogue_manifests(plugins_dir).await?;
+        let manifest = manifests
+            .into_iter()
+            .filter(|m| m.try_version().is_ok_and(|v| self.version.matches(&v)))
+            .filter(|m| m.has_compatible_package() && m.is_compatible_spin_version(spin_version))
+            .max_by_key(|m| m.try_version().ok())
+            .ok_or_else(|| {
+                anyhow!(
+                    "No version of plugin '{}' matching '{}' is available for this version of Spin and platform",
+                    self.name,
+                    self.version
+                )
+            })?;
+        let lookup = PluginLookup::new(&self.name, manifest.try_version().ok())
+            .with_catalogue(lookup.resolved_catalogue_name(plugins_dir));
+        Ok((manifest, lookup))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawProjectRequirements {
+    #[serde(default)]
+    plugins: BTreeMap<String, RawPluginRequirement>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawPluginRequirement {
+    Version(String),
+    Detailed {
+        version: String,
+        catalogue: Option<String>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_requirements() {
+        let requirements = ProjectRequirements::parse(
+            r#"
+            [plugins]
+            cloud = ">=0.7"
+            js2wasm = { version = "^0.6", catalogue = "acme" }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                PluginRequirement {
+                    name: "cloud".to_owned(),
+                    version: VersionReq::parse(">=0.7").unwrap(),
+                    catalogue: None,
+                },
+                PluginRequirement {
+                    name: "js2wasm".to_owned(),
+                    version: VersionReq::parse("^0.6").unwrap(),
+                    catalogue: Some("acme".to_owned()),
+                },
+            ],
+            requirements.requirements()
+        );
+    }
+
+    #[test]
+    fn invalid_version_requirement_is_an_error() {
+        let err = ProjectRequirements::parse(
+            r#"
+            [plugins]
+            cloud = "not a version"
+            "#,
+        )
+        .expect_err("should have rejected invalid version requirement");
+        assert!(err.to_string().contains("cloud"));
+    }
+
+    #[test]
+    fn reports_unsatisfied_requirements() {
+        let plugins_dir = tempfile::tempdir().unwrap();
+        let store = PluginStore::new(plugins_dir.path());
+        let manifest: PluginManifest = serde_json::from_str(include_str!(
+            "../tests/some-spin-ver-some-not/some-spin-ver-some-not@98.0.0.json"
+        ))
+        .unwrap();
+        store.add_manifest(&manifest).unwrap();
+
+        let requirements = ProjectRequirements::parse(
+            r#"
+            [plugins]
+            some-spin-ver-some-not = "^99"
+            not-installed = "*"
+            "#,
+        )
+        .unwrap();
+
+        let unsatisfied = requirements.unsatisfied(&store);
+        assert_eq!(2, unsatisfied.len());
+        assert_eq!("not-installed", unsatisfied[0].0.name);
+        assert_eq!(RequirementProblem::NotInstalled, unsatisfied[0].1);
+        assert_eq!("some-spin-ver-some-not", unsatisfied[1].0.name);
+        assert_eq!(
+            RequirementProblem::WrongVersion {
+                installed: "98.0.0".to_owned()
+            },
+            unsatisfied[1].1
+        );
+
+        let requirements = ProjectRequirements::parse(
+            r#"
+            [plugins]
+            some-spin-ver-some-not = ">=98, <99"
+            "#,
+        )
+        .unwrap();
+        assert!(requirements.unsatisfied(&store).is_empty());
+    }
+
+    #[test]
+    fn reports_plugins_from_wrong_catalogue() {
+        let plugins_dir = tempfile::tempdir().unwrap();
+        let store = PluginStore::new(plugins_dir.path());
+        let manifest: PluginManifest = serde_json::from_str(include_str!(
+            "../tests/some-spin-ver-some-not/some-spin-ver-some-not@98.0.0.json"
+        ))
+        .unwrap();
+        store.add_manifest(&manifest).unwrap();
+        let record_path = store.installation_record_file("some-spin-ver-some-not");
+        std::fs::create_dir_all(record_path.parent().unwrap()).unwrap();
+        std::fs::write(
+            &record_path,
+            r#"{ "source": "PluginsRepository", "catalogue": "acme" }"#,
+        )
+        .unwrap();
+
+        let requirements = ProjectRequirements::parse(
+            r#"
+            [plugins]
+            some-spin-ver-some-not = { version = "^98", catalogue = "other" }
+            "#,
+        )
+        .unwrap();
+        let unsatisfied = requirements.unsatisfied(&store);
+        assert_eq!(1, unsatisfied.len());
+        assert_eq!(
+            RequirementProblem::WrongCatalogue {
+                installed: Some("acme".to_owned())
+            },
+            unsatisfied[0].1
+        );
+
+        let requirements = ProjectRequirements::parse(
+            r#"
+            [plugins]
+            some-spin-ver-some-not = { version = "^98", catalogue = "acme" }
+            "#,
+        )
+        .unwrap();
+        assert!(requirements.unsatisfied(&store).is_empty());
+
+        // Plugins installed from a manifest file are not from any catalogue
+        std::fs::write(&record_path, r#"{ "source": "Local", "file": "/p.json" }"#).unwrap();
+        assert_eq!(
+            RequirementProblem::WrongCatalogue { installed: None },
+            requirements.unsatisfied(&store)[0].1
+        );
+    }
+}
@@ -9,7 +9,12 @@ use std::{
 use tar::Archive;
 use tracing::log;
 
-use crate::{error::*, manifest::PluginManifest};
+use crate::{
+    catalogue::{Catalogue, Catalogues, DEFAULT_CATALOGUE},
+    error::*,
+    manager::RawInstallRecord,
+    manifest::PluginManifest,
+};
 
 /// Directory where the manifests of installed plugins are stored.
 pub const PLUGIN_MANIFESTS_DIRECTORY_NAME: &str = "manifests";
@@ -69,6 +74,20 @@ impl PluginStore {
             .join(INSTALLATION_RECORD_FILE_NAME)
     }
 
+    /// The catalogue from which an installed plugin was installed, if it
+    /// was installed from a catalogue.
+    pub fn installed_catalogue(&self, plugin_name: &str) -> Option<String> {
+        let record_text = fs::read_to_string(self.installation_record_file(plugin_name)).ok()?;
+        match serde_json::from_str(&record_text).ok()? {
+            // Plugins installed before catalogues were configurable came from
+            // the public catalogue.
+            RawInstallRecord::PluginsRepository { catalogue } => {
+                Some(catalogue.unwrap_or_else(|| DEFAULT_CATALOGUE.to_owned()))
+            }
+            _ => None,
+        }
+    }
+
     pub fn installed_manifests(&self) -> Result<Vec<PluginManifest>> {
         let manifests_dir = self.installed_manifests_directory();
         let manifest_paths = Self::json_files_in(&manifests_dir);
@@ -79,18 +98,51 @@ impl PluginStore {
         Ok(manifests)
     }
 
+    /// The manifests of all plugins in the configured catalogues. If several
+    /// catalogues list a plugin, only the highest-precedence one's versions
+    /// are returned.
     // TODO: report errors on individuals
     pub fn catalogue_manifests(&self) -> Result<Vec<PluginManifest>> {
+        let catalogues = Catalogues::load(self.get_plugins_directory())?;
+        let mut manifests: Vec<PluginManifest> = vec![];
+        for catalogue in catalogues.iter() {
+            let catalogue_manifests = self.all_catalogue_manifests(catalogue)?;
+            let shadowed = |m: &PluginManifest| manifests.iter().any(|o| o.name() == m.name());
+            let new_manifests = catalogue_manifests
+                .into_iter()
+                .filter(|m| !shadowed(m))
+                .collect::<Vec<_>>();
+            manifests.extend(new_manifests);
+        }
+        Ok(manifests)
+    }
+
+    /// The manifests of all versions of a plugin in the given catalogue.
+    pub fn catalogue_manifests_for(
+        &self,
+        catalogue: &Catalogue,
+        plugin_name: &str,
+    ) -> Vec<PluginManifest> {
+        let plugin_dir = catalogue
+            .manifests_dir(self.get_plugins_directory())
+            .join(plugin_name);
+        Self::json_files_in(&plugin_dir)
+            .iter()
+            .filter_map(|path| Self::try_read_manifest_from(path))
+            .filter(|m| m.name() == plugin_name)
+            .collect()
+    }
+
+    fn all_catalogue_manifests(&self, catalogue: &Catalogue) -> Result<Vec<PluginManifest>> {
         // Structure:
-        // CATALOGUE_DIR (spin/plugins/.spin-plugins/manifests)
+        // CATALOGUE_DIR (e.g. spin/plugins/.spin-plugins/manifests)
         // |- foo
         // |  |- foo@0.1.2.json
         // |  |- foo@1.2.3.json
         // |  |- foo.json
         // |- bar
         //    |- bar.json
-        let catalogue_dir =
-            crate::lookup::spin_plugins_repo_manifest_dir(self.get_plugins_directory());
+        let catalogue_dir = catalogue.manifests_dir(self.get_plugins_directory());
 
         // Catalogue directory doesn't exist so likely nothing has been installed.
         if !catalogue_dir.exists() {
@@ -99,7 +151,7 @@ impl PluginStore {
 
         let plugin_dirs = catalogue_dir
             .read_dir()
-            .context("reading manifest catalogue at {catalogue_dir:?}")?
+            .with_context(|| format!("reading manifest catalogue at {catalogue_dir:?}"))?
             .filter_map(|d| d.ok())
             .map(|d| d.path())
             .filter(|p| p.is_dir());
@@ -68,6 +68,32 @@ impl<const DELIM: char> TryFrom<String> for Id<DELIM> {
     }
 }
 
+impl<const DELIM: char> schemars::JsonSchema for Id<DELIM> {
+    fn schema_name() -> String {
+        match DELIM {
+            '-' => "KebabId".into(),
+            '_' => "SnakeId".into(),
+            _ => "Id".into(),
+        }
+    }
+
+    fn json_schema(_gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::{InstanceType, SchemaObject, StringValidation};
+
+        // Mirrors the validation in `TryFrom<String>`
+        let word = "([a-z][a-z0-9]*|[A-Z][A-Z0-9]*)";
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                pattern: Some(format!("^{word}({DELIM}{word})*$")),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
 const fn wrong_delim<const DELIM: char>() -> Option<char> {
     match DELIM {
         '_' => Some('-'),
@@ -1,3 +1,4 @@
+use schemars::schema::{InstanceType, Schema, SchemaObject};
 use serde::{Deserialize, Serialize};
 
 /// FixedVersion represents a version integer field with a const value.
@@ -22,6 +23,16 @@ impl<const V: usize> TryFrom<usize> for FixedVersion<V> {
     }
 }
 
+impl<const V: usize> schemars::JsonSchema for FixedVersion<V> {
+    fn schema_name() -> String {
+        format!("FixedVersion{V}")
+    }
+
+    fn json_schema(_gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        const_schema(InstanceType::Integer, V.into())
+    }
+}
+
 /// FixedStringVersion represents a version string field with a const value.
 #[derive(Clone, Debug, Default, Deserialize)]
 #[serde(into = "String", try_from = "String")]
@@ -43,3 +54,22 @@ impl<const V: usize> TryFrom<String> for FixedStringVersion<V> {
         Ok(Self)
     }
 }
+
+impl<const V: usize> schemars::JsonSchema for FixedStringVersion<V> {
+    fn schema_name() -> String {
+        format!("FixedStringVersion{V}")
+    }
+
+    fn json_schema(_gen: &mut schemars::gen::SchemaGenerator) -> Schema {
+        const_schema(InstanceType::String, V.to_string().into())
+    }
+}
+
+fn const_schema(instance_type: InstanceType, value: serde_json::Value) -> Schema {
+    SchemaObject {
+        instance_type: Some(instance_type.into()),
+        const_value: Some(value),
+        ..Default::default()
+    }
+    .into()
+}
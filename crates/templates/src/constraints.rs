@@ -3,6 +3,7 @@ use regex::Regex;
 #[derive(Clone, Debug)]
 pub(crate) struct StringConstraints {
     pub regex: Option<Regex>,
+    pub allowed_values: Option<Vec<String>>,
 }
 
 impl StringConstraints {
@@ -12,6 +13,23 @@ impl StringConstraints {
                 anyhow::bail!("Input '{}' does not match pattern '{}'", text, regex);
             }
         }
+        if let Some(allowed_values) = self.allowed_values.as_ref() {
+            if !allowed_values.contains(&text) {
+                anyhow::bail!(
+                    "Input '{}' is not one of the allowed values ({})",
+                    text,
+                    allowed_values.join(", ")
+                );
+            }
+        }
         Ok(text)
     }
 }
+
+pub(crate) fn parse_bool(text: &str) -> anyhow::Result<bool> {
+    match text.to_lowercase().as_str() {
+        "true" | "yes" | "y" => Ok(true),
+        "false" | "no" | "n" => Ok(false),
+        _ => anyhow::bail!("Input '{}' is not 'true' or 'false'", text),
+    }
+}
@@ -2,13 +2,13 @@ use std::{collections::HashMap, path::Path};
 
 use crate::{
     cancellable::Cancellable,
-    template::{TemplateParameter, TemplateParameterDataType},
+    template::{PostRenderHook, TemplateParameter, TemplateParameterDataType},
     Run,
 };
 
 use anyhow::anyhow;
 // use console::style;
-use dialoguer::{Confirm, Input};
+use dialoguer::{Confirm, Input, Select};
 
 pub(crate) trait InteractionStrategy {
     fn allow_generate_into(&self, target_dir: &Path) -> Cancellable<(), anyhow::Error>;
@@ -18,10 +18,23 @@ pub(crate) trait InteractionStrategy {
     ) -> Cancellable<HashMap<String, String>, anyhow::Error> {
         let mut values = HashMap::new();
         for parameter in run.template.parameters(&run.options.variant) {
-            match self.populate_parameter(run, parameter) {
-                Cancellable::Ok(value) => {
-                    values.insert(parameter.id().to_owned(), value);
+            if run.template.is_parameter_skipped(parameter, &values) {
+                if let Some(default_value) = parameter.default_value() {
+                    values.insert(parameter.id().to_owned(), default_value.clone());
                 }
+                continue;
+            }
+            match self.populate_parameter(run, parameter) {
+                Cancellable::Ok(value) => match parameter.validate_value(value) {
+                    Ok(value) => {
+                        values.insert(parameter.id().to_owned(), value);
+                    }
+                    Err(e) => {
+                        return Cancellable::Err(
+                            e.context(format!("Invalid value for parameter '{}'", parameter.id())),
+                        )
+                    }
+                },
                 Cancellable::Cancelled => return Cancellable::Cancelled,
                 Cancellable::Err(e) => return Cancellable::Err(e),
             }
@@ -33,6 +46,7 @@ pub(crate) trait InteractionStrategy {
         run: &Run,
         parameter: &TemplateParameter,
     ) -> Cancellable<String, anyhow::Error>;
+    fn allow_post_render_hook(&self, hook: &PostRenderHook) -> bool;
 }
 
 pub(crate) struct Interactive;
@@ -71,6 +85,14 @@ impl InteractionStrategy for Interactive {
             },
         }
     }
+
+    fn allow_post_render_hook(&self, hook: &PostRenderHook) -> bool {
+        let prompt = match hook.description() {
+            Some(description) => format!("{description} (runs `{}`)?", hook.command()),
+            None => format!("Run `{}`?", hook.command()),
+        };
+        crate::interaction::confirm(&prompt).unwrap_or(false)
+    }
 }
 
 impl InteractionStrategy for Silent {
@@ -99,6 +121,11 @@ impl InteractionStrategy for Silent {
             },
         }
     }
+
+    fn allow_post_render_hook(&self, _hook: &PostRenderHook) -> bool {
+        // Hooks run arbitrary commands, so require the user's explicit say-so.
+        false
+    }
 }
 
 pub(crate) fn confirm(text: &str) -> std::io::Result<bool> {
@@ -111,7 +138,11 @@ pub(crate) fn prompt_parameter(parameter: &TemplateParameter) -> Option<String>
 
     loop {
         let input = match parameter.data_type() {
-            TemplateParameterDataType::String(_) => ask_free_text(prompt, default_value),
+            TemplateParameterDataType::String(constraints) => match &constraints.allowed_values {
+                Some(allowed_values) => ask_choice(prompt, allowed_values, default_value),
+                None => ask_free_text(prompt, default_value),
+            },
+            TemplateParameterDataType::Bool => ask_yes_no(prompt, default_value),
         };
 
         match input {
@@ -138,6 +169,33 @@ fn ask_free_text(prompt: &str, default_value: &Option<String>) -> anyhow::Result
     Ok(result)
 }
 
+fn ask_choice(
+    prompt: &str,
+    allowed_values: &[String],
+    default_value: &Option<String>,
+) -> anyhow::Result<String> {
+    let default_index = default_value
+        .as_ref()
+        .and_then(|d| allowed_values.iter().position(|v| v == d))
+        .unwrap_or_default();
+    let index = Select::new()
+        .with_prompt(prompt)
+        .items(allowed_values)
+        .default(default_index)
+        .interact()?;
+    Ok(allowed_values[index].clone())
+}
+
+fn ask_yes_no(prompt: &str, default_value: &Option<String>) -> anyhow::Result<String> {
+    let mut confirm = Confirm::new();
+    confirm.with_prompt(prompt);
+    if let Some(s) = default_value {
+        confirm.default(crate::constraints::parse_bool(s)?);
+    }
+    let result = confirm.interact()?;
+    Ok(result.to_string())
+}
+
 fn is_directory_empty(path: &Path) -> bool {
     if !path.exists() {
         return true;
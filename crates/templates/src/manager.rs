@@ -3,6 +3,7 @@ use std::path::Path;
 use anyhow::Context;
 
 use crate::{
+    reader::RawInstalledFrom,
     source::TemplateSource,
     store::{TemplateLayout, TemplateStore},
     template::Template,
@@ -134,13 +135,14 @@ impl TemplateManager {
             .template_directories()
             .await
             .context("Could not find templates in source")?;
+        let install_record = source.to_install_record(&local_source);
 
         let mut installed = vec![];
         let mut skipped = vec![];
 
         for template_dir in template_dirs {
             let install_result = self
-                .install_one(&template_dir, options, source, reporter)
+                .install_one(&template_dir, options, install_record.as_ref(), reporter)
                 .await
                 .with_context(|| {
                     format!("Failed to install template from {}", template_dir.display())
@@ -161,7 +163,7 @@ impl TemplateManager {
         &self,
         source_dir: &Path,
         options: &InstallOptions,
-        source: &TemplateSource,
+        install_record: Option<&RawInstalledFrom>,
         reporter: &impl ProgressReporter,
     ) -> anyhow::Result<InstallationResult> {
         let layout = TemplateLayout::new(source_dir);
@@ -195,11 +197,11 @@ impl TemplateManager {
                     ))
                 }
                 ExistsBehaviour::Update => {
-                    copy_template_over_existing(id, source_dir, &dest_dir, source).await?
+                    copy_template_over_existing(id, source_dir, &dest_dir, install_record).await?
                 }
             }
         } else {
-            copy_template_into(id, source_dir, &dest_dir, source).await?
+            copy_template_into(id, source_dir, &dest_dir, install_record).await?
         };
 
         Ok(InstallationResult::Installed(template))
@@ -273,7 +275,7 @@ async fn copy_template_over_existing(
     id: &str,
     source_dir: &Path,
     dest_dir: &Path,
-    source: &TemplateSource,
+    install_record: Option<&RawInstalledFrom>,
 ) -> anyhow::Result<Template> {
     // The nearby directory to which we initially copy the source
     let stage_dir = dest_dir.with_extension(".stage");
@@ -307,7 +309,7 @@ async fn copy_template_over_existing(
 
     // Copy template source into stage directory, and do best effort
     // cleanup if it goes wrong.
-    let copy_to_stage_err = copy_template_into(id, source_dir, &stage_dir, source)
+    let copy_to_stage_err = copy_template_into(id, source_dir, &stage_dir, install_record)
         .await
         .err();
     if let Some(e) = copy_to_stage_err {
@@ -359,7 +361,7 @@ async fn copy_template_into(
     id: &str,
     source_dir: &Path,
     dest_dir: &Path,
-    source: &TemplateSource,
+    install_record: Option<&RawInstalledFrom>,
 ) -> anyhow::Result<Template> {
     tokio::fs::create_dir_all(&dest_dir)
         .await
@@ -380,17 +382,16 @@ async fn copy_template_into(
         )
     })?;
 
-    write_install_record(dest_dir, source);
+    write_install_record(dest_dir, install_record);
 
     load_template_from(id, dest_dir)
 }
 
-fn write_install_record(dest_dir: &Path, source: &TemplateSource) {
+fn write_install_record(dest_dir: &Path, install_record: Option<&RawInstalledFrom>) {
     let layout = TemplateLayout::new(dest_dir);
     let install_record_path = layout.installation_record_file();
 
     // A failure here shouldn't fail the install
-    let install_record = source.to_install_record();
     if let Ok(record_text) = toml::to_string_pretty(&install_record) {
         _ = std::fs::write(install_record_path, record_text);
     }
@@ -1291,6 +1292,77 @@ mod tests {
         assert!(!http_empty.supports_variant(&add_component));
     }
 
+    async fn run_conditional_template(
+        values: &[(&str, &str)],
+    ) -> (tempfile::TempDir, PathBuf, anyhow::Result<()>) {
+        let temp_dir = tempdir().unwrap();
+        let store = TemplateStore::new(temp_dir.path());
+        let manager = TemplateManager { store };
+        let source = TemplateSource::File(test_data_root());
+
+        manager
+            .install(&source, &InstallOptions::default(), &DiscardingReporter)
+            .await
+            .unwrap();
+
+        let template = manager.get("conditional-content").unwrap().unwrap();
+
+        let dest_temp_dir = tempdir().unwrap();
+        let output_dir = dest_temp_dir.path().join("myproj");
+        let values = values
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let options = RunOptions {
+            variant: crate::template::TemplateVariantInfo::NewApplication,
+            output_path: output_dir.clone(),
+            name: "my project".to_owned(),
+            values,
+            accept_defaults: true,
+        };
+
+        let result = template.run(options).silent().await;
+        (dest_temp_dir, output_dir, result)
+    }
+
+    #[tokio::test]
+    async fn can_include_conditional_content() {
+        let (_dest, output_dir, result) =
+            run_conditional_template(&[("store-name", "snacks")]).await;
+        result.unwrap();
+
+        let readme = tokio::fs::read_to_string(output_dir.join("README.md"))
+            .await
+            .unwrap();
+        assert_contains(&readme, "Uses the snacks store.");
+        assert_contains(&readme, "Written in go.");
+        assert!(output_dir.join("kv").join("store.txt").exists());
+        assert!(output_dir.join("main.go").exists());
+    }
+
+    #[tokio::test]
+    async fn can_skip_conditional_content() {
+        let (_dest, output_dir, result) =
+            run_conditional_template(&[("use-kv", "no"), ("language", "rust")]).await;
+        result.unwrap();
+
+        let readme = tokio::fs::read_to_string(output_dir.join("README.md"))
+            .await
+            .unwrap();
+        assert_contains(&readme, "Does not use storage.");
+        assert!(!readme.contains("store."));
+        assert!(!output_dir.join("kv").exists());
+        assert!(!output_dir.join("main.go").exists());
+    }
+
+    #[tokio::test]
+    async fn cannot_use_value_outside_allowed_values() {
+        let (_dest, _, result) = run_conditional_template(&[("language", "cobol")]).await;
+        let err = result.expect_err("Expected template to fail but it passed");
+
+        assert_contains(&err.to_string(), "not one of the allowed values");
+    }
+
     #[tokio::test]
     async fn fails_on_unknown_filter() {
         let temp_dir = tempdir().unwrap();
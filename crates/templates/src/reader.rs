@@ -22,6 +22,8 @@ pub(crate) struct RawTemplateManifestV1 {
     pub new_application: Option<RawTemplateVariant>,
     pub add_component: Option<RawTemplateVariant>,
     pub parameters: Option<IndexMap<String, RawParameter>>,
+    pub conditionals: Option<IndexMap<String, RawConditional>>,
+    pub post_render: Option<Vec<RawPostRenderHook>>,
     pub custom_filters: Option<serde::de::IgnoredAny>, // kept for error messaging
 }
 
@@ -41,8 +43,49 @@ pub(crate) struct RawParameter {
     pub data_type: String,
     pub prompt: String,
     #[serde(rename = "default")]
-    pub default_value: Option<String>,
+    pub default_value: Option<RawScalar>,
     pub pattern: Option<String>,
+    pub allowed_values: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub(crate) struct RawConditional {
+    pub condition: RawCondition,
+    pub skip_files: Option<Vec<String>>,
+    pub skip_parameters: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub(crate) struct RawCondition {
+    pub parameter: String,
+    pub equals: RawScalar,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "snake_case")]
+pub(crate) struct RawPostRenderHook {
+    pub command: String,
+    pub description: Option<String>,
+}
+
+/// A value which may be written as a string or (for boolean parameters)
+/// as a TOML boolean.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum RawScalar {
+    Bool(bool),
+    String(String),
+}
+
+impl RawScalar {
+    pub fn to_value_string(&self) -> String {
+        match self {
+            Self::Bool(b) => b.to_string(),
+            Self::String(s) => s.clone(),
+        }
+    }
 }
 
 pub(crate) fn parse_manifest_toml(text: impl AsRef<str>) -> anyhow::Result<RawTemplateManifest> {
@@ -54,6 +97,8 @@ pub(crate) fn parse_manifest_toml(text: impl AsRef<str>) -> anyhow::Result<RawTe
 pub(crate) enum RawInstalledFrom {
     Git { git: String },
     File { dir: String },
+    Oci { oci: String },
+    Tar { tar: String, digest: Option<String> },
 }
 
 pub(crate) fn parse_installed_from(text: impl AsRef<str>) -> Option<RawInstalledFrom> {
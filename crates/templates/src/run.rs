@@ -6,13 +6,14 @@ use std::{
 use anyhow::{anyhow, Context};
 use itertools::Itertools;
 use path_absolutize::Absolutize;
+use tokio::process::Command;
 use walkdir::WalkDir;
 
 use crate::{
     cancellable::Cancellable,
     interaction::{InteractionStrategy, Interactive, Silent},
     renderer::MergeTarget,
-    template::TemplateVariantInfo,
+    template::{PostRenderHook, TemplateVariantInfo},
 };
 use crate::{
     renderer::{RenderOperation, TemplateContent, TemplateRenderer},
@@ -61,17 +62,19 @@ impl Run {
     }
 
     async fn run(&self, interaction: impl InteractionStrategy) -> anyhow::Result<()> {
-        self.build_renderer(interaction)
+        self.build_renderer(&interaction)
             .await
             .and_then(|t| t.render())
             .and_then_async(|o| async move { o.write().await })
             .await
+            .and_then_async(|_| self.run_post_render_hooks(&interaction))
+            .await
             .err()
     }
 
     async fn build_renderer(
         &self,
-        interaction: impl InteractionStrategy,
+        interaction: &impl InteractionStrategy,
     ) -> Cancellable<TemplateRenderer, anyhow::Error> {
         self.build_renderer_raw(interaction).await.into()
     }
@@ -83,7 +86,7 @@ impl Run {
     // a better way but I don't see one yet...
     async fn build_renderer_raw(
         &self,
-        interaction: impl InteractionStrategy,
+        interaction: &impl InteractionStrategy,
     ) -> anyhow::Result<Option<TemplateRenderer>> {
         self.validate_version()?;
         self.validate_trigger()?;
@@ -99,13 +102,21 @@ impl Run {
 
         self.validate_provided_values()?;
 
+        // Parameter values determine which conditional content is included,
+        // so they must be known before the content files are read.
+        let parameter_values = match interaction.populate_parameters(self) {
+            Cancellable::Ok(parameter_values) => parameter_values,
+            Cancellable::Cancelled => return Ok(None),
+            Cancellable::Err(e) => return Err(e),
+        };
+
         let files = match self.template.content_dir() {
             None => vec![],
             Some(path) => {
                 let from = path
                     .absolutize()
                     .context("Failed to get absolute path of template directory")?;
-                self.included_files(&from, &to)?
+                self.included_files(&from, &to, &parameter_values)?
             }
         };
 
@@ -118,30 +129,39 @@ impl Run {
 
         let render_operations = files.into_iter().chain(snippets).collect();
 
-        match interaction.populate_parameters(self) {
-            Cancellable::Ok(parameter_values) => {
-                let values = self
-                    .special_values()
-                    .await
-                    .into_iter()
-                    .chain(parameter_values)
-                    .collect();
-                let prepared_template = TemplateRenderer {
-                    render_operations,
-                    parameter_values: values,
-                };
-                Ok(Some(prepared_template))
-            }
-            Cancellable::Cancelled => Ok(None),
-            Cancellable::Err(e) => Err(e),
-        }
+        let special_values = self
+            .special_values()
+            .await
+            .into_iter()
+            .map(|(k, v)| (k, liquid_core::Value::scalar(v)));
+        let typed_parameter_values = parameter_values.into_iter().map(|(k, v)| {
+            let value = match self.template.parameter(&k) {
+                Some(p) => p.to_liquid_value(v),
+                None => liquid_core::Value::scalar(v),
+            };
+            (k, value)
+        });
+        let values = special_values.chain(typed_parameter_values).collect();
+        let prepared_template = TemplateRenderer {
+            render_operations,
+            parameter_values: values,
+        };
+        Ok(Some(prepared_template))
     }
 
-    fn included_files(&self, from: &Path, to: &Path) -> anyhow::Result<Vec<RenderOperation>> {
+    fn included_files(
+        &self,
+        from: &Path,
+        to: &Path,
+        parameter_values: &HashMap<String, String>,
+    ) -> anyhow::Result<Vec<RenderOperation>> {
         let all_content_files = Self::list_content_files(from)?;
-        let included_files =
-            self.template
-                .included_files(from, all_content_files, &self.options.variant);
+        let included_files = self.template.included_files(
+            from,
+            all_content_files,
+            &self.options.variant,
+            parameter_values,
+        );
         let template_contents = self.read_all(included_files)?;
         let outputs = Self::to_output_paths(from, to, template_contents);
         let file_ops = outputs
@@ -151,6 +171,36 @@ impl Run {
         Ok(file_ops)
     }
 
+    async fn run_post_render_hooks(
+        &self,
+        interaction: &impl InteractionStrategy,
+    ) -> anyhow::Result<()> {
+        let working_dir = self.generation_target_dir();
+        for hook in self.template.post_render_hooks() {
+            if !interaction.allow_post_render_hook(hook) {
+                continue;
+            }
+            // The project has already been generated, so a failing hook
+            // should not make the whole operation look as if it failed.
+            if let Err(e) = Self::run_post_render_hook(hook, &working_dir).await {
+                tracing::warn!("{e:#}");
+            }
+        }
+        Ok(())
+    }
+
+    async fn run_post_render_hook(hook: &PostRenderHook, working_dir: &Path) -> anyhow::Result<()> {
+        let status = shell_command(hook.command())
+            .current_dir(working_dir)
+            .status()
+            .await
+            .with_context(|| format!("Failed to run `{}`", hook.command()))?;
+        if !status.success() {
+            anyhow::bail!("`{}` exited with status {status}", hook.command());
+        }
+        Ok(())
+    }
+
     async fn special_values(&self) -> HashMap<String, String> {
         let mut values = HashMap::new();
 
@@ -347,6 +397,20 @@ impl Run {
     }
 }
 
+#[cfg(not(windows))]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd.exe");
+    cmd.arg("/C").raw_arg(command);
+    cmd
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -25,6 +25,17 @@ pub enum TemplateSource {
     /// Templates much be in a `/templates` directory under the specified
     /// root.
     File(PathBuf),
+    /// Install from an artifact in an OCI registry. The artifact must have a
+    /// single gzipped tar layer.
+    ///
+    /// Templates much be in a `/templates` directory under the root of the
+    /// archive.
+    Oci(String),
+    /// Install from a gzipped tar archive at the specified URL.
+    ///
+    /// Templates much be in a `/templates` directory under the root of the
+    /// archive, or under a single top-level directory in the archive.
+    Tar(TarTemplateSource),
 }
 
 /// Settings for installing templates from a Git repository.
@@ -39,6 +50,15 @@ pub struct GitTemplateSource {
     spin_version: String,
 }
 
+/// Settings for installing templates from a tar archive.
+#[derive(Debug)]
+pub struct TarTemplateSource {
+    /// The URL of the archive.
+    url: Url,
+    /// The expected SHA256 digest of the archive, if it should be verified.
+    digest: Option<String>,
+}
+
 impl TemplateSource {
     /// Creates a `TemplateSource` referring to the specified Git repository
     /// and branch.
@@ -57,7 +77,22 @@ impl TemplateSource {
         }))
     }
 
-    pub(crate) fn to_install_record(&self) -> Option<crate::reader::RawInstalledFrom> {
+    /// Creates a `TemplateSource` referring to the specified tar archive URL.
+    /// If a digest is given, the downloaded archive must match it.
+    pub fn try_from_tar(tar_url: impl AsRef<str>, digest: &Option<String>) -> anyhow::Result<Self> {
+        let url_str = tar_url.as_ref();
+        let url =
+            Url::parse(url_str).with_context(|| format!("Failed to parse {} as URL", url_str))?;
+        let digest = digest
+            .as_ref()
+            .map(|d| d.strip_prefix("sha256:").unwrap_or(d).to_ascii_lowercase());
+        Ok(Self::Tar(TarTemplateSource { url, digest }))
+    }
+
+    pub(crate) fn to_install_record(
+        &self,
+        local: &LocalTemplateSource,
+    ) -> Option<crate::reader::RawInstalledFrom> {
         match self {
             Self::Git(g) => Some(crate::reader::RawInstalledFrom::Git {
                 git: g.url.to_string(),
@@ -72,6 +107,14 @@ impl TemplateSource {
                     None
                 }
             }
+            Self::Oci(reference) => Some(crate::reader::RawInstalledFrom::Oci {
+                oci: reference.clone(),
+            }),
+            // The digest records which archive the templates were installed from
+            Self::Tar(t) => Some(crate::reader::RawInstalledFrom::Tar {
+                tar: t.url.to_string(),
+                digest: local.archive_digest.clone(),
+            }),
         }
     }
 
@@ -88,6 +131,9 @@ impl TemplateSource {
 
 pub(crate) struct LocalTemplateSource {
     root: PathBuf,
+    /// The SHA256 digest of the archive from which the source was unpacked,
+    /// if any.
+    archive_digest: Option<String>,
     _temp_dir: Option<TempDir>,
 }
 
@@ -96,6 +142,8 @@ impl TemplateSource {
         match self {
             Self::Git(git_source) => clone_local(git_source).await,
             Self::File(path) => check_local(path).await,
+            Self::Oci(reference) => pull_local(reference).await,
+            Self::Tar(tar_source) => download_local(tar_source).await,
         }
     }
 
@@ -103,6 +151,7 @@ impl TemplateSource {
         match self {
             Self::Git { .. } => true,
             Self::File(_) => false,
+            Self::Oci(_) | Self::Tar(_) => true,
         }
     }
 }
@@ -152,12 +201,78 @@ async fn clone_local(git_source: &GitTemplateSource) -> anyhow::Result<LocalTemp
     match clone_result {
         Ok(_) => Ok(LocalTemplateSource {
             root: path,
+            archive_digest: None,
             _temp_dir: Some(temp_dir),
         }),
         Err(e) => Err(anyhow!("Error cloning Git repo {}: {}", url_str, e)),
     }
 }
 
+async fn pull_local(reference: &str) -> anyhow::Result<LocalTemplateSource> {
+    let temp_dir = tempdir()?;
+    let mut client = spin_oci::Client::new(false, None).await?;
+    client
+        .pull_archive(reference, temp_dir.path())
+        .await
+        .with_context(|| format!("Error pulling templates from {}", reference))?;
+    Ok(LocalTemplateSource {
+        root: archive_root(temp_dir.path())?,
+        archive_digest: None,
+        _temp_dir: Some(temp_dir),
+    })
+}
+
+async fn download_local(tar_source: &TarTemplateSource) -> anyhow::Result<LocalTemplateSource> {
+    let url_str = tar_source.url.as_str();
+    let response = reqwest::get(tar_source.url.clone())
+        .await
+        .and_then(|r| r.error_for_status())
+        .with_context(|| format!("Error downloading {}", url_str))?;
+    let bytes = response
+        .bytes()
+        .await
+        .with_context(|| format!("Error downloading {}", url_str))?;
+
+    let actual = spin_common::sha256::hex_digest_from_bytes(&bytes);
+    if let Some(expected) = &tar_source.digest {
+        if &actual != expected {
+            return Err(anyhow!(
+                "Archive {} does not match the expected digest: expected sha256:{}, downloaded sha256:{}",
+                url_str,
+                expected,
+                actual
+            ));
+        }
+    }
+
+    let temp_dir = tempdir()?;
+    let archive_path = temp_dir.path().join("templates.tar.gz");
+    let unpack_dir = temp_dir.path().join("unpacked");
+    tokio::fs::write(&archive_path, &bytes).await?;
+    tokio::fs::create_dir_all(&unpack_dir).await?;
+    spin_oci::utils::unarchive(&archive_path, &unpack_dir)
+        .await
+        .with_context(|| format!("Error unpacking {}", url_str))?;
+    Ok(LocalTemplateSource {
+        root: archive_root(&unpack_dir)?,
+        archive_digest: Some(actual),
+        _temp_dir: Some(temp_dir),
+    })
+}
+
+/// Archives such as GitHub release tarballs often wrap their content in a
+/// single top-level directory. If the unpacked archive has no templates
+/// directory but does have such a wrapper, returns the wrapper directory.
+fn archive_root(unpacked: &Path) -> anyhow::Result<PathBuf> {
+    if unpacked.join(TEMPLATE_SOURCE_DIR).exists() {
+        return Ok(unpacked.to_owned());
+    }
+    match subdirectories(unpacked)?.as_slice() {
+        [wrapper] if wrapper.join(TEMPLATE_SOURCE_DIR).exists() => Ok(wrapper.clone()),
+        _ => Ok(unpacked.to_owned()),
+    }
+}
+
 async fn version_matched_tag(url: &str, spin_version: &str) -> Option<String> {
     let preferred_tag = version_preferred_tag(spin_version);
 
@@ -185,6 +300,7 @@ async fn check_local(path: &Path) -> anyhow::Result<LocalTemplateSource> {
     if path.exists() {
         Ok(LocalTemplateSource {
             root: path.to_owned(),
+            archive_digest: None,
             _temp_dir: None,
         })
     } else {
@@ -195,6 +311,7 @@ async fn check_local(path: &Path) -> anyhow::Result<LocalTemplateSource> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use spin_test_http_server::TestServer;
 
     #[test]
     fn preferred_tag_excludes_patch_version() {
@@ -223,4 +340,81 @@ mod test {
         assert_eq!("spin/templates/v1.2.3.4", version_preferred_tag("1.2.3.4"));
         assert_eq!("spin/templates/vgarbage", version_preferred_tag("garbage"));
     }
+
+    #[test]
+    fn tar_digest_prefix_is_optional() {
+        for digest in ["sha256:ABCD1234", "abcd1234"] {
+            let source =
+                TemplateSource::try_from_tar("https://example.com/t.tar.gz", &Some(digest.into()))
+                    .unwrap();
+            let TemplateSource::Tar(tar) = source else {
+                panic!("expected tar source");
+            };
+            assert_eq!(Some("abcd1234"), tar.digest.as_deref());
+        }
+    }
+
+    // Serves the body in response to every request, returning the server and
+    // the URL of the archive on it.
+    async fn serve(body: Vec<u8>) -> (TestServer, Url) {
+        let server = TestServer::serve("application/gzip", body).await;
+        let url = Url::parse(&server.url("/templates.tar.gz")).unwrap();
+        (server, url)
+    }
+
+    async fn templates_archive() -> (Vec<u8>, TempDir) {
+        let temp_dir = tempdir().unwrap();
+        let source = temp_dir.path().join("source");
+        std::fs::create_dir_all(source.join(TEMPLATE_SOURCE_DIR).join("my-template")).unwrap();
+        let archive = spin_oci::utils::archive(&source, temp_dir.path())
+            .await
+            .unwrap();
+        (std::fs::read(archive).unwrap(), temp_dir)
+    }
+
+    #[tokio::test]
+    async fn download_local_records_archive_digest() {
+        let (archive, _temp_dir) = templates_archive().await;
+        let digest = spin_common::sha256::hex_digest_from_bytes(&archive);
+        let (_server, url) = serve(archive).await;
+
+        for expected in [None, Some(format!("sha256:{digest}"))] {
+            let source = TemplateSource::try_from_tar(url.as_str(), &expected).unwrap();
+            let local = source.get_local().await.unwrap();
+            assert_eq!(1, local.template_directories().await.unwrap().len());
+            assert_eq!(Some(&digest), local.archive_digest.as_ref());
+
+            let Some(crate::reader::RawInstalledFrom::Tar {
+                digest: recorded, ..
+            }) = source.to_install_record(&local)
+            else {
+                panic!("expected tar install record");
+            };
+            assert_eq!(Some(&digest), recorded.as_ref());
+        }
+    }
+
+    #[tokio::test]
+    async fn download_local_rejects_mismatched_digest() {
+        let (archive, _temp_dir) = templates_archive().await;
+        let (_server, url) = serve(archive).await;
+
+        let source =
+            TemplateSource::try_from_tar(url.as_str(), &Some("sha256:abcd".into())).unwrap();
+        let err = source.get_local().await.err().unwrap();
+        assert!(
+            err.to_string()
+                .contains("does not match the expected digest"),
+            "{err:#}"
+        );
+    }
+
+    #[test]
+    fn archive_root_unwraps_single_top_level_directory() {
+        let temp_dir = tempdir().unwrap();
+        let wrapper = temp_dir.path().join("spin-1.0.0");
+        std::fs::create_dir_all(wrapper.join(TEMPLATE_SOURCE_DIR)).unwrap();
+        assert_eq!(wrapper, archive_root(temp_dir.path()).unwrap());
+        assert_eq!(wrapper, archive_root(&wrapper).unwrap());
+    }
 }
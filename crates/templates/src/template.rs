@@ -8,8 +8,11 @@ use indexmap::IndexMap;
 use regex::Regex;
 
 use crate::{
-    constraints::StringConstraints,
-    reader::{RawParameter, RawTemplateManifest, RawTemplateManifestV1, RawTemplateVariant},
+    constraints::{parse_bool, StringConstraints},
+    reader::{
+        RawConditional, RawParameter, RawPostRenderHook, RawTemplateManifest,
+        RawTemplateManifestV1, RawTemplateVariant,
+    },
     run::{Run, RunOptions},
     store::TemplateLayout,
 };
@@ -24,6 +27,8 @@ pub struct Template {
     trigger: TemplateTriggerCompatibility,
     variants: HashMap<TemplateVariantKind, TemplateVariant>,
     parameters: Vec<TemplateParameter>,
+    conditionals: Vec<TemplateConditional>,
+    post_render_hooks: Vec<PostRenderHook>,
     snippets_dir: Option<PathBuf>,
     content_dir: Option<PathBuf>, // TODO: maybe always need a spin.toml file in there?
 }
@@ -32,6 +37,8 @@ pub struct Template {
 enum InstalledFrom {
     Git(String),
     Directory(String),
+    Oci(String),
+    Tar { url: String, digest: Option<String> },
     Unknown,
 }
 
@@ -103,6 +110,7 @@ pub(crate) enum TemplateTriggerCompatibility {
 #[derive(Clone, Debug)]
 pub(crate) enum TemplateParameterDataType {
     String(StringConstraints),
+    Bool,
 }
 
 #[derive(Debug)]
@@ -113,6 +121,23 @@ pub(crate) struct TemplateParameter {
     default_value: Option<String>,
 }
 
+/// Files and parameters which are skipped if a parameter has a given value.
+#[derive(Clone, Debug)]
+pub(crate) struct TemplateConditional {
+    parameter: String,
+    value: String,
+    skip_files: Vec<String>,
+    skip_parameters: Vec<String>,
+}
+
+/// A command to be run in the generated directory once the template has
+/// been rendered, if the user agrees.
+#[derive(Clone, Debug)]
+pub(crate) struct PostRenderHook {
+    command: String,
+    description: Option<String>,
+}
+
 impl Template {
     pub(crate) fn load_from(layout: &TemplateLayout) -> anyhow::Result<Self> {
         let manifest_path = layout.manifest_path();
@@ -147,17 +172,23 @@ impl Template {
         let installed_from = read_install_record(layout);
 
         let template = match raw {
-            RawTemplateManifest::V1(raw) => Self {
-                id: raw.id.clone(),
-                tags: raw.tags.map(Self::normalize_tags).unwrap_or_default(),
-                description: raw.description.clone(),
-                installed_from,
-                trigger: Self::parse_trigger_type(raw.trigger_type, layout),
-                variants: Self::parse_template_variants(raw.new_application, raw.add_component),
-                parameters: Self::parse_parameters(&raw.parameters)?,
-                snippets_dir,
-                content_dir,
-            },
+            RawTemplateManifest::V1(raw) => {
+                let parameters = Self::parse_parameters(&raw.parameters)?;
+                let conditionals = Self::parse_conditionals(raw.conditionals, &parameters)?;
+                Self {
+                    id: raw.id.clone(),
+                    tags: raw.tags.map(Self::normalize_tags).unwrap_or_default(),
+                    description: raw.description.clone(),
+                    installed_from,
+                    trigger: Self::parse_trigger_type(raw.trigger_type, layout),
+                    variants: Self::parse_template_variants(raw.new_application, raw.add_component),
+                    parameters,
+                    conditionals,
+                    post_render_hooks: Self::parse_post_render_hooks(raw.post_render),
+                    snippets_dir,
+                    content_dir,
+                }
+            }
         };
         Ok(template)
     }
@@ -208,12 +239,42 @@ impl Template {
         }
     }
 
+    /// The OCI registry reference from which the template was installed, if
+    /// it was installed from a registry; otherwise None.
+    pub fn source_oci_reference(&self) -> Option<&str> {
+        match &self.installed_from {
+            InstalledFrom::Oci(reference) => Some(reference),
+            _ => None,
+        }
+    }
+
+    /// The URL of the tar archive from which the template was installed, if
+    /// it was installed from an archive; otherwise None.
+    pub fn source_tar_url(&self) -> Option<&str> {
+        match &self.installed_from {
+            InstalledFrom::Tar { url, .. } => Some(url),
+            _ => None,
+        }
+    }
+
+    /// The SHA256 digest of the tar archive from which the template was
+    /// installed, if it was installed from an archive and the digest was
+    /// recorded; otherwise None.
+    pub fn source_tar_digest(&self) -> Option<&str> {
+        match &self.installed_from {
+            InstalledFrom::Tar { digest, .. } => digest.as_deref(),
+            _ => None,
+        }
+    }
+
     /// A human-readable description of where the template was installed
     /// from.
     pub fn installed_from_or_empty(&self) -> &str {
         match &self.installed_from {
             InstalledFrom::Git(repo) => repo,
             InstalledFrom::Directory(path) => path,
+            InstalledFrom::Oci(reference) => reference,
+            InstalledFrom::Tar { url, .. } => url,
             InstalledFrom::Unknown => "",
         }
     }
@@ -237,6 +298,21 @@ impl Template {
         self.parameters.iter().find(|p| p.id == name.as_ref())
     }
 
+    /// Whether the parameter is skipped because of the values of parameters
+    /// which have already been set.
+    pub(crate) fn is_parameter_skipped(
+        &self,
+        parameter: &TemplateParameter,
+        values: &HashMap<String, String>,
+    ) -> bool {
+        self.met_conditionals(values)
+            .any(|c| c.skip_parameters.contains(&parameter.id))
+    }
+
+    pub(crate) fn post_render_hooks(&self) -> &[PostRenderHook] {
+        &self.post_render_hooks
+    }
+
     pub(crate) fn content_dir(&self) -> &Option<PathBuf> {
         &self.content_dir
     }
@@ -331,6 +407,54 @@ impl Template {
         }
     }
 
+    fn parse_conditionals(
+        raw: Option<IndexMap<String, RawConditional>>,
+        parameters: &[TemplateParameter],
+    ) -> anyhow::Result<Vec<TemplateConditional>> {
+        raw.unwrap_or_default()
+            .into_iter()
+            .map(|(name, raw)| {
+                let parameter = parameters
+                    .iter()
+                    .find(|p| p.id == raw.condition.parameter)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Conditional '{name}' refers to non-existent parameter '{}'",
+                            raw.condition.parameter
+                        )
+                    })?;
+                let value = parameter
+                    .validate_value(raw.condition.equals.to_value_string())
+                    .with_context(|| format!("Conditional '{name}' has an invalid value"))?;
+                Ok(TemplateConditional {
+                    parameter: parameter.id.clone(),
+                    value,
+                    skip_files: raw.skip_files.unwrap_or_default(),
+                    skip_parameters: raw.skip_parameters.unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+
+    fn parse_post_render_hooks(raw: Option<Vec<RawPostRenderHook>>) -> Vec<PostRenderHook> {
+        raw.unwrap_or_default()
+            .into_iter()
+            .map(|raw| PostRenderHook {
+                command: raw.command,
+                description: raw.description,
+            })
+            .collect()
+    }
+
+    fn met_conditionals<'a>(
+        &'a self,
+        values: &'a HashMap<String, String>,
+    ) -> impl Iterator<Item = &'a TemplateConditional> {
+        self.conditionals
+            .iter()
+            .filter(|c| values.get(&c.parameter) == Some(&c.value))
+    }
+
     fn parse_parameters(
         raw: &Option<IndexMap<String, RawParameter>>,
     ) -> anyhow::Result<Vec<TemplateParameter>> {
@@ -348,11 +472,17 @@ impl Template {
         base: &std::path::Path,
         all_files: Vec<PathBuf>,
         variant_kind: &TemplateVariantInfo,
+        values: &HashMap<String, String>,
     ) -> Vec<PathBuf> {
         let variant = self.variant(variant_kind).unwrap(); // TODO: for now
+        let conditional_skips = self
+            .met_conditionals(values)
+            .flat_map(|c| &c.skip_files)
+            .collect::<Vec<_>>();
         all_files
             .into_iter()
             .filter(|path| !variant.skip_file(base, path))
+            .filter(|path| !conditional_skips.iter().any(|s| is_skipped(base, s, path)))
             .collect()
     }
 
@@ -420,7 +550,7 @@ impl TemplateParameter {
             id: id.to_owned(),
             data_type,
             prompt: raw.prompt.clone(),
-            default_value: raw.default_value.clone(),
+            default_value: raw.default_value.as_ref().map(|v| v.to_value_string()),
         })
     }
 
@@ -443,12 +573,31 @@ impl TemplateParameter {
     pub fn validate_value(&self, value: impl AsRef<str>) -> anyhow::Result<String> {
         self.data_type.validate_value(value.as_ref().to_owned())
     }
+
+    /// Converts a validated value to the type used when rendering the template.
+    pub(crate) fn to_liquid_value(&self, value: String) -> liquid_core::Value {
+        match self.data_type {
+            TemplateParameterDataType::Bool => liquid_core::Value::scalar(value == "true"),
+            TemplateParameterDataType::String(_) => liquid_core::Value::scalar(value),
+        }
+    }
+}
+
+impl PostRenderHook {
+    pub(crate) fn command(&self) -> &str {
+        &self.command
+    }
+
+    pub(crate) fn description(&self) -> &Option<String> {
+        &self.description
+    }
 }
 
 impl TemplateParameterDataType {
     fn parse(raw: &RawParameter) -> anyhow::Result<Self> {
         match &raw.data_type[..] {
             "string" => Ok(Self::String(parse_string_constraints(raw)?)),
+            "bool" => Ok(Self::Bool),
             _ => Err(anyhow!("Unrecognised data type '{}'", raw.data_type)),
         }
     }
@@ -456,16 +605,14 @@ impl TemplateParameterDataType {
     fn validate_value(&self, value: String) -> anyhow::Result<String> {
         match self {
             TemplateParameterDataType::String(constraints) => constraints.validate(value),
+            TemplateParameterDataType::Bool => Ok(parse_bool(&value)?.to_string()),
         }
     }
 }
 
 impl TemplateVariant {
     pub(crate) fn skip_file(&self, base: &std::path::Path, path: &std::path::Path) -> bool {
-        self.skip_files
-            .iter()
-            .map(|s| base.join(s))
-            .any(|f| path == f)
+        self.skip_files.iter().any(|s| is_skipped(base, s, path))
     }
 
     pub(crate) fn skip_parameter(&self, parameter: &TemplateParameter) -> bool {
@@ -473,10 +620,24 @@ impl TemplateVariant {
     }
 }
 
+/// Whether the path is skipped by the skip entry (relative to the base). An
+/// entry ending in `/` skips everything in the directory it names; any other
+/// entry skips only the file it names.
+fn is_skipped(base: &std::path::Path, skip: &str, path: &std::path::Path) -> bool {
+    match skip.strip_suffix('/') {
+        Some(dir) => path.starts_with(base.join(dir)),
+        None => path == base.join(skip),
+    }
+}
+
 fn parse_string_constraints(raw: &RawParameter) -> anyhow::Result<StringConstraints> {
     let regex = raw.pattern.as_ref().map(|re| Regex::new(re)).transpose()?;
+    let allowed_values = raw.allowed_values.clone();
 
-    Ok(StringConstraints { regex })
+    Ok(StringConstraints {
+        regex,
+        allowed_values,
+    })
 }
 
 fn read_install_record(layout: &TemplateLayout) -> InstalledFrom {
@@ -486,6 +647,8 @@ fn read_install_record(layout: &TemplateLayout) -> InstalledFrom {
     match installed_from_text.and_then(parse_installed_from) {
         Some(RawInstalledFrom::Git { git }) => InstalledFrom::Git(git),
         Some(RawInstalledFrom::File { dir }) => InstalledFrom::Directory(dir),
+        Some(RawInstalledFrom::Oci { oci }) => InstalledFrom::Oci(oci),
+        Some(RawInstalledFrom::Tar { tar, digest }) => InstalledFrom::Tar { url: tar, digest },
         None => InstalledFrom::Unknown,
     }
 }
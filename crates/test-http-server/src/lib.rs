@@ -0,0 +1,141 @@
+//! A local HTTP server for tests which need to talk to a real server, such as
+//! registry, catalogue and inferencing clients. Many methods will panic in the
+//! slightest breeze, so DO NOT USE IN NON-TEST CODE.
+
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use http::{HeaderMap, Method, Response};
+use http_body_util::{BodyExt, Full};
+use hyper::{
+    body::{Bytes, Incoming},
+    server::conn::http1,
+    service::service_fn,
+};
+use hyper_util::rt::tokio::TokioIo;
+use tokio::net::TcpListener;
+
+pub use http::StatusCode;
+
+/// A request received by a [`TestServer`].
+#[derive(Clone, Debug)]
+pub struct ReceivedRequest {
+    pub method: Method,
+    /// The path and query of the request URI.
+    pub path: String,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl ReceivedRequest {
+    /// Returns the value of the named header, if it is present and valid UTF-8.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)?.to_str().ok()
+    }
+}
+
+type Handler = dyn Fn(&ReceivedRequest) -> Response<Vec<u8>> + Send + Sync;
+
+/// A server listening on a local port, which answers every request with the
+/// response returned by its handler and records the requests it receives.
+pub struct TestServer {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<ReceivedRequest>>>,
+}
+
+impl TestServer {
+    /// Starts a server which answers requests with `handler`. The server runs
+    /// until the test's runtime shuts down.
+    pub async fn start(
+        handler: impl Fn(&ReceivedRequest) -> Response<Vec<u8>> + Send + Sync + 'static,
+    ) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handler: Arc<Handler> = Arc::new(handler);
+        let requests = Arc::new(Mutex::new(vec![]));
+
+        let received = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let handler = handler.clone();
+                let received = received.clone();
+                let service = service_fn(move |request| {
+                    let handler = handler.clone();
+                    let received = received.clone();
+                    async move {
+                        let request = receive(request).await;
+                        let response = handler(&request);
+                        received.lock().unwrap().push(request);
+                        Ok::<_, Infallible>(response.map(|body| Full::new(Bytes::from(body))))
+                    }
+                });
+                tokio::spawn(async move {
+                    _ = http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await;
+                });
+            }
+        });
+
+        Self { addr, requests }
+    }
+
+    /// Starts a server which answers every request with the same body.
+    pub async fn serve(content_type: &str, body: impl Into<Vec<u8>>) -> Self {
+        let response = ok(content_type, body);
+        Self::start(move |_| clone_response(&response)).await
+    }
+
+    /// The address the server is listening on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The URL of the given path on the server.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{path}", self.addr)
+    }
+
+    /// The requests received so far, in the order they were answered.
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// A 200 OK response with the given content type and body.
+pub fn ok(content_type: &str, body: impl Into<Vec<u8>>) -> Response<Vec<u8>> {
+    Response::builder()
+        .header(http::header::CONTENT_TYPE, content_type)
+        .body(body.into())
+        .unwrap()
+}
+
+/// An empty response with the given status.
+pub fn status(status: StatusCode) -> Response<Vec<u8>> {
+    Response::builder().status(status).body(vec![]).unwrap()
+}
+
+async fn receive(request: http::Request<Incoming>) -> ReceivedRequest {
+    let (parts, body) = request.into_parts();
+    let body = body.collect().await.unwrap().to_bytes().to_vec();
+    ReceivedRequest {
+        method: parts.method,
+        path: parts
+            .uri
+            .path_and_query()
+            .map(|p| p.as_str().to_owned())
+            .unwrap_or_default(),
+        headers: parts.headers,
+        body,
+    }
+}
+
+fn clone_response(response: &Response<Vec<u8>>) -> Response<Vec<u8>> {
+    let mut clone = Response::new(response.body().clone());
+    *clone.status_mut() = response.status();
+    *clone.headers_mut() = response.headers().clone();
+    clone
+}
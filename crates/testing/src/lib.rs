@@ -72,6 +72,7 @@ impl HttpTestConfig {
         self.http_trigger_config = HttpTriggerConfig {
             component: "test-component".to_string(),
             route: route.into(),
+            host: None,
             executor: None,
         };
         self
@@ -85,6 +86,7 @@ impl HttpTestConfig {
         self.http_trigger_config = HttpTriggerConfig {
             component: "test-component".to_string(),
             route: route.into(),
+            host: None,
             executor: Some(HttpExecutorType::Wagi(wagi_config)),
         };
         self
@@ -0,0 +1,253 @@
+//! Answering `wasi:http` requests from one component of an app to another
+//! in-process, rather than over the network.
+//!
+//! A component addresses another as `http://<component-id>.spin.internal`,
+//! followed by the path it would be requested at through the listener. The
+//! calling component must list that URL (or `http://*.spin.internal`) in its
+//! `allowed_outbound_hosts`; wildcard hosts such as `*://*:*` do not allow
+//! requests to other components.
+
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    sync::Arc,
+};
+
+use http::Uri;
+use spin_outbound_networking::{intercept::SharedInterceptor, SERVICE_CHAINING_DOMAIN_SUFFIX};
+use wasmtime::component::Resource;
+use wasmtime_wasi::preview2::spawn;
+use wasmtime_wasi_http::{
+    bindings::wasi::http::types::ErrorCode,
+    body::HyperIncomingBody,
+    types::{HostFutureIncomingResponse, IncomingResponseInternal, OutgoingRequest},
+    WasiHttpView,
+};
+
+use crate::{HttpTrigger, HttpTriggerState};
+
+/// The maximum number of chained requests which may be made in answering one
+/// request to the app, to stop components calling each other forever.
+const MAX_CHAIN_DEPTH: u32 = 10;
+
+/// The client address reported to components handling chained requests.
+const CHAINED_CLIENT_ADDR: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0));
+
+/// Returns the ID of the component addressed by `uri`, if it is a chained
+/// request.
+pub(crate) fn component_id(uri: &Uri) -> Option<String> {
+    let component_id = uri.host()?.strip_suffix(SERVICE_CHAINING_DOMAIN_SUFFIX)?;
+    if component_id.is_empty() || component_id.contains('.') {
+        return None;
+    }
+    Some(component_id.to_owned())
+}
+
+/// Executes requests against the version of the app which is handling the
+/// calling component's own request.
+#[derive(Clone)]
+pub(crate) struct ChainedRequestHandler {
+    pub(crate) state: Arc<HttpTriggerState>,
+    pub(crate) outbound_interceptor: Option<SharedInterceptor>,
+    /// The number of chained requests leading to the calling component's own
+    /// request; 0 if it was called through the listener.
+    pub(crate) depth: u32,
+}
+
+impl ChainedRequestHandler {
+    /// Sends the request to the given component rather than upstream.
+    pub(crate) fn send_request(
+        self,
+        view: &mut dyn WasiHttpView,
+        component_id: String,
+        request: OutgoingRequest,
+    ) -> wasmtime::Result<Resource<HostFutureIncomingResponse>> {
+        let between_bytes_timeout = request.between_bytes_timeout;
+        let handle = spawn(async move {
+            let resp = self.respond(&component_id, request.request).await;
+            Ok(resp.map(|resp| IncomingResponseInternal {
+                resp,
+                // The response body is driven by the handling component's own task.
+                worker: Arc::new(spawn(async { Ok(()) })),
+                between_bytes_timeout,
+            }))
+        });
+        Ok(view.table().push(HostFutureIncomingResponse::new(handle))?)
+    }
+
+    async fn respond(
+        self,
+        component_id: &str,
+        request: hyper::Request<HyperIncomingBody>,
+    ) -> Result<hyper::Response<HyperIncomingBody>, ErrorCode> {
+        if !self
+            .state
+            .component_trigger_configs
+            .contains_key(component_id)
+        {
+            tracing::warn!("Chained request to unknown component '{component_id}'");
+            return Err(ErrorCode::DestinationNotFound);
+        }
+        if self.depth >= MAX_CHAIN_DEPTH {
+            tracing::warn!(
+                "Chained request to '{component_id}' exceeds the limit of {MAX_CHAIN_DEPTH} chained requests"
+            );
+            return Err(ErrorCode::LoopDetected);
+        }
+        HttpTrigger::execute(
+            &self.state,
+            self.outbound_interceptor,
+            component_id,
+            request,
+            CHAINED_CLIENT_ADDR,
+            self.depth + 1,
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!("Error processing chained request to '{component_id}': {e:?}");
+            ErrorCode::InternalError(Some(e.to_string()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use http::StatusCode;
+    use http_body_util::BodyExt;
+    use hyper::body::Bytes;
+    use spin_core::{OutboundWasiHttpHandler, WasiVersion};
+    use spin_http::body;
+    use spin_outbound_networking::AllowedHostsConfig;
+
+    use crate::HttpRuntimeData;
+
+    use super::*;
+
+    async fn test_handler(depth: u32) -> ChainedRequestHandler {
+        let trigger: HttpTrigger = spin_testing::HttpTestConfig::default()
+            .test_program("rust-http-test.wasm")
+            .http_spin_trigger("/test")
+            .build_trigger()
+            .await;
+        ChainedRequestHandler {
+            state: trigger.state(),
+            outbound_interceptor: None,
+            depth,
+        }
+    }
+
+    fn test_request(uri: &str) -> hyper::Request<HyperIncomingBody> {
+        hyper::Request::post(uri)
+            .header("x-custom-foo", "bar")
+            .header("x-custom-foo2", "bar2")
+            .body(body::full(Bytes::from_static(b"Fermyon")))
+            .unwrap()
+    }
+
+    fn outgoing_request(uri: &str) -> OutgoingRequest {
+        let request = test_request(uri);
+        OutgoingRequest {
+            use_tls: false,
+            authority: request.uri().authority().unwrap().to_string(),
+            request,
+            connect_timeout: Duration::from_secs(5),
+            first_byte_timeout: Duration::from_secs(5),
+            between_bytes_timeout: Duration::from_secs(5),
+        }
+    }
+
+    fn send_with_allowed_hosts(
+        handler: ChainedRequestHandler,
+        allowed_hosts: &[&str],
+        uri: &str,
+    ) -> wasmtime::Result<Resource<HostFutureIncomingResponse>> {
+        let data = HttpRuntimeData {
+            allowed_hosts: AllowedHostsConfig::parse(allowed_hosts).unwrap(),
+            chained_handler: Some(handler.clone()),
+            ..Default::default()
+        };
+        let mut store = handler
+            .state
+            .engine
+            .engine
+            .store_builder(WasiVersion::Preview2)
+            .build_with_data(data)
+            .unwrap();
+        HttpRuntimeData::send_request(store.as_mut().data_mut(), outgoing_request(uri))
+    }
+
+    #[test]
+    fn recognises_chained_request_hosts() {
+        let id = |uri: &str| component_id(&uri.parse().unwrap());
+        assert_eq!(
+            Some("backend".to_owned()),
+            id("http://backend.spin.internal/api")
+        );
+        assert_eq!(
+            Some("backend".to_owned()),
+            id("http://backend.spin.internal:80/")
+        );
+        assert_eq!(None, id("http://spin.internal/"));
+        assert_eq!(None, id("http://a.backend.spin.internal/"));
+        assert_eq!(None, id("http://backend.example.com/"));
+    }
+
+    #[tokio::test]
+    async fn dispatches_to_addressed_component() {
+        let handler = test_handler(0).await;
+        let res = handler
+            .respond(
+                "test-component",
+                test_request("http://test-component.spin.internal/test?abc=def"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body_bytes = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body_bytes.to_vec(), b"Hello, Fermyon");
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_components() {
+        let handler = test_handler(0).await;
+        let err = handler
+            .respond(
+                "missing",
+                test_request("http://missing.spin.internal/test?abc=def"),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ErrorCode::DestinationNotFound));
+    }
+
+    #[tokio::test]
+    async fn rejects_requests_beyond_max_depth() {
+        let handler = test_handler(MAX_CHAIN_DEPTH).await;
+        let err = handler
+            .respond(
+                "test-component",
+                test_request("http://test-component.spin.internal/test?abc=def"),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ErrorCode::LoopDetected));
+    }
+
+    #[tokio::test]
+    async fn chained_requests_must_be_allowed_explicitly() {
+        let uri = "http://test-component.spin.internal/test?abc=def";
+        let handler = test_handler(0).await;
+
+        let err = send_with_allowed_hosts(handler.clone(), &["*://*:*"], uri).unwrap_err();
+        assert!(err.to_string().contains("destination-not-allowed"));
+
+        send_with_allowed_hosts(
+            handler.clone(),
+            &["http://test-component.spin.internal"],
+            uri,
+        )
+        .unwrap();
+        send_with_allowed_hosts(handler, &["http://*.spin.internal"], uri).unwrap();
+    }
+}
@@ -1,6 +1,6 @@
 use std::{net::SocketAddr, str, str::FromStr};
 
-use crate::{Body, HttpExecutor, HttpTrigger, Store};
+use crate::{chained::ChainedRequestHandler, Body, HttpExecutor, HttpTrigger, Store};
 use anyhow::bail;
 use anyhow::{anyhow, Context, Result};
 use futures::TryFutureExt;
@@ -13,14 +13,21 @@ use spin_core::wasi_2023_10_18::exports::wasi::http::incoming_handler::IncomingH
 use spin_core::wasi_2023_11_10::exports::wasi::http::incoming_handler::IncomingHandler as IncomingHandler2023_11_10;
 use spin_core::Instance;
 use spin_http::body;
+use spin_outbound_networking::intercept::SharedInterceptor;
 use spin_trigger::{EitherInstance, TriggerAppEngine};
 use spin_world::v1::http_types;
 use std::sync::Arc;
 use tokio::{sync::oneshot, task};
 use wasmtime_wasi_http::{proxy::Proxy, WasiHttpView};
 
-#[derive(Clone)]
-pub struct HttpHandlerExecutor;
+#[derive(Clone, Default)]
+pub struct HttpHandlerExecutor {
+    /// If set, answers outbound HTTP requests made by the component.
+    pub outbound_interceptor: Option<SharedInterceptor>,
+    /// If set, answers requests made by the component to other components
+    /// of the app.
+    pub(crate) chained_handler: Option<ChainedRequestHandler>,
+}
 
 #[async_trait]
 impl HttpExecutor for HttpHandlerExecutor {
@@ -44,6 +51,10 @@ impl HttpExecutor for HttpHandlerExecutor {
         };
 
         set_http_origin_from_request(&mut store, engine, &req);
+        if let Some(interceptor) = &self.outbound_interceptor {
+            set_outbound_interceptor(&mut store, engine, interceptor);
+        }
+        store.as_mut().data_mut().as_mut().chained_handler = self.chained_handler.clone();
 
         let resp = match HandlerType::from_exports(instance.exports(&mut store)) {
             Some(HandlerType::Wasi) => {
@@ -370,6 +381,25 @@ fn set_http_origin_from_request(
     }
 }
 
+/// Answers the instance's permitted outbound HTTP requests, made through
+/// either Spin's or WASI's HTTP interface, using the interceptor.
+pub(crate) fn set_outbound_interceptor(
+    store: &mut Store,
+    engine: &TriggerAppEngine<HttpTrigger>,
+    interceptor: &SharedInterceptor,
+) {
+    if let Some(outbound_http_handle) = engine
+        .engine
+        .find_host_component_handle::<Arc<OutboundHttpComponent>>()
+    {
+        store
+            .host_components_data()
+            .get_or_insert(outbound_http_handle)
+            .interceptor = Some(interceptor.clone());
+    }
+    store.as_mut().data_mut().as_mut().interceptor = Some(interceptor.clone());
+}
+
 fn contextualise_err(e: anyhow::Error) -> anyhow::Error {
     if e.to_string()
         .contains("failed to find function export `canonical_abi_free`")
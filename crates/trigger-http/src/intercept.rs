@@ -0,0 +1,70 @@
+//! Answering outbound `wasi:http` requests with an [`OutboundHttpInterceptor`].
+//!
+//! [`OutboundHttpInterceptor`]: spin_outbound_networking::intercept::OutboundHttpInterceptor
+
+use std::sync::Arc;
+
+use http_body_util::BodyExt;
+use spin_http::body;
+use spin_outbound_networking::intercept::{InterceptedRequest, SharedInterceptor};
+use wasmtime::component::Resource;
+use wasmtime_wasi::preview2::spawn;
+use wasmtime_wasi_http::{
+    bindings::wasi::http::types::ErrorCode,
+    body::{HyperIncomingBody, HyperOutgoingBody},
+    types::{HostFutureIncomingResponse, IncomingResponseInternal, OutgoingRequest},
+    WasiHttpView,
+};
+
+/// Sends the request to the interceptor rather than upstream.
+pub fn send_intercepted_request(
+    view: &mut dyn WasiHttpView,
+    interceptor: SharedInterceptor,
+    request: OutgoingRequest,
+) -> wasmtime::Result<Resource<HostFutureIncomingResponse>> {
+    let between_bytes_timeout = request.between_bytes_timeout;
+    let handle = spawn(async move {
+        let resp = respond(interceptor, request.request).await?;
+        Ok(resp.map(|resp| IncomingResponseInternal {
+            resp,
+            // The response is fully buffered so there is no connection to drive.
+            worker: Arc::new(spawn(async { Ok(()) })),
+            between_bytes_timeout,
+        }))
+    });
+    Ok(view.table().push(HostFutureIncomingResponse::new(handle))?)
+}
+
+async fn respond(
+    interceptor: SharedInterceptor,
+    request: hyper::Request<HyperOutgoingBody>,
+) -> anyhow::Result<Result<hyper::Response<HyperIncomingBody>, ErrorCode>> {
+    let (parts, body) = request.into_parts();
+    let body = match body.collect().await {
+        Ok(body) => body.to_bytes().to_vec(),
+        Err(e) => return Ok(Err(e)),
+    };
+    let request = InterceptedRequest {
+        method: parts.method.to_string(),
+        url: parts.uri.to_string(),
+        headers: parts
+            .headers
+            .iter()
+            .filter_map(|(k, v)| Some((k.to_string(), v.to_str().ok()?.to_owned())))
+            .collect(),
+        body,
+    };
+
+    let resp = match interceptor.respond(request).await {
+        Ok(resp) => resp,
+        Err(e) => {
+            tracing::warn!("Outbound HTTP interceptor error: {e:?}");
+            return Ok(Err(ErrorCode::InternalError(Some(e.to_string()))));
+        }
+    };
+    let mut builder = hyper::Response::builder().status(resp.status);
+    for (name, value) in resp.headers {
+        builder = builder.header(name, value);
+    }
+    Ok(Ok(builder.body(body::full(resp.body.into()))?))
+}
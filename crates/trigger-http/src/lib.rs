@@ -1,6 +1,8 @@
 //! Implementation for the Spin HTTP engine.
 
+mod chained;
 mod handler;
+pub mod intercept;
 mod tls;
 mod wagi;
 
@@ -8,7 +10,7 @@ use std::{
     collections::HashMap,
     net::{Ipv4Addr, SocketAddr, ToSocketAddrs},
     path::PathBuf,
-    sync::Arc,
+    sync::{Arc, RwLock},
 };
 
 use anyhow::{Context, Result};
@@ -23,6 +25,7 @@ use hyper::{
     Request, Response,
 };
 use hyper_util::rt::tokio::TokioIo;
+use outbound_http::cassette::{MatchRules, OutboundRecorder, OutboundReplayer};
 use spin_app::{AppComponent, APP_DESCRIPTION_KEY};
 use spin_core::{Engine, OutboundWasiHttpHandler};
 use spin_http::{
@@ -31,8 +34,8 @@ use spin_http::{
     config::{HttpExecutorType, HttpTriggerConfig},
     routes::{RoutePattern, Router},
 };
-use spin_outbound_networking::{AllowedHostsConfig, OutboundUrl};
-use spin_trigger::{EitherInstancePre, TriggerAppEngine, TriggerExecutor};
+use spin_outbound_networking::{intercept::SharedInterceptor, AllowedHostsConfig, OutboundUrl};
+use spin_trigger::{EitherInstancePre, TriggerAppEngine, TriggerExecutor, TriggerReloader};
 use tokio::{
     io::{AsyncRead, AsyncWrite},
     net::TcpListener,
@@ -41,7 +44,7 @@ use tokio::{
 use tracing::log;
 use wasmtime_wasi_http::body::HyperIncomingBody as Body;
 
-use crate::{handler::HttpHandlerExecutor, wagi::WagiHttpExecutor};
+use crate::{chained::ChainedRequestHandler, handler::HttpHandlerExecutor, wagi::WagiHttpExecutor};
 
 pub use tls::TlsConfig;
 
@@ -49,8 +52,17 @@ pub(crate) type RuntimeData = HttpRuntimeData;
 pub(crate) type Store = spin_core::Store<RuntimeData>;
 
 /// The Spin HTTP trigger.
+#[derive(Clone)]
 pub struct HttpTrigger {
-    engine: TriggerAppEngine<Self>,
+    // The app currently being served. This is replaced as a whole when the app
+    // is reloaded, so that each request is handled by a single version of the app.
+    state: Arc<RwLock<Arc<HttpTriggerState>>>,
+    // If set, answers outbound HTTP requests made by components.
+    outbound_interceptor: Option<SharedInterceptor>,
+}
+
+struct HttpTriggerState {
+    engine: TriggerAppEngine<HttpTrigger>,
     router: Router,
     // Base path for component routes.
     base: String,
@@ -71,9 +83,42 @@ pub struct CliArgs {
     /// The path to the certificate key to use for https, if this is not set, normal http will be used. The key should be in PKCS#8 format
     #[clap(long, env = "SPIN_TLS_KEY", requires = "tls-cert")]
     pub tls_key: Option<PathBuf>,
+
+    /// Send outbound HTTP requests as normal, and record each request and its
+    /// response to the given file.
+    #[clap(long = "record-outbound", conflicts_with = "replay-outbound")]
+    pub record_outbound: Option<PathBuf>,
+
+    /// Answer outbound HTTP requests from a file recorded with --record-outbound,
+    /// instead of sending them. Requests are matched by method, URL and body.
+    #[clap(long = "replay-outbound")]
+    pub replay_outbound: Option<PathBuf>,
+
+    /// When replaying outbound HTTP requests, also match requests by the given
+    /// header. Can be used multiple times.
+    #[clap(long = "match-outbound-header", requires = "replay-outbound")]
+    pub match_outbound_headers: Vec<String>,
 }
 
 impl CliArgs {
+    fn outbound_interceptor(&self) -> Result<Option<SharedInterceptor>> {
+        if let Some(path) = &self.record_outbound {
+            terminal::step!("Recording", "outbound HTTP requests to {}", path.display());
+            return Ok(Some(Arc::new(OutboundRecorder::new(path))));
+        }
+        if let Some(path) = &self.replay_outbound {
+            let rules = MatchRules::with_headers(&self.match_outbound_headers);
+            let replayer = OutboundReplayer::from_file(path, rules)?;
+            terminal::step!(
+                "Replaying",
+                "outbound HTTP requests from {}",
+                path.display()
+            );
+            return Ok(Some(Arc::new(replayer)));
+        }
+        Ok(None)
+    }
+
     fn into_tls_config(self) -> Option<TlsConfig> {
         match (self.tls_cert, self.tls_key) {
             (Some(cert_path), Some(key_path)) => Some(TlsConfig {
@@ -94,52 +139,18 @@ impl TriggerExecutor for HttpTrigger {
     type RunConfig = CliArgs;
 
     async fn new(engine: TriggerAppEngine<Self>) -> Result<Self> {
-        let mut base = engine
-            .app()
-            .require_metadata(spin_http::trigger::METADATA_KEY)?
-            .base;
-        if !base.starts_with('/') {
-            base = format!("/{base}");
-        }
-
-        let component_routes = engine
-            .trigger_configs()
-            .map(|(_, config)| (config.component.as_str(), config.route.as_str()));
-
-        let (router, duplicate_routes) = Router::build(&base, component_routes)?;
-
-        if !duplicate_routes.is_empty() {
-            log::error!("The following component routes are duplicates and will never be used:");
-            for dup in &duplicate_routes {
-                log::error!(
-                    "  {}: {} (duplicate of {})",
-                    dup.replaced_id,
-                    dup.route.full_pattern_non_empty(),
-                    dup.effective_id,
-                );
-            }
-        }
-
-        log::trace!(
-            "Constructed router for application {}: {:?}",
-            engine.app_name,
-            router.routes().collect::<Vec<_>>()
-        );
-
-        let component_trigger_configs = engine
-            .trigger_configs()
-            .map(|(_, config)| (config.component.clone(), config.clone()))
-            .collect();
-
+        let state = HttpTriggerState::new(engine)?;
         Ok(Self {
-            engine,
-            router,
-            base,
-            component_trigger_configs,
+            state: Arc::new(RwLock::new(Arc::new(state))),
+            outbound_interceptor: None,
         })
     }
 
-    async fn run(self, config: Self::RunConfig) -> Result<()> {
+    async fn run(mut self, config: Self::RunConfig) -> Result<()> {
+        if let Some(interceptor) = config.outbound_interceptor()? {
+            self.set_outbound_interceptor(interceptor);
+        }
+
         let listen_addr = config.address;
         let tls = config.into_tls_config();
 
@@ -149,16 +160,31 @@ impl TriggerExecutor for HttpTrigger {
         terminal::step!("\nServing", "{}", base_url);
         log::info!("Serving {}", base_url);
 
+        let state = self.state();
         println!("Available Routes:");
-        for (route, component_id) in self.router.routes() {
-            println!("  {}: {}{}", component_id, base_url, route);
-            if let Some(component) = self.engine.app().get_component(component_id) {
+        for (host, route, component_id) in state.router.routes() {
+            if host.is_any() {
+                println!("  {}: {}{}", component_id, base_url, route);
+            } else {
+                println!(
+                    "  {}: {}://{}:{}{}",
+                    component_id,
+                    scheme,
+                    host,
+                    listen_addr.port(),
+                    route
+                );
+            }
+            if let Some(component) = state.engine.app().get_component(component_id) {
                 if let Some(description) = component.get_metadata(APP_DESCRIPTION_KEY)? {
                     println!("    {}", description);
                 }
             }
         }
 
+        // The app may be reloaded while serving, so don't hold on to this version.
+        drop(state);
+
         if let Some(tls) = tls {
             self.serve_tls(listen_addr, tls).await?
         } else {
@@ -182,9 +208,94 @@ impl TriggerExecutor for HttpTrigger {
             Ok(EitherInstancePre::Component(engine.instantiate_pre(&comp)?))
         }
     }
+
+    fn reloader(&self) -> Option<Arc<dyn TriggerReloader>> {
+        Some(Arc::new(self.clone()))
+    }
+}
+
+#[async_trait]
+impl TriggerReloader for HttpTrigger {
+    async fn reload(&self) -> Result<()> {
+        let engine = self.state().engine.reload().await?;
+        let state = HttpTriggerState::new(engine)?;
+        *self.state.write().unwrap() = Arc::new(state);
+        Ok(())
+    }
+}
+
+impl HttpTriggerState {
+    fn new(engine: TriggerAppEngine<HttpTrigger>) -> Result<Self> {
+        let mut base = engine
+            .app()
+            .require_metadata(spin_http::trigger::METADATA_KEY)?
+            .base;
+        if !base.starts_with('/') {
+            base = format!("/{base}");
+        }
+
+        let component_routes = engine.trigger_configs().map(|(_, config)| {
+            (
+                config.component.as_str(),
+                config.host.as_deref(),
+                config.route.as_str(),
+            )
+        });
+
+        let (router, duplicate_routes) = Router::build_with_hosts(&base, component_routes)?;
+
+        if !duplicate_routes.is_empty() {
+            log::error!("The following component routes are duplicates and will never be used:");
+            for dup in &duplicate_routes {
+                let host = if dup.host.is_any() {
+                    String::new()
+                } else {
+                    dup.host.to_string()
+                };
+                log::error!(
+                    "  {}: {}{} (duplicate of {})",
+                    dup.replaced_id,
+                    host,
+                    dup.route.full_pattern_non_empty(),
+                    dup.effective_id,
+                );
+            }
+        }
+
+        log::trace!(
+            "Constructed router for application {}: {:?}",
+            engine.app_name,
+            router.routes().collect::<Vec<_>>()
+        );
+
+        let component_trigger_configs = engine
+            .trigger_configs()
+            .map(|(_, config)| (config.component.clone(), config.clone()))
+            .collect();
+
+        Ok(Self {
+            engine,
+            router,
+            base,
+            component_trigger_configs,
+        })
+    }
 }
 
 impl HttpTrigger {
+    /// Returns the app currently being served.
+    fn state(&self) -> Arc<HttpTriggerState> {
+        self.state.read().unwrap().clone()
+    }
+
+    /// Answers outbound HTTP requests made by components with the given
+    /// interceptor rather than sending them upstream.
+    ///
+    /// This applies only to components using the Spin HTTP executor.
+    pub fn set_outbound_interceptor(&mut self, interceptor: SharedInterceptor) {
+        self.outbound_interceptor = Some(interceptor);
+    }
+
     /// Handles incoming requests using an HTTP executor.
     pub async fn handle(
         &self,
@@ -194,59 +305,38 @@ impl HttpTrigger {
     ) -> Result<Response<Body>> {
         set_req_uri(&mut req, scheme)?;
 
+        let state = self.state();
+
         log::info!(
             "Processing request for application {} on URI {}",
-            &self.engine.app_name,
+            &state.engine.app_name,
             req.uri()
         );
 
         let path = req.uri().path();
+        let host = req.uri().host();
 
         // Handle well-known spin paths
         if let Some(well_known) = path.strip_prefix(spin_http::WELL_KNOWN_PREFIX) {
             return match well_known {
                 "health" => Ok(Response::new(body::full(Bytes::from_static(b"OK")))),
-                "info" => self.app_info(),
+                "info" => Self::app_info(&state),
                 _ => Self::not_found(),
             };
         }
 
         // Route to app component
-        match self.router.route(path) {
+        match state.router.route_host(host, path) {
             Ok(component_id) => {
-                let trigger = self.component_trigger_configs.get(component_id).unwrap();
-
-                let executor = trigger.executor.as_ref().unwrap_or(&HttpExecutorType::Http);
-
-                let res = match executor {
-                    HttpExecutorType::Http => {
-                        HttpHandlerExecutor
-                            .execute(
-                                &self.engine,
-                                component_id,
-                                &self.base,
-                                &trigger.route,
-                                req,
-                                addr,
-                            )
-                            .await
-                    }
-                    HttpExecutorType::Wagi(wagi_config) => {
-                        let executor = WagiHttpExecutor {
-                            wagi_config: wagi_config.clone(),
-                        };
-                        executor
-                            .execute(
-                                &self.engine,
-                                component_id,
-                                &self.base,
-                                &trigger.route,
-                                req,
-                                addr,
-                            )
-                            .await
-                    }
-                };
+                let res = Self::execute(
+                    &state,
+                    self.outbound_interceptor.clone(),
+                    component_id,
+                    req,
+                    addr,
+                    0,
+                )
+                .await;
                 match res {
                     Ok(res) => Ok(res),
                     Err(e) => {
@@ -259,9 +349,67 @@ impl HttpTrigger {
         }
     }
 
+    /// Executes a request with the given component, using the executor
+    /// configured for that component. `chain_depth` is the number of chained
+    /// requests which led to this one.
+    async fn execute(
+        state: &Arc<HttpTriggerState>,
+        outbound_interceptor: Option<SharedInterceptor>,
+        component_id: &str,
+        req: Request<Body>,
+        addr: SocketAddr,
+        chain_depth: u32,
+    ) -> Result<Response<Body>> {
+        let trigger = state
+            .component_trigger_configs
+            .get(component_id)
+            .with_context(|| format!("component '{component_id}' has no HTTP trigger"))?;
+
+        let executor = trigger.executor.as_ref().unwrap_or(&HttpExecutorType::Http);
+
+        match executor {
+            HttpExecutorType::Http => {
+                let executor = HttpHandlerExecutor {
+                    outbound_interceptor: outbound_interceptor.clone(),
+                    chained_handler: Some(ChainedRequestHandler {
+                        state: state.clone(),
+                        outbound_interceptor,
+                        depth: chain_depth,
+                    }),
+                };
+                executor
+                    .execute(
+                        &state.engine,
+                        component_id,
+                        &state.base,
+                        &trigger.route,
+                        req,
+                        addr,
+                    )
+                    .await
+            }
+            HttpExecutorType::Wagi(wagi_config) => {
+                let executor = WagiHttpExecutor {
+                    wagi_config: wagi_config.clone(),
+                    outbound_interceptor,
+                };
+                executor
+                    .execute(
+                        &state.engine,
+                        component_id,
+                        &state.base,
+                        &trigger.route,
+                        req,
+                        addr,
+                    )
+                    .await
+            }
+        }
+    }
+
     /// Returns spin status information.
-    fn app_info(&self) -> Result<Response<Body>> {
-        let info = AppInfo::new(self.engine.app());
+    fn app_info(state: &HttpTriggerState) -> Result<Response<Body>> {
+        let info = AppInfo::new(state.engine.app());
         let body = serde_json::to_vec_pretty(&info)?;
         Ok(Response::builder()
             .header("content-type", "application/json")
@@ -455,6 +603,10 @@ pub struct HttpRuntimeData {
     origin: Option<String>,
     /// The hosts this app is allowed to make outbound requests to
     allowed_hosts: AllowedHostsConfig,
+    /// If set, answers outbound requests in place of the upstream server
+    interceptor: Option<SharedInterceptor>,
+    /// If set, answers requests to other components of the app in-process
+    chained_handler: Option<ChainedRequestHandler>,
 }
 
 impl OutboundWasiHttpHandler for HttpRuntimeData {
@@ -532,6 +684,19 @@ impl OutboundWasiHttpHandler for HttpRuntimeData {
             anyhow::bail!("destination-not-allowed (error 1)")
         }
 
+        if let Some(component_id) = chained::component_id(request.request.uri()) {
+            if let Some(chained_handler) = this.chained_handler.clone() {
+                return chained_handler.send_request(data, component_id, request);
+            }
+        }
+
+        if let Some(interceptor) = this.interceptor.clone() {
+            let method = request.request.method().as_str();
+            if interceptor.handles(method, &request.request.uri().to_string()) {
+                return intercept::send_intercepted_request(data, interceptor, request);
+            }
+        }
+
         wasmtime_wasi_http::types::default_send_request(data, request)
     }
 }
@@ -6,14 +6,17 @@ use http_body_util::BodyExt;
 use hyper::{Request, Response};
 use spin_core::WasiVersion;
 use spin_http::{config::WagiTriggerConfig, routes::RoutePattern, wagi};
+use spin_outbound_networking::intercept::SharedInterceptor;
 use spin_trigger::{EitherInstance, TriggerAppEngine};
 use wasi_common_preview1::{pipe::WritePipe, I32Exit};
 
-use crate::{Body, HttpExecutor, HttpTrigger};
+use crate::{handler::set_outbound_interceptor, Body, HttpExecutor, HttpTrigger};
 
 #[derive(Clone)]
 pub struct WagiHttpExecutor {
     pub wagi_config: WagiTriggerConfig,
+    /// If set, answers outbound HTTP requests made by the module.
+    pub outbound_interceptor: Option<SharedInterceptor>,
 }
 
 #[async_trait]
@@ -97,6 +100,10 @@ impl HttpExecutor for WagiHttpExecutor {
             unreachable!()
         };
 
+        if let Some(interceptor) = &self.outbound_interceptor {
+            set_outbound_interceptor(&mut store, engine, interceptor);
+        }
+
         let start = instance
             .get_func(&mut store, &self.wagi_config.entrypoint)
             .ok_or_else(|| {
@@ -1,21 +1,25 @@
-use std::path::PathBuf;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use anyhow::{Context, Result};
 use clap::{Args, IntoApp, Parser};
 use serde::de::DeserializeOwned;
 use spin_app::Loader;
-use spin_common::{arg_parser::parse_kv, sloth};
+use spin_common::{arg_parser::parse_kv, sloth, ui::quoted_path};
 
 use crate::network::Network;
 use crate::runtime_config::llm::LLmOptions;
 use crate::runtime_config::sqlite::SqlitePersistenceMessageHook;
+use crate::runtime_config::volume::VolumeMounter;
 use crate::stdio::StdioLoggingTriggerHooks;
 use crate::{
     loader::TriggerLoader,
     runtime_config::{key_value::KeyValuePersistenceMessageHook, RuntimeConfig},
     stdio::FollowComponents,
 };
-use crate::{TriggerExecutor, TriggerExecutorBuilder};
+use crate::{TriggerExecutor, TriggerExecutorBuilder, TriggerReloader};
 
 pub const APP_LOG_DIR: &str = "APP_LOG_DIR";
 pub const DISABLE_WASMTIME_CACHE: &str = "DISABLE_WASMTIME_CACHE";
@@ -28,6 +32,12 @@ pub const SPIN_LOCKED_URL: &str = "SPIN_LOCKED_URL";
 pub const SPIN_LOCAL_APP_DIR: &str = "SPIN_LOCAL_APP_DIR";
 pub const SPIN_WORKING_DIR: &str = "SPIN_WORKING_DIR";
 
+// Set by `spin watch`: if present, `spin up` and the trigger executor reload the
+// app in-process on receipt of SIGUSR1, rather than the app having to be restarted.
+// The value is the path of a file which the trigger executor creates once it is
+// listening for reload requests; until then, a SIGUSR1 would terminate it.
+pub const SPIN_HOT_RELOAD: &str = "SPIN_HOT_RELOAD";
+
 /// A command that runs a TriggerExecutor.
 #[derive(Parser, Debug)]
 #[clap(
@@ -147,6 +157,17 @@ where
             return Ok(());
         }
 
+        // Listen for reload requests before loading the app, as a request that
+        // arrives with no listener installed would terminate the process.
+        let reload_signals = match std::env::var_os(SPIN_HOT_RELOAD) {
+            Some(ready_file) => {
+                let reload_signals = ReloadSignals::listen()?;
+                reload_signals.mark_ready(Path::new(&ready_file))?;
+                Some(reload_signals)
+            }
+            None => None,
+        };
+
         // Required env vars
         let working_dir = std::env::var(SPIN_WORKING_DIR).context(SPIN_WORKING_DIR)?;
         let locked_url = std::env::var(SPIN_LOCKED_URL).context(SPIN_LOCKED_URL)?;
@@ -160,6 +181,16 @@ where
         let loader = TriggerLoader::new(working_dir, self.allow_transient_write);
         let executor = self.build_executor(loader, locked_url, init_data).await?;
 
+        if let Some(reload_signals) = reload_signals {
+            match executor.reloader() {
+                Some(reloader) => reload_signals.reload_on_signal(reloader),
+                None => tracing::warn!(
+                    "The {} trigger does not support reloading; changes will require a restart",
+                    Executor::TRIGGER_TYPE
+                ),
+            }
+        }
+
         let run_fut = executor.run(self.run_config);
 
         let (abortable, abort_handle) = futures::future::abortable(run_fut);
@@ -191,12 +222,13 @@ where
         let _sloth_guard = warn_if_wasm_build_slothful();
 
         let mut builder = TriggerExecutorBuilder::new(loader);
-        self.update_config(builder.config_mut())?;
+        self.update_config(builder.config_mut(), &runtime_config)?;
 
         builder.hooks(StdioLoggingTriggerHooks::new(self.follow_components()));
         builder.hooks(Network);
         builder.hooks(KeyValuePersistenceMessageHook);
         builder.hooks(SqlitePersistenceMessageHook);
+        builder.hooks(VolumeMounter::default());
 
         builder.build(locked_url, runtime_config, init_data).await
     }
@@ -227,7 +259,18 @@ where
         }
     }
 
-    fn update_config(&self, config: &mut spin_core::Config) -> Result<()> {
+    fn update_config(
+        &self,
+        config: &mut spin_core::Config,
+        runtime_config: &RuntimeConfig,
+    ) -> Result<()> {
+        // Apply the runtime config `[wasmtime]` section
+        if let Some(wasmtime) = runtime_config.wasmtime() {
+            wasmtime
+                .apply(config)
+                .context("Invalid `[wasmtime]` runtime config")?;
+        }
+
         // Apply --cache / --disable-cache
         if !self.disable_cache {
             config.enable_cache(&self.cache)?;
@@ -241,6 +284,61 @@ where
     }
 }
 
+/// Listens for requests to reload the app, which are sent as SIGUSR1.
+struct ReloadSignals {
+    #[cfg(unix)]
+    signals: tokio::signal::unix::Signal,
+}
+
+impl ReloadSignals {
+    #[cfg(unix)]
+    fn listen() -> Result<Self> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let signals =
+            signal(SignalKind::user_defined1()).context("Failed to listen for reload signal")?;
+        Ok(Self { signals })
+    }
+
+    #[cfg(not(unix))]
+    fn listen() -> Result<Self> {
+        tracing::warn!(
+            "Reloading is not supported on this platform; changes will require a restart"
+        );
+        Ok(Self {})
+    }
+
+    /// Creates the given file to tell `spin watch` that reload requests can
+    /// now be received safely.
+    #[cfg(unix)]
+    fn mark_ready(&self, ready_file: &Path) -> Result<()> {
+        std::fs::write(ready_file, "")
+            .with_context(|| format!("Failed to create {}", quoted_path(ready_file)))
+    }
+
+    #[cfg(not(unix))]
+    fn mark_ready(&self, _ready_file: &Path) -> Result<()> {
+        Ok(())
+    }
+
+    /// Reloads the app each time a reload request is received.
+    #[cfg(unix)]
+    fn reload_on_signal(mut self, reloader: Arc<dyn TriggerReloader>) {
+        tokio::spawn(async move {
+            while self.signals.recv().await.is_some() {
+                tracing::info!("Reloading application");
+                match reloader.reload().await {
+                    Ok(()) => terminal::step!("Reloaded", "application"),
+                    Err(e) => terminal::error!("Failed to reload application: {e:#}"),
+                }
+            }
+        });
+    }
+
+    #[cfg(not(unix))]
+    fn reload_on_signal(self, _reloader: Arc<dyn TriggerReloader>) {}
+}
+
 const SLOTH_WARNING_DELAY_MILLIS: u64 = 1250;
 
 fn warn_if_wasm_build_slothful() -> sloth::SlothGuard {
@@ -276,3 +374,51 @@ pub mod help {
         }
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::*;
+
+    #[derive(Default)]
+    struct CountingReloader {
+        reloads: AtomicUsize,
+        reloaded: tokio::sync::Notify,
+    }
+
+    #[async_trait::async_trait]
+    impl TriggerReloader for CountingReloader {
+        async fn reload(&self) -> Result<()> {
+            self.reloads.fetch_add(1, Ordering::SeqCst);
+            self.reloaded.notify_one();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn reload_signal_reloads_app() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let ready_file = dir.path().join("reload-ready");
+
+        let reload_signals = ReloadSignals::listen()?;
+        reload_signals.mark_ready(&ready_file)?;
+        assert!(ready_file.exists());
+
+        let reloader = Arc::new(CountingReloader::default());
+        reload_signals.reload_on_signal(reloader.clone());
+
+        let status = std::process::Command::new("kill")
+            .arg("-USR1")
+            .arg(std::process::id().to_string())
+            .status()?;
+        assert!(status.success());
+
+        tokio::time::timeout(Duration::from_secs(5), reloader.reloaded.notified()).await?;
+        assert_eq!(1, reloader.reloads.load(Ordering::SeqCst));
+        Ok(())
+    }
+}
@@ -4,11 +4,10 @@ mod network;
 mod runtime_config;
 mod stdio;
 
-use std::{collections::HashMap, marker::PhantomData};
+use std::{collections::HashMap, marker::PhantomData, sync::Arc, time::SystemTime};
 
 use anyhow::{Context, Result};
 pub use async_trait::async_trait;
-use runtime_config::llm::LLmOptions;
 use serde::de::DeserializeOwned;
 
 use spin_app::{App, AppComponent, AppLoader, AppTrigger, Loader, OwnedApp, APP_NAME_KEY};
@@ -17,13 +16,22 @@ use spin_core::{
     OutboundWasiHttpHandler, Store, StoreBuilder, WasiVersion,
 };
 
-pub use crate::runtime_config::RuntimeConfig;
+pub use crate::runtime_config::{llm::LLmOptions, RuntimeConfig};
 
 pub enum EitherInstancePre<T> {
     Component(InstancePre<T>),
     Module(ModuleInstancePre<T>),
 }
 
+impl<T> Clone for EitherInstancePre<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Component(pre) => Self::Component(pre.clone()),
+            Self::Module(pre) => Self::Module(pre.clone()),
+        }
+    }
+}
+
 pub enum EitherInstance {
     Component(Instance),
     Module(ModuleInstance),
@@ -42,6 +50,13 @@ pub trait TriggerExecutor: Sized + Send + Sync {
     /// Run the trigger executor.
     async fn run(self, config: Self::RunConfig) -> Result<()>;
 
+    /// Returns a handle through which the running executor can be asked to
+    /// reload its app in-process. Executors which do not support this return
+    /// `None` (the default).
+    fn reloader(&self) -> Option<Arc<dyn TriggerReloader>> {
+        None
+    }
+
     /// Make changes to the ExecutionContext using the given Builder.
     fn configure_engine(_builder: &mut EngineBuilder<Self::RuntimeData>) -> Result<()> {
         Ok(())
@@ -61,9 +76,19 @@ pub trait TriggerExecutor: Sized + Send + Sync {
     }
 }
 
+/// A handle for reloading the app of a running [`TriggerExecutor`].
+#[async_trait]
+pub trait TriggerReloader: Send + Sync {
+    /// Reloads the app (see [`TriggerAppEngine::reload`]) and swaps it in for
+    /// subsequent executions. Executions already in progress complete against
+    /// the previous app. On error, the executor continues to run the previous app.
+    async fn reload(&self) -> Result<()>;
+}
+
 pub struct TriggerExecutorBuilder<Executor: TriggerExecutor> {
     loader: AppLoader,
     config: Config,
+    config_error: Option<anyhow::Error>,
     hooks: Vec<Box<dyn TriggerHooks>>,
     disable_default_host_components: bool,
     _phantom: PhantomData<Executor>,
@@ -72,9 +97,15 @@ pub struct TriggerExecutorBuilder<Executor: TriggerExecutor> {
 impl<Executor: TriggerExecutor> TriggerExecutorBuilder<Executor> {
     /// Create a new TriggerExecutorBuilder with the given Application.
     pub fn new(loader: impl Loader + Send + Sync + 'static) -> Self {
+        // Apply `SPIN_WASMTIME_*` env vars before anything else can update the
+        // config, so that later settings (e.g. runtime config) override them.
+        // An invalid value is reported by `build`.
+        let mut config = Config::default();
+        let config_error = config.update_pooling_from_env().err();
         Self {
             loader: AppLoader::new(loader),
-            config: Default::default(),
+            config,
+            config_error,
             hooks: Default::default(),
             disable_default_host_components: false,
             _phantom: PhantomData,
@@ -107,6 +138,10 @@ impl<Executor: TriggerExecutor> TriggerExecutorBuilder<Executor> {
     where
         Executor::TriggerConfig: DeserializeOwned,
     {
+        if let Some(err) = self.config_error.take() {
+            return Err(err);
+        }
+
         let engine = {
             let mut builder = Engine::builder(&self.config)?;
 
@@ -151,6 +186,10 @@ impl<Executor: TriggerExecutor> TriggerExecutorBuilder<Executor> {
                     runtime_config::sqlite::build_component(&runtime_config, &init_data.sqlite)
                         .await?,
                 )?;
+                self.loader.add_dynamic_host_component(
+                    &mut builder,
+                    runtime_config::vector_store::build_vector_store_component(&runtime_config)?,
+                )?;
                 self.loader.add_dynamic_host_component(
                     &mut builder,
                     outbound_http::OutboundHttpComponent,
@@ -176,7 +215,10 @@ impl<Executor: TriggerExecutor> TriggerExecutorBuilder<Executor> {
             .try_for_each(|h| h.app_loaded(app.borrowed(), &runtime_config))?;
 
         // Run trigger executor
-        Executor::new(TriggerAppEngine::new(engine, app_name, app, self.hooks).await?).await
+        Executor::new(
+            TriggerAppEngine::new(engine, app_name, app, self.hooks, runtime_config).await?,
+        )
+        .await
     }
 }
 
@@ -208,17 +250,21 @@ impl HostComponentInitData {
 /// Execution context for a TriggerExecutor executing a particular App.
 pub struct TriggerAppEngine<Executor: TriggerExecutor> {
     /// Engine to be used with this executor.
-    pub engine: Engine<Executor::RuntimeData>,
+    pub engine: Arc<Engine<Executor::RuntimeData>>,
     /// Name of the app for e.g. logging.
     pub app_name: String,
     // An owned wrapper of the App.
     app: OwnedApp,
     // Trigger hooks
-    hooks: Vec<Box<dyn TriggerHooks>>,
+    hooks: Vec<Arc<dyn TriggerHooks>>,
+    // Runtime config the hooks were loaded with, for reloading the app
+    runtime_config: Arc<RuntimeConfig>,
     // Trigger configs for this trigger type, with order matching `app.triggers_with_type(Executor::TRIGGER_TYPE)`
     trigger_configs: Vec<Executor::TriggerConfig>,
     // Map of {Component ID -> InstancePre} for each component.
     component_instance_pres: HashMap<String, EitherInstancePre<Executor::RuntimeData>>,
+    // Map of {Component ID -> SourceFingerprint} for each pre-instantiated component.
+    component_fingerprints: HashMap<String, SourceFingerprint>,
 }
 
 impl<Executor: TriggerExecutor> TriggerAppEngine<Executor> {
@@ -229,6 +275,68 @@ impl<Executor: TriggerExecutor> TriggerAppEngine<Executor> {
         app_name: String,
         app: OwnedApp,
         hooks: Vec<Box<dyn TriggerHooks>>,
+        runtime_config: RuntimeConfig,
+    ) -> Result<Self>
+    where
+        <Executor as TriggerExecutor>::TriggerConfig: DeserializeOwned,
+    {
+        Self::new_with_previous(
+            Arc::new(engine),
+            app_name,
+            app,
+            hooks.into_iter().map(Arc::from).collect(),
+            Arc::new(runtime_config),
+            None,
+        )
+        .await
+    }
+
+    /// Reloads the app from its original URI, returning a new TriggerAppEngine
+    /// which shares this one's [`Engine`].
+    ///
+    /// Hooks which hold state derived from the app are replaced by the fresh
+    /// hooks returned by [`TriggerHooks::for_reload`], which are notified of
+    /// the reloaded app; other hooks are shared with this TriggerAppEngine.
+    ///
+    /// Components whose Wasm source is unchanged reuse their existing
+    /// pre-instantiation; only new or changed components are loaded and
+    /// pre-instantiated again. Other component configuration (such as files,
+    /// environment and variables) is read from the new app at instantiation.
+    pub async fn reload(&self) -> Result<Self>
+    where
+        <Executor as TriggerExecutor>::TriggerConfig: DeserializeOwned,
+    {
+        let app = self.app.reload().await?;
+        let app_name = app.borrowed().require_metadata(APP_NAME_KEY)?;
+        let hooks = self
+            .hooks
+            .iter()
+            .map(|hooks| match hooks.for_reload() {
+                Some(mut fresh) => {
+                    fresh.app_loaded(app.borrowed(), &self.runtime_config)?;
+                    Ok(Arc::from(fresh))
+                }
+                None => Ok(hooks.clone()),
+            })
+            .collect::<Result<_>>()?;
+        Self::new_with_previous(
+            self.engine.clone(),
+            app_name,
+            app,
+            hooks,
+            self.runtime_config.clone(),
+            Some(self),
+        )
+        .await
+    }
+
+    async fn new_with_previous(
+        engine: Arc<Engine<Executor::RuntimeData>>,
+        app_name: String,
+        app: OwnedApp,
+        hooks: Vec<Arc<dyn TriggerHooks>>,
+        runtime_config: Arc<RuntimeConfig>,
+        previous: Option<&Self>,
     ) -> Result<Self>
     where
         <Executor as TriggerExecutor>::TriggerConfig: DeserializeOwned,
@@ -247,6 +355,7 @@ impl<Executor: TriggerExecutor> TriggerAppEngine<Executor> {
             .collect::<Result<Vec<_>>>()?;
 
         let mut component_instance_pres = HashMap::default();
+        let mut component_fingerprints = HashMap::default();
         for component in app.borrowed().components() {
             let id = component.id();
             // There is an issue here for triggers that consider the trigger config during
@@ -257,12 +366,23 @@ impl<Executor: TriggerExecutor> TriggerAppEngine<Executor> {
                 .find(|(c, _)| c == id)
                 .map(|(_, cfg)| cfg);
             if let Some(config) = trigger_config {
-                component_instance_pres.insert(
-                    id.to_owned(),
-                    Executor::instantiate_pre(&engine, &component, config)
+                let fingerprint = SourceFingerprint::new(&component)?;
+                let reusable = previous.and_then(|previous| {
+                    (previous.component_fingerprints.get(id) == Some(&fingerprint))
+                        .then(|| previous.component_instance_pres.get(id))
+                        .flatten()
+                });
+                let instance_pre = match reusable {
+                    Some(pre) => {
+                        tracing::debug!("Reusing unchanged component '{id}'");
+                        pre.clone()
+                    }
+                    None => Executor::instantiate_pre(&engine, &component, config)
                         .await
                         .with_context(|| format!("Failed to instantiate component '{id}'"))?,
-                );
+                };
+                component_instance_pres.insert(id.to_owned(), instance_pre);
+                component_fingerprints.insert(id.to_owned(), fingerprint);
             } else {
                 tracing::warn!(
                     "component '{id}' is not used by any triggers in app '{app_name}'",
@@ -277,8 +397,10 @@ impl<Executor: TriggerExecutor> TriggerAppEngine<Executor> {
             app_name,
             app,
             hooks,
+            runtime_config,
             trigger_configs: trigger_configs.into_iter().map(|(_, v)| v).collect(),
             component_instance_pres,
+            component_fingerprints,
         })
     }
 
@@ -367,6 +489,36 @@ impl<Executor: TriggerExecutor> TriggerAppEngine<Executor> {
     }
 }
 
+/// Identifies the Wasm source of a component, so that a reload can tell
+/// whether the component needs to be pre-instantiated again.
+#[derive(PartialEq)]
+struct SourceFingerprint {
+    locked_source: String,
+    // Local file sources typically have no digest, so the file's size and
+    // modification time stand in for its content.
+    file_metadata: Option<(u64, SystemTime)>,
+}
+
+impl SourceFingerprint {
+    fn new(component: &AppComponent) -> Result<Self> {
+        let source = component.source();
+        let locked_source = serde_json::to_string(source)?;
+        let file_metadata = source
+            .content
+            .source
+            .as_deref()
+            .and_then(|url| url::Url::parse(url).ok())
+            .filter(|url| url.scheme() == "file")
+            .and_then(|url| url.to_file_path().ok())
+            .and_then(|path| std::fs::metadata(path).ok())
+            .and_then(|md| Some((md.len(), md.modified().ok()?)));
+        Ok(Self {
+            locked_source,
+            file_metadata,
+        })
+    }
+}
+
 /// TriggerHooks allows a Spin environment to hook into a TriggerAppEngine's
 /// configuration and execution processes.
 pub trait TriggerHooks: Send + Sync {
@@ -377,6 +529,14 @@ pub trait TriggerHooks: Send + Sync {
         Ok(())
     }
 
+    /// Called when a running App is reloaded. Hooks which hold state derived
+    /// from the App return fresh hooks, whose `app_loaded` is called with the
+    /// reloaded App; these hooks continue to serve the previous App. Hooks
+    /// which return `None` (the default) are shared with the reloaded App.
+    fn for_reload(&self) -> Option<Box<dyn TriggerHooks>> {
+        None
+    }
+
     /// Called while an AppComponent is being prepared for execution.
     /// Implementations may update the given StoreBuilder to change the
     /// environment of the instance to be executed.
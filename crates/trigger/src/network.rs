@@ -24,6 +24,8 @@ impl TriggerHooks for Network {
                                 store_builder.inherit_limited_network()
                             }
                             spin_outbound_networking::HostConfig::ToSelf => {}
+                            // Components of the app are not reachable over sockets
+                            spin_outbound_networking::HostConfig::AnyComponent => {}
                             spin_outbound_networking::HostConfig::List(hosts) => {
                                 for host in hosts {
                                     let Ok(ip_net) =
@@ -1,7 +1,10 @@
+pub mod engine;
 pub mod key_value;
 pub mod llm;
 pub mod sqlite;
 pub mod variables_provider;
+pub mod vector_store;
+pub mod volume;
 
 use std::{
     collections::HashMap,
@@ -16,10 +19,13 @@ use spin_common::ui::quoted_path;
 use spin_sqlite::Connection;
 
 use self::{
+    engine::WasmtimeOpts,
     key_value::{KeyValueStore, KeyValueStoreOpts},
-    llm::LlmComputeOpts,
+    llm::{LlmComputeOpts, LlmQuotaOpts},
     sqlite::SqliteDatabaseOpts,
     variables_provider::{VariablesProvider, VariablesProviderOpts},
+    vector_store::{VectorStore, VectorStoreOpts},
+    volume::VolumeOpts,
 };
 
 pub const DEFAULT_STATE_DIR: &str = ".spin";
@@ -45,6 +51,11 @@ impl RuntimeConfig {
         }
     }
 
+    /// Returns the JSON Schema for runtime config files.
+    pub fn json_schema() -> schemars::schema::RootSchema {
+        schemars::schema_for!(RuntimeConfigOpts)
+    }
+
     /// Load a runtime config file from the given path. Options specified in a
     /// later-loaded file take precedence over any earlier-loaded files.
     pub fn merge_config_file(&mut self, path: impl Into<PathBuf>) -> Result<()> {
@@ -131,6 +142,49 @@ impl RuntimeConfig {
         Ok(databases.into_iter())
     }
 
+    /// Return an iterator of named configured [`VectorStore`]s.
+    pub fn vector_stores(&self) -> Result<impl IntoIterator<Item = (String, VectorStore)>> {
+        let mut stores = HashMap::new();
+        // Insert explicitly-configured stores
+        for opts in self.opts_layers() {
+            for (name, store) in &opts.vector_stores {
+                if !stores.contains_key(name) {
+                    let store = store.build_store(opts)?;
+                    stores.insert(name.to_owned(), store);
+                }
+            }
+        }
+        // Upsert default store
+        if !stores.contains_key("default") {
+            let store = VectorStoreOpts::default_store_opts(self)
+                .build_store(&RuntimeConfigOpts::default())?;
+            stores.insert("default".into(), store);
+        }
+        Ok(stores.into_iter())
+    }
+
+    /// Return the explicitly-configured volumes, with their paths resolved.
+    pub fn volumes(&self) -> Result<HashMap<String, VolumeOpts>> {
+        let mut volumes = HashMap::new();
+        for opts in self.opts_layers() {
+            for (name, volume) in &opts.volumes {
+                if !volumes.contains_key(name) {
+                    let path = volume
+                        .path
+                        .as_deref()
+                        .map(|path| resolve_config_path(path, opts))
+                        .transpose()?;
+                    let volume = VolumeOpts {
+                        path,
+                        ..volume.clone()
+                    };
+                    volumes.insert(name.to_owned(), volume);
+                }
+            }
+        }
+        Ok(volumes)
+    }
+
     /// Set the state dir, overriding any other runtime config source.
     pub fn set_state_dir(&mut self, state_dir: impl Into<String>) {
         self.overrides.state_dir = Some(state_dir.into());
@@ -183,6 +237,16 @@ impl RuntimeConfig {
         }
     }
 
+    /// Return the LLM quotas if any are configured.
+    pub fn llm_quota(&self) -> Option<&LlmQuotaOpts> {
+        self.find_opt(|opts| &opts.llm_quota)
+    }
+
+    /// Return the Wasmtime engine options if any are configured.
+    pub fn wasmtime(&self) -> Option<&WasmtimeOpts> {
+        self.find_opt(|opts| &opts.wasmtime)
+    }
+
     /// Returns an iterator of RuntimeConfigOpts in order of decreasing precedence
     fn opts_layers(&self) -> impl Iterator<Item = &RuntimeConfigOpts> {
         std::iter::once(&self.overrides).chain(self.files.iter().rev())
@@ -194,7 +258,7 @@ impl RuntimeConfig {
     }
 }
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, schemars::JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct RuntimeConfigOpts {
     #[serde(default)]
@@ -206,6 +270,12 @@ pub struct RuntimeConfigOpts {
     #[serde(default)]
     pub llm_compute: Option<LlmComputeOpts>,
 
+    #[serde(default)]
+    pub llm_quota: Option<LlmQuotaOpts>,
+
+    #[serde(default)]
+    pub wasmtime: Option<WasmtimeOpts>,
+
     #[serde(rename = "variables_provider", alias = "config_provider", default)]
     pub variables_providers: Vec<VariablesProviderOpts>,
 
@@ -215,6 +285,12 @@ pub struct RuntimeConfigOpts {
     #[serde(rename = "sqlite_database", default)]
     pub sqlite_databases: HashMap<String, SqliteDatabaseOpts>,
 
+    #[serde(rename = "vector_store", default)]
+    pub vector_stores: HashMap<String, VectorStoreOpts>,
+
+    #[serde(rename = "volume", default)]
+    pub volumes: HashMap<String, VolumeOpts>,
+
     #[serde(skip)]
     pub file_path: Option<PathBuf>,
 }
@@ -407,6 +483,141 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn openai_llm_compute_from_file() -> Result<()> {
+        let mut config = RuntimeConfig::new(None);
+        assert!(matches!(config.llm_compute(), LlmComputeOpts::Spin));
+
+        merge_config_toml(
+            &mut config,
+            toml! {
+                [llm_compute]
+                type = "openai"
+                url = "http://localhost:8080"
+
+                [llm_compute.models]
+                "llama2-chat" = "llama-2-7b-chat"
+            },
+        );
+        assert!(
+            matches!(config.llm_compute(), LlmComputeOpts::OpenAi(_)),
+            "expected OpenAI compute",
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn llm_quota_from_file() -> Result<()> {
+        let mut config = RuntimeConfig::new(None);
+        assert!(config.llm_quota().is_none());
+
+        merge_config_toml(
+            &mut config,
+            toml! {
+                [llm_quota]
+                tokens_per_minute = 1000
+
+                [llm_quota.component.chatbot]
+                total_tokens = 50000
+            },
+        );
+        let quotas = spin_llm::LlmQuotas::from(config.llm_quota().unwrap());
+        assert_eq!(Some(1000), quotas.default.tokens_per_minute);
+        assert_eq!(Some(50000), quotas.components["chatbot"].total_tokens);
+
+        Ok(())
+    }
+
+    #[test]
+    fn vector_stores_from_file() -> Result<()> {
+        let mut config = RuntimeConfig::new(None);
+
+        // One default store
+        assert_eq!(config.vector_stores().unwrap().into_iter().count(), 1);
+
+        merge_config_toml(
+            &mut config,
+            toml! {
+                [vector_store.default]
+                type = "spin"
+
+                [vector_store.other]
+                type = "spin"
+            },
+        );
+        assert_eq!(config.vector_stores().unwrap().into_iter().count(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn volumes_from_file() -> Result<()> {
+        let mut config = RuntimeConfig::new(None);
+        assert!(config.volumes()?.is_empty());
+
+        merge_config_toml(
+            &mut config,
+            toml! {
+                [volume.cache]
+                path = "/var/spin/cache"
+                max_size = 1048576
+
+                [volume.scratch]
+                path = "scratch"
+            },
+        );
+        let volumes = config.volumes()?;
+        assert_eq!(Some(1048576), volumes["cache"].max_size);
+        assert_eq!(
+            Some(PathBuf::from("/var/spin/cache")),
+            volumes["cache"].path
+        );
+        // Relative paths are resolved against the runtime config file
+        assert!(volumes["scratch"].path.as_ref().unwrap().is_absolute());
+
+        Ok(())
+    }
+
+    #[test]
+    fn wasmtime_from_file() -> Result<()> {
+        let mut config = RuntimeConfig::new(None);
+        assert!(config.wasmtime().is_none());
+
+        merge_config_toml(
+            &mut config,
+            toml! {
+                [wasmtime]
+                total_component_instances = 100
+                opt_level = "speed_and_size"
+                epoch_tick_interval_ms = 5
+            },
+        );
+        let opts = config.wasmtime().unwrap();
+        assert_eq!(Some(100), opts.total_component_instances);
+        opts.apply(&mut spin_core::Config::default())?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn wasmtime_invalid_values_are_errors() {
+        let mut config = RuntimeConfig::new(None);
+        merge_config_toml(
+            &mut config,
+            toml! {
+                [wasmtime]
+                total_memories = 0
+            },
+        );
+        let err = config
+            .wasmtime()
+            .unwrap()
+            .apply(&mut spin_core::Config::default())
+            .unwrap_err();
+        assert!(err.to_string().contains("total_memories"), "{err}");
+    }
+
     fn merge_config_toml(config: &mut RuntimeConfig, value: toml::Value) {
         let data = toml::to_vec(&value).expect("encode toml");
         let mut file = NamedTempFile::new().expect("temp file");
@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use anyhow::{ensure, Result};
+use serde::Deserialize;
+use spin_core::wasmtime::OptLevel;
+
+// Holds deserialized options from the `[wasmtime]` runtime config section.
+// Unset options keep the engine defaults (including any `SPIN_WASMTIME_*`
+// environment variable overrides).
+#[derive(Clone, Debug, Default, Deserialize, schemars::JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct WasmtimeOpts {
+    /// The maximum number of component instances which may be live at once.
+    pub total_component_instances: Option<u32>,
+    /// The maximum size, in bytes, of a component instance's host data.
+    pub max_component_instance_size: Option<usize>,
+    pub max_core_instances_per_component: Option<u32>,
+    pub max_tables_per_component: Option<u32>,
+    pub table_elements: Option<u32>,
+    pub max_memories_per_component: Option<u32>,
+    pub total_memories: Option<u32>,
+    pub total_tables: Option<u32>,
+    /// The maximum number of 64KiB Wasm pages in a linear memory.
+    pub memory_pages: Option<u64>,
+    /// The number of bytes of a linear memory kept resident between instantiations.
+    pub linear_memory_keep_resident: Option<usize>,
+    /// The number of bytes of a table kept resident between instantiations.
+    pub table_keep_resident: Option<usize>,
+    pub opt_level: Option<WasmtimeOptLevel>,
+    /// The amount of fuel each instance may consume before trapping.
+    pub fuel: Option<u64>,
+    /// How often, in milliseconds, the engine epoch is incremented.
+    pub epoch_tick_interval_ms: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WasmtimeOptLevel {
+    None,
+    Speed,
+    SpeedAndSize,
+}
+
+impl From<WasmtimeOptLevel> for OptLevel {
+    fn from(level: WasmtimeOptLevel) -> Self {
+        match level {
+            WasmtimeOptLevel::None => OptLevel::None,
+            WasmtimeOptLevel::Speed => OptLevel::Speed,
+            WasmtimeOptLevel::SpeedAndSize => OptLevel::SpeedAndSize,
+        }
+    }
+}
+
+impl WasmtimeOpts {
+    /// Applies these options to the given engine config.
+    pub fn apply(&self, config: &mut spin_core::Config) -> Result<()> {
+        self.validate()?;
+
+        config.update_pooling(|pooling| {
+            macro_rules! set {
+                ($($field:ident),*) => {
+                    $(if let Some(value) = self.$field {
+                        pooling.$field = value;
+                    })*
+                };
+            }
+            set!(
+                total_component_instances,
+                max_component_instance_size,
+                max_core_instances_per_component,
+                max_tables_per_component,
+                table_elements,
+                max_memories_per_component,
+                total_memories,
+                total_tables,
+                memory_pages,
+                linear_memory_keep_resident,
+                table_keep_resident
+            );
+        });
+        if let Some(level) = self.opt_level {
+            config.cranelift_opt_level(level.into());
+        }
+        if let Some(fuel) = self.fuel {
+            config.fuel_per_store(fuel);
+        }
+        if let Some(ms) = self.epoch_tick_interval_ms {
+            config.epoch_tick_interval(Duration::from_millis(ms));
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<()> {
+        let nonzero = [
+            ("total_component_instances", self.total_component_instances),
+            (
+                "max_core_instances_per_component",
+                self.max_core_instances_per_component,
+            ),
+            ("total_memories", self.total_memories),
+            ("total_tables", self.total_tables),
+        ];
+        for (name, value) in nonzero {
+            ensure!(value != Some(0), "wasmtime.{name} must be greater than 0");
+        }
+        ensure!(
+            self.memory_pages
+                .map_or(true, |pages| pages <= MAX_MEMORY_PAGES),
+            "wasmtime.memory_pages must be at most {MAX_MEMORY_PAGES} (4GiB)"
+        );
+        ensure!(self.fuel != Some(0), "wasmtime.fuel must be greater than 0");
+        ensure!(
+            self.epoch_tick_interval_ms != Some(0),
+            "wasmtime.epoch_tick_interval_ms must be greater than 0"
+        );
+        Ok(())
+    }
+}
+
+// 32-bit linear memories can't address more than 4GiB.
+const MAX_MEMORY_PAGES: u64 = (4 << 30) / (64 << 10);
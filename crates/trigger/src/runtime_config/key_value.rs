@@ -56,7 +56,7 @@ pub async fn build_key_value_component(
 }
 
 // Holds deserialized options from a `[key_value_store.<name>]` runtime config section.
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum KeyValueStoreOpts {
     Spin(SpinKeyValueStoreOpts),
@@ -78,7 +78,7 @@ impl KeyValueStoreOpts {
     }
 }
 
-#[derive(Clone, Debug, Default, Deserialize)]
+#[derive(Clone, Debug, Default, Deserialize, schemars::JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct SpinKeyValueStoreOpts {
     pub path: Option<PathBuf>,
@@ -108,7 +108,7 @@ impl SpinKeyValueStoreOpts {
     }
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, schemars::JsonSchema)]
 pub struct RedisKeyValueStoreOpts {
     pub url: String,
 }
@@ -120,7 +120,7 @@ impl RedisKeyValueStoreOpts {
     }
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, schemars::JsonSchema)]
 pub struct AzureCosmosConfig {
     key: String,
     account: String,
@@ -1,7 +1,8 @@
 use async_trait::async_trait;
-use spin_llm::LlmEngine;
-use spin_llm_remote_http::RemoteHttpLlmEngine;
+use spin_llm::{LlmEngine, LlmQuota, LlmQuotas};
+use spin_llm_remote_http::{OpenAiApi, OpenAiLlmEngine, RemoteHttpLlmEngine};
 use spin_world::v2::llm as wasi_llm;
+use std::collections::HashMap;
 use url::Url;
 
 #[derive(Default)]
@@ -13,7 +14,7 @@ pub(crate) async fn build_component(
     runtime_config: &crate::RuntimeConfig,
     use_gpu: bool,
 ) -> spin_llm::LlmComponent {
-    match runtime_config.llm_compute() {
+    let component = match runtime_config.llm_compute() {
         #[cfg(feature = "llm")]
         LlmComputeOpts::Spin => {
             let path = runtime_config
@@ -34,22 +35,98 @@ pub(crate) async fn build_component(
                 RemoteHttpLlmEngine::new(config.url.to_owned(), config.auth_token.to_owned());
             spin_llm::LlmComponent::new(move || Box::new(engine.clone()))
         }
+        LlmComputeOpts::OpenAi(config) => {
+            tracing::log::info!("Using OpenAI-compatible compute for LLMs");
+            let engine = OpenAiLlmEngine::new(
+                config.url.to_owned(),
+                config.api_key.to_owned(),
+                config.api,
+                config.models.to_owned(),
+            );
+            spin_llm::LlmComponent::new(move || Box::new(engine.clone()))
+        }
+    };
+    match runtime_config.llm_quota() {
+        Some(quota) => component.with_quotas(quota.into()),
+        None => component,
     }
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum LlmComputeOpts {
     Spin,
     RemoteHttp(RemoteHttpComputeOpts),
+    #[serde(rename = "openai")]
+    OpenAi(OpenAiComputeOpts),
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct RemoteHttpComputeOpts {
     url: Url,
     auth_token: String,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct OpenAiComputeOpts {
+    /// The server's base URL, to which `/v1/...` endpoint paths are appended.
+    url: Url,
+    api_key: Option<String>,
+    #[serde(default)]
+    api: OpenAiApi,
+    /// Maps Spin model ids to the names the server knows them by.
+    #[serde(default)]
+    models: HashMap<String, String>,
+}
+
+/// Limits on components' use of LLMs. The limits at the top level apply to
+/// every component, unless overridden for a component in the `component`
+/// table.
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LlmQuotaOpts {
+    max_tokens_per_request: Option<u32>,
+    tokens_per_minute: Option<u64>,
+    total_tokens: Option<u64>,
+    #[serde(default)]
+    component: HashMap<String, LlmComponentQuotaOpts>,
+}
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct LlmComponentQuotaOpts {
+    max_tokens_per_request: Option<u32>,
+    tokens_per_minute: Option<u64>,
+    total_tokens: Option<u64>,
+}
+
+impl From<&LlmQuotaOpts> for LlmQuotas {
+    fn from(opts: &LlmQuotaOpts) -> Self {
+        LlmQuotas {
+            default: LlmQuota {
+                max_tokens_per_request: opts.max_tokens_per_request,
+                tokens_per_minute: opts.tokens_per_minute,
+                total_tokens: opts.total_tokens,
+            },
+            components: opts
+                .component
+                .iter()
+                .map(|(id, quota)| (id.clone(), quota.into()))
+                .collect(),
+        }
+    }
+}
+
+impl From<&LlmComponentQuotaOpts> for LlmQuota {
+    fn from(opts: &LlmComponentQuotaOpts) -> Self {
+        LlmQuota {
+            max_tokens_per_request: opts.max_tokens_per_request,
+            tokens_per_minute: opts.tokens_per_minute,
+            total_tokens: opts.total_tokens,
+        }
+    }
+}
+
 #[derive(Clone)]
 struct NoopLlmEngine;
 
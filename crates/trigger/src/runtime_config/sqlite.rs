@@ -76,7 +76,7 @@ async fn execute_statements(
 }
 
 // Holds deserialized options from a `[sqlite_database.<name>]` runtime config section.
-#[derive(Clone, Debug, serde::Deserialize)]
+#[derive(Clone, Debug, serde::Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum SqliteDatabaseOpts {
     Spin(SpinSqliteDatabaseOpts),
@@ -96,7 +96,7 @@ impl SqliteDatabaseOpts {
     }
 }
 
-#[derive(Clone, Debug, serde::Deserialize)]
+#[derive(Clone, Debug, serde::Deserialize, schemars::JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct SpinSqliteDatabaseOpts {
     pub path: Option<PathBuf>,
@@ -127,7 +127,7 @@ impl SpinSqliteDatabaseOpts {
     }
 }
 
-#[derive(Clone, Debug, serde::Deserialize)]
+#[derive(Clone, Debug, serde::Deserialize, schemars::JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct LibsqlOpts {
     url: String,
@@ -8,7 +8,7 @@ use super::RuntimeConfig;
 pub type VariablesProvider = Box<dyn spin_variables::Provider>;
 
 // Holds deserialized options from a `[[config_provider]]` runtime config section.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum VariablesProviderOpts {
     Env(EnvVariablesProviderOpts),
@@ -30,7 +30,7 @@ impl VariablesProviderOpts {
     }
 }
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, schemars::JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct EnvVariablesProviderOpts {
     /// A prefix to add to variable names when resolving from the environment.
@@ -62,7 +62,7 @@ impl EnvVariablesProviderOpts {
     }
 }
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, schemars::JsonSchema)]
 #[serde(deny_unknown_fields)]
 pub struct VaultVariablesProviderOpts {
     pub url: String,
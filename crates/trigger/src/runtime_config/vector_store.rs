@@ -0,0 +1,76 @@
+use std::{fs, path::PathBuf, sync::Arc};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use spin_sqlite_inproc::{InProcConnection, InProcDatabaseLocation};
+use spin_vector_store::{DelegatingStoreManager, Store, VectorStoreComponent};
+use spin_vector_store_sqlite::SqliteVectorStore;
+
+use crate::runtime_config::RuntimeConfig;
+
+use super::{resolve_config_path, RuntimeConfigOpts};
+
+const DEFAULT_SPIN_STORE_FILENAME: &str = "sqlite_vector_store.db";
+
+pub type VectorStore = Arc<dyn Store>;
+
+/// Builds a [`VectorStoreComponent`] from the given [`RuntimeConfig`].
+pub fn build_vector_store_component(
+    runtime_config: &RuntimeConfig,
+) -> Result<VectorStoreComponent> {
+    let stores = runtime_config
+        .vector_stores()
+        .context("Failed to build vector store component")?;
+    let manager = DelegatingStoreManager::new(stores);
+    Ok(VectorStoreComponent::new(Arc::new(manager)))
+}
+
+// Holds deserialized options from a `[vector_store.<name>]` runtime config section.
+#[derive(Clone, Debug, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum VectorStoreOpts {
+    Spin(SpinVectorStoreOpts),
+}
+
+impl VectorStoreOpts {
+    pub fn default_store_opts(runtime_config: &RuntimeConfig) -> Self {
+        Self::Spin(SpinVectorStoreOpts::default_store_opts(runtime_config))
+    }
+
+    pub fn build_store(&self, config_opts: &RuntimeConfigOpts) -> Result<VectorStore> {
+        match self {
+            Self::Spin(opts) => opts.build_store(config_opts),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, schemars::JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SpinVectorStoreOpts {
+    pub path: Option<PathBuf>,
+}
+
+impl SpinVectorStoreOpts {
+    fn default_store_opts(runtime_config: &RuntimeConfig) -> Self {
+        // If the state dir is set, build the default path
+        let path = runtime_config
+            .state_dir()
+            .map(|dir| dir.join(DEFAULT_SPIN_STORE_FILENAME));
+        Self { path }
+    }
+
+    fn build_store(&self, config_opts: &RuntimeConfigOpts) -> Result<VectorStore> {
+        let location = match self.path.as_ref() {
+            Some(path) => {
+                let path = resolve_config_path(path, config_opts)?;
+                // Create the store's parent directory if necessary
+                fs::create_dir_all(path.parent().unwrap())
+                    .context("Failed to create vector store")?;
+                InProcDatabaseLocation::Path(path)
+            }
+            None => InProcDatabaseLocation::InMemory,
+        };
+        let connection = InProcConnection::new(location)?;
+        Ok(Arc::new(SqliteVectorStore::new(Arc::new(connection))))
+    }
+}
@@ -0,0 +1,294 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use spin_app::{App, AppComponent, MetadataKey};
+use spin_common::ui::quoted_path;
+use spin_core::StoreBuilder;
+
+use crate::{runtime_config::RuntimeConfig, TriggerHooks};
+
+const VOLUMES_KEY: MetadataKey<HashMap<String, String>> = MetadataKey::new("volumes");
+
+const DEFAULT_VOLUMES_DIRNAME: &str = "volumes";
+
+/// The size limit of each component's copy of a volume, unless configured.
+const DEFAULT_MAX_SIZE: u64 = 100 * 1024 * 1024;
+
+/// How often the size of each component's copy of a volume is measured.
+const SIZE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+// Holds deserialized options from a `[volume.<name>]` runtime config section.
+#[derive(Clone, Debug, Default, Deserialize, schemars::JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct VolumeOpts {
+    /// The host directory backing the volume. Each component which mounts the
+    /// volume gets its own subdirectory. Defaults to `volumes/<name>` in the
+    /// state dir.
+    pub path: Option<PathBuf>,
+    /// The maximum size, in bytes, of each component's copy of the volume.
+    pub max_size: Option<u64>,
+}
+
+/// Mounts the volumes used by each component read-write into its instances.
+///
+/// Each component gets its own directory for each volume, so components can't
+/// see each other's data.
+///
+/// Size limits are soft. Directory sizes are measured periodically in the
+/// background, and a component's copy of a volume which had reached its limit
+/// when last measured is mounted read-only in new instances until space is
+/// freed on the host. Writes are not checked, so instances may
+/// exceed the limit by whatever they write between measurements.
+#[derive(Default)]
+pub struct VolumeMounter {
+    volumes: HashMap<String, VolumeOpts>,
+    default_root: Option<PathBuf>,
+    usage: Arc<Mutex<HashMap<PathBuf, VolumeUsage>>>,
+}
+
+/// The size of a component's copy of a volume, as last measured.
+#[derive(Clone, Copy)]
+struct VolumeUsage {
+    size: u64,
+    max_size: u64,
+}
+
+impl VolumeUsage {
+    fn is_full(&self) -> bool {
+        self.size >= self.max_size
+    }
+}
+
+impl VolumeMounter {
+    // Returns the host directory and size limit of a component's copy of a volume.
+    fn component_volume(&self, name: &str, component_id: &str) -> Result<(PathBuf, u64)> {
+        let opts = self.volumes.get(name);
+        let root = match opts.and_then(|opts| opts.path.clone()) {
+            Some(path) => path,
+            None => self
+                .default_root
+                .as_ref()
+                .map(|root| root.join(name))
+                .with_context(|| {
+                    format!(
+                        "Volume '{name}' has no backing directory. \
+                        Set a state dir, or a path in the `[volume.{name}]` runtime config."
+                    )
+                })?,
+        };
+        let max_size = opts
+            .and_then(|opts| opts.max_size)
+            .unwrap_or(DEFAULT_MAX_SIZE);
+        Ok((root.join(component_id), max_size))
+    }
+
+    // Whether the volume directory had reached its size limit when last measured.
+    fn is_full(&self, dir: &Path) -> bool {
+        let usage = self.usage.lock().unwrap();
+        usage.get(dir).is_some_and(VolumeUsage::is_full)
+    }
+}
+
+// Measures every volume directory in `usage`, logging any which can't be measured.
+fn measure_volumes(usage: &Mutex<HashMap<PathBuf, VolumeUsage>>) {
+    let dirs: Vec<PathBuf> = usage.lock().unwrap().keys().cloned().collect();
+    for dir in dirs {
+        // Walk the directory without holding the lock
+        match dir_size(&dir) {
+            Ok(size) => {
+                if let Some(usage) = usage.lock().unwrap().get_mut(&dir) {
+                    usage.size = size;
+                }
+            }
+            Err(e) => tracing::warn!("Failed to measure volume {}: {e:#}", quoted_path(&dir)),
+        }
+    }
+}
+
+// Measures volumes every `SIZE_CHECK_INTERVAL` until the mounter is dropped.
+fn spawn_size_checks(usage: Weak<Mutex<HashMap<PathBuf, VolumeUsage>>>) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(SIZE_CHECK_INTERVAL);
+        let Some(usage) = usage.upgrade() else {
+            return;
+        };
+        measure_volumes(&usage);
+    });
+}
+
+impl TriggerHooks for VolumeMounter {
+    fn app_loaded(&mut self, app: &App, runtime_config: &RuntimeConfig) -> Result<()> {
+        self.volumes = runtime_config.volumes()?;
+        self.default_root = runtime_config
+            .state_dir()
+            .map(|dir| dir.join(DEFAULT_VOLUMES_DIRNAME));
+
+        // Check every volume can be mounted before serving anything
+        let mut usage = HashMap::new();
+        for component in app.components() {
+            let volumes = component.get_metadata(VOLUMES_KEY)?.unwrap_or_default();
+            for name in volumes.keys() {
+                let (dir, max_size) = self.component_volume(name, component.id())?;
+                fs::create_dir_all(&dir).with_context(|| {
+                    format!("Failed to create volume directory {}", quoted_path(&dir))
+                })?;
+                let size = dir_size(&dir)
+                    .with_context(|| format!("Failed to measure volume {}", quoted_path(&dir)))?;
+                usage.insert(dir, VolumeUsage { size, max_size });
+            }
+        }
+
+        let has_volumes = !usage.is_empty();
+        *self.usage.lock().unwrap() = usage;
+        if has_volumes {
+            spawn_size_checks(Arc::downgrade(&self.usage));
+        }
+        Ok(())
+    }
+
+    fn for_reload(&self) -> Option<Box<dyn TriggerHooks>> {
+        Some(Box::new(Self::default()))
+    }
+
+    fn component_store_builder(
+        &self,
+        component: &AppComponent,
+        store_builder: &mut StoreBuilder,
+    ) -> Result<()> {
+        let volumes = component.get_metadata(VOLUMES_KEY)?.unwrap_or_default();
+        for (name, guest_path) in volumes {
+            let (dir, max_size) = self.component_volume(&name, component.id())?;
+            if self.is_full(&dir) {
+                tracing::warn!(
+                    "Volume '{name}' of component '{}' has reached its limit of {max_size} bytes; mounting it read-only",
+                    component.id()
+                );
+                store_builder.read_only_preopened_dir(&dir, guest_path.into())?;
+            } else {
+                store_builder.read_write_preopened_dir(&dir, guest_path.into())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Returns the total size of the files under `dir`, not following symlinks.
+fn dir_size(dir: &Path) -> Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else {
+            size += metadata.len();
+        }
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use spin_app::AppLoader;
+    use spin_core::{Engine, WasiVersion};
+
+    use super::*;
+    use crate::loader::TriggerLoader;
+
+    // Loads an app whose two components each mount the `data` volume.
+    fn load_test_app(dir: &Path) -> Result<(AppLoader, String)> {
+        let locked = serde_json::json!({
+            "spin_lock_version": 0,
+            "triggers": [],
+            "components": ["first", "second"].map(|id| serde_json::json!({
+                "id": id,
+                "metadata": { "volumes": { "data": "/data" } },
+                "source": {
+                    "content_type": "application/wasm",
+                    "source": format!("file:///{id}.wasm"),
+                },
+            })),
+        });
+        let locked_path = dir.join("spin.lock");
+        fs::write(&locked_path, locked.to_string())?;
+        let loader = AppLoader::new(TriggerLoader::new(dir, false));
+        let locked_url = url::Url::from_file_path(&locked_path).unwrap().to_string();
+        Ok((loader, locked_url))
+    }
+
+    fn runtime_config(state_dir: &Path, max_size: u64) -> Result<RuntimeConfig> {
+        let config_file = state_dir.join("runtime-config.toml");
+        fs::write(
+            &config_file,
+            format!("[volume.data]\nmax_size = {max_size}\n"),
+        )?;
+        let mut config = RuntimeConfig::new(None);
+        config.set_state_dir(state_dir.to_str().unwrap());
+        config.merge_config_file(config_file)?;
+        Ok(config)
+    }
+
+    fn mount(mounter: &VolumeMounter, app: &App, component_id: &str) -> Result<()> {
+        let engine = Engine::<()>::builder(&Default::default())?.build();
+        let mut store_builder = engine.store_builder(WasiVersion::Preview2);
+        mounter.component_store_builder(
+            &app.get_component(component_id).unwrap(),
+            &mut store_builder,
+        )
+    }
+
+    #[tokio::test]
+    async fn components_get_their_own_volume_directories() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (loader, locked_url) = load_test_app(dir.path())?;
+        let app = loader.load_app(locked_url).await?;
+        let mut mounter = VolumeMounter::default();
+        mounter.app_loaded(&app, &runtime_config(dir.path(), 1024)?)?;
+
+        let (first, _) = mounter.component_volume("data", "first")?;
+        let (second, _) = mounter.component_volume("data", "second")?;
+        assert_ne!(first, second);
+        assert!(first.is_dir());
+        assert!(second.is_dir());
+
+        fs::write(first.join("file.txt"), "first's data")?;
+        assert!(!second.join("file.txt").exists());
+        mount(&mounter, &app, "first")?;
+        mount(&mounter, &app, "second")?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn full_volumes_are_mounted_read_only() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (loader, locked_url) = load_test_app(dir.path())?;
+        let app = loader.load_app(locked_url).await?;
+        let mut mounter = VolumeMounter::default();
+        mounter.app_loaded(&app, &runtime_config(dir.path(), 10)?)?;
+
+        let (first, _) = mounter.component_volume("data", "first")?;
+        let (second, _) = mounter.component_volume("data", "second")?;
+        fs::create_dir(first.join("nested"))?;
+        fs::write(first.join("nested/file.txt"), "more than ten bytes")?;
+        // Sizes are only measured periodically
+        assert!(!mounter.is_full(&first));
+
+        measure_volumes(&mounter.usage);
+        assert!(mounter.is_full(&first));
+        assert!(!mounter.is_full(&second));
+        mount(&mounter, &app, "first")?;
+
+        // Freeing space makes the volume writable again
+        fs::remove_file(first.join("nested/file.txt"))?;
+        measure_volumes(&mounter.usage);
+        assert!(!mounter.is_full(&first));
+        Ok(())
+    }
+}
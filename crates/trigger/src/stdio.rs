@@ -106,6 +106,10 @@ impl TriggerHooks for StdioLoggingTriggerHooks {
         Ok(())
     }
 
+    fn for_reload(&self) -> Option<Box<dyn TriggerHooks>> {
+        Some(Box::new(Self::new(self.follow_components.clone())))
+    }
+
     fn component_store_builder(
         &self,
         component: &spin_app::AppComponent,
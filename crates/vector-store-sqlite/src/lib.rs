@@ -0,0 +1,407 @@
+use spin_core::async_trait;
+use spin_sqlite::Connection;
+use spin_vector_store::{DistanceMetric, Entry, Error, QueryMatch, Store};
+use spin_world::v2::sqlite::{self, Value};
+use std::sync::Arc;
+use tokio::sync::OnceCell;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS spin_vector_collections (
+        name       TEXT NOT NULL PRIMARY KEY,
+        dimensions INTEGER NOT NULL,
+        metric     TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS spin_vector_entries (
+        collection TEXT NOT NULL,
+        id         TEXT NOT NULL,
+        vector     BLOB NOT NULL,
+        metadata   TEXT NOT NULL,
+
+        PRIMARY KEY (collection, id)
+    );
+";
+
+/// The number of entries written or deleted by a single statement, keeping
+/// the number of statement parameters well within SQLite's limit.
+const ROWS_PER_STATEMENT: usize = 200;
+
+/// A vector store kept in a SQLite database. Vectors are stored as blobs, and
+/// queries compare the query vector with every vector in the collection.
+pub struct SqliteVectorStore {
+    connection: Arc<dyn Connection>,
+    schema_created: OnceCell<()>,
+}
+
+/// The properties a collection was created with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Collection {
+    dimensions: u32,
+    metric: DistanceMetric,
+}
+
+impl SqliteVectorStore {
+    pub fn new(connection: Arc<dyn Connection>) -> Self {
+        Self {
+            connection,
+            schema_created: OnceCell::new(),
+        }
+    }
+
+    async fn query_rows(
+        &self,
+        statement: &str,
+        parameters: Vec<Value>,
+    ) -> Result<Vec<Vec<Value>>, Error> {
+        self.schema_created
+            .get_or_try_init(|| async {
+                self.connection
+                    .execute_batch(SCHEMA)
+                    .await
+                    .map_err(|e| Error::Other(format!("{e:#}")))
+            })
+            .await?;
+        let result = self
+            .connection
+            .query(statement, parameters)
+            .await
+            .map_err(sqlite_error)?;
+        Ok(result.rows.into_iter().map(|row| row.values).collect())
+    }
+
+    async fn collection(&self, name: &str) -> Result<Collection, Error> {
+        let rows = self
+            .query_rows(
+                "SELECT dimensions, metric FROM spin_vector_collections WHERE name = ?",
+                vec![Value::Text(name.to_owned())],
+            )
+            .await?;
+        match rows.first().map(Vec::as_slice) {
+            Some([Value::Integer(dimensions), Value::Text(metric)]) => Ok(Collection {
+                dimensions: *dimensions as u32,
+                metric: parse_metric(metric)?,
+            }),
+            Some(_) => Err(Error::Other(format!(
+                "collection '{name}' has invalid properties"
+            ))),
+            None => Err(Error::NoSuchCollection),
+        }
+    }
+}
+
+#[async_trait]
+impl Store for SqliteVectorStore {
+    async fn create_collection(
+        &self,
+        name: &str,
+        dimensions: u32,
+        metric: DistanceMetric,
+    ) -> Result<(), Error> {
+        if dimensions == 0 {
+            return Err(Error::InvalidInput(
+                "collections must have at least one dimension".to_owned(),
+            ));
+        }
+        let requested = Collection { dimensions, metric };
+        match self.collection(name).await {
+            Ok(existing) if existing == requested => Ok(()),
+            Ok(existing) => Err(Error::InvalidInput(format!(
+                "collection '{name}' already exists with {} dimensions and {} metric",
+                existing.dimensions,
+                metric_name(existing.metric)
+            ))),
+            Err(Error::NoSuchCollection) => {
+                self.query_rows(
+                    "INSERT INTO spin_vector_collections (name, dimensions, metric) VALUES (?, ?, ?)",
+                    vec![
+                        Value::Text(name.to_owned()),
+                        Value::Integer(dimensions.into()),
+                        Value::Text(metric_name(metric).to_owned()),
+                    ],
+                )
+                .await?;
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn delete_collection(&self, name: &str) -> Result<(), Error> {
+        self.query_rows(
+            "DELETE FROM spin_vector_entries WHERE collection = ?",
+            vec![Value::Text(name.to_owned())],
+        )
+        .await?;
+        self.query_rows(
+            "DELETE FROM spin_vector_collections WHERE name = ?",
+            vec![Value::Text(name.to_owned())],
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn list_collections(&self) -> Result<Vec<String>, Error> {
+        let rows = self
+            .query_rows(
+                "SELECT name FROM spin_vector_collections ORDER BY name",
+                vec![],
+            )
+            .await?;
+        rows.into_iter()
+            .map(|row| match row.as_slice() {
+                [Value::Text(name)] => Ok(name.clone()),
+                _ => Err(Error::Other("invalid collection name".to_owned())),
+            })
+            .collect()
+    }
+
+    async fn upsert(&self, collection: &str, entries: Vec<Entry>) -> Result<(), Error> {
+        let Collection { dimensions, .. } = self.collection(collection).await?;
+        if let Some(entry) = entries
+            .iter()
+            .find(|e| e.vector.len() != dimensions as usize)
+        {
+            return Err(dimension_mismatch(
+                &entry.id,
+                entry.vector.len(),
+                dimensions,
+            ));
+        }
+
+        for chunk in entries.chunks(ROWS_PER_STATEMENT) {
+            let placeholders = vec!["(?, ?, ?, ?)"; chunk.len()].join(", ");
+            let statement = format!(
+                "INSERT INTO spin_vector_entries (collection, id, vector, metadata) VALUES {placeholders}
+                 ON CONFLICT(collection, id) DO UPDATE SET vector = excluded.vector, metadata = excluded.metadata"
+            );
+            let mut parameters = Vec::with_capacity(chunk.len() * 4);
+            for entry in chunk {
+                let metadata = serde_json::to_string(&entry.metadata)
+                    .map_err(|e| Error::Other(e.to_string()))?;
+                parameters.extend([
+                    Value::Text(collection.to_owned()),
+                    Value::Text(entry.id.clone()),
+                    Value::Blob(encode_vector(&entry.vector)),
+                    Value::Text(metadata),
+                ]);
+            }
+            self.query_rows(&statement, parameters).await?;
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, collection: &str, ids: Vec<String>) -> Result<(), Error> {
+        self.collection(collection).await?;
+        for chunk in ids.chunks(ROWS_PER_STATEMENT) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let statement = format!(
+                "DELETE FROM spin_vector_entries WHERE collection = ? AND id IN ({placeholders})"
+            );
+            let parameters = std::iter::once(collection.to_owned())
+                .chain(chunk.iter().cloned())
+                .map(Value::Text)
+                .collect();
+            self.query_rows(&statement, parameters).await?;
+        }
+        Ok(())
+    }
+
+    async fn query(
+        &self,
+        collection: &str,
+        vector: Vec<f32>,
+        k: u32,
+    ) -> Result<Vec<QueryMatch>, Error> {
+        let Collection { dimensions, metric } = self.collection(collection).await?;
+        if vector.len() != dimensions as usize {
+            return Err(dimension_mismatch("query", vector.len(), dimensions));
+        }
+
+        let rows = self
+            .query_rows(
+                "SELECT id, vector, metadata FROM spin_vector_entries WHERE collection = ?",
+                vec![Value::Text(collection.to_owned())],
+            )
+            .await?;
+        let matches = rows
+            .into_iter()
+            .map(|row| match row.as_slice() {
+                [Value::Text(id), Value::Blob(candidate), Value::Text(metadata)] => {
+                    let metadata =
+                        serde_json::from_str(metadata).map_err(|e| Error::Other(e.to_string()))?;
+                    Ok(QueryMatch {
+                        id: id.clone(),
+                        score: spin_vector_store::score(metric, &vector, &decode_vector(candidate)),
+                        metadata,
+                    })
+                }
+                _ => Err(Error::Other("invalid vector entry".to_owned())),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(spin_vector_store::nearest(metric, matches, k))
+    }
+}
+
+fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decode_vector(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+fn metric_name(metric: DistanceMetric) -> &'static str {
+    match metric {
+        DistanceMetric::Cosine => "cosine",
+        DistanceMetric::Euclidean => "euclidean",
+        DistanceMetric::DotProduct => "dot-product",
+    }
+}
+
+fn parse_metric(name: &str) -> Result<DistanceMetric, Error> {
+    match name {
+        "cosine" => Ok(DistanceMetric::Cosine),
+        "euclidean" => Ok(DistanceMetric::Euclidean),
+        "dot-product" => Ok(DistanceMetric::DotProduct),
+        _ => Err(Error::Other(format!("unknown distance metric '{name}'"))),
+    }
+}
+
+fn dimension_mismatch(id: &str, actual: usize, expected: u32) -> Error {
+    Error::InvalidInput(format!(
+        "vector '{id}' has {actual} dimensions but the collection has {expected}"
+    ))
+}
+
+fn sqlite_error(error: sqlite::Error) -> Error {
+    match error {
+        sqlite::Error::Io(message) => Error::Other(message),
+        other => Error::Other(format!("{other:?}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spin_sqlite_inproc::{InProcConnection, InProcDatabaseLocation};
+
+    fn store() -> SqliteVectorStore {
+        let connection = InProcConnection::new(InProcDatabaseLocation::InMemory).unwrap();
+        SqliteVectorStore::new(Arc::new(connection))
+    }
+
+    fn entry(id: &str, vector: &[f32]) -> Entry {
+        Entry {
+            id: id.to_owned(),
+            vector: vector.to_vec(),
+            metadata: vec![("name".to_owned(), id.to_owned())],
+        }
+    }
+
+    #[tokio::test]
+    async fn query_returns_nearest_first() {
+        let store = store();
+        store
+            .create_collection("docs", 2, DistanceMetric::Cosine)
+            .await
+            .unwrap();
+        store
+            .upsert(
+                "docs",
+                vec![
+                    entry("east", &[1.0, 0.0]),
+                    entry("north", &[0.0, 1.0]),
+                    entry("northeast", &[1.0, 1.0]),
+                ],
+            )
+            .await
+            .unwrap();
+
+        let matches = store.query("docs", vec![1.0, 0.1], 2).await.unwrap();
+        let ids: Vec<_> = matches.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(vec!["east", "northeast"], ids);
+        assert_eq!(
+            vec![("name".to_owned(), "east".to_owned())],
+            matches[0].metadata
+        );
+    }
+
+    #[tokio::test]
+    async fn euclidean_query_returns_closest_first() {
+        let store = store();
+        store
+            .create_collection("points", 1, DistanceMetric::Euclidean)
+            .await
+            .unwrap();
+        store
+            .upsert("points", vec![entry("far", &[10.0]), entry("near", &[2.0])])
+            .await
+            .unwrap();
+
+        let matches = store.query("points", vec![1.0], 1).await.unwrap();
+        assert_eq!("near", matches[0].id);
+        assert_eq!(1.0, matches[0].score);
+    }
+
+    #[tokio::test]
+    async fn upsert_replaces_and_delete_removes() {
+        let store = store();
+        store
+            .create_collection("docs", 2, DistanceMetric::DotProduct)
+            .await
+            .unwrap();
+        store
+            .upsert(
+                "docs",
+                vec![entry("a", &[1.0, 0.0]), entry("b", &[0.5, 0.0])],
+            )
+            .await
+            .unwrap();
+        store
+            .upsert("docs", vec![entry("a", &[0.1, 0.0])])
+            .await
+            .unwrap();
+
+        let matches = store.query("docs", vec![1.0, 0.0], 10).await.unwrap();
+        let ids: Vec<_> = matches.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(vec!["b", "a"], ids);
+
+        store.delete("docs", vec!["b".to_owned()]).await.unwrap();
+        let matches = store.query("docs", vec![1.0, 0.0], 10).await.unwrap();
+        assert_eq!(1, matches.len());
+    }
+
+    #[tokio::test]
+    async fn collections_are_validated() {
+        let store = store();
+        assert!(matches!(
+            store.query("missing", vec![1.0], 1).await,
+            Err(Error::NoSuchCollection)
+        ));
+
+        store
+            .create_collection("docs", 2, DistanceMetric::Cosine)
+            .await
+            .unwrap();
+        store
+            .create_collection("docs", 2, DistanceMetric::Cosine)
+            .await
+            .expect("re-creating an identical collection should succeed");
+        assert!(matches!(
+            store
+                .create_collection("docs", 3, DistanceMetric::Cosine)
+                .await,
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            store.upsert("docs", vec![entry("a", &[1.0])]).await,
+            Err(Error::InvalidInput(_))
+        ));
+        assert_eq!(vec!["docs"], store.list_collections().await.unwrap());
+
+        store.delete_collection("docs").await.unwrap();
+        assert!(store.list_collections().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{StoreManager, VectorStoreDispatch, VECTOR_STORES_KEY};
+use anyhow::anyhow;
+use spin_app::{AppComponent, DynamicHostComponent};
+use spin_core::HostComponent;
+use std::sync::Arc;
+
+pub struct VectorStoreComponent {
+    manager: Arc<dyn StoreManager>,
+}
+
+impl VectorStoreComponent {
+    pub fn new(manager: Arc<dyn StoreManager>) -> Self {
+        Self { manager }
+    }
+}
+
+impl HostComponent for VectorStoreComponent {
+    type Data = VectorStoreDispatch;
+
+    fn add_to_linker<T: Send>(
+        linker: &mut spin_core::Linker<T>,
+        get: impl Fn(&mut spin_core::Data<T>) -> &mut Self::Data + Send + Sync + Copy + 'static,
+    ) -> anyhow::Result<()> {
+        spin_world::v2_1::vector_store::add_to_linker(linker, get)
+    }
+
+    fn build_data(&self) -> Self::Data {
+        VectorStoreDispatch::new(self.manager.clone())
+    }
+}
+
+impl DynamicHostComponent for VectorStoreComponent {
+    fn update_data(&self, data: &mut Self::Data, component: &AppComponent) -> anyhow::Result<()> {
+        let vector_stores = component
+            .get_metadata(VECTOR_STORES_KEY)?
+            .unwrap_or_default();
+        data.init(vector_stores.into_iter().collect());
+        Ok(())
+    }
+
+    fn validate_app(&self, app: &spin_app::App) -> anyhow::Result<()> {
+        let mut errors = vec![];
+
+        for component in app.components() {
+            for allowed in component
+                .get_metadata(VECTOR_STORES_KEY)?
+                .unwrap_or_default()
+            {
+                if !self.manager.is_defined(&allowed) {
+                    let err = format!(
+                        "- Component {} uses vector store '{allowed}'",
+                        component.id()
+                    );
+                    errors.push(err);
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            let prologue = vec![
+                "One or more components use vector stores which are not defined.",
+                "Check the spelling, or pass a runtime configuration file that defines these stores.",
+                "Details:",
+            ];
+            let lines: Vec<_> = prologue
+                .into_iter()
+                .map(|s| s.to_owned())
+                .chain(errors)
+                .collect();
+            Err(anyhow!(lines.join("\n")))
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use anyhow::{Context, Result};
+use spin_app::MetadataKey;
+use spin_core::{async_trait, wasmtime::component::Resource};
+use spin_world::v2_1::vector_store;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use table::Table;
+
+mod host_component;
+
+pub use host_component::VectorStoreComponent;
+pub use vector_store::{DistanceMetric, Entry, Error, QueryMatch};
+
+pub const VECTOR_STORES_KEY: MetadataKey<Vec<String>> = MetadataKey::new("vector_stores");
+
+const DEFAULT_STORE_TABLE_CAPACITY: u32 = 256;
+
+/// A collection of named vector stores
+#[async_trait]
+pub trait StoreManager: Sync + Send {
+    async fn get(&self, name: &str) -> Result<Arc<dyn Store>, Error>;
+    fn is_defined(&self, store_name: &str) -> bool;
+}
+
+#[async_trait]
+pub trait Store: Sync + Send {
+    async fn create_collection(
+        &self,
+        name: &str,
+        dimensions: u32,
+        metric: DistanceMetric,
+    ) -> Result<(), Error>;
+    async fn delete_collection(&self, name: &str) -> Result<(), Error>;
+    async fn list_collections(&self) -> Result<Vec<String>, Error>;
+    async fn upsert(&self, collection: &str, entries: Vec<Entry>) -> Result<(), Error>;
+    async fn delete(&self, collection: &str, ids: Vec<String>) -> Result<(), Error>;
+    async fn query(
+        &self,
+        collection: &str,
+        vector: Vec<f32>,
+        k: u32,
+    ) -> Result<Vec<QueryMatch>, Error>;
+}
+
+/// A [`StoreManager`] which serves stores from a map of store names.
+pub struct DelegatingStoreManager {
+    stores: HashMap<String, Arc<dyn Store>>,
+}
+
+impl DelegatingStoreManager {
+    pub fn new(stores: impl IntoIterator<Item = (String, Arc<dyn Store>)>) -> Self {
+        Self {
+            stores: stores.into_iter().collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl StoreManager for DelegatingStoreManager {
+    async fn get(&self, name: &str) -> Result<Arc<dyn Store>, Error> {
+        self.stores.get(name).cloned().ok_or(Error::NoSuchStore)
+    }
+
+    fn is_defined(&self, store_name: &str) -> bool {
+        self.stores.contains_key(store_name)
+    }
+}
+
+/// Scores `candidate` against `query` under `metric`. Both vectors must have
+/// the same number of dimensions.
+pub fn score(metric: DistanceMetric, query: &[f32], candidate: &[f32]) -> f32 {
+    match metric {
+        DistanceMetric::Cosine => {
+            let dot = dot_product(query, candidate);
+            let norms = dot_product(query, query).sqrt() * dot_product(candidate, candidate).sqrt();
+            if norms == 0.0 {
+                0.0
+            } else {
+                dot / norms
+            }
+        }
+        DistanceMetric::Euclidean => query
+            .iter()
+            .zip(candidate)
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f32>()
+            .sqrt(),
+        DistanceMetric::DotProduct => dot_product(query, candidate),
+    }
+}
+
+/// Sorts matches most similar first under `metric`, and keeps the first `k`.
+pub fn nearest(metric: DistanceMetric, mut matches: Vec<QueryMatch>, k: u32) -> Vec<QueryMatch> {
+    matches.sort_by(|a, b| match metric {
+        DistanceMetric::Euclidean => a.score.total_cmp(&b.score),
+        DistanceMetric::Cosine | DistanceMetric::DotProduct => b.score.total_cmp(&a.score),
+    });
+    matches.truncate(k as usize);
+    matches
+}
+
+fn dot_product(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+pub struct VectorStoreDispatch {
+    allowed_stores: HashSet<String>,
+    manager: Arc<dyn StoreManager>,
+    stores: Table<Arc<dyn Store>>,
+}
+
+impl VectorStoreDispatch {
+    pub fn new(manager: Arc<dyn StoreManager>) -> Self {
+        Self {
+            allowed_stores: HashSet::new(),
+            manager,
+            stores: Table::new(DEFAULT_STORE_TABLE_CAPACITY),
+        }
+    }
+
+    pub fn init(&mut self, allowed_stores: HashSet<String>) {
+        self.allowed_stores = allowed_stores;
+    }
+
+    fn get_store(&self, store: Resource<vector_store::Store>) -> Result<&Arc<dyn Store>> {
+        self.stores.get(store.rep()).context("invalid vector store")
+    }
+}
+
+#[async_trait]
+impl vector_store::Host for VectorStoreDispatch {}
+
+#[async_trait]
+impl vector_store::HostStore for VectorStoreDispatch {
+    async fn open(
+        &mut self,
+        label: String,
+    ) -> Result<Result<Resource<vector_store::Store>, Error>> {
+        Ok(async {
+            if !self.allowed_stores.contains(&label) {
+                return Err(Error::AccessDenied);
+            }
+            let store = self.manager.get(&label).await?;
+            let rep = self
+                .stores
+                .push(store)
+                .map_err(|()| Error::Other("too many vector stores opened".to_string()))?;
+            Ok(Resource::new_own(rep))
+        }
+        .await)
+    }
+
+    async fn create_collection(
+        &mut self,
+        store: Resource<vector_store::Store>,
+        name: String,
+        dimensions: u32,
+        metric: DistanceMetric,
+    ) -> Result<Result<(), Error>> {
+        let store = self.get_store(store)?;
+        Ok(store.create_collection(&name, dimensions, metric).await)
+    }
+
+    async fn delete_collection(
+        &mut self,
+        store: Resource<vector_store::Store>,
+        name: String,
+    ) -> Result<Result<(), Error>> {
+        let store = self.get_store(store)?;
+        Ok(store.delete_collection(&name).await)
+    }
+
+    async fn list_collections(
+        &mut self,
+        store: Resource<vector_store::Store>,
+    ) -> Result<Result<Vec<String>, Error>> {
+        let store = self.get_store(store)?;
+        Ok(store.list_collections().await)
+    }
+
+    async fn upsert(
+        &mut self,
+        store: Resource<vector_store::Store>,
+        collection: String,
+        entries: Vec<Entry>,
+    ) -> Result<Result<(), Error>> {
+        let store = self.get_store(store)?;
+        Ok(store.upsert(&collection, entries).await)
+    }
+
+    async fn delete(
+        &mut self,
+        store: Resource<vector_store::Store>,
+        collection: String,
+        ids: Vec<String>,
+    ) -> Result<Result<(), Error>> {
+        let store = self.get_store(store)?;
+        Ok(store.delete(&collection, ids).await)
+    }
+
+    async fn query(
+        &mut self,
+        store: Resource<vector_store::Store>,
+        collection: String,
+        vector: Vec<f32>,
+        k: u32,
+    ) -> Result<Result<Vec<QueryMatch>, Error>> {
+        let store = self.get_store(store)?;
+        Ok(store.query(&collection, vector, k).await)
+    }
+
+    fn drop(&mut self, store: Resource<vector_store::Store>) -> Result<()> {
+        self.stores.remove(store.rep());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use vector_store::HostStore;
+
+    /// A store which keeps upserted entries in memory and scores them with
+    /// the cosine metric.
+    #[derive(Default)]
+    struct MemoryStore {
+        entries: Mutex<HashMap<String, Vec<Entry>>>,
+    }
+
+    #[async_trait]
+    impl Store for MemoryStore {
+        async fn create_collection(
+            &self,
+            name: &str,
+            _dimensions: u32,
+            _metric: DistanceMetric,
+        ) -> Result<(), Error> {
+            self.entries
+                .lock()
+                .unwrap()
+                .entry(name.to_owned())
+                .or_default();
+            Ok(())
+        }
+
+        async fn delete_collection(&self, name: &str) -> Result<(), Error> {
+            self.entries.lock().unwrap().remove(name);
+            Ok(())
+        }
+
+        async fn list_collections(&self) -> Result<Vec<String>, Error> {
+            Ok(self.entries.lock().unwrap().keys().cloned().collect())
+        }
+
+        async fn upsert(&self, collection: &str, entries: Vec<Entry>) -> Result<(), Error> {
+            let mut all_entries = self.entries.lock().unwrap();
+            let existing = all_entries
+                .get_mut(collection)
+                .ok_or(Error::NoSuchCollection)?;
+            existing.extend(entries);
+            Ok(())
+        }
+
+        async fn delete(&self, collection: &str, ids: Vec<String>) -> Result<(), Error> {
+            let mut all_entries = self.entries.lock().unwrap();
+            let existing = all_entries
+                .get_mut(collection)
+                .ok_or(Error::NoSuchCollection)?;
+            existing.retain(|entry| !ids.contains(&entry.id));
+            Ok(())
+        }
+
+        async fn query(
+            &self,
+            collection: &str,
+            vector: Vec<f32>,
+            k: u32,
+        ) -> Result<Vec<QueryMatch>, Error> {
+            let all_entries = self.entries.lock().unwrap();
+            let existing = all_entries.get(collection).ok_or(Error::NoSuchCollection)?;
+            let matches = existing
+                .iter()
+                .map(|entry| QueryMatch {
+                    id: entry.id.clone(),
+                    score: score(DistanceMetric::Cosine, &vector, &entry.vector),
+                    metadata: entry.metadata.clone(),
+                })
+                .collect();
+            Ok(nearest(DistanceMetric::Cosine, matches, k))
+        }
+    }
+
+    fn dispatch(allowed_stores: &[&str]) -> VectorStoreDispatch {
+        let manager = DelegatingStoreManager::new([
+            (
+                "default".to_owned(),
+                Arc::new(MemoryStore::default()) as Arc<dyn Store>,
+            ),
+            (
+                "secret".to_owned(),
+                Arc::new(MemoryStore::default()) as Arc<dyn Store>,
+            ),
+        ]);
+        let mut dispatch = VectorStoreDispatch::new(Arc::new(manager));
+        dispatch.init(allowed_stores.iter().map(|s| s.to_string()).collect());
+        dispatch
+    }
+
+    fn entry(id: &str, vector: &[f32]) -> Entry {
+        Entry {
+            id: id.to_owned(),
+            vector: vector.to_vec(),
+            metadata: vec![("name".to_owned(), id.to_owned())],
+        }
+    }
+
+    #[tokio::test]
+    async fn dispatches_to_opened_store() -> Result<()> {
+        let mut dispatch = dispatch(&["default"]);
+        let store = dispatch.open("default".into()).await?.unwrap();
+        let rep = store.rep();
+        let handle = || Resource::<vector_store::Store>::new_own(rep);
+
+        dispatch
+            .create_collection(handle(), "docs".into(), 2, DistanceMetric::Cosine)
+            .await?
+            .unwrap();
+        dispatch
+            .upsert(
+                handle(),
+                "docs".into(),
+                vec![entry("x", &[1.0, 0.0]), entry("y", &[0.0, 1.0])],
+            )
+            .await?
+            .unwrap();
+        assert_eq!(
+            vec!["docs".to_owned()],
+            dispatch.list_collections(handle()).await?.unwrap()
+        );
+
+        let matches = dispatch
+            .query(handle(), "docs".into(), vec![0.1, 1.0], 1)
+            .await?
+            .unwrap();
+        assert_eq!(1, matches.len());
+        assert_eq!("y", matches[0].id);
+        assert_eq!(
+            vec![("name".to_owned(), "y".to_owned())],
+            matches[0].metadata
+        );
+
+        dispatch
+            .delete(handle(), "docs".into(), vec!["y".into()])
+            .await?
+            .unwrap();
+        let matches = dispatch
+            .query(handle(), "docs".into(), vec![0.1, 1.0], 5)
+            .await?
+            .unwrap();
+        assert_eq!(
+            vec!["x"],
+            matches.iter().map(|m| m.id.as_str()).collect::<Vec<_>>()
+        );
+
+        assert!(matches!(
+            dispatch.upsert(handle(), "missing".into(), vec![]).await?,
+            Err(Error::NoSuchCollection)
+        ));
+
+        // Closed stores cannot be used
+        HostStore::drop(&mut dispatch, handle())?;
+        dispatch.list_collections(handle()).await.unwrap_err();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn denies_access_to_unlisted_stores() -> Result<()> {
+        let mut dispatch = dispatch(&["default", "undefined"]);
+        // The store exists, but the component is not allowed to use it
+        assert!(matches!(
+            dispatch.open("secret".into()).await?,
+            Err(Error::AccessDenied)
+        ));
+        // The component is allowed to use the store, but it does not exist
+        assert!(matches!(
+            dispatch.open("undefined".into()).await?,
+            Err(Error::NoSuchStore)
+        ));
+        assert!(dispatch.open("default".into()).await?.is_ok());
+        Ok(())
+    }
+}
@@ -5,7 +5,7 @@ wasmtime::component::bindgen!({
     package fermyon:runtime;
     world host {
         include fermyon:spin/host;
-        include fermyon:spin/platform@2.0.0;
+        include fermyon:spin/platform@2.1.0;
     }
     "#,
     path: "../../wit",
@@ -14,5 +14,6 @@ wasmtime::component::bindgen!({
 
 pub use fermyon::spin as v1;
 pub use fermyon::spin2_0_0 as v2;
+pub use fermyon::spin2_1_0 as v2_1;
 
 mod conversions;
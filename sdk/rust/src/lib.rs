@@ -11,6 +11,9 @@ pub mod sqlite;
 /// Large Language Model APIs
 pub mod llm;
 
+/// Vector storage and nearest-neighbour search.
+pub mod vector_store;
+
 /// Exports the procedural macros for writing handlers for Spin components.
 pub use spin_macro::*;
 
@@ -22,10 +25,11 @@ pub mod wit {
     #![allow(missing_docs)]
 
     wit_bindgen::generate!({
-        world: "platform",
+        world: "fermyon:spin/platform@2.1.0",
         path: "./wit",
     });
     pub use fermyon::spin2_0_0 as v2;
+    pub use fermyon::spin2_1_0 as v2_1;
 }
 
 /// Needed by the export macro
@@ -2,6 +2,7 @@ pub use crate::wit::v2::llm::{
     self, EmbeddingsResult, EmbeddingsUsage, Error, InferencingParams, InferencingResult,
     InferencingUsage,
 };
+pub use crate::wit::v2_1::llm_streaming::{self, InferencingStream};
 
 /// The model use for inferencing
 #[allow(missing_docs)]
@@ -50,6 +51,30 @@ pub fn infer_with_options(
     llm::infer(&model.to_string(), prompt, Some(options))
 }
 
+/// Perform inferencing using the provided model and prompt, returning the
+/// generated text in chunks as it becomes available
+pub fn infer_stream(model: InferencingModel, prompt: &str) -> Result<InferencingStream, Error> {
+    llm_streaming::infer_stream(&model.to_string(), prompt, None)
+}
+
+/// Perform inferencing using the provided model, prompt, and options,
+/// returning the generated text in chunks as it becomes available
+pub fn infer_stream_with_options(
+    model: InferencingModel,
+    prompt: &str,
+    options: InferencingParams,
+) -> Result<InferencingStream, Error> {
+    llm_streaming::infer_stream(&model.to_string(), prompt, Some(options))
+}
+
+impl Iterator for InferencingStream {
+    type Item = Result<String, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_chunk().transpose()
+    }
+}
+
 /// Model used for generating embeddings
 #[allow(missing_docs)]
 #[derive(Debug, Clone, Copy)]
@@ -0,0 +1,46 @@
+//! Spin vector storage
+//!
+//! This module provides an interface for storing vectors, such as embeddings generated with the
+//! [`llm`](crate::llm) module, and finding those nearest to a query vector. Collections are
+//! created with a fixed number of dimensions and a distance metric used to compare vectors.
+
+use super::wit::v2_1::vector_store;
+
+#[doc(inline)]
+pub use vector_store::{DistanceMetric, Entry, Error, QueryMatch, Store};
+
+impl Store {
+    /// Open the default store.
+    ///
+    /// This is equivalent to `Store::open("default")`.
+    pub fn open_default() -> Result<Self, Error> {
+        Self::open("default")
+    }
+}
+
+impl Entry {
+    /// Create an entry with no metadata.
+    pub fn new(id: impl Into<String>, vector: Vec<f32>) -> Self {
+        Self {
+            id: id.into(),
+            vector,
+            metadata: vec![],
+        }
+    }
+
+    /// Add a metadata item to the entry.
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.push((key.into(), value.into()));
+        self
+    }
+}
+
+impl QueryMatch {
+    /// Get the value of a metadata item, if the entry has it.
+    pub fn metadata_value(&self, key: &str) -> Option<&str> {
+        self.metadata
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
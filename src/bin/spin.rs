@@ -8,10 +8,12 @@ use spin_cli::commands::{
     cloud::{DeployCommand, LoginCommand},
     doctor::DoctorCommand,
     external::execute_external_subcommand,
+    manifest::ManifestCommands,
     new::{AddCommand, NewCommand},
     plugins::PluginCommands,
     registry::RegistryCommands,
     templates::TemplateCommands,
+    test::TestCommand,
     up::UpCommand,
     watch::WatchCommand,
 };
@@ -128,6 +130,8 @@ enum SpinApp {
     Login(LoginCommand),
     #[clap(subcommand, alias = "oci")]
     Registry(RegistryCommands),
+    #[clap(subcommand)]
+    Manifest(ManifestCommands),
     #[clap(alias = "b")]
     Build(BuildCommand),
     #[clap(subcommand, alias = "plugin")]
@@ -139,6 +143,7 @@ enum SpinApp {
     #[clap(alias = "w")]
     Watch(WatchCommand),
     Doctor(DoctorCommand),
+    Test(TestCommand),
 }
 
 #[derive(Subcommand)]
@@ -160,6 +165,7 @@ impl SpinApp {
             Self::Deploy(cmd) => cmd.run(SpinApp::command()).await,
             Self::Login(cmd) => cmd.run(SpinApp::command()).await,
             Self::Registry(cmd) => cmd.run().await,
+            Self::Manifest(cmd) => cmd.run().await,
             Self::Build(cmd) => cmd.run().await,
             Self::Trigger(TriggerCommands::Http(cmd)) => cmd.run().await,
             Self::Trigger(TriggerCommands::Redis(cmd)) => cmd.run().await,
@@ -168,6 +174,7 @@ impl SpinApp {
             Self::External(cmd) => execute_external_subcommand(cmd, app).await,
             Self::Watch(cmd) => cmd.run().await,
             Self::Doctor(cmd) => cmd.run().await,
+            Self::Test(cmd) => cmd.run().await,
         }
     }
 }
@@ -8,6 +8,8 @@ pub mod cloud;
 pub mod doctor;
 /// Commands for external subcommands (i.e. plugins)
 pub mod external;
+/// Commands for working with application manifests.
+pub mod manifest;
 /// Command for creating a new application.
 pub mod new;
 /// Command for adding a plugin to Spin
@@ -16,6 +18,8 @@ pub mod plugins;
 pub mod registry;
 /// Commands for working with templates.
 pub mod templates;
+/// Command for running an application's tests.
+pub mod test;
 /// Commands for starting the runtime.
 pub mod up;
 /// Command for rebuilding and restarting a Spin app when files change.
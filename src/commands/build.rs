@@ -3,7 +3,7 @@ use std::{ffi::OsString, path::PathBuf};
 use anyhow::Result;
 use clap::Parser;
 
-use crate::opts::{APP_MANIFEST_FILE_OPT, BUILD_UP_OPT, DEFAULT_MANIFEST_FILE};
+use crate::opts::{APP_MANIFEST_FILE_OPT, BUILD_UP_OPT, DEFAULT_MANIFEST_FILE, PROFILE_ENV};
 
 use super::up::UpCommand;
 
@@ -27,6 +27,14 @@ pub struct BuildCommand {
     #[clap(short = 'c', long, multiple = true)]
     pub component_id: Vec<String>,
 
+    /// Build components even if they are up to date with their watched files.
+    #[clap(long)]
+    pub force: bool,
+
+    /// The manifest profile to apply when building (and running) the application.
+    #[clap(long = "profile", env = PROFILE_ENV)]
+    pub profile: Option<String>,
+
     /// Run the application after building.
     #[clap(name = BUILD_UP_OPT, short = 'u', long = "up")]
     pub up: bool,
@@ -38,7 +46,14 @@ pub struct BuildCommand {
 impl BuildCommand {
     pub async fn run(self) -> Result<()> {
         let manifest_file = spin_common::paths::resolve_manifest_file_path(&self.app_source)?;
-        spin_build::build(&manifest_file, &self.component_id).await?;
+        if let Some(app_dir) = manifest_file.parent() {
+            super::plugins::warn_unsatisfied_plugin_requirements(app_dir);
+        }
+        let options = spin_build::BuildOptions {
+            force: self.force,
+            profile: self.profile.clone(),
+        };
+        spin_build::build_with_options(&manifest_file, &self.component_id, &options).await?;
 
         if self.up {
             let mut cmd = UpCommand::parse_from(
@@ -49,6 +64,9 @@ impl BuildCommand {
                 .chain(self.up_args),
             );
             cmd.file_source = Some(manifest_file);
+            if cmd.profile.is_none() {
+                cmd.profile = self.profile;
+            }
             cmd.run().await
         } else {
             Ok(())
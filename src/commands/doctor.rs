@@ -1,9 +1,10 @@
 use std::{fmt::Debug, path::PathBuf};
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{bail, Result};
+use clap::{Parser, ValueEnum};
 use dialoguer::{console::Emoji, Confirm, Select};
-use spin_doctor::{Diagnosis, DryRunNotSupported, PatientDiagnosis};
+use serde::Serialize;
+use spin_doctor::{Diagnosis, DryRunNotSupported, PatientApp, PatientDiagnosis, Treatment};
 
 use crate::opts::{APP_MANIFEST_FILE_OPT, DEFAULT_MANIFEST_FILE};
 
@@ -21,66 +22,191 @@ pub struct DoctorCommand {
         default_value = DEFAULT_MANIFEST_FILE
     )]
     pub app_source: PathBuf,
+
+    /// The format in which to report problems. The JSON format never prompts
+    /// for treatments.
+    #[clap(value_enum, long = "format", default_value = "human")]
+    pub format: DoctorFormat,
+
+    /// Apply all safe treatments (those which only edit the application's
+    /// files) without prompting. Other treatments are not applied.
+    #[clap(long = "fix", takes_value = false)]
+    pub fix: bool,
+}
+
+#[derive(ValueEnum, Clone, Debug, PartialEq, Eq)]
+pub enum DoctorFormat {
+    Human,
+    Json,
 }
 
 impl DoctorCommand {
     pub async fn run(self) -> Result<()> {
         let manifest_file = spin_common::paths::resolve_manifest_file_path(&self.app_source)?;
+        let human = self.format == DoctorFormat::Human;
 
-        println!("{icon}The Spin Doctor is in.", icon = Emoji("📟 ", ""));
-        println!(
-            "{icon}Checking {}...",
-            manifest_file.display(),
-            icon = Emoji("🩺 ", "")
-        );
+        if human {
+            println!("{icon}The Spin Doctor is in.", icon = Emoji("📟 ", ""));
+            println!(
+                "{icon}Checking {}...",
+                manifest_file.display(),
+                icon = Emoji("🩺 ", "")
+            );
+        }
 
         let mut checkup = spin_doctor::Checkup::new(manifest_file)?;
-        let mut has_problems = false;
+        let mut reports = vec![];
         while let Some(PatientDiagnosis { diagnosis, patient }) = checkup.next_diagnosis().await? {
-            show_diagnosis(&*diagnosis);
-            has_problems = true;
-
-            if let Some(treatment) = diagnosis.treatment() {
-                let dry_run = match treatment.dry_run(patient).await {
-                    Ok(desc) => Some(desc),
-                    Err(err) => {
-                        if !err.is::<DryRunNotSupported>() {
-                            show_error("Treatment dry run failed: ", err);
-                            return Ok(());
-                        }
-                        None
-                    }
-                };
-
-                let should_treat =
-                    prompt_treatment(treatment.summary(), dry_run).unwrap_or_else(|err| {
-                        show_error("Prompt error: ", err);
-                        false
-                    });
-
-                if should_treat {
-                    match treatment.treat(patient).await {
-                        Ok(()) => {
-                            println!("{icon}Treatment applied!", icon = Emoji("❤  ", ""));
-                        }
-                        Err(err) => {
-                            match err.downcast_ref::<spin_doctor::StopDiagnosing>() {
-                                Some(stop) => {
-                                    terminal::einfo!("Action required!", "{}", stop.message());
-                                    return Ok(());
-                                }
-                                None => show_error("Treatment failed: ", err),
-                            };
-                        }
-                    }
-                }
+            if human {
+                show_diagnosis(&*diagnosis);
+            }
+
+            let outcome = match diagnosis.treatment() {
+                Some(treatment) => self.offer_treatment(treatment, patient).await,
+                None => Outcome::Untreated,
+            };
+            reports.push(DiagnosisReport::new(
+                &*diagnosis,
+                matches!(outcome, Outcome::Treated),
+            ));
+            if let Outcome::Stop(message) = outcome {
+                terminal::einfo!("Action required!", "{}", message);
+                break;
             }
         }
-        if has_problems {
-            println!("{icon}No problems found.", icon = Emoji("❤  ", ""));
+
+        match self.format {
+            DoctorFormat::Human if reports.is_empty() => {
+                println!("{icon}No problems found.", icon = Emoji("❤  ", ""));
+            }
+            DoctorFormat::Human => (),
+            DoctorFormat::Json => println!("{}", serde_json::to_string_pretty(&reports)?),
+        }
+
+        // Warnings (such as deprecations) don't stop the app from running, so
+        // only unresolved critical problems are a failure.
+        let unresolved = reports
+            .iter()
+            .filter(|report| !report.treated && report.severity == Severity::Error)
+            .count();
+        if unresolved > 0 {
+            bail!(
+                "{unresolved} critical problem{} remain{} unresolved",
+                if unresolved == 1 { "" } else { "s" },
+                if unresolved == 1 { "s" } else { "" },
+            );
         }
         Ok(())
     }
+
+    /// Applies the treatment if the options allow it, prompting the user if
+    /// running interactively.
+    async fn offer_treatment(
+        &self,
+        treatment: &dyn Treatment,
+        patient: &mut PatientApp,
+    ) -> Outcome {
+        if self.fix {
+            if treatment.is_safe() {
+                return self.apply_treatment(treatment, patient).await;
+            }
+            if self.format == DoctorFormat::Human {
+                println!(
+                    "{icon}Not applying \"{}\"; run `spin doctor` without --fix to apply it",
+                    treatment.summary(),
+                    icon = Emoji("🩹 ", "")
+                );
+            }
+            return Outcome::Untreated;
+        }
+        if self.format != DoctorFormat::Human {
+            return Outcome::Untreated;
+        }
+
+        let dry_run = match treatment.dry_run(patient).await {
+            Ok(desc) => Some(desc),
+            Err(err) => {
+                if !err.is::<DryRunNotSupported>() {
+                    show_error("Treatment dry run failed: ", err);
+                    return Outcome::Untreated;
+                }
+                None
+            }
+        };
+
+        let should_treat = prompt_treatment(treatment.summary(), dry_run).unwrap_or_else(|err| {
+            show_error("Prompt error: ", err);
+            false
+        });
+        if !should_treat {
+            return Outcome::Untreated;
+        }
+        self.apply_treatment(treatment, patient).await
+    }
+
+    async fn apply_treatment(
+        &self,
+        treatment: &dyn Treatment,
+        patient: &mut PatientApp,
+    ) -> Outcome {
+        match treatment.treat(patient).await {
+            Ok(()) => {
+                if self.format == DoctorFormat::Human {
+                    println!("{icon}Treatment applied!", icon = Emoji("❤  ", ""));
+                }
+                Outcome::Treated
+            }
+            Err(err) => match err.downcast_ref::<spin_doctor::StopDiagnosing>() {
+                Some(stop) => Outcome::Stop(stop.message().to_owned()),
+                None => {
+                    show_error("Treatment failed: ", err);
+                    Outcome::Untreated
+                }
+            },
+        }
+    }
+}
+
+/// The result of offering a treatment for a diagnosis.
+enum Outcome {
+    Treated,
+    Untreated,
+    /// The user must intervene before diagnosing can continue.
+    Stop(String),
+}
+
+#[derive(Serialize)]
+struct DiagnosisReport {
+    description: String,
+    severity: Severity,
+    treatable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    treatment: Option<String>,
+    treated: bool,
+}
+
+impl DiagnosisReport {
+    fn new(diagnosis: &dyn Diagnosis, treated: bool) -> Self {
+        let treatment = diagnosis.treatment().map(|treatment| treatment.summary());
+        Self {
+            description: diagnosis.description(),
+            severity: if diagnosis.is_critical() {
+                Severity::Error
+            } else {
+                Severity::Warning
+            },
+            treatable: treatment.is_some(),
+            treatment,
+            treated,
+        }
+    }
+}
+
+#[derive(Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Error,
+    Warning,
 }
 
 fn show_diagnosis(diagnosis: &dyn Diagnosis) {
@@ -127,5 +253,5 @@ fn prompt_treatment(summary: String, dry_run: Option<String>) -> Result<bool> {
 
 fn show_error(prefix: &str, err: impl Debug) {
     let icon = Emoji("⁉️ ", "");
-    println!("{icon}{prefix}{err:?}");
+    eprintln!("{icon}{prefix}{err:?}");
 }
@@ -0,0 +1,118 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use spin_common::ui::quoted_path;
+use spin_manifest::{validate::validate_manifest, ManifestVersion};
+use spin_trigger::RuntimeConfig;
+
+use crate::opts::{APP_MANIFEST_FILE_OPT, DEFAULT_MANIFEST_FILE};
+
+/// Commands for working with application manifests.
+#[derive(Subcommand, Debug)]
+pub enum ManifestCommands {
+    /// Check an application manifest for problems, reporting all that are found.
+    Validate(Validate),
+    /// Print the JSON Schema for application manifests or runtime config files.
+    Schema(Schema),
+}
+
+impl ManifestCommands {
+    pub async fn run(self) -> Result<()> {
+        match self {
+            ManifestCommands::Validate(cmd) => cmd.run().await,
+            ManifestCommands::Schema(cmd) => cmd.run().await,
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+pub struct Validate {
+    /// The application to validate. This may be a manifest (spin.toml) file, or a
+    /// directory containing a spin.toml file.
+    /// If omitted, it defaults to "spin.toml".
+    #[clap(
+        name = APP_MANIFEST_FILE_OPT,
+        short = 'f',
+        long = "from",
+        alias = "file",
+        default_value = DEFAULT_MANIFEST_FILE
+    )]
+    pub app_source: PathBuf,
+
+    /// A runtime config file to validate as well as the manifest.
+    #[clap(long = "runtime-config-file")]
+    pub runtime_config_file: Option<PathBuf>,
+}
+
+impl Validate {
+    pub async fn run(self) -> Result<()> {
+        let manifest_path = spin_common::paths::resolve_manifest_file_path(&self.app_source)?;
+        let manifest = read_file(&manifest_path).await?;
+
+        let diagnostics = validate_manifest(&manifest);
+        for diagnostic in &diagnostics {
+            terminal::error!("{}", diagnostic.message);
+            print_location(&manifest_path, diagnostic.line_col(&manifest));
+        }
+        let mut problems = diagnostics.len();
+
+        if let Some(runtime_config_path) = &self.runtime_config_file {
+            if let Err(err) = RuntimeConfig::default().merge_config_file(runtime_config_path) {
+                terminal::error!("{err:#}");
+                problems += 1;
+            }
+        }
+
+        if problems > 0 {
+            bail!(
+                "Found {problems} problem{}",
+                if problems == 1 { "" } else { "s" }
+            );
+        }
+        println!("{} is valid", quoted_path(&manifest_path));
+        Ok(())
+    }
+}
+
+#[derive(Parser, Debug)]
+pub struct Schema {
+    /// The kind of file to print the schema for.
+    #[clap(value_enum, default_value = "manifest")]
+    pub kind: SchemaKind,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum SchemaKind {
+    /// An application manifest (spin.toml), current version.
+    Manifest,
+    /// An application manifest (spin.toml), version 1.
+    ManifestV1,
+    /// A runtime config file.
+    RuntimeConfig,
+}
+
+impl Schema {
+    pub async fn run(self) -> Result<()> {
+        let schema = match self.kind {
+            SchemaKind::Manifest => ManifestVersion::V2.json_schema(),
+            SchemaKind::ManifestV1 => ManifestVersion::V1.json_schema(),
+            SchemaKind::RuntimeConfig => RuntimeConfig::json_schema(),
+        };
+        println!("{}", serde_json::to_string_pretty(&schema)?);
+        Ok(())
+    }
+}
+
+async fn read_file(path: &Path) -> Result<String> {
+    tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read {}", quoted_path(path)))
+}
+
+fn print_location(path: &Path, line_col: Option<(usize, usize)>) {
+    match line_col {
+        Some((line, column)) => eprintln!("  --> {}:{line}:{column}", path.display()),
+        None => eprintln!("  --> {}", path.display()),
+    }
+}
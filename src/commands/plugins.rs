@@ -4,11 +4,14 @@
 use anyhow::{anyhow, Context, Result};
 use clap::{Parser, Subcommand};
 use semver::Version;
+use spin_common::ui::quoted_path;
 use spin_plugins::{
     error::Error,
-    lookup::{fetch_plugins_repo, plugins_repo_url, PluginLookup},
+    lookup::{fetch_plugins_repo, PluginLookup},
     manager::{self, InstallAction, ManifestLocation, PluginManager},
     manifest::{PluginManifest, PluginPackage},
+    requirements::{ProjectRequirements, RequirementProblem, PROJECT_REQUIREMENTS_FILE_NAME},
+    PluginStore,
 };
 use std::path::{Path, PathBuf};
 use tracing::log;
@@ -38,7 +41,7 @@ pub enum PluginCommands {
     /// Upgrade one or all plugins.
     Upgrade(Upgrade),
 
-    /// Fetch the latest Spin plugins from the spin-plugins repository.
+    /// Fetch the latest Spin plugins from the configured plugin catalogues.
     Update,
 }
 
@@ -58,12 +61,13 @@ impl PluginCommands {
 /// Install plugins from remote source
 #[derive(Parser, Debug)]
 pub struct Install {
-    /// Name of Spin plugin.
+    /// Name of Spin plugin. Use `name@catalogue` to install from a specific
+    /// plugin catalogue rather than the first catalogue that lists the plugin.
     #[clap(
         name = PLUGIN_NAME_OPT,
         conflicts_with = PLUGIN_REMOTE_PLUGIN_MANIFEST_OPT,
         conflicts_with = PLUGIN_LOCAL_PLUGIN_MANIFEST_OPT,
-        required_unless_present_any = [PLUGIN_REMOTE_PLUGIN_MANIFEST_OPT, PLUGIN_LOCAL_PLUGIN_MANIFEST_OPT],
+        required_unless_present_any = [PLUGIN_REMOTE_PLUGIN_MANIFEST_OPT, PLUGIN_LOCAL_PLUGIN_MANIFEST_OPT, PLUGIN_PROJECT_OPT],
     )]
     pub name: Option<String>,
 
@@ -105,10 +109,27 @@ pub struct Install {
         requires(PLUGIN_NAME_OPT)
     )]
     pub version: Option<Version>,
+
+    /// Install the plugins required by a project, as listed in the
+    /// spin-plugins.toml file alongside its application manifest. The value may
+    /// be a manifest file or a directory containing spin.toml; if omitted, the
+    /// current directory and its parents are searched for spin.toml. Each plugin
+    /// is installed at the highest available version which satisfies the
+    /// requirement.
+    #[clap(
+        name = PLUGIN_PROJECT_OPT,
+        long = "project",
+        value_name = "APP",
+        conflicts_with_all = &[PLUGIN_NAME_OPT, PLUGIN_REMOTE_PLUGIN_MANIFEST_OPT, PLUGIN_LOCAL_PLUGIN_MANIFEST_OPT],
+    )]
+    pub project: Option<Option<PathBuf>>,
 }
 
 impl Install {
     pub async fn run(&self) -> Result<()> {
+        if let Some(project) = &self.project {
+            return self.install_project_requirements(project.as_deref()).await;
+        }
         let manifest_location = match (&self.local_manifest_src, &self.remote_manifest_src, &self.name) {
             (Some(path), None, None) => ManifestLocation::Local(path.to_path_buf()),
             (None, Some(url), None) => ManifestLocation::Remote(url.clone()),
@@ -136,6 +157,52 @@ impl Install {
         .await?;
         Ok(())
     }
+
+    async fn install_project_requirements(&self, project: Option<&Path>) -> Result<()> {
+        let manifest_file = match project {
+            Some(path) => spin_common::paths::resolve_manifest_file_path(path)?,
+            None => {
+                let current_dir = std::env::current_dir()?;
+                spin_common::paths::search_upwards_for_manifest(&current_dir).ok_or_else(|| {
+                    anyhow!(
+                        "No {DEFAULT_MANIFEST_FILE} found in the current directory or its parents"
+                    )
+                })?
+            }
+        };
+        let project_dir = spin_common::paths::parent_dir(&manifest_file)?;
+        let requirements = ProjectRequirements::load(&project_dir)?.ok_or_else(|| {
+            anyhow!(
+                "No {PROJECT_REQUIREMENTS_FILE_NAME} file found in {}",
+                quoted_path(&project_dir)
+            )
+        })?;
+        let manager = PluginManager::try_default()?;
+        let plugins_dir = manager.store().get_plugins_directory();
+
+        let unsatisfied = requirements.unsatisfied(manager.store());
+        if unsatisfied.is_empty() {
+            println!("All plugins required by the project are installed");
+            return Ok(());
+        }
+        for (requirement, _) in unsatisfied {
+            let (manifest, lookup) = requirement.resolve(plugins_dir, SPIN_VERSION).await?;
+            let manifest_location = ManifestLocation::PluginsRepository(lookup);
+            // The project's requirement may exclude the installed version
+            // because it is too new, so permit downgrades.
+            let downgrade = true;
+            try_install(
+                &manifest,
+                &manager,
+                self.yes_to_all,
+                self.override_compatibility_check,
+                downgrade,
+                &manifest_location,
+            )
+            .await?;
+        }
+        Ok(())
+    }
 }
 
 /// Uninstalls specified plugin.
@@ -278,10 +345,9 @@ impl Upgrade {
         // Getting only eligible plugins to upgrade
         for installed_plugin in installed_in_catalogue {
             let manager = PluginManager::try_default()?;
-            let manifest_location = ManifestLocation::PluginsRepository(PluginLookup::new(
-                &installed_plugin.name,
-                None,
-            ));
+            let manifest_location = ManifestLocation::PluginsRepository(
+                manager.upgrade_lookup(&installed_plugin.name, None),
+            );
 
             // Attempt to get the manifest to check eligibility to upgrade
             if let Ok(manifest) = manager
@@ -333,10 +399,9 @@ impl Upgrade {
         // Upgrade plugins selected
         for (installed_plugin, manifest) in plugins_selected {
             let manager = PluginManager::try_default()?;
-            let manifest_location = ManifestLocation::PluginsRepository(PluginLookup::new(
-                &installed_plugin.name,
-                None,
-            ));
+            let manifest_location = ManifestLocation::PluginsRepository(
+                manager.upgrade_lookup(&installed_plugin.name, None),
+            );
 
             try_install(&manifest, &manager, true, false, false, &manifest_location).await?;
         }
@@ -356,7 +421,7 @@ impl Upgrade {
                 .ok_or_else(|| anyhow!("Cannot convert path {} stem to str", path.display()))?
                 .to_string();
             let manifest_location =
-                ManifestLocation::PluginsRepository(PluginLookup::new(&name, None));
+                ManifestLocation::PluginsRepository(manager.upgrade_lookup(&name, None));
             let manifest = match manager
                 .get_manifest(
                     &manifest_location,
@@ -390,12 +455,14 @@ impl Upgrade {
         let manifest_location = match (self.local_manifest_src, self.remote_manifest_src) {
             (Some(path), None) => ManifestLocation::Local(path),
             (None, Some(url)) => ManifestLocation::Remote(url),
-            _ => ManifestLocation::PluginsRepository(PluginLookup::new(
-                self.name
-                    .as_ref()
-                    .context("plugin name is required for upgrades")?,
-                self.version,
-            )),
+            _ => ManifestLocation::PluginsRepository(
+                manager.upgrade_lookup(
+                    self.name
+                        .as_ref()
+                        .context("plugin name is required for upgrades")?,
+                    self.version,
+                ),
+            ),
         };
         let manifest = manager
             .get_manifest(
@@ -630,11 +697,55 @@ pub(crate) async fn update_silent() -> Result<()> {
     }
 
     let plugins_dir = manager.store().get_plugins_directory();
-    let url = plugins_repo_url()?;
-    fetch_plugins_repo(&url, plugins_dir, true).await?;
+    fetch_plugins_repo(plugins_dir, true).await?;
     Ok(())
 }
 
+/// Warns if the installed plugins do not satisfy the requirements of the
+/// project in the given directory. This never fails, as an unsatisfied
+/// requirement should not stop the user from working.
+pub(crate) fn warn_unsatisfied_plugin_requirements(project_dir: &Path) {
+    let requirements = match ProjectRequirements::load(project_dir) {
+        Ok(Some(requirements)) => requirements,
+        Ok(None) => return,
+        Err(e) => {
+            terminal::warn!("{e:#}");
+            return;
+        }
+    };
+    let Ok(store) = PluginStore::try_default() else {
+        return;
+    };
+
+    let unsatisfied = requirements.unsatisfied(&store);
+    for (requirement, problem) in &unsatisfied {
+        match problem {
+            RequirementProblem::NotInstalled => terminal::warn!(
+                "This project requires plugin '{}' ({}), but it is not installed.",
+                requirement.name,
+                requirement.version
+            ),
+            RequirementProblem::WrongVersion { installed } => terminal::warn!(
+                "This project requires plugin '{}' {}, but version {installed} is installed.",
+                requirement.name,
+                requirement.version
+            ),
+            RequirementProblem::WrongCatalogue { installed } => terminal::warn!(
+                "This project requires plugin '{}' from catalogue '{}', but it was installed {}.",
+                requirement.name,
+                requirement.catalogue.as_deref().unwrap_or_default(),
+                match installed {
+                    Some(catalogue) => format!("from catalogue '{catalogue}'"),
+                    None => "from a manifest file or URL".to_owned(),
+                }
+            ),
+        }
+    }
+    if !unsatisfied.is_empty() {
+        eprintln!("Run `spin plugins install --project` to install the required plugins.");
+    }
+}
+
 fn continue_to_install(
     manifest: &PluginManifest,
     package: &PluginPackage,
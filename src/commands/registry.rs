@@ -1,9 +1,15 @@
 use crate::opts::*;
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use clap::{Parser, Subcommand};
-use indicatif::{ProgressBar, ProgressStyle};
-use spin_oci::Client;
-use std::{io::Read, path::PathBuf, time::Duration};
+use indicatif::{HumanBytes, HumanDuration, ProgressBar, ProgressStyle};
+use spin_loader::cache::Cache;
+use spin_oci::cache::PrunePolicy;
+use spin_oci::{Client, SigningKey, VerificationPolicy};
+use std::{
+    io::Read,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
 
 /// Commands for working with OCI registries to distribute applications.
 #[derive(Subcommand, Debug)]
@@ -14,6 +20,9 @@ pub enum RegistryCommands {
     Pull(Pull),
     /// Log in to a registry.
     Login(Login),
+    /// Inspect and prune the cache of apps pulled from registries.
+    #[clap(subcommand)]
+    Cache(CacheCommands),
 }
 
 impl RegistryCommands {
@@ -22,6 +31,7 @@ impl RegistryCommands {
             RegistryCommands::Push(cmd) => cmd.run().await,
             RegistryCommands::Pull(cmd) => cmd.run().await,
             RegistryCommands::Login(cmd) => cmd.run().await,
+            RegistryCommands::Cache(cmd) => cmd.run().await,
         }
     }
 }
@@ -53,6 +63,19 @@ pub struct Push {
     #[clap(long, takes_value = false, env = ALWAYS_BUILD_ENV)]
     pub build: bool,
 
+    /// The manifest profile to apply to the application before pushing it.
+    #[clap(long = "profile", env = PROFILE_ENV)]
+    pub profile: Option<String>,
+
+    /// Sign the pushed application with the private key given by `--key`.
+    #[clap(long, takes_value = false, requires = "key")]
+    pub sign: bool,
+
+    /// The unencrypted PKCS#8 PEM ECDSA P-256 private key to sign the
+    /// application with.
+    #[clap(long, env = SIGNING_KEY_ENV)]
+    pub key: Option<PathBuf>,
+
     /// Reference in the registry of the Spin application.
     /// This is a string whose format is defined by the registry standard, and generally consists of <registry>/<username>/<application-name>:<version>. E.g. ghcr.io/ogghead/spin-test-app:0.1.0
     #[clap()]
@@ -63,19 +86,38 @@ impl Push {
     pub async fn run(self) -> Result<()> {
         let app_file = spin_common::paths::resolve_manifest_file_path(&self.app_source)?;
         if self.build {
-            spin_build::build(&app_file, &[]).await?;
+            let options = spin_build::BuildOptions {
+                profile: self.profile.clone(),
+                ..Default::default()
+            };
+            spin_build::build_with_options(&app_file, &[], &options).await?;
         }
 
+        // Load the key up front so that a bad key doesn't leave an unsigned push behind.
+        let signing_key = match (self.sign, &self.key) {
+            (true, Some(key)) => Some(SigningKey::from_pem_file(key)?),
+            _ => None,
+        };
+
         let mut client = spin_oci::Client::new(self.insecure, None).await?;
 
         let _spinner = create_dotted_spinner(2000, "Pushing app to the Registry".to_owned());
 
-        let digest = client.push(&app_file, &self.reference).await?;
-        match digest {
+        let digest = client
+            .push(&app_file, &self.reference, self.profile.as_deref())
+            .await?;
+        match &digest {
             Some(digest) => println!("Pushed with digest {digest}"),
             None => println!("Pushed; the registry did not return the digest"),
         };
 
+        if let Some(key) = signing_key {
+            let digest =
+                digest.context("Cannot sign the app: the registry did not return its digest")?;
+            client.sign(&self.reference, &digest, &key).await?;
+            println!("Signed {}@{digest}", self.reference);
+        }
+
         Ok(())
     }
 }
@@ -91,6 +133,9 @@ pub struct Pull {
     )]
     pub insecure: bool,
 
+    #[clap(flatten)]
+    pub verification: VerificationOptions,
+
     /// Reference in the registry of the published Spin application.
     /// This is a string whose format is defined by the registry standard, and generally consists of <registry>/<username>/<application-name>:<version>. E.g. ghcr.io/ogghead/spin-test-app:0.1.0
     #[clap()]
@@ -101,6 +146,7 @@ impl Pull {
     /// Pull a Spin application from an OCI registry
     pub async fn run(self) -> Result<()> {
         let mut client = spin_oci::Client::new(self.insecure, None).await?;
+        client.set_verification_policy(self.verification.policy()?);
 
         let _spinner = create_dotted_spinner(2000, "Pulling app from the Registry".to_owned());
 
@@ -110,6 +156,28 @@ impl Pull {
     }
 }
 
+/// Options for verifying the signatures of apps pulled from a registry.
+#[derive(Parser, Clone, Debug, Default)]
+pub struct VerificationOptions {
+    /// A PEM ECDSA P-256 public key (such as a cosign.pub file) which the
+    /// application may be signed with. May be given multiple times. These are
+    /// in addition to any keys in the registry trust policy file.
+    #[clap(long = "verify-key", multiple_occurrences = true)]
+    pub verify_keys: Vec<PathBuf>,
+
+    /// Refuse to run the application unless it is signed with a trusted key.
+    #[clap(long = "require-signature", takes_value = false)]
+    pub require_signature: bool,
+}
+
+impl VerificationOptions {
+    /// The verification policy from the registry trust policy file, extended
+    /// with these options.
+    pub fn policy(&self) -> Result<VerificationPolicy> {
+        VerificationPolicy::load_default(&self.verify_keys, self.require_signature)
+    }
+}
+
 #[derive(Parser, Debug)]
 pub struct Login {
     /// Username for the registry
@@ -179,6 +247,132 @@ impl Login {
     }
 }
 
+/// Commands for the cache of apps pulled from registries.
+#[derive(Subcommand, Debug)]
+pub enum CacheCommands {
+    /// List the cached apps and the space they use.
+    List,
+    /// Remove cached apps according to age or size limits, and any content
+    /// not used by a remaining app.
+    Prune(PruneCache),
+    /// Remove everything from the cache.
+    Clear,
+}
+
+impl CacheCommands {
+    pub async fn run(self) -> Result<()> {
+        let cache = Cache::new(None).await?;
+        match self {
+            CacheCommands::List => list_cache(&cache),
+            CacheCommands::Prune(cmd) => cmd.run(&cache),
+            CacheCommands::Clear => {
+                let size = spin_oci::cache::total_size(&cache)?;
+                cache.clear().await?;
+                println!("Cleared the registry cache, freeing {}", HumanBytes(size));
+                Ok(())
+            }
+        }
+    }
+}
+
+fn list_cache(cache: &Cache) -> Result<()> {
+    let apps = spin_oci::cache::cached_apps(cache)?;
+    if apps.is_empty() {
+        println!("No apps in the registry cache");
+    }
+    let now = SystemTime::now();
+    for app in apps.iter().rev() {
+        let age = now.duration_since(app.last_pulled).unwrap_or_default();
+        println!(
+            "{} ({}, pulled {} ago)",
+            app.reference,
+            HumanBytes(app.size()),
+            HumanDuration(age)
+        );
+    }
+    println!(
+        "Total cache size: {}",
+        HumanBytes(spin_oci::cache::total_size(cache)?)
+    );
+    Ok(())
+}
+
+#[derive(Parser, Debug)]
+pub struct PruneCache {
+    /// Remove apps last pulled longer ago than this (e.g. 12h, 30d).
+    #[clap(long = "older-than", parse(try_from_str = parse_age))]
+    pub older_than: Option<Duration>,
+
+    /// Remove the least recently pulled apps until the cache is no larger
+    /// than this (e.g. 500MB, 2GiB).
+    #[clap(long = "max-size", parse(try_from_str = parse_size))]
+    pub max_size: Option<u64>,
+}
+
+impl PruneCache {
+    fn run(self, cache: &Cache) -> Result<()> {
+        let policy = PrunePolicy {
+            older_than: self.older_than,
+            max_size: self.max_size,
+        };
+        let summary = spin_oci::cache::prune(cache, &policy)?;
+        for reference in &summary.removed_apps {
+            println!("Removed {reference}");
+        }
+        println!(
+            "Removed {} apps and {} files, freeing {}",
+            summary.removed_apps.len(),
+            summary.removed_blobs,
+            HumanBytes(summary.freed)
+        );
+        Ok(())
+    }
+}
+
+fn parse_age(age: &str) -> Result<Duration> {
+    let (number, unit) = split_number(age);
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("invalid age '{age}'"))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => {
+            return Err(anyhow!(
+                "invalid age '{age}': expected a unit of s, m, h, d or w"
+            ))
+        }
+    };
+    Ok(Duration::from_secs(number * seconds))
+}
+
+fn parse_size(size: &str) -> Result<u64> {
+    let (number, unit) = split_number(size);
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("invalid size '{size}'"))?;
+    let multiplier: u64 = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1000,
+        "mb" => 1000 * 1000,
+        "gb" => 1000 * 1000 * 1000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        _ => return Err(anyhow!("invalid size '{size}': unknown unit '{unit}'")),
+    };
+    Ok(number * multiplier)
+}
+
+fn split_number(s: &str) -> (&str, &str) {
+    let s = s.trim();
+    let unit_start = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    (&s[..unit_start], s[unit_start..].trim())
+}
+
 fn create_dotted_spinner(interval: u64, message: String) -> ProgressBar {
     let spinner = ProgressBar::new_spinner();
     spinner.enable_steady_tick(Duration::from_millis(interval));
@@ -190,3 +384,25 @@ fn create_dotted_spinner(interval: u64, message: String) -> ProgressBar {
     spinner.set_message(message);
     spinner
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ages() {
+        assert_eq!(Duration::from_secs(90), parse_age("90s").unwrap());
+        assert_eq!(Duration::from_secs(12 * 3600), parse_age("12h").unwrap());
+        assert_eq!(Duration::from_secs(30 * 86400), parse_age("30d").unwrap());
+        parse_age("30").unwrap_err();
+        parse_age("d").unwrap_err();
+    }
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(1024, parse_size("1024").unwrap());
+        assert_eq!(500_000_000, parse_size("500MB").unwrap());
+        assert_eq!(2 << 30, parse_size("2 GiB").unwrap());
+        parse_size("2TB").unwrap_err();
+    }
+}
@@ -15,6 +15,8 @@ use crate::build_info::*;
 
 const INSTALL_FROM_DIR_OPT: &str = "FROM_DIR";
 const INSTALL_FROM_GIT_OPT: &str = "FROM_GIT";
+const INSTALL_FROM_OCI_OPT: &str = "FROM_OCI";
+const INSTALL_FROM_TAR_OPT: &str = "FROM_TAR";
 const UPGRADE_ONLY: &str = "GIT_URL";
 
 const DEFAULT_TEMPLATES_INSTALL_PROMPT: &str =
@@ -24,7 +26,8 @@ const DEFAULT_TEMPLATE_REPO: &str = "https://github.com/fermyon/spin";
 /// Commands for working with WebAssembly component templates.
 #[derive(Subcommand, Debug)]
 pub enum TemplateCommands {
-    /// Install templates from a Git repository or local directory.
+    /// Install templates from a Git repository, local directory, OCI registry
+    /// or tar archive.
     ///
     /// The files of the templates are copied to the local template store: a
     /// directory in your data or home directory.
@@ -54,7 +57,8 @@ impl TemplateCommands {
     }
 }
 
-/// Install templates from a Git repository or local directory.
+/// Install templates from a Git repository, local directory, OCI registry or
+/// tar archive.
 #[derive(Parser, Debug)]
 pub struct Install {
     /// The URL of the templates git repository.
@@ -62,7 +66,7 @@ pub struct Install {
     #[clap(
         name = INSTALL_FROM_GIT_OPT,
         long = "git",
-        conflicts_with = INSTALL_FROM_DIR_OPT,
+        conflicts_with_all = &[INSTALL_FROM_DIR_OPT, INSTALL_FROM_OCI_OPT, INSTALL_FROM_TAR_OPT],
     )]
     pub git: Option<String>,
 
@@ -74,10 +78,34 @@ pub struct Install {
     #[clap(
         name = INSTALL_FROM_DIR_OPT,
         long = "dir",
-        conflicts_with = INSTALL_FROM_GIT_OPT,
+        conflicts_with_all = &[INSTALL_FROM_GIT_OPT, INSTALL_FROM_OCI_OPT, INSTALL_FROM_TAR_OPT],
     )]
     pub dir: Option<PathBuf>,
 
+    /// The OCI registry reference of a templates artifact. The artifact must
+    /// contain a single gzipped tar layer, with the templates in a "templates"
+    /// directory.
+    #[clap(
+        name = INSTALL_FROM_OCI_OPT,
+        long = "oci",
+        conflicts_with_all = &[INSTALL_FROM_GIT_OPT, INSTALL_FROM_DIR_OPT, INSTALL_FROM_TAR_OPT],
+    )]
+    pub oci: Option<String>,
+
+    /// The URL of a gzipped tar archive containing the templates in a
+    /// "templates" directory.
+    #[clap(
+        name = INSTALL_FROM_TAR_OPT,
+        long = "tar",
+        conflicts_with_all = &[INSTALL_FROM_GIT_OPT, INSTALL_FROM_DIR_OPT, INSTALL_FROM_OCI_OPT],
+    )]
+    pub tar: Option<String>,
+
+    /// The expected SHA256 digest of the tar archive. If present, installation
+    /// fails if the downloaded archive does not match.
+    #[clap(long = "digest", requires = INSTALL_FROM_TAR_OPT)]
+    pub digest: Option<String>,
+
     /// If present, updates existing templates instead of skipping.
     #[clap(long = "upgrade", alias = "update")]
     pub update: bool,
@@ -118,13 +146,19 @@ impl Install {
     pub async fn run(self) -> Result<()> {
         let template_manager = TemplateManager::try_default()
             .context("Failed to construct template directory path")?;
-        let source = match (&self.git, &self.dir) {
-            (Some(git), None) => TemplateSource::try_from_git(git, &self.branch, SPIN_VERSION)?,
-            (None, Some(dir)) => {
+        let source = match (&self.git, &self.dir, &self.oci, &self.tar) {
+            (Some(git), None, None, None) => {
+                TemplateSource::try_from_git(git, &self.branch, SPIN_VERSION)?
+            }
+            (None, Some(dir), None, None) => {
                 let abs_dir = dir.absolutize().map(|d| d.to_path_buf());
                 TemplateSource::File(abs_dir.unwrap_or_else(|_| dir.clone()))
             }
-            _ => anyhow::bail!("Exactly one of `git` and `dir` sources must be specified"),
+            (None, None, Some(oci), None) => TemplateSource::Oci(oci.clone()),
+            (None, None, None, Some(tar)) => TemplateSource::try_from_tar(tar, &self.digest)?,
+            _ => anyhow::bail!(
+                "Exactly one of `git`, `dir`, `oci` and `tar` sources must be specified"
+            ),
         };
 
         let reporter = ConsoleProgressReporter;
@@ -187,6 +221,9 @@ impl Upgrade {
                 git: self.git.clone(),
                 branch: self.branch.clone(),
                 dir: None,
+                oci: None,
+                tar: None,
+                digest: None,
                 update: true,
             };
 
@@ -226,9 +263,11 @@ impl Upgrade {
         template_manager: &TemplateManager,
     ) -> anyhow::Result<Option<Vec<RepoSelection>>> {
         let existing_templates = template_manager.list().await?.templates;
-        let (origin, no_origin): (Vec<_>, Vec<_>) = existing_templates
-            .iter()
-            .partition(|t| t.source_repo().is_some());
+        let (origin, no_origin): (Vec<_>, Vec<_>) = existing_templates.iter().partition(|t| {
+            t.source_repo().is_some()
+                || t.source_oci_reference().is_some()
+                || t.source_tar_url().is_some()
+        });
 
         let mut repos = origin
             .iter()
@@ -253,6 +292,17 @@ impl Upgrade {
             }
         }
 
+        let references = origin
+            .iter()
+            .filter_map(|t| t.source_oci_reference())
+            .collect::<HashSet<_>>();
+        sources.extend(references.into_iter().map(RepoSelection::from_oci));
+        let tar_sources = origin
+            .iter()
+            .filter_map(|t| t.source_tar_url())
+            .collect::<HashSet<_>>();
+        sources.extend(tar_sources.into_iter().filter_map(RepoSelection::from_tar));
+
         if sources.is_empty() {
             eprintln!("No template repositories found to upgrade");
             eprintln!();
@@ -262,7 +312,7 @@ impl Upgrade {
                 eprintln!("Your template repositories were either:");
                 eprintln!("* Installed from a directory; or");
                 eprintln!("* Installed using an older version of Spin");
-                eprintln!("To upgrade them, run `spin templates install --upgrade` with the --git or --dir option");
+                eprintln!("To upgrade them, run `spin templates install --upgrade` with the --git, --dir, --oci or --tar option");
             }
             return Ok(None);
         }
@@ -286,7 +336,7 @@ impl Upgrade {
             for template in no_origin {
                 eprintln!("- {}", template.id());
             }
-            eprintln!("To upgrade them, run `spin templates install --upgrade` with the --git or --dir option");
+            eprintln!("To upgrade them, run `spin templates install --upgrade` with the --git, --dir, --oci or --tar option");
             eprintln!();
             if !self.all {
                 eprintln!("The following template repositories can be automatically upgraded.");
@@ -372,6 +422,26 @@ impl RepoSelection {
             resolved_tag,
         })
     }
+
+    fn from_oci(reference: &str) -> Self {
+        Self {
+            repo: reference.to_owned(),
+            template_source: TemplateSource::Oci(reference.to_owned()),
+            resolved_tag: None,
+        }
+    }
+
+    // Upgrading fetches whatever archive is now at the URL, so the digest
+    // recorded at install time is not checked; the new archive's digest is
+    // recorded in its place.
+    fn from_tar(url: &str) -> Option<Self> {
+        let template_source = TemplateSource::try_from_tar(url, &None).ok()?;
+        Some(Self {
+            repo: url.to_owned(),
+            template_source,
+            resolved_tag: None,
+        })
+    }
 }
 
 impl std::fmt::Display for RepoSelection {
@@ -603,6 +673,9 @@ async fn install_default_templates() -> anyhow::Result<()> {
         git: Some(DEFAULT_TEMPLATE_REPO.to_owned()),
         branch: None,
         dir: None,
+        oci: None,
+        tar: None,
+        digest: None,
         update: false,
     };
     install_cmd
@@ -0,0 +1,394 @@
+mod components;
+mod manifest;
+mod report;
+mod stubs;
+
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use clap::{Parser, ValueEnum};
+use http::uri::Scheme;
+use http_body_util::BodyExt;
+use reqwest::Url;
+use spin_app::locked::LockedApp;
+use spin_common::ui::quoted_path;
+use spin_http::routes::RoutePattern;
+use spin_loader::FilesMountStrategy;
+use spin_oci::{ComponentRegistry, VerificationPolicy};
+use spin_trigger::{
+    loader::TriggerLoader, HostComponentInitData, LLmOptions, RuntimeConfig, TriggerExecutorBuilder,
+};
+use spin_trigger_http::HttpTrigger;
+use tempfile::TempDir;
+
+use crate::opts::{APP_MANIFEST_FILE_OPT, DEFAULT_MANIFEST_FILE, PROFILE_ENV};
+
+use self::{
+    components::TestComponents,
+    manifest::{default_test_manifest_path, TestCase, TestManifest},
+    report::TestResult,
+    stubs::ScriptedResponses,
+};
+
+/// Run an application's tests.
+#[derive(Parser, Debug)]
+#[clap(about = "Run the tests for a Spin application against a mocked host")]
+pub struct TestCommand {
+    /// The application to test. This may be a manifest (spin.toml) file, or a
+    /// directory containing a spin.toml file.
+    /// If omitted, it defaults to "spin.toml".
+    #[clap(
+        name = APP_MANIFEST_FILE_OPT,
+        short = 'f',
+        long = "from",
+        alias = "file",
+        default_value = DEFAULT_MANIFEST_FILE
+    )]
+    pub app_source: PathBuf,
+
+    /// The test manifest describing the tests to run and the host state to run
+    /// them against. If omitted, it defaults to "spin-test.toml" in the same
+    /// directory as the application manifest.
+    #[clap(long = "tests")]
+    pub test_manifest: Option<PathBuf>,
+
+    /// The manifest profile to apply to the application.
+    #[clap(long = "profile", env = PROFILE_ENV)]
+    pub profile: Option<String>,
+
+    /// The format in which to report test results.
+    #[clap(value_enum, long = "format", default_value = "human")]
+    pub format: ReportFormat,
+
+    /// Run only tests whose names contain this string.
+    pub filter: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum ReportFormat {
+    Human,
+    Junit,
+}
+
+impl TestCommand {
+    pub async fn run(self) -> Result<()> {
+        let manifest_path = spin_common::paths::resolve_manifest_file_path(&self.app_source)?;
+        let test_manifest_path = self
+            .test_manifest
+            .clone()
+            .unwrap_or_else(|| default_test_manifest_path(&manifest_path));
+        let test_manifest = TestManifest::from_file(&test_manifest_path)?;
+
+        let working_dir = TempDir::with_prefix("spintest-")?;
+        let mut locked_app = spin_loader::from_file(
+            &manifest_path,
+            FilesMountStrategy::Copy(working_dir.path().join("assets")),
+            None,
+            self.profile.as_deref(),
+            Some(Arc::new(ComponentRegistry::new(false, || {
+                Ok(VerificationPolicy::default())
+            }))),
+        )
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to load manifest from {}",
+                quoted_path(&manifest_path)
+            )
+        })?;
+        ensure!(
+            locked_app
+                .triggers
+                .iter()
+                .all(|t| t.trigger_type == HTTP_TRIGGER_TYPE),
+            "spin test supports only applications with HTTP triggers"
+        );
+        apply_variables(&mut locked_app, &test_manifest.variables)?;
+        let locked_url = write_locked_app(&locked_app, working_dir.path()).await?;
+
+        let responses = Arc::new(ScriptedResponses::default());
+
+        let tests = test_manifest
+            .tests
+            .iter()
+            .filter(|test| match &self.filter {
+                Some(filter) => test.name.contains(filter),
+                None => true,
+            });
+        let mut results = vec![];
+        for test in tests {
+            let mut stubs = test.outbound_http.clone();
+            stubs.extend(test_manifest.outbound_http.iter().cloned());
+            responses.reset(stubs);
+
+            // Each test gets a new trigger, so that it sees only the initial
+            // host state and none of the changes made by other tests.
+            let mut trigger =
+                build_trigger(working_dir.path(), locked_url.clone(), &test_manifest).await?;
+            trigger.set_outbound_interceptor(responses.clone());
+
+            let start = Instant::now();
+            let mut failures = match run_test(&trigger, &locked_app, test).await {
+                Ok(failures) => failures,
+                Err(e) => vec![format!("Error running test: {e:#}")],
+            };
+            for request in responses.unmatched() {
+                failures.push(format!("Unexpected outbound request: {request}"));
+            }
+            results.push(TestResult {
+                name: test.name.clone(),
+                duration: start.elapsed(),
+                failures,
+            });
+        }
+
+        if !test_manifest.test_components.is_empty() {
+            let test_components =
+                TestComponents::load(&test_manifest, &locked_app, working_dir.path()).await?;
+            let component_results = test_components
+                .run(&responses, self.filter.as_deref(), || {
+                    init_data(&test_manifest)
+                })
+                .await;
+            results.extend(component_results);
+        }
+
+        match self.format {
+            ReportFormat::Human => print!("{}", report::human(&results)),
+            ReportFormat::Junit => {
+                let suite_name = locked_app
+                    .metadata
+                    .get("name")
+                    .and_then(|n| n.as_str())
+                    .unwrap_or("spin");
+                print!("{}", report::junit(suite_name, &results))
+            }
+        }
+
+        let failed = results.iter().filter(|r| !r.passed()).count();
+        if failed > 0 {
+            bail!("{failed} of {} tests failed", results.len());
+        }
+        Ok(())
+    }
+}
+
+const HTTP_TRIGGER_TYPE: &str = "http";
+
+async fn build_trigger(
+    working_dir: &Path,
+    locked_url: String,
+    test_manifest: &TestManifest,
+) -> Result<HttpTrigger> {
+    let loader = TriggerLoader::new(working_dir, false);
+    let builder = TriggerExecutorBuilder::<HttpTrigger>::new(loader);
+
+    // With no state directory, the default key-value store and SQLite
+    // database are in memory.
+    let runtime_config = RuntimeConfig::new(None);
+    builder
+        .build(locked_url, runtime_config, init_data(test_manifest))
+        .await
+}
+
+/// Returns the initial host state for a test. Each trigger built from this
+/// gets its own in-memory stores, so a new one is built for every test.
+fn init_data(test_manifest: &TestManifest) -> HostComponentInitData {
+    HostComponentInitData::new(
+        test_manifest
+            .key_value
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect::<Vec<_>>(),
+        test_manifest.sqlite.clone(),
+        LLmOptions { use_gpu: false },
+    )
+}
+
+/// Runs the test, returning the ways in which the response did not meet
+/// the test's expectations.
+async fn run_test(trigger: &HttpTrigger, app: &LockedApp, test: &TestCase) -> Result<Vec<String>> {
+    let mut request = http::Request::builder()
+        .method(test.method.as_str())
+        .uri(request_path(app, test)?);
+    for (name, value) in &test.headers {
+        request = request.header(name, value);
+    }
+    let request = request.body(spin_http::body::full(test.body.clone().into()))?;
+
+    let client_addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let response = trigger.handle(request, Scheme::HTTP, client_addr).await?;
+
+    let (parts, body) = response.into_parts();
+    let body = body
+        .collect()
+        .await
+        .map_err(|e| anyhow!("Failed to read response body: {e:?}"))?
+        .to_bytes();
+    let body = String::from_utf8_lossy(&body);
+
+    let expect = &test.expect;
+    let mut failures = vec![];
+    if parts.status.as_u16() != expect.status {
+        failures.push(format!(
+            "Expected status {}, got {}",
+            expect.status,
+            parts.status.as_u16()
+        ));
+    }
+    for (name, expected) in &expect.headers {
+        let actual = parts
+            .headers
+            .get(name.as_str())
+            .and_then(|v| v.to_str().ok());
+        if actual != Some(expected.as_str()) {
+            failures.push(format!(
+                "Expected header {name}: {expected:?}, got {actual:?}"
+            ));
+        }
+    }
+    if let Some(expected) = &expect.body {
+        if body != expected.as_str() {
+            failures.push(format!("Expected body {expected:?}, got {body:?}"));
+        }
+    }
+    for expected in &expect.body_contains {
+        if !body.contains(expected.as_str()) {
+            failures.push(format!(
+                "Expected body to contain {expected:?}, got {body:?}"
+            ));
+        }
+    }
+    Ok(failures)
+}
+
+/// Returns the path to send the test request to: the test path, appended to
+/// the route of the test's component if it has one.
+fn request_path(app: &LockedApp, test: &TestCase) -> Result<String> {
+    let path = match test.path.as_str() {
+        "" => "/".to_owned(),
+        p if p.starts_with('/') => p.to_owned(),
+        p => format!("/{p}"),
+    };
+    let Some(component) = &test.component else {
+        return Ok(path);
+    };
+
+    let trigger = app
+        .triggers
+        .iter()
+        .find(|t| {
+            t.trigger_config.get("component").and_then(|c| c.as_str()) == Some(component.as_str())
+        })
+        .with_context(|| {
+            format!(
+                "Test '{}' targets component '{component}', which has no HTTP trigger",
+                test.name
+            )
+        })?;
+    let route = trigger
+        .trigger_config
+        .get("route")
+        .and_then(|r| r.as_str())
+        .unwrap_or("/");
+    let base = app
+        .metadata
+        .get("trigger")
+        .and_then(|t| t.get("base"))
+        .and_then(|b| b.as_str())
+        .unwrap_or("/");
+    let prefix = match RoutePattern::from(base, route) {
+        RoutePattern::Exact(p) | RoutePattern::Wildcard(p) => p,
+    };
+    match path.as_str() {
+        "/" if !prefix.is_empty() => Ok(prefix),
+        _ => Ok(format!("{}{path}", prefix.trim_end_matches('/'))),
+    }
+}
+
+fn apply_variables(app: &mut LockedApp, variables: &BTreeMap<String, String>) -> Result<()> {
+    for (name, value) in variables {
+        let variable = app.variables.get_mut(name).with_context(|| {
+            format!("Test manifest sets variable '{name}', which the application does not define")
+        })?;
+        variable.default = Some(value.clone());
+    }
+    Ok(())
+}
+
+async fn write_locked_app(locked_app: &LockedApp, working_dir: &Path) -> Result<String> {
+    let locked_path = working_dir.join("spin.lock");
+    let locked_app_contents =
+        serde_json::to_vec_pretty(&locked_app).context("failed to serialize locked app")?;
+    tokio::fs::write(&locked_path, locked_app_contents)
+        .await
+        .with_context(|| format!("failed to write {}", quoted_path(&locked_path)))?;
+    let locked_url = Url::from_file_path(&locked_path)
+        .map_err(|_| anyhow!("cannot convert to file URL: {}", quoted_path(&locked_path)))?
+        .to_string();
+    Ok(locked_url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> LockedApp {
+        LockedApp::from_json(
+            br#"{
+                "spin_lock_version": 0,
+                "variables": { "api_key": { "default": "real" } },
+                "triggers": [
+                    {
+                        "id": "t1",
+                        "trigger_type": "http",
+                        "trigger_config": { "component": "api", "route": "/api/..." }
+                    }
+                ],
+                "components": []
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn test_case(component: Option<&str>, path: &str) -> TestCase {
+        let mut test: TestCase = toml::from_str(&format!("name = 't'\npath = '{path}'")).unwrap();
+        test.component = component.map(Into::into);
+        test
+    }
+
+    #[test]
+    fn request_path_is_relative_to_component_route() {
+        let app = app();
+        assert_eq!(
+            "/hello",
+            request_path(&app, &test_case(None, "hello")).unwrap()
+        );
+        assert_eq!(
+            "/api",
+            request_path(&app, &test_case(Some("api"), "")).unwrap()
+        );
+        assert_eq!(
+            "/api/users?id=1",
+            request_path(&app, &test_case(Some("api"), "/users?id=1")).unwrap()
+        );
+        request_path(&app, &test_case(Some("missing"), "/")).unwrap_err();
+    }
+
+    #[test]
+    fn variables_override_defaults() {
+        let mut app = app();
+        let variables = BTreeMap::from([("api_key".to_owned(), "test".to_owned())]);
+        apply_variables(&mut app, &variables).unwrap();
+        assert_eq!(Some("test"), app.variables["api_key"].default.as_deref());
+
+        let unknown = BTreeMap::from([("nope".to_owned(), "x".to_owned())]);
+        apply_variables(&mut app, &unknown).unwrap_err();
+    }
+}
@@ -0,0 +1,399 @@
+//! Running the test functions exported by test components.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use http::Uri;
+use outbound_http::OutboundHttpComponent;
+use reqwest::Url;
+use serde::Deserialize;
+use spin_app::locked::{
+    ContentRef, LockedApp, LockedComponent, LockedComponentSource, LockedTrigger,
+};
+use spin_common::ui::quoted_path;
+use spin_core::OutboundWasiHttpHandler;
+use spin_outbound_networking::intercept::SharedInterceptor;
+use spin_trigger::{
+    cli::NoArgs, loader::TriggerLoader, EitherInstance, HostComponentInitData, RuntimeConfig,
+    TriggerAppEngine, TriggerExecutor, TriggerExecutorBuilder,
+};
+use wasmparser::{ComponentExternalKind, Parser, Payload};
+
+use super::{
+    manifest::{OutboundHttpStub, TestComponent, TestManifest},
+    report::TestResult,
+    stubs::{ScriptedResponses, TEST_ORIGIN},
+};
+
+/// The trigger type under which test components are loaded. Test components
+/// are never part of the app itself.
+const TEST_TRIGGER_TYPE: &str = "spin-test";
+
+/// The test components of a suite, ready to run.
+pub struct TestComponents {
+    /// The lock file URL of the app made up of the test components.
+    locked_url: String,
+    working_dir: PathBuf,
+    components: Vec<LoadedTestComponent>,
+    /// Responses to outbound HTTP requests made in any test.
+    outbound_http: Vec<OutboundHttpStub>,
+}
+
+struct LoadedTestComponent {
+    id: String,
+    /// The name under which the component's tests are reported.
+    name: String,
+    tests: Vec<String>,
+}
+
+impl TestComponents {
+    /// Prepares the given test components to run against the app's variables.
+    pub async fn load(
+        test_manifest: &TestManifest,
+        app: &LockedApp,
+        working_dir: &Path,
+    ) -> Result<Self> {
+        let mut test_app = app.clone();
+        test_app.components.clear();
+        test_app.triggers.clear();
+
+        let mut components = vec![];
+        for (index, test_component) in test_manifest.test_components.iter().enumerate() {
+            let source = &test_component.source;
+            let wasm = std::fs::read(source).with_context(|| {
+                format!("Failed to read test component {}", quoted_path(source))
+            })?;
+            let tests = match &test_component.tests {
+                Some(tests) => tests.clone(),
+                None => test_exports(&wasm).with_context(|| {
+                    format!("Failed to read exports of {}", quoted_path(source))
+                })?,
+            };
+
+            let id = format!("test-component-{index}");
+            test_app
+                .components
+                .push(locked_component(&id, test_component)?);
+            test_app.triggers.push(LockedTrigger {
+                id: format!("test-trigger-{index}"),
+                trigger_type: TEST_TRIGGER_TYPE.into(),
+                trigger_config: serde_json::json!({ "component": id }),
+            });
+            components.push(LoadedTestComponent {
+                id,
+                name: source
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| format!("test-component-{index}")),
+                tests,
+            });
+        }
+
+        let test_app_dir = working_dir.join("test-components");
+        std::fs::create_dir_all(&test_app_dir)?;
+        let locked_url = super::write_locked_app(&test_app, &test_app_dir).await?;
+
+        Ok(Self {
+            locked_url,
+            working_dir: working_dir.to_owned(),
+            components,
+            outbound_http: test_manifest.outbound_http.clone(),
+        })
+    }
+
+    /// Runs the tests whose names contain the filter (if any), each in a new
+    /// instance of its component, against host state created by `init_data`.
+    pub async fn run(
+        &self,
+        responses: &Arc<ScriptedResponses>,
+        filter: Option<&str>,
+        init_data: impl Fn() -> HostComponentInitData,
+    ) -> Vec<TestResult> {
+        let mut results = vec![];
+        for component in &self.components {
+            for test in &component.tests {
+                let name = format!("{}::{test}", component.name);
+                if filter.is_some_and(|filter| !name.contains(filter)) {
+                    continue;
+                }
+
+                responses.reset(self.outbound_http.clone());
+                let interceptor: SharedInterceptor = responses.clone();
+                let start = Instant::now();
+                let mut failures = match self
+                    .run_test(&component.id, test, init_data(), interceptor)
+                    .await
+                {
+                    Ok(Ok(())) => vec![],
+                    Ok(Err(message)) => vec![message],
+                    Err(e) => vec![format!("Error running test: {e:#}")],
+                };
+                for request in responses.unmatched() {
+                    failures.push(format!("Unexpected outbound request: {request}"));
+                }
+                results.push(TestResult {
+                    name,
+                    duration: start.elapsed(),
+                    failures,
+                });
+            }
+        }
+        results
+    }
+
+    /// Runs the test in a new executor, so that it sees only the initial host
+    /// state and none of the changes made by other tests.
+    async fn run_test(
+        &self,
+        component_id: &str,
+        test: &str,
+        init_data: HostComponentInitData,
+        interceptor: SharedInterceptor,
+    ) -> Result<std::result::Result<(), String>> {
+        let loader = TriggerLoader::new(&self.working_dir, false);
+        let executor = TriggerExecutorBuilder::<TestComponentExecutor>::new(loader)
+            .build(self.locked_url.clone(), RuntimeConfig::new(None), init_data)
+            .await?;
+        executor.run_test(component_id, test, interceptor).await
+    }
+}
+
+/// Returns the names of the component's exported test functions: those named
+/// `test` or starting with `test-`.
+fn test_exports(wasm: &[u8]) -> Result<Vec<String>> {
+    if !Parser::is_component(wasm) {
+        bail!("test components must be Wasm components, not modules");
+    }
+
+    let mut tests = vec![];
+    // Exports of nested modules and components are not exports of the test component.
+    let mut depth = 0;
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::ModuleSection { .. } | Payload::ComponentSection { .. } => depth += 1,
+            Payload::End(_) => depth -= 1,
+            Payload::ComponentExportSection(exports) if depth == 0 => {
+                for export in exports {
+                    let export = export?;
+                    let name = export.name.0;
+                    if export.kind == ComponentExternalKind::Func
+                        && (name == "test" || name.starts_with("test-"))
+                    {
+                        tests.push(name.to_owned());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(tests)
+}
+
+fn locked_component(id: &str, test_component: &TestComponent) -> Result<LockedComponent> {
+    let source = &test_component.source;
+    let source_url = Url::from_file_path(source.canonicalize()?)
+        .map_err(|_| anyhow!("cannot convert to file URL: {}", quoted_path(source)))?;
+
+    // Test components get the same default stores as the app. Outbound requests
+    // are all answered by the test manifest's scripted responses.
+    let metadata = serde_json::json!({
+        "key_value_stores": ["default"],
+        "sqlite_databases": ["default"],
+        "allowed_outbound_hosts": ["*://*:*"],
+    });
+    Ok(LockedComponent {
+        id: id.to_owned(),
+        metadata: serde_json::from_value(metadata)?,
+        source: LockedComponentSource {
+            content_type: "application/wasm".into(),
+            content: ContentRef {
+                source: Some(source_url.to_string()),
+                ..Default::default()
+            },
+        },
+        env: Default::default(),
+        files: Default::default(),
+        config: test_component.variables.clone(),
+    })
+}
+
+/// Runs test functions in test components.
+struct TestComponentExecutor {
+    engine: TriggerAppEngine<Self>,
+}
+
+#[derive(Deserialize)]
+struct TestTriggerConfig {}
+
+#[derive(Default)]
+struct TestRuntimeData {
+    interceptor: Option<SharedInterceptor>,
+}
+
+impl OutboundWasiHttpHandler for TestRuntimeData {
+    fn send_request(
+        data: &mut spin_core::Data<Self>,
+        mut request: wasmtime_wasi_http::types::OutgoingRequest,
+    ) -> wasmtime::Result<
+        wasmtime::component::Resource<wasmtime_wasi_http::types::HostFutureIncomingResponse>,
+    >
+    where
+        Self: Sized,
+    {
+        let interceptor = data
+            .as_ref()
+            .interceptor
+            .clone()
+            .context("test components cannot make outbound requests outside a test")?;
+        // Relative URLs are requests to the app under test.
+        if request.request.uri().authority().is_none() {
+            let path_and_query = request
+                .request
+                .uri()
+                .path_and_query()
+                .map(|p| p.as_str())
+                .unwrap_or("/");
+            let uri: Uri = format!("{TEST_ORIGIN}{path_and_query}").parse()?;
+            *request.request.uri_mut() = uri;
+        }
+        spin_trigger_http::intercept::send_intercepted_request(data, interceptor, request)
+    }
+}
+
+#[async_trait::async_trait]
+impl TriggerExecutor for TestComponentExecutor {
+    const TRIGGER_TYPE: &'static str = TEST_TRIGGER_TYPE;
+    type RuntimeData = TestRuntimeData;
+    type TriggerConfig = TestTriggerConfig;
+    type RunConfig = NoArgs;
+
+    async fn new(engine: TriggerAppEngine<Self>) -> Result<Self> {
+        Ok(Self { engine })
+    }
+
+    async fn run(self, _config: Self::RunConfig) -> Result<()> {
+        bail!("test components are run by `spin test`")
+    }
+}
+
+impl TestComponentExecutor {
+    /// Calls the test function, returning `Ok(Err(message))` if the test failed.
+    async fn run_test(
+        &self,
+        component_id: &str,
+        test: &str,
+        interceptor: SharedInterceptor,
+    ) -> Result<std::result::Result<(), String>> {
+        let (instance, mut store) = self.engine.prepare_instance(component_id).await?;
+        let EitherInstance::Component(instance) = instance else {
+            bail!("test components must be Wasm components");
+        };
+
+        if let Some(handle) = self
+            .engine
+            .engine
+            .find_host_component_handle::<Arc<OutboundHttpComponent>>()
+        {
+            let outbound_http = store.host_components_data().get_or_insert(handle);
+            outbound_http.origin = TEST_ORIGIN.into();
+            outbound_http.interceptor = Some(interceptor.clone());
+        }
+        store.as_mut().data_mut().as_mut().interceptor = Some(interceptor);
+
+        let func = instance
+            .get_func(&mut store, test)
+            .with_context(|| format!("component does not export test function `{test}`"))?;
+        let outcome = if let Ok(func) = func.typed::<(), (Result<(), String>,)>(&store) {
+            let result = func.call_async(&mut store, ()).await;
+            result.map(|(result,)| result)
+        } else {
+            let func = func.typed::<(), ()>(&store).with_context(|| {
+                format!("test function `{test}` must return nothing or `result<_, string>`")
+            })?;
+            func.call_async(&mut store, ()).await.map(Ok)
+        };
+        Ok(match outcome {
+            Ok(result) => result,
+            Err(trap) => Err(format!("Test trapped: {trap:#}")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_COMPONENT: &str = r#"
+        (component
+            (core module $m
+                (func (export "passes"))
+                (func (export "fails") unreachable)
+            )
+            (core instance $i (instantiate $m))
+            (func (export "test-passes") (canon lift (core func $i "passes")))
+            (func (export "test-fails") (canon lift (core func $i "fails")))
+            (func (export "helper") (canon lift (core func $i "passes")))
+        )
+    "#;
+
+    #[test]
+    fn discovers_exported_test_functions() {
+        let wasm = wat::parse_str(TEST_COMPONENT).unwrap();
+        assert_eq!(
+            vec!["test-passes", "test-fails"],
+            test_exports(&wasm).unwrap()
+        );
+
+        let module = wat::parse_str("(module (func (export \"test-x\")))").unwrap();
+        test_exports(&module).unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn runs_exported_test_functions() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let source = dir.path().join("my_tests.wasm");
+        std::fs::write(&source, wat::parse_str(TEST_COMPONENT)?)?;
+
+        let test_manifest = TestManifest {
+            test_components: vec![TestComponent {
+                source,
+                tests: None,
+                variables: Default::default(),
+            }],
+            ..Default::default()
+        };
+        let app = LockedApp::from_json(
+            br#"{
+                "spin_lock_version": 0,
+                "metadata": { "name": "test-app" },
+                "triggers": [],
+                "components": []
+            }"#,
+        )?;
+
+        let components =
+            TestComponents::load(&test_manifest, &app, dir.path(), Default::default()).await?;
+        let responses = Arc::new(ScriptedResponses::default());
+        let results = components.run(&responses, None).await;
+
+        let outcomes = results
+            .iter()
+            .map(|r| (r.name.as_str(), r.passed()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("my_tests::test-passes", true),
+                ("my_tests::test-fails", false)
+            ],
+            outcomes
+        );
+
+        let filtered = components.run(&responses, Some("passes")).await;
+        assert_eq!(1, filtered.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use spin_common::ui::quoted_path;
+
+/// A test manifest (spin-test.toml) describing a suite of tests for an app.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestManifest {
+    /// Values for application variables, overriding any defaults in the app manifest.
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+    /// Entries to place in the default key-value store before running tests.
+    #[serde(default)]
+    pub key_value: BTreeMap<String, String>,
+    /// Statements to run against the default SQLite database before running
+    /// tests. A statement of the form `@file.sql` runs the contents of the
+    /// file, relative to the test manifest.
+    #[serde(default)]
+    pub sqlite: Vec<String>,
+    /// Responses to outbound HTTP requests made by components in any test.
+    #[serde(default)]
+    pub outbound_http: Vec<OutboundHttpStub>,
+    /// The tests to run.
+    #[serde(default, rename = "test")]
+    pub tests: Vec<TestCase>,
+    /// Components whose exported test functions are run as tests.
+    #[serde(default, rename = "test_component")]
+    pub test_components: Vec<TestComponent>,
+}
+
+impl TestManifest {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read test manifest {}", quoted_path(path)))?;
+        let mut manifest: Self = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse test manifest {}", quoted_path(path)))?;
+        if let Some(dir) = path.parent() {
+            manifest.resolve_sqlite_files(dir);
+            for component in &mut manifest.test_components {
+                component.source = dir.join(&component.source);
+            }
+        }
+        Ok(manifest)
+    }
+
+    // `--sqlite @file` statements are resolved relative to the current
+    // directory; make test manifest statements relative to the manifest.
+    fn resolve_sqlite_files(&mut self, dir: &Path) {
+        for statement in &mut self.sqlite {
+            if let Some(file) = statement.strip_prefix('@') {
+                *statement = format!("@{}", dir.join(file).display());
+            }
+        }
+    }
+}
+
+/// A scripted response to outbound HTTP requests.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutboundHttpStub {
+    /// The request method to match. If omitted, any method matches.
+    pub method: Option<String>,
+    /// The request URL to match. A trailing `*` matches any URL with the
+    /// given prefix; a URL starting with `/` matches requests to the app itself.
+    pub url: String,
+    /// The response status code.
+    #[serde(default = "default_status")]
+    pub status: u16,
+    /// The response headers.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// The response body.
+    #[serde(default)]
+    pub body: String,
+}
+
+/// A Wasm component which exports test functions. Each test function takes
+/// no arguments and returns either nothing or `result<_, string>`; it fails
+/// if it traps or returns an error.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestComponent {
+    /// The path to the component, relative to the test manifest.
+    pub source: PathBuf,
+    /// The exported functions to run. If omitted, every exported function
+    /// named `test` or starting with `test-` is run.
+    pub tests: Option<Vec<String>>,
+    /// Values for the component's variables. As in the app manifest, these
+    /// may refer to application variables.
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+}
+
+/// A single test: a request to send to the app, and what to expect of the response.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TestCase {
+    pub name: String,
+    /// The component to send the request to. The request path is appended
+    /// to the component's route.
+    pub component: Option<String>,
+    /// The request path, including any query string.
+    #[serde(default)]
+    pub path: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: String,
+    /// Responses to outbound HTTP requests made during this test. These take
+    /// precedence over the suite-wide responses.
+    #[serde(default)]
+    pub outbound_http: Vec<OutboundHttpStub>,
+    #[serde(default)]
+    pub expect: Expectation,
+}
+
+/// What a test expects of the app's response.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expectation {
+    #[serde(default = "default_status")]
+    pub status: u16,
+    /// If set, the exact expected response body.
+    pub body: Option<String>,
+    /// Strings which must each appear in the response body.
+    #[serde(default)]
+    pub body_contains: Vec<String>,
+    /// Headers which must be present in the response with the given values.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl Default for Expectation {
+    fn default() -> Self {
+        Self {
+            status: default_status(),
+            body: None,
+            body_contains: vec![],
+            headers: Default::default(),
+        }
+    }
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+fn default_method() -> String {
+    "GET".into()
+}
+
+/// Returns the default test manifest path for the given app manifest.
+pub fn default_test_manifest_path(app_manifest: &Path) -> PathBuf {
+    app_manifest
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(DEFAULT_TEST_MANIFEST_FILE)
+}
+
+pub const DEFAULT_TEST_MANIFEST_FILE: &str = "spin-test.toml";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_test_manifest() {
+        let manifest: TestManifest = toml::from_str(
+            r#"
+            sqlite = ["@fixtures.sql"]
+
+            [variables]
+            api_key = "test"
+
+            [key_value]
+            greeting = "hello"
+
+            [[outbound_http]]
+            url = "https://example.com/*"
+            body = "stubbed"
+
+            [[test]]
+            name = "greets"
+            path = "/hello"
+
+            [[test]]
+            name = "creates"
+            component = "api"
+            method = "POST"
+            body = "{}"
+            [test.expect]
+            status = 201
+            body_contains = ["created"]
+
+            [[test_component]]
+            source = "tests/api_tests.wasm"
+            variables = { token = "{{ api_key }}" }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!("test", manifest.variables["api_key"]);
+        assert_eq!("hello", manifest.key_value["greeting"]);
+        assert_eq!(200, manifest.outbound_http[0].status);
+        assert_eq!(None, manifest.outbound_http[0].method);
+        assert_eq!(2, manifest.tests.len());
+        assert_eq!("GET", manifest.tests[0].method);
+        assert_eq!(200, manifest.tests[0].expect.status);
+        assert_eq!(Some("api"), manifest.tests[1].component.as_deref());
+        assert_eq!(201, manifest.tests[1].expect.status);
+        assert_eq!(1, manifest.test_components.len());
+        assert_eq!(None, manifest.test_components[0].tests);
+        assert_eq!(
+            "{{ api_key }}",
+            manifest.test_components[0].variables["token"]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        toml::from_str::<TestManifest>(
+            r#"
+            [[test]]
+            name = "typo"
+            pth = "/hello"
+            "#,
+        )
+        .unwrap_err();
+    }
+}
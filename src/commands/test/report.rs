@@ -0,0 +1,152 @@
+use std::{fmt::Write, time::Duration};
+
+/// The outcome of a single test.
+#[derive(Debug)]
+pub struct TestResult {
+    pub name: String,
+    pub duration: Duration,
+    /// The reasons the test failed; empty if it passed.
+    pub failures: Vec<String>,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Formats results in the style of `cargo test`.
+pub fn human(results: &[TestResult]) -> String {
+    let mut out = String::new();
+    for result in results {
+        let status = if result.passed() { "ok" } else { "FAILED" };
+        writeln!(out, "test {} ... {status}", result.name).unwrap();
+    }
+
+    let failed: Vec<_> = results.iter().filter(|r| !r.passed()).collect();
+    if !failed.is_empty() {
+        writeln!(out, "\nfailures:").unwrap();
+        for result in &failed {
+            writeln!(out, "\n---- {} ----", result.name).unwrap();
+            for failure in &result.failures {
+                writeln!(out, "{failure}").unwrap();
+            }
+        }
+    }
+
+    let outcome = if failed.is_empty() { "ok" } else { "FAILED" };
+    let total: Duration = results.iter().map(|r| r.duration).sum();
+    writeln!(
+        out,
+        "\ntest result: {outcome}. {} passed; {} failed; finished in {:.2}s",
+        results.len() - failed.len(),
+        failed.len(),
+        total.as_secs_f64(),
+    )
+    .unwrap();
+    out
+}
+
+/// Formats results as a JUnit XML report.
+pub fn junit(suite_name: &str, results: &[TestResult]) -> String {
+    let failures = results.iter().filter(|r| !r.passed()).count();
+    let total: Duration = results.iter().map(|r| r.duration).sum();
+
+    let mut out = String::new();
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+    writeln!(
+        out,
+        r#"<testsuites tests="{}" failures="{failures}" time="{:.3}">"#,
+        results.len(),
+        total.as_secs_f64(),
+    )
+    .unwrap();
+    writeln!(
+        out,
+        r#"  <testsuite name="{}" tests="{}" failures="{failures}" errors="0" time="{:.3}">"#,
+        escape(suite_name),
+        results.len(),
+        total.as_secs_f64(),
+    )
+    .unwrap();
+    for result in results {
+        write!(
+            out,
+            r#"    <testcase name="{}" classname="{}" time="{:.3}""#,
+            escape(&result.name),
+            escape(suite_name),
+            result.duration.as_secs_f64(),
+        )
+        .unwrap();
+        if result.passed() {
+            writeln!(out, " />").unwrap();
+        } else {
+            writeln!(out, ">").unwrap();
+            let message = result.failures.join("\n");
+            writeln!(
+                out,
+                r#"      <failure message="{}">{}</failure>"#,
+                escape(result.failures.first().map(String::as_str).unwrap_or("")),
+                escape(&message),
+            )
+            .unwrap();
+            writeln!(out, "    </testcase>").unwrap();
+        }
+    }
+    writeln!(out, "  </testsuite>").unwrap();
+    writeln!(out, "</testsuites>").unwrap();
+    out
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results() -> Vec<TestResult> {
+        vec![
+            TestResult {
+                name: "passes".into(),
+                duration: Duration::from_millis(10),
+                failures: vec![],
+            },
+            TestResult {
+                name: "fails <badly>".into(),
+                duration: Duration::from_millis(20),
+                failures: vec!["expected status 200, got 500".into()],
+            },
+        ]
+    }
+
+    #[test]
+    fn human_report_summarises() {
+        let report = human(&results());
+        assert!(report.contains("test passes ... ok"));
+        assert!(report.contains("test fails <badly> ... FAILED"));
+        assert!(report.contains("expected status 200, got 500"));
+        assert!(report.contains("test result: FAILED. 1 passed; 1 failed"));
+    }
+
+    #[test]
+    fn junit_report_is_escaped() {
+        let report = junit("my-app", &results());
+        assert!(report.contains(r#"<testsuite name="my-app" tests="2" failures="1""#));
+        assert!(report.contains(r#"<testcase name="passes" classname="my-app""#));
+        assert!(report.contains(r#"<testcase name="fails &lt;badly&gt;""#));
+        assert!(report.contains(r#"<failure message="expected status 200, got 500">"#));
+    }
+}
@@ -0,0 +1,151 @@
+use std::sync::Mutex;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use spin_outbound_networking::intercept::{
+    InterceptedRequest, InterceptedResponse, OutboundHttpInterceptor,
+};
+
+use super::manifest::OutboundHttpStub;
+
+/// The origin of requests sent to the app under test. Outbound requests with
+/// relative URLs are made to this origin.
+pub const TEST_ORIGIN: &str = "http://localhost";
+
+/// Answers all outbound HTTP requests from a set of scripted responses, so
+/// that tests never reach the network.
+#[derive(Default)]
+pub struct ScriptedResponses {
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    stubs: Vec<OutboundHttpStub>,
+    unmatched: Vec<String>,
+}
+
+impl ScriptedResponses {
+    /// Replaces the scripted responses, and forgets any unmatched requests.
+    /// Stubs earlier in the list take precedence.
+    pub fn reset(&self, stubs: Vec<OutboundHttpStub>) {
+        *self.state.lock().unwrap() = State {
+            stubs,
+            unmatched: vec![],
+        };
+    }
+
+    /// Returns the requests (as "METHOD URL") that matched no scripted
+    /// response since the last reset.
+    pub fn unmatched(&self) -> Vec<String> {
+        self.state.lock().unwrap().unmatched.clone()
+    }
+}
+
+#[async_trait]
+impl OutboundHttpInterceptor for ScriptedResponses {
+    fn handles(&self, _method: &str, _url: &str) -> bool {
+        true
+    }
+
+    async fn respond(&self, request: InterceptedRequest) -> Result<InterceptedResponse> {
+        let mut state = self.state.lock().unwrap();
+        let Some(stub) = state
+            .stubs
+            .iter()
+            .find(|stub| stub_matches(stub, &request.method, &request.url))
+        else {
+            let description = format!("{} {}", request.method, request.url);
+            state.unmatched.push(description.clone());
+            return Err(anyhow!("no outbound_http response for {description}"));
+        };
+        Ok(InterceptedResponse {
+            status: stub.status,
+            headers: stub
+                .headers
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            body: stub.body.clone().into_bytes(),
+        })
+    }
+}
+
+fn stub_matches(stub: &OutboundHttpStub, method: &str, url: &str) -> bool {
+    if let Some(stub_method) = &stub.method {
+        if !stub_method.eq_ignore_ascii_case(method) {
+            return false;
+        }
+    }
+    let pattern = if stub.url.starts_with('/') {
+        format!("{TEST_ORIGIN}{}", stub.url)
+    } else {
+        stub.url.clone()
+    };
+    match pattern.strip_suffix('*') {
+        Some(prefix) => url.starts_with(prefix),
+        None => url == pattern,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stub(method: Option<&str>, url: &str) -> OutboundHttpStub {
+        OutboundHttpStub {
+            method: method.map(Into::into),
+            url: url.into(),
+            status: 200,
+            headers: Default::default(),
+            body: Default::default(),
+        }
+    }
+
+    #[test]
+    fn matches_stubs() {
+        let any = stub(None, "https://example.com/a");
+        assert!(stub_matches(&any, "GET", "https://example.com/a"));
+        assert!(stub_matches(&any, "POST", "https://example.com/a"));
+        assert!(!stub_matches(&any, "GET", "https://example.com/ab"));
+
+        let post = stub(Some("post"), "https://example.com/a");
+        assert!(stub_matches(&post, "POST", "https://example.com/a"));
+        assert!(!stub_matches(&post, "GET", "https://example.com/a"));
+
+        let prefix = stub(None, "https://example.com/api/*");
+        assert!(stub_matches(
+            &prefix,
+            "GET",
+            "https://example.com/api/users"
+        ));
+        assert!(!stub_matches(&prefix, "GET", "https://example.com/other"));
+
+        let relative = stub(None, "/self");
+        assert!(stub_matches(&relative, "GET", "http://localhost/self"));
+    }
+
+    #[tokio::test]
+    async fn records_unmatched_requests() {
+        let responses = ScriptedResponses::default();
+        responses.reset(vec![stub(None, "https://example.com/a")]);
+
+        let request = |url: &str| InterceptedRequest {
+            method: "GET".into(),
+            url: url.into(),
+            ..Default::default()
+        };
+        responses
+            .respond(request("https://example.com/a"))
+            .await
+            .unwrap();
+        responses
+            .respond(request("https://example.com/b"))
+            .await
+            .unwrap_err();
+        assert_eq!(vec!["GET https://example.com/b"], responses.unmatched());
+
+        responses.reset(vec![]);
+        assert!(responses.unmatched().is_empty());
+    }
+}
@@ -1,9 +1,11 @@
 mod app_source;
+mod lock_file;
 
 use std::{
     ffi::OsString,
     fmt::Debug,
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
 use anyhow::{anyhow, bail, Context, Result};
@@ -12,18 +14,18 @@ use reqwest::Url;
 use spin_app::locked::LockedApp;
 use spin_common::ui::quoted_path;
 use spin_loader::FilesMountStrategy;
-use spin_oci::OciLoader;
-use spin_trigger::cli::{SPIN_LOCAL_APP_DIR, SPIN_LOCKED_URL, SPIN_WORKING_DIR};
+use spin_oci::{ComponentRegistry, OciLoader};
+use spin_trigger::cli::{SPIN_HOT_RELOAD, SPIN_LOCAL_APP_DIR, SPIN_LOCKED_URL, SPIN_WORKING_DIR};
 use tempfile::TempDir;
 
-use crate::opts::*;
+use crate::{commands::registry::VerificationOptions, opts::*};
 
 use self::app_source::{AppSource, ResolvedAppSource};
 
 const APPLICATION_OPT: &str = "APPLICATION";
 
 /// Start the Fermyon runtime.
-#[derive(Parser, Debug, Default)]
+#[derive(Parser, Clone, Debug, Default)]
 #[clap(
     about = "Start the Spin application",
     allow_hyphen_values = true,
@@ -65,6 +67,18 @@ pub struct UpCommand {
     )]
     pub registry_source: Option<String>,
 
+    /// The application to run, as a standalone lock file written by
+    /// `--export-lock`. The digests of all the application's content are
+    /// verified before it starts.
+    #[clap(long = "from-lock", group = "source")]
+    pub lock_source: Option<PathBuf>,
+
+    /// Instead of running the application, write it to a standalone lock file
+    /// at this path, with its Wasm and static files in a `content` directory
+    /// alongside. The lock file can be run later with `--from-lock`.
+    #[clap(long = "export-lock")]
+    pub export_lock: Option<PathBuf>,
+
     /// Ignore server certificate errors from a registry
     #[clap(
         name = INSECURE_OPT,
@@ -74,6 +88,9 @@ pub struct UpCommand {
     )]
     pub insecure: bool,
 
+    #[clap(flatten)]
+    pub verification: VerificationOptions,
+
     /// Pass an environment variable (key=value) to all components of the application.
     #[clap(short = 'e', long = "env", parse(try_from_str = parse_env_var))]
     pub env: Vec<(String, String)>,
@@ -90,6 +107,12 @@ pub struct UpCommand {
     #[clap(long, takes_value = false)]
     pub direct_mounts: bool,
 
+    /// For local apps, the manifest profile to apply to the application.
+    ///
+    /// This is ignored on remote applications, as profiles are applied before they are pushed.
+    #[clap(long = "profile", env = PROFILE_ENV)]
+    pub profile: Option<String>,
+
     /// For local apps, specifies to perform `spin build` before running the application.
     ///
     /// This is ignored on remote applications, as they are already built.
@@ -136,8 +159,12 @@ impl UpCommand {
             }
         }
 
+        // Listen for reload requests before doing any slow work, as a request
+        // that arrives with no listener installed would terminate the process.
+        let reload_signals = listen_for_reload()?;
+
         if self.build {
-            app_source.build().await?;
+            app_source.build(self.profile.as_deref()).await?;
         }
 
         // Get working dir holder and hold on to it for the rest of the function.
@@ -157,18 +184,46 @@ impl UpCommand {
             return self.run_trigger(trigger_cmd, None).await;
         }
 
+        if let Some(app_dir) = app_source.local_app_dir() {
+            super::plugins::warn_unsatisfied_plugin_requirements(app_dir);
+        }
+
+        // The registry client and trust policy are only set up if a component
+        // is pulled, and are then reused by any reloads.
+        let verification = self.verification.clone();
+        let registry = Arc::new(ComponentRegistry::new(self.insecure, move || {
+            verification.policy()
+        }));
+
+        // Only local apps can be re-locked from their source when a reload is requested.
+        let reload = match (&resolved_app_source, reload_signals) {
+            (ResolvedAppSource::File { manifest_path, .. }, Some(signals)) => Some(ReloadOpts {
+                manifest_path: manifest_path.clone(),
+                registry: registry.clone(),
+                signals,
+            }),
+            _ => None,
+        };
+
         let mut locked_app = self
-            .load_resolved_app_source(resolved_app_source, &working_dir)
+            .load_resolved_app_source(resolved_app_source, &working_dir, registry)
             .await?;
 
         self.update_locked_app(&mut locked_app);
 
+        if let Some(lock_path) = &self.export_lock {
+            lock_file::export(&locked_app, lock_path).await?;
+            println!("Exported {app_source} to {}", quoted_path(lock_path));
+            return Ok(());
+        }
+
         let local_app_dir = app_source.local_app_dir().map(Into::into);
 
         let run_opts = RunTriggerOpts {
             locked_app,
             working_dir,
             local_app_dir,
+            reload,
         };
 
         self.run_trigger(trigger_cmd, Some(run_opts)).await
@@ -200,10 +255,13 @@ impl UpCommand {
         let mut cmd = std::process::Command::new(std::env::current_exe().unwrap());
         cmd.args(&trigger_cmd);
 
+        let mut reload_opts = None;
+
         if let Some(RunTriggerOpts {
             locked_app,
             working_dir,
             local_app_dir,
+            reload,
         }) = opts
         {
             let locked_url = self.write_locked_app(&locked_app, &working_dir).await?;
@@ -215,6 +273,8 @@ impl UpCommand {
             if let Some(local_app_dir) = local_app_dir {
                 cmd.env(SPIN_LOCAL_APP_DIR, local_app_dir);
             }
+
+            reload_opts = reload.map(|reload| (reload, working_dir));
         } else {
             cmd.arg("--help-args-only");
         }
@@ -223,6 +283,14 @@ impl UpCommand {
 
         let mut child = cmd.spawn().context("Failed to execute trigger")?;
 
+        #[cfg(not(windows))]
+        if let Some((reload, working_dir)) = reload_opts {
+            let pid = nix::unistd::Pid::from_raw(child.id() as i32);
+            self.relock_on_signal(reload, working_dir, pid);
+        }
+        #[cfg(windows)]
+        let _ = reload_opts;
+
         // Terminate trigger executor if `spin up` itself receives a termination signal
         #[cfg(not(windows))]
         {
@@ -243,7 +311,60 @@ impl UpCommand {
         }
     }
 
+    /// Whenever `spin up` receives SIGUSR1, re-locks the app from its manifest
+    /// into the existing lock file, then passes the signal on to the trigger
+    /// executor so that it reloads the app in-process.
+    #[cfg(not(windows))]
+    fn relock_on_signal(
+        self,
+        reload: ReloadOpts,
+        working_dir: PathBuf,
+        trigger_pid: nix::unistd::Pid,
+    ) {
+        let ReloadOpts {
+            manifest_path,
+            registry,
+            mut signals,
+        } = reload;
+        tokio::spawn(async move {
+            while signals.recv().await.is_some() {
+                if let Err(e) = self
+                    .relock(&manifest_path, &working_dir, registry.clone())
+                    .await
+                {
+                    terminal::error!("Failed to reload application: {e:#}");
+                    continue;
+                }
+                if let Err(err) = nix::sys::signal::kill(trigger_pid, nix::sys::signal::SIGUSR1) {
+                    tracing::warn!("Failed to signal trigger handler process to reload: {err:?}")
+                }
+            }
+        });
+    }
+
+    #[cfg(not(windows))]
+    async fn relock(
+        &self,
+        manifest_path: &Path,
+        working_dir: &Path,
+        registry: Arc<ComponentRegistry>,
+    ) -> Result<()> {
+        let resolved = ResolvedAppSource::File {
+            manifest_path: manifest_path.to_owned(),
+            manifest: spin_manifest::manifest_from_file(manifest_path)?,
+        };
+        let mut locked_app = self
+            .load_resolved_app_source(resolved, working_dir, registry)
+            .await?;
+        self.update_locked_app(&mut locked_app);
+        self.write_locked_app(&locked_app, working_dir).await?;
+        Ok(())
+    }
+
     fn app_source(&self) -> AppSource {
+        if let Some(lock_path) = &self.lock_source {
+            return AppSource::LockFile(lock_path.clone());
+        }
         match (&self.app_source, &self.file_source, &self.registry_source) {
             (None, None, None) => self.default_manifest_or_none(),
             (Some(source), None, None) => AppSource::infer_source(source),
@@ -311,12 +432,22 @@ impl UpCommand {
                 let mut client = spin_oci::Client::new(self.insecure, None)
                     .await
                     .context("cannot create registry client")?;
+                client.set_verification_policy(self.verification.policy()?);
 
                 let locked_app = OciLoader::new(working_dir)
                     .load_app(&mut client, reference)
                     .await?;
                 ResolvedAppSource::OciRegistry { locked_app }
             }
+            AppSource::LockFile(lock_path) => {
+                let locked_app =
+                    lock_file::load(lock_path, working_dir)
+                        .await
+                        .with_context(|| {
+                            format!("Failed to load lock file {}", quoted_path(lock_path))
+                        })?;
+                ResolvedAppSource::LockFile { locked_app }
+            }
             AppSource::Unresolvable(err) => bail!("{err}"),
             AppSource::None => bail!("Internal error - should have shown help"),
         })
@@ -327,6 +458,7 @@ impl UpCommand {
         &self,
         resolved: ResolvedAppSource,
         working_dir: &Path,
+        registry: Arc<ComponentRegistry>,
     ) -> anyhow::Result<LockedApp> {
         match resolved {
             ResolvedAppSource::File { manifest_path, .. } => {
@@ -335,16 +467,23 @@ impl UpCommand {
                 } else {
                     FilesMountStrategy::Copy(working_dir.join("assets"))
                 };
-                spin_loader::from_file(&manifest_path, files_mount_strategy, None)
-                    .await
-                    .with_context(|| {
-                        format!(
-                            "Failed to load manifest from {}",
-                            quoted_path(&manifest_path)
-                        )
-                    })
+                spin_loader::from_file(
+                    &manifest_path,
+                    files_mount_strategy,
+                    None,
+                    self.profile.as_deref(),
+                    Some(registry),
+                )
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to load manifest from {}",
+                        quoted_path(&manifest_path)
+                    )
+                })
             }
-            ResolvedAppSource::OciRegistry { locked_app } => Ok(locked_app),
+            ResolvedAppSource::OciRegistry { locked_app }
+            | ResolvedAppSource::LockFile { locked_app } => Ok(locked_app),
         }
     }
 
@@ -362,6 +501,39 @@ struct RunTriggerOpts {
     locked_app: LockedApp,
     working_dir: PathBuf,
     local_app_dir: Option<PathBuf>,
+    reload: Option<ReloadOpts>,
+}
+
+/// How to re-lock a local app when `spin up` is asked to reload it.
+#[cfg_attr(windows, allow(dead_code))]
+struct ReloadOpts {
+    manifest_path: PathBuf,
+    registry: Arc<ComponentRegistry>,
+    signals: ReloadSignals,
+}
+
+#[cfg(not(windows))]
+type ReloadSignals = tokio::signal::unix::Signal;
+#[cfg(windows)]
+type ReloadSignals = ();
+
+/// If reloading was requested (via `SPIN_HOT_RELOAD`), starts listening for
+/// reload signals (SIGUSR1).
+#[cfg(not(windows))]
+fn listen_for_reload() -> Result<Option<ReloadSignals>> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    if std::env::var_os(SPIN_HOT_RELOAD).is_none() {
+        return Ok(None);
+    }
+    let signals =
+        signal(SignalKind::user_defined1()).context("Failed to listen for reload signal")?;
+    Ok(Some(signals))
+}
+
+#[cfg(windows)]
+fn listen_for_reload() -> Result<Option<ReloadSignals>> {
+    Ok(None)
 }
 
 enum WorkingDirectory {
@@ -543,6 +715,19 @@ mod test {
         assert_eq!(AppSource::OciRegistry(reference), source);
     }
 
+    #[test]
+    fn lock_file_source_is_used_as_is() {
+        let lock_path = PathBuf::from("release/spin.lock");
+
+        let source = UpCommand {
+            lock_source: Some(lock_path.clone()),
+            ..Default::default()
+        }
+        .app_source();
+
+        assert_eq!(AppSource::LockFile(lock_path), source);
+    }
+
     #[test]
     fn can_reject_complete_gibberish() {
         let garbage = repo_path("ftp://🤡***🤡 HELLO MR CLOWN?!");
@@ -13,6 +13,7 @@ use spin_manifest::schema::v2::AppManifest;
 pub enum AppSource {
     File(PathBuf),
     OciRegistry(String),
+    LockFile(PathBuf),
     Unresolvable(String),
     None,
 }
@@ -50,9 +51,15 @@ impl AppSource {
         }
     }
 
-    pub async fn build(&self) -> anyhow::Result<()> {
+    pub async fn build(&self, profile: Option<&str>) -> anyhow::Result<()> {
         match self {
-            Self::File(path) => spin_build::build(path, &[]).await,
+            Self::File(path) => {
+                let options = spin_build::BuildOptions {
+                    profile: profile.map(ToOwned::to_owned),
+                    ..Default::default()
+                };
+                spin_build::build_with_options(path, &[], &options).await
+            }
             _ => Ok(()),
         }
     }
@@ -63,6 +70,7 @@ impl std::fmt::Display for AppSource {
         match self {
             Self::File(path) => write!(f, "local app {}", quoted_path(path)),
             Self::OciRegistry(reference) => write!(f, "remote app {reference:?}"),
+            Self::LockFile(path) => write!(f, "locked app {}", quoted_path(path)),
             Self::Unresolvable(s) => write!(f, "unknown app source: {s:?}"),
             Self::None => write!(f, "<no source>"),
         }
@@ -80,6 +88,9 @@ pub enum ResolvedAppSource {
     OciRegistry {
         locked_app: LockedApp,
     },
+    LockFile {
+        locked_app: LockedApp,
+    },
 }
 
 impl ResolvedAppSource {
@@ -88,7 +99,8 @@ impl ResolvedAppSource {
             ResolvedAppSource::File { manifest, .. } => {
                 manifest.triggers.keys().collect::<HashSet<_>>()
             }
-            ResolvedAppSource::OciRegistry { locked_app } => locked_app
+            ResolvedAppSource::OciRegistry { locked_app }
+            | ResolvedAppSource::LockFile { locked_app } => locked_app
                 .triggers
                 .iter()
                 .map(|t| &t.trigger_type)
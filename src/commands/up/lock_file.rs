@@ -0,0 +1,328 @@
+//! Standalone lock files, which pin the exact content of an app so that it can
+//! be run later, or elsewhere.
+//!
+//! A standalone lock file is written alongside a `content` directory holding
+//! the app's Wasm and static files, named by digest. Content sources in the
+//! lock file are relative to the lock file's directory, so the two can be
+//! moved together.
+
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use reqwest::Url;
+use spin_common::{sha256, ui::quoted_path, url::parse_file_url};
+use spin_locked_app::locked::{ContentPath, ContentRef, LockedApp};
+use walkdir::WalkDir;
+
+const CONTENT_DIR: &str = "content";
+
+/// Writes the app to a standalone lock file at `lock_path`, copying its
+/// content into a `content` directory next to it.
+pub async fn export(locked_app: &LockedApp, lock_path: &Path) -> Result<()> {
+    let lock_dir = parent_dir(lock_path);
+    let content_dir = lock_dir.join(CONTENT_DIR);
+    tokio::fs::create_dir_all(&content_dir)
+        .await
+        .with_context(|| format!("Failed to create {}", quoted_path(&content_dir)))?;
+
+    let mut locked_app = locked_app.clone();
+    for component in &mut locked_app.components {
+        export_content(&mut component.source.content, &content_dir)
+            .await
+            .with_context(|| format!("Failed to export Wasm for component {:?}", component.id))?;
+
+        let mut files = vec![];
+        for mount in std::mem::take(&mut component.files) {
+            export_files(mount, &content_dir, &mut files)
+                .await
+                .with_context(|| {
+                    format!("Failed to export files for component {:?}", component.id)
+                })?;
+        }
+        component.files = files;
+    }
+
+    let contents =
+        serde_json::to_vec_pretty(&locked_app).context("Failed to serialize locked app")?;
+    tokio::fs::write(lock_path, contents)
+        .await
+        .with_context(|| format!("Failed to write {}", quoted_path(lock_path)))
+}
+
+/// Loads the app from a standalone lock file, verifying the digest of all its
+/// content. Static files are copied into `working_dir` to be mounted.
+pub async fn load(lock_path: &Path, working_dir: &Path) -> Result<LockedApp> {
+    let contents = tokio::fs::read(lock_path)
+        .await
+        .with_context(|| format!("Failed to read {}", quoted_path(lock_path)))?;
+    let mut locked_app = LockedApp::from_json(&contents)
+        .with_context(|| format!("Failed to parse lock file {}", quoted_path(lock_path)))?;
+    let lock_dir = parent_dir(lock_path)
+        .canonicalize()
+        .with_context(|| format!("Failed to find directory of {}", quoted_path(lock_path)))?;
+
+    for component in &mut locked_app.components {
+        let content = &mut component.source.content;
+        let path = resolve_content_path(content, &lock_dir)?;
+        verify_file(content, &path)
+            .await
+            .with_context(|| format!("Invalid Wasm for component {:?}", component.id))?;
+        content.source = Some(file_url(&path)?);
+
+        if component.files.is_empty() {
+            continue;
+        }
+        let mount_dir = working_dir.join("assets").join(&component.id);
+        for file in &component.files {
+            materialize_file(file, &lock_dir, &mount_dir)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Invalid file {:?} for component {:?}",
+                        file.path, component.id
+                    )
+                })?;
+        }
+        tokio::fs::create_dir_all(&mount_dir).await?;
+        component.files = vec![ContentPath {
+            content: ContentRef {
+                source: Some(file_url(&mount_dir)?),
+                ..Default::default()
+            },
+            path: "/".into(),
+        }];
+    }
+    Ok(locked_app)
+}
+
+/// Copies content into the content directory, replacing its source with the
+/// relative path of the copy and recording its digest.
+async fn export_content(content: &mut ContentRef, content_dir: &Path) -> Result<()> {
+    if let Some(inline) = &content.inline {
+        let digest = format!("sha256:{}", sha256::hex_digest_from_bytes(inline));
+        check_digest(content, &digest)?;
+        content.digest = Some(digest);
+        content.source = None;
+        return Ok(());
+    }
+    let source = content
+        .source
+        .as_deref()
+        .context("content has neither a source nor inline data")?;
+    let path = parse_file_url(source)?;
+    let hex = sha256::hex_digest_from_file(&path)
+        .with_context(|| format!("Failed to read {}", quoted_path(&path)))?;
+    check_digest(content, &format!("sha256:{hex}"))?;
+
+    let dest = content_dir.join(&hex);
+    if !dest.exists() {
+        tokio::fs::copy(&path, &dest)
+            .await
+            .with_context(|| format!("Failed to copy {}", quoted_path(&path)))?;
+    }
+    content.source = Some(format!("{CONTENT_DIR}/{hex}"));
+    content.digest = Some(format!("sha256:{hex}"));
+    Ok(())
+}
+
+/// Exports each file under a files mount as a separate entry, so that each can
+/// be verified by digest.
+async fn export_files(
+    mount: ContentPath,
+    content_dir: &Path,
+    files: &mut Vec<ContentPath>,
+) -> Result<()> {
+    if mount.content.inline.is_some() {
+        let mut content = mount.content;
+        export_content(&mut content, content_dir).await?;
+        files.push(ContentPath {
+            content,
+            path: mount.path,
+        });
+        return Ok(());
+    }
+    let source = mount
+        .content
+        .source
+        .as_deref()
+        .context("files mount has no source")?;
+    let source = parse_file_url(source)?;
+    for entry in WalkDir::new(&source) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        // Can unwrap because we got to 'entry' from walking 'source'
+        let rel_path = entry.path().strip_prefix(&source).unwrap();
+        let mut content = ContentRef {
+            source: Some(file_url(entry.path())?),
+            ..Default::default()
+        };
+        export_content(&mut content, content_dir).await?;
+        files.push(ContentPath {
+            content,
+            path: mount.path.join(rel_path),
+        });
+    }
+    Ok(())
+}
+
+/// Verifies a file's content and copies it to its guest path under the mount
+/// directory.
+async fn materialize_file(file: &ContentPath, lock_dir: &Path, mount_dir: &Path) -> Result<()> {
+    let relative: PathBuf = file
+        .path
+        .components()
+        .filter(|c| !matches!(c, Component::RootDir))
+        .collect();
+    ensure!(
+        relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_))),
+        "invalid file path {:?}",
+        file.path
+    );
+    let dest = mount_dir.join(relative);
+    if let Some(parent) = dest.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    if let Some(inline) = &file.content.inline {
+        verify_bytes(&file.content, inline)?;
+        tokio::fs::write(&dest, inline).await?;
+    } else {
+        let path = resolve_content_path(&file.content, lock_dir)?;
+        verify_file(&file.content, &path).await?;
+        tokio::fs::copy(&path, &dest)
+            .await
+            .with_context(|| format!("Failed to copy {}", quoted_path(&path)))?;
+    }
+    Ok(())
+}
+
+/// Resolves a content source, which may be relative to the lock file.
+fn resolve_content_path(content: &ContentRef, lock_dir: &Path) -> Result<PathBuf> {
+    let source = content.source.as_deref().context("content has no source")?;
+    if source.starts_with("file:") {
+        parse_file_url(source)
+    } else {
+        Ok(lock_dir.join(source))
+    }
+}
+
+async fn verify_file(content: &ContentRef, path: &Path) -> Result<()> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read {}", quoted_path(path)))?;
+    verify_bytes(content, &bytes)
+}
+
+fn verify_bytes(content: &ContentRef, bytes: &[u8]) -> Result<()> {
+    let expected = content
+        .digest
+        .as_deref()
+        .context("content has no digest; standalone lock files must pin all content")?;
+    let actual = format!("sha256:{}", sha256::hex_digest_from_bytes(bytes));
+    ensure!(
+        actual == expected,
+        "content digest mismatch: expected {expected}, got {actual}"
+    );
+    Ok(())
+}
+
+fn check_digest(content: &ContentRef, actual: &str) -> Result<()> {
+    match content.digest.as_deref() {
+        Some(expected) if expected != actual => {
+            bail!("content digest mismatch: expected {expected}, got {actual}")
+        }
+        _ => Ok(()),
+    }
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+fn file_url(path: &Path) -> Result<String> {
+    let path = std::fs::canonicalize(path)
+        .with_context(|| format!("Failed to resolve {}", quoted_path(path)))?;
+    Url::from_file_path(&path)
+        .map(|url| url.to_string())
+        .map_err(|_| anyhow!("cannot convert to file URL: {}", quoted_path(&path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(wasm_url: &str, files_url: &str) -> LockedApp {
+        LockedApp::from_json(
+            serde_json::json!({
+                "spin_lock_version": 0,
+                "triggers": [],
+                "components": [{
+                    "id": "hello",
+                    "source": {
+                        "content_type": "application/wasm",
+                        "source": wasm_url,
+                    },
+                    "files": [{ "content": { "source": files_url }, "path": "/static" }],
+                }],
+            })
+            .to_string()
+            .as_bytes(),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn exported_lock_files_round_trip() -> Result<()> {
+        let source_dir = tempfile::tempdir()?;
+        let wasm = source_dir.path().join("hello.wasm");
+        std::fs::write(&wasm, "wasm")?;
+        let files = source_dir.path().join("files");
+        std::fs::create_dir_all(files.join("sub"))?;
+        std::fs::write(files.join("sub/index.html"), "hello")?;
+
+        let export_dir = tempfile::tempdir()?;
+        let lock_path = export_dir.path().join("spin.lock");
+        export(&app(&file_url(&wasm)?, &file_url(&files)?), &lock_path).await?;
+
+        // The export must not depend on the original sources.
+        drop(source_dir);
+
+        let working_dir = tempfile::tempdir()?;
+        let loaded = load(&lock_path, working_dir.path()).await?;
+        let component = &loaded.components[0];
+        let mount = parse_file_url(component.files[0].content.source.as_deref().unwrap())?;
+        assert_eq!(
+            "hello",
+            std::fs::read_to_string(mount.join("static/sub/index.html"))?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn load_rejects_modified_content() -> Result<()> {
+        let source_dir = tempfile::tempdir()?;
+        let wasm = source_dir.path().join("hello.wasm");
+        std::fs::write(&wasm, "wasm")?;
+        let files = source_dir.path().join("files");
+        std::fs::create_dir_all(&files)?;
+
+        let export_dir = tempfile::tempdir()?;
+        let lock_path = export_dir.path().join("spin.lock");
+        export(&app(&file_url(&wasm)?, &file_url(&files)?), &lock_path).await?;
+
+        let hex = sha256::hex_digest_from_bytes("wasm");
+        std::fs::write(export_dir.path().join(CONTENT_DIR).join(hex), "tampered")?;
+
+        let working_dir = tempfile::tempdir()?;
+        let err = load(&lock_path, working_dir.path()).await.unwrap_err();
+        assert!(format!("{err:#}").contains("digest mismatch"), "{err:#}");
+        Ok(())
+    }
+}
@@ -13,7 +13,7 @@ use uuid::Uuid;
 use watchexec::Watchexec;
 
 use crate::opts::{
-    APP_MANIFEST_FILE_OPT, DEFAULT_MANIFEST_FILE, WATCH_CLEAR_OPT, WATCH_DEBOUNCE_OPT,
+    APP_MANIFEST_FILE_OPT, DEFAULT_MANIFEST_FILE, PROFILE_ENV, WATCH_CLEAR_OPT, WATCH_DEBOUNCE_OPT,
     WATCH_SKIP_BUILD_OPT,
 };
 
@@ -66,6 +66,10 @@ pub struct WatchCommand {
     #[clap(name = WATCH_SKIP_BUILD_OPT, long = "skip-build")]
     pub skip_build: bool,
 
+    /// The manifest profile to apply when building and running the application.
+    #[clap(long = "profile", env = PROFILE_ENV)]
+    pub profile: Option<String>,
+
     /// Arguments to be passed through to spin up.
     #[clap()]
     pub up_args: Vec<String>,
@@ -77,6 +81,8 @@ impl WatchCommand {
         // * The Uppificator runs `spin up`, and watches the manifest artifacts (component.source and component.files)
         //   (and the manifest if build is not in play). When it detects a change, it restarts `spin up`.
         //   THAT'S ALL, THAT'S ALL IT DOES.
+        //   * Except that if the manifest is unchanged and the app uses only HTTP triggers, it asks `spin up`
+        //     to reload the app in-process instead of restarting it, so that in-flight requests complete.
         //   * If `spin up` crashes, the Uppificator restarts it.  BUT APART FROM THAT THAT'S ALL IT DOES OKAY.
         // * The Buildifier, if in play, watches the manifest and component.build.watch collections. When it detects a
         //   change, it PAUSES the Uppificator, does the build, then unpauses the Uppificator.
@@ -110,20 +116,30 @@ impl WatchCommand {
         let mut buildifier = Buildifier {
             spin_bin: spin_bin.clone(),
             manifest: manifest_file.clone(),
+            profile: self.profile.clone(),
             clear_screen: self.clear,
             has_ever_built: false,
             watched_changes: source_code_rx,
             uppificator_pauser: pause_tx,
         };
 
+        // The profile goes before the user's args, as those may end with trigger args.
+        let mut up_args = vec![];
+        if let Some(profile) = &self.profile {
+            up_args.extend(["--profile".to_owned(), profile.clone()]);
+        }
+        up_args.extend(self.up_args.iter().cloned());
+
         let mut uppificator = Uppificator {
             spin_bin: spin_bin.clone(),
             manifest: manifest_file.clone(),
-            up_args: self.up_args.clone(),
+            up_args,
             clear_screen: self.clear,
             watched_changes: artifact_rx,
             pause_feed: pause_rx,
             stopper: stop_rx,
+            reloadable_manifest: None,
+            reload_ready_dir: tempfile::TempDir::with_prefix("spin-watch-")?,
         };
 
         // Start `watchexec` tasks to monitor artifact and build files.
@@ -245,6 +261,7 @@ impl WatchCommand {
         let rtf = RuntimeConfigFactory {
             manifest_file: manifest_file.to_owned(),
             manifest_dir: manifest_dir.to_owned(),
+            profile: self.profile.clone(),
             filter_factory,
             notifier,
             impact_description,
@@ -268,6 +285,7 @@ impl WatchCommand {
 pub struct RuntimeConfigFactory {
     manifest_file: PathBuf,
     manifest_dir: PathBuf,
+    profile: Option<String>,
     filter_factory: Box<dyn FilterFactory>,
     notifier: Arc<tokio::sync::watch::Sender<Uuid>>,
     impact_description: &'static str,
@@ -277,7 +295,12 @@ pub struct RuntimeConfigFactory {
 impl RuntimeConfigFactory {
     async fn build_config(&self) -> anyhow::Result<watchexec::config::RuntimeConfig> {
         let manifest_str = tokio::fs::read_to_string(&self.manifest_file).await?;
-        let manifest = spin_manifest::manifest_from_str(&manifest_str)?;
+        let mut manifest = spin_manifest::manifest_from_str(&manifest_str)?;
+        if self.profile.is_some() {
+            // Watch the files and sources of the components as the profile configures them.
+            spin_manifest::normalize::normalize_manifest(&mut manifest);
+            spin_manifest::normalize::apply_profile(&mut manifest, self.profile.as_deref())?;
+        }
         let filterer = self
             .filter_factory
             .build_filter(&self.manifest_file, &self.manifest_dir, &manifest)
@@ -1,5 +1,6 @@
 use command_group::AsyncCommandGroup;
-use std::path::PathBuf;
+use spin_trigger::cli::SPIN_HOT_RELOAD;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 pub(crate) struct Uppificator {
@@ -10,6 +11,12 @@ pub(crate) struct Uppificator {
     pub watched_changes: tokio::sync::watch::Receiver<Uuid>,
     pub pause_feed: tokio::sync::mpsc::Receiver<Pause>,
     pub stopper: tokio::sync::watch::Receiver<Uuid>,
+    // The manifest text as of when `spin up` was started, if the running app can
+    // be reloaded in-process. Changes to the manifest require a restart.
+    pub reloadable_manifest: Option<String>,
+    // Holds the file the trigger executor creates once it can safely be sent
+    // reload requests.
+    pub reload_ready_dir: tempfile::TempDir,
 }
 
 #[derive(Debug)]
@@ -20,6 +27,7 @@ pub(crate) enum Pause {
 
 enum UppificatorAction {
     Restart,
+    Reload,
     Resume,
     Stop,
     Wait,
@@ -37,11 +45,18 @@ impl Uppificator {
         }
 
         'run: loop {
+            self.reloadable_manifest = reloadable_manifest(&self.manifest);
+
             let mut cmd = tokio::process::Command::new(&self.spin_bin);
             cmd.arg("up")
                 .arg("-f")
                 .arg(&self.manifest)
                 .args(&self.up_args);
+            if self.reloadable_manifest.is_some() {
+                let ready_file = self.reload_ready_file();
+                _ = std::fs::remove_file(&ready_file);
+                cmd.env(SPIN_HOT_RELOAD, ready_file);
+            }
             let mut child = match cmd.group_spawn() {
                 Ok(ch) => ch,
                 Err(e) => {
@@ -55,6 +70,7 @@ impl Uppificator {
             loop {
                 match self.next_event(&mut child).await {
                     UppificatorAction::Restart => break,
+                    UppificatorAction::Reload => continue,
                     UppificatorAction::Resume => {
                         resuming_after_build = true;
                         continue;
@@ -79,8 +95,12 @@ impl Uppificator {
                 UppificatorAction::Wait
             },
             _ = self.watched_changes.changed() => {
-                stop(child).await;
-                UppificatorAction::Restart
+                if self.manifest_unchanged() && self.reload_ready() && reload(child) {
+                    UppificatorAction::Reload
+                } else {
+                    stop(child).await;
+                    UppificatorAction::Restart
+                }
             },
             p = self.pause_feed.recv() => {
                 if matches!(p, Some(Pause::Pause)) {
@@ -100,6 +120,62 @@ impl Uppificator {
             }
         }
     }
+
+    fn reload_ready_file(&self) -> PathBuf {
+        self.reload_ready_dir.path().join("reload-ready")
+    }
+
+    // A reload request sent before the trigger executor is listening for it
+    // would terminate the app, so until then changes cause a restart.
+    fn reload_ready(&self) -> bool {
+        self.reload_ready_file().exists()
+    }
+
+    fn manifest_unchanged(&self) -> bool {
+        match &self.reloadable_manifest {
+            Some(manifest) => {
+                std::fs::read_to_string(&self.manifest).ok().as_ref() == Some(manifest)
+            }
+            None => false,
+        }
+    }
+}
+
+/// Returns the manifest text if the app can be reloaded in-process. Only
+/// the built-in HTTP trigger supports this.
+fn reloadable_manifest(manifest_file: &Path) -> Option<String> {
+    if cfg!(windows) {
+        return None;
+    }
+    let manifest_str = std::fs::read_to_string(manifest_file).ok()?;
+    let manifest = spin_manifest::manifest_from_str(&manifest_str).ok()?;
+    manifest
+        .triggers
+        .keys()
+        .all(|trigger_type| trigger_type == "http")
+        .then_some(manifest_str)
+}
+
+#[cfg(unix)]
+fn reload(child: &mut command_group::AsyncGroupChild) -> bool {
+    // Signal only `spin up` (not the whole group): it re-locks the app and
+    // passes the signal on to the trigger executor.
+    let Some(child_id) = child.id() else {
+        return false;
+    };
+    let pid = nix::unistd::Pid::from_raw(child_id as i32);
+    match nix::sys::signal::kill(pid, Some(nix::sys::signal::Signal::SIGUSR1)) {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!("Could not send reload signal to child process: {e:#}");
+            false
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn reload(_child: &mut command_group::AsyncGroupChild) -> bool {
+    false
 }
 
 #[cfg(unix)]
@@ -6,6 +6,7 @@ pub const PLUGIN_NAME_OPT: &str = "PLUGIN_NAME";
 pub const PLUGIN_REMOTE_PLUGIN_MANIFEST_OPT: &str = "REMOTE_PLUGIN_MANIFEST";
 pub const PLUGIN_LOCAL_PLUGIN_MANIFEST_OPT: &str = "LOCAL_PLUGIN_MANIFEST";
 pub const PLUGIN_ALL_OPT: &str = "ALL";
+pub const PLUGIN_PROJECT_OPT: &str = "PROJECT";
 pub const PLUGIN_OVERRIDE_COMPATIBILITY_CHECK_FLAG: &str = "override-compatibility-check";
 pub const HELP_ARGS_ONLY_TRIGGER_TYPE: &str = "provide-help-args-no-app";
 pub const FROM_REGISTRY_OPT: &str = "REGISTRY_REFERENCE";
@@ -13,3 +14,5 @@ pub const WATCH_CLEAR_OPT: &str = "CLEAR";
 pub const WATCH_DEBOUNCE_OPT: &str = "DEBOUNCE";
 pub const WATCH_SKIP_BUILD_OPT: &str = "SKIP_BUILD";
 pub const ALWAYS_BUILD_ENV: &str = "SPIN_ALWAYS_BUILD";
+pub const PROFILE_ENV: &str = "SPIN_PROFILE";
+pub const SIGNING_KEY_ENV: &str = "SPIN_SIGNING_KEY";
@@ -1,6 +1,6 @@
 pub mod bindings {
     wit_bindgen::generate!({
-        world: "platform",
+        world: "fermyon:spin/platform@2.0.0",
         path: "../../../wit",
         runtime_path: "::wit_bindgen::rt"
     });
@@ -19,7 +19,7 @@ macro_rules! define_component {
         // For now, this assumes the crate using this macro has `wit-bindgen` as a dependency
         mod bindings {
             $crate::wit_bindgen::generate!({
-                world: "http-trigger",
+                world: "fermyon:spin/http-trigger@2.0.0",
                 path: "../../../../wit",
                 exports: {
                     "wasi:http/incoming-handler": super::Component